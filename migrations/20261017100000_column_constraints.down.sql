@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE sensor_schema DROP COLUMN IF EXISTS col_constraints;
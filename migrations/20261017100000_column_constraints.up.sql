@@ -0,0 +1,8 @@
+-- Add up migration script here
+-----------------------------------------------------------------------------------
+-- Validation constraints for sensor data columns
+
+-----
+-- Serialized (JSON) column constraints, enforced during data ingestion
+-----
+ALTER TABLE sensor_schema ADD COLUMN col_constraints text;
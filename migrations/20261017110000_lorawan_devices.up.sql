@@ -0,0 +1,12 @@
+-- Add up migration script here
+-----------------------------------------------------------------------------------
+-- LoRaWAN end devices
+
+-----
+-- Maps the DevEUI of an end device to the sensor receiving its uplinks
+-----
+CREATE TABLE lorawan_device (
+    dev_eui char(16) PRIMARY KEY,     -- DevEUI of the end device (upper case hex)
+    sensor_id uuid NOT NULL           -- reference to the sensor receiving the uplinks
+        REFERENCES sensor(id) ON UPDATE CASCADE ON DELETE CASCADE
+);
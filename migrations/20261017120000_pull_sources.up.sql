@@ -0,0 +1,15 @@
+-- Add up migration script here
+-----------------------------------------------------------------------------------
+-- Pull based data ingest
+
+-----
+-- HTTP endpoint periodically polled for the data of the sensor
+-----
+CREATE TABLE sensor_pull_source (
+    sensor_id uuid PRIMARY KEY        -- reference to the sensor receiving the polled data
+        REFERENCES sensor(id) ON UPDATE CASCADE ON DELETE CASCADE,
+    url text NOT NULL,                -- the polled url
+    headers text,                     -- serialized (JSON) request headers
+    interval_secs integer NOT NULL,   -- seconds between two requests
+    json_pointer text                 -- selects the data entries in the JSON response
+);
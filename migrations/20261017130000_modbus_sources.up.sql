@@ -0,0 +1,15 @@
+-- Add up migration script here
+-----------------------------------------------------------------------------------
+-- Modbus TCP polling
+
+-----
+-- Modbus TCP device periodically polled for the registers mapped to the sensor columns
+-----
+CREATE TABLE sensor_modbus_source (
+    sensor_id uuid PRIMARY KEY        -- reference to the sensor receiving the polled data
+        REFERENCES sensor(id) ON UPDATE CASCADE ON DELETE CASCADE,
+    host text NOT NULL,               -- host of the modbus device or gateway
+    port integer NOT NULL,            -- tcp port of the device
+    interval_secs integer NOT NULL,   -- seconds between two polls
+    registers text NOT NULL           -- serialized (JSON) register to column mapping
+);
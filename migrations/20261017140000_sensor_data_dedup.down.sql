@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS sensor_ingest_message;
+ALTER TABLE sensor DROP COLUMN IF EXISTS storage_dedup;
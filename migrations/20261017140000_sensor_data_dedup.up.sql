@@ -0,0 +1,19 @@
+-- Add up migration script here
+-----------------------------------------------------------------------------------
+-- Duplicate detection of ingested data
+
+-----
+-- Serialized (JSON) deduplication mode of the sensor data
+-----
+ALTER TABLE sensor ADD COLUMN storage_dedup text;
+
+-----
+-- Message ids of recently ingested data, remembered for the dedup window of the sensor
+-----
+CREATE TABLE sensor_ingest_message (
+    sensor_id uuid NOT NULL           -- reference to the sensor that ingested the message
+        REFERENCES sensor(id) ON UPDATE CASCADE ON DELETE CASCADE,
+    message_id text NOT NULL,         -- client supplied id of the message
+    received_at timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (sensor_id, message_id)
+);
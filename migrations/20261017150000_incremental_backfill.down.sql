@@ -0,0 +1,48 @@
+-- Add down migration script here
+DO $$
+DECLARE
+    c record;
+BEGIN
+    FOR c IN SELECT s.tbl_name, sc.col_name FROM sensor s JOIN sensor_schema sc ON s.id = sc.sensor_id WHERE sc.col_ingest = 1
+    LOOP
+        PERFORM remove_sensor_column_ingest_incremental(c.tbl_name, c.col_name);
+    END LOOP;
+END;
+$$;
+
+CREATE OR REPLACE FUNCTION create_sensor_column_ingest_incremental(tbl character varying, col character varying) RETURNS void
+    LANGUAGE plpgsql
+    AS $_$
+BEGIN
+    EXECUTE format('create or replace function incr_sdata_ingest_' || hashed_col_name(tbl, col, 8) || '()
+    returns trigger language plpgsql as
+    $$
+    begin
+        new. ' || col || ' := add_values((select ' || col || ' from ' || tbl || ' order by created_at desc limit 1), new. ' || col || ');
+        return new;
+    end;
+    $$;');
+    EXECUTE format('create or replace trigger _' || hashed_col_name(tbl, col, 8) || '_ingest before insert on ' || tbl || '
+                for each row
+                execute function incr_sdata_ingest_' || hashed_col_name(tbl, col, 8) || '()');
+END;
+$_$;
+
+CREATE OR REPLACE FUNCTION remove_sensor_column_ingest_incremental(tbl character varying, col character varying) RETURNS void
+    LANGUAGE plpgsql
+    AS $$
+BEGIN
+    EXECUTE format('DROP FUNCTION incr_sdata_ingest_' || hashed_col_name(tbl, col, 8) || ' CASCADE');
+END;
+$$;
+
+DO $$
+DECLARE
+    c record;
+BEGIN
+    FOR c IN SELECT s.tbl_name, sc.col_name FROM sensor s JOIN sensor_schema sc ON s.id = sc.sensor_id WHERE sc.col_ingest = 1
+    LOOP
+        PERFORM create_sensor_column_ingest_incremental(c.tbl_name, c.col_name);
+    END LOOP;
+END;
+$$;
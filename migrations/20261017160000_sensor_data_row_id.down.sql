@@ -0,0 +1,11 @@
+-- Add down migration script here
+DO $$
+DECLARE
+    s record;
+BEGIN
+    FOR s IN SELECT tbl_name FROM sensor
+    LOOP
+        EXECUTE format('ALTER TABLE IF EXISTS %I DROP COLUMN IF EXISTS row_id', s.tbl_name);
+    END LOOP;
+END;
+$$;
@@ -0,0 +1,14 @@
+-- Add up migration script here
+-----------------------------------------------------------------------------------
+-- Stable row ids of the sensor data tables, used as tie-breaker of paginated reads
+
+DO $$
+DECLARE
+    s record;
+BEGIN
+    FOR s IN SELECT tbl_name FROM sensor
+    LOOP
+        EXECUTE format('ALTER TABLE IF EXISTS %I ADD COLUMN row_id bigint GENERATED ALWAYS AS IDENTITY', s.tbl_name);
+    END LOOP;
+END;
+$$;
@@ -0,0 +1,132 @@
+-- Add up migration script here
+-----------------------------------------------------------------------------------
+-- Appended batches of incremental columns skip the re-accumulation
+
+-----
+-- Re-accumulation trigger of incremental columns
+-- Back-filled, updated or deleted rows re-accumulate all rows from the earliest modified timestamp onwards.
+-- Inserted rows that follow all stored rows in the order of their insertion (row_id) have already been
+-- accumulated on insert, regardless of the batch size.
+-----
+CREATE OR REPLACE FUNCTION create_sensor_column_incremental_recalc(tbl character varying, col character varying) RETURNS void
+    LANGUAGE plpgsql
+    AS $_$
+DECLARE
+    fn text := hashed_col_name(tbl, col, 8);
+    delta text := 'incr$' || col;
+    total text;
+BEGIN
+    SELECT CASE WHEN atttypid IN ('integer'::regtype, 'double precision'::regtype)
+                THEN format('(sum(%I) over (order by created_at))::%s', delta, format_type(atttypid, NULL))
+                ELSE format('string_agg(%I, %L) over (order by created_at)', delta, '') END
+        INTO total
+        FROM pg_attribute WHERE attrelid = tbl::regclass AND attname = col;
+
+    EXECUTE format('create or replace function %1$I()
+    returns trigger language plpgsql as
+    $$
+    declare
+        ts timestamp;
+    begin
+        -- Rows dropped by other triggers (e.g. ring buffers) prune the history without changing the totals
+        if pg_trigger_depth() > 1 then
+            return null;
+        end if;
+
+        select min(created_at) into ts from changed_rows;
+
+        if ts is null then
+            return null;
+        end if;
+
+        -- Appended rows have already been accumulated on insert if no stored row is at or after them
+        -- and they were inserted in the order of their timestamps
+        if tg_op = %5$L
+            and not exists (select 1 from %2$I where created_at >= ts and row_id not in (select row_id from changed_rows))
+            and not exists (select 1 from (select created_at, lag(created_at) over (order by row_id) as prev from changed_rows) r
+                            where r.created_at <= r.prev) then
+            return null;
+        end if;
+
+        update %2$I t set %4$I = s.total from (
+            select row_id as id, add_values(
+                (select %4$I from %2$I where created_at < ts order by created_at desc limit 1),
+                %6$s) as total
+            from %2$I where created_at >= ts
+        ) s where t.row_id = s.id;
+
+        return null;
+    end;
+    $$;', 'incr_sdata_recalc_' || fn, tbl, delta, col, 'INSERT', total);
+    EXECUTE format('create or replace trigger %I after insert on %I referencing new table as changed_rows
+                for each statement
+                execute function %I()', '_' || fn || '_recalc_ins', tbl, 'incr_sdata_recalc_' || fn);
+    EXECUTE format('create or replace trigger %I after update on %I referencing new table as changed_rows
+                for each statement
+                execute function %I()', '_' || fn || '_recalc_upd', tbl, 'incr_sdata_recalc_' || fn);
+    EXECUTE format('create or replace trigger %I after delete on %I referencing old table as changed_rows
+                for each statement
+                execute function %I()', '_' || fn || '_recalc_del', tbl, 'incr_sdata_recalc_' || fn);
+END;
+$_$;
+
+-----
+-- Incremental sensor data ingest trigger
+-- The raw (delta) value of each row is kept in the hidden column "incr$<col>", which can't clash with
+-- column names of the sensor. Cumulative values are summed up in the order of created_at, rows with equal
+-- timestamps share the same cumulative value.
+-----
+CREATE OR REPLACE FUNCTION create_sensor_column_ingest_incremental(tbl character varying, col character varying) RETURNS void
+    LANGUAGE plpgsql
+    AS $_$
+DECLARE
+    fn text := hashed_col_name(tbl, col, 8);
+    delta text := 'incr$' || col;
+    col_type text;
+    derive text;
+BEGIN
+    SELECT format_type(atttypid, atttypmod),
+           CASE WHEN atttypid IN ('integer'::regtype, 'double precision'::regtype)
+                THEN format('%1$I - coalesce(lag(%1$I) over (order by created_at), 0)', col)
+                ELSE format('substr(%1$I, length(coalesce(lag(%1$I) over (order by created_at), %2$L)) + 1)', col, '') END
+        INTO col_type, derive
+        FROM pg_attribute WHERE attrelid = tbl::regclass AND attname = col;
+
+    EXECUTE format('ALTER TABLE %I ADD COLUMN %I %s', tbl, delta, col_type);
+    EXECUTE format('CREATE INDEX IF NOT EXISTS %I ON %I (created_at)', tbl || '_created_at_idx', tbl);
+
+    -- Deltas of already stored rows are derived from their cumulative values
+    EXECUTE format('UPDATE %1$I t SET %2$I = s.delta FROM (SELECT row_id AS id, %3$s AS delta FROM %1$I) s WHERE t.row_id = s.id',
+        tbl, delta, derive);
+
+    -- Appended rows accumulate on top of their predecessor
+    EXECUTE format('create or replace function %1$I()
+    returns trigger language plpgsql as
+    $$
+    begin
+        new.%3$I := new.%4$I;
+        new.%4$I := add_values((select %4$I from %2$I where created_at <= new.created_at order by created_at desc limit 1), new.%3$I);
+        return new;
+    end;
+    $$;', 'incr_sdata_ingest_' || fn, tbl, delta, col);
+    EXECUTE format('create or replace trigger %I before insert on %I
+                for each row
+                execute function %I()', '_' || fn || '_ingest', tbl, 'incr_sdata_ingest_' || fn);
+
+    PERFORM create_sensor_column_incremental_recalc(tbl, col);
+END;
+$_$;
+
+-----
+-- Recreate the re-accumulation triggers of existing incremental columns
+-----
+DO $$
+DECLARE
+    c record;
+BEGIN
+    FOR c IN SELECT s.tbl_name, sc.col_name FROM sensor s JOIN sensor_schema sc ON s.id = sc.sensor_id WHERE sc.col_ingest = 1
+    LOOP
+        PERFORM create_sensor_column_incremental_recalc(c.tbl_name, c.col_name);
+    END LOOP;
+END;
+$$;
@@ -0,0 +1,5 @@
+-- Add up migration script here
+-----------------------------------------------------------------------------------
+-- Unconstrained columns are stored as NULL instead of the serialized JSON null
+
+UPDATE sensor_schema SET col_constraints = NULL WHERE col_constraints = 'null';
@@ -19,12 +19,22 @@ use utoipa_swagger_ui::SwaggerUi;
         sensor_mgmt::handler::sensor_hdl::get_sensor_info_handler,
         sensor_mgmt::handler::sensor_hdl::create_sensor_handler,
         sensor_mgmt::handler::sensor_hdl::edit_sensor_handler,
+        sensor_mgmt::handler::sensor_hdl::edit_sensor_schema_handler,
         sensor_mgmt::handler::sensor_hdl::delete_sensor_handler,
         sensor_mgmt::handler::sensor_hdl::create_sensor_api_key_handler,
         sensor_mgmt::handler::sensor_hdl::delete_sensor_api_key_handler,
+        sensor_mgmt::handler::sensor_hdl::register_lorawan_device_handler,
+        sensor_mgmt::handler::sensor_hdl::list_lorawan_devices_handler,
+        sensor_mgmt::handler::sensor_hdl::delete_lorawan_device_handler,
         sensor_mgmt::handler::sensor_hdl::load_data_chain_handler,
         sensor_mgmt::handler::sensor_hdl::set_data_chain_handler,
         sensor_mgmt::handler::sensor_hdl::delete_data_chain_handler,
+        sensor_mgmt::handler::sensor_hdl::load_pull_source_handler,
+        sensor_mgmt::handler::sensor_hdl::set_pull_source_handler,
+        sensor_mgmt::handler::sensor_hdl::delete_pull_source_handler,
+        sensor_mgmt::handler::sensor_hdl::load_modbus_source_handler,
+        sensor_mgmt::handler::sensor_hdl::set_modbus_source_handler,
+        sensor_mgmt::handler::sensor_hdl::delete_modbus_source_handler,
 
         sensor_mgmt::handler::data_transform_hdl::list_data_transformer_handler,
         sensor_mgmt::handler::data_transform_hdl::load_data_transformer_handler,
@@ -38,8 +48,15 @@ use utoipa_swagger_ui::SwaggerUi;
         sensor_mgmt::handler::event_handler_hdl::create_event_handler_handler,
 
         sensor_mgmt::handler::data_ingest::http::ingest_sensor_data_handler,
+        sensor_mgmt::handler::data_ingest::http::ingest_line_protocol_handler,
+        sensor_mgmt::handler::data_ingest::http::ingest_lorawan_handler,
+        sensor_mgmt::handler::data_ingest::http::mqtt_auth_user_handler,
+        sensor_mgmt::handler::data_ingest::http::mqtt_auth_superuser_handler,
+        sensor_mgmt::handler::data_ingest::http::mqtt_auth_acl_handler,
         sensor_mgmt::handler::data_hdl::delete_sensor_data_handler,
         sensor_mgmt::handler::data_hdl::get_sensor_data_handler,
+        sensor_mgmt::handler::data_hdl::get_aligned_sensor_data_handler,
+        sensor_mgmt::handler::data_hdl::export_sensor_data_handler,
 
         sensor_mgmt::handler::user_hdl::list_users_handler,
         sensor_mgmt::handler::user_hdl::register_user_handler,
@@ -1,45 +1,23 @@
 use anyhow::anyhow;
 use serde_json::{Value};
 use reqwest::Client;
-use csv::Reader;
 use crate::help::*;
 use crate::me::get_write_api_key;
 
-async fn load_from_file(filename: &str) -> anyhow::Result<Value> {
+/// Payload of the ingest request, CSV files are parsed by the server
+enum IngestPayload {
+    Json(Value),
+    Csv(String),
+}
+
+async fn load_from_file(filename: &str) -> anyhow::Result<IngestPayload> {
     if filename.ends_with(".json") {
         let content = tokio::fs::read_to_string(filename).await?;
         let value: Value = serde_json::from_str(&content)?;
-        Ok(value)
+        Ok(IngestPayload::Json(value))
     } else if filename.ends_with(".csv") {
         let content = tokio::fs::read_to_string(filename).await?;
-        let mut rdr = Reader::from_reader(content.as_bytes());
-        let headers = rdr.headers()?.clone();
-        let mut records = Vec::new();
-        for result in rdr.records() {
-            let record = result?;
-            // Wandelt jede Zeile in ein HashMap um: Spaltenname -> Wert
-            let mut map = serde_json::Map::new();
-            for (header, field) in headers.iter().zip(record.iter()) {
-                // Versuche, das Feld als Zahl zu parsen
-                if let Ok(n) = field.parse::<i64>() {
-                    map.insert(header.to_string(), Value::Number(n.into()));
-                } else if let Ok(f) = field.parse::<f64>() {
-                    // f64 muss überNumber::from_f64 erzeugt werden
-                    if let Some(num) = serde_json::Number::from_f64(f) {
-                        map.insert(header.to_string(), Value::Number(num));
-                    } else {
-                        map.insert(header.to_string(), Value::String(field.to_string()));
-                    }
-                } else {
-                    map.insert(header.to_string(), Value::String(field.to_string()));
-                }
-            }
-            records.push(Value::Object(map));
-        }
-        // println!("records: {:?}", records);
-        let value = Value::Array(records);
-        println!("value: {:?}", value);
-        Ok(value)
+        Ok(IngestPayload::Csv(content))
     } else {
         Err(anyhow!("File must be a JSON or CSV file"))
     }
@@ -60,15 +38,15 @@ pub async fn handle_ingest_cmd(cmd: Vec<&str>, url: &str) -> anyhow::Result<()>
         println!("missing API key for writes"); 
         return Err(anyhow!("Missing API key"));  
     }
-    let mut value: Value = Value::Null;
+    let payload: IngestPayload;
     match cmd[2] {
         "file" => {
             let filename = cmd[3].trim().trim_matches('\'');
-            value = load_from_file(filename).await?;
+            payload = load_from_file(filename).await?;
         }
         "json" => {
             let str = &cmd[3..].concat().to_string();
-            value = serde_json::from_str(str)?;
+            payload = IngestPayload::Json(serde_json::from_str(str)?);
         }
         "help" => { print_ingest_help_message();
             return Ok(())
@@ -80,10 +58,19 @@ pub async fn handle_ingest_cmd(cmd: Vec<&str>, url: &str) -> anyhow::Result<()>
     }
     let client = Client::new();
 
-    println!("ingest value: {:?}", value);
-    let res = client
-        .post(format!("{}/api/sensors/{}/data/ingest?key={}", url, sensor_id, api_key.unwrap()))    
-        .json(&value)
+    let req = client
+        .post(format!("{}/api/sensors/{}/data/ingest?key={}", url, sensor_id, api_key.unwrap()));
+    let req = match payload {
+        IngestPayload::Json(value) => {
+            println!("ingest value: {:?}", value);
+            req.json(&value)
+        }
+        IngestPayload::Csv(content) => {
+            println!("ingest csv: {} lines", content.lines().count());
+            req.header(reqwest::header::CONTENT_TYPE, "text/csv").body(content)
+        }
+    };
+    let res = req
         .send()
         .await?;
     let status = res.status();
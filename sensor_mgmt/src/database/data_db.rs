@@ -1,20 +1,42 @@
+use std::collections::{BTreeMap, HashSet};
 use std::sync::Arc;
 
-use crate::database::models::db_structs::DBOrdering;
+use crate::database::models::db_structs::{DBFill, DBOrdering, DBTimeUnit};
 use crate::database::models::sensor::{ColumnType, FullSensorInfo, SensorColumn};
-use crate::features::cache;
-use crate::features::config::TIMESTAMP_FORMAT;
+use crate::features::{cache, sensor_col_constraints, sensor_data_dedup};
+use crate::features::config::{
+    get_data_export_timeout_secs, get_data_max_page_size, TIMESTAMP_FORMAT,
+};
+use crate::features::sensor_data_export::{DataExportEncoder, DataExportFormat};
+use crate::features::sensor_data_filter::DataFilter;
 use crate::handler::models::requests::{
-    DataLoadRequestParams, SensorDataDeletionParams, SensorDataIngestEntry,
+    AlignedDataFormat, AlignedDataRequest, DataExportRequestParams, DataLoadRequestParams,
+    SensorDataDeletionParams, SensorDataIngestEntry,
 };
 use crate::state::AppState;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use bytes::Bytes;
+use futures_util::{stream, Stream};
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
-use sqlx::{Execute, Postgres, QueryBuilder, Row};
+use sqlx::{Execute, Postgres, QueryBuilder, Row, Transaction};
+use std::time::Duration;
+use tokio::sync::{mpsc, OwnedSemaphorePermit};
+use tokio::time::{timeout_at, Instant};
 
 pub const TIME_COL_NAME: &str = "created_at";
 pub const GROUPED_TIME_COL_NAME: &str = "grouped_time";
 
-/// Deletes entries from the sensor data table in the specified time range.
+/// Name of the database cursor used for streaming data exports
+const EXPORT_CURSOR_NAME: &str = "sensor_data_export";
+/// Rows fetched per round trip from the export cursor
+const EXPORT_FETCH_SIZE: usize = 5000;
+
+/// Identity column of the sensor data tables, breaks ties of equal timestamps when paginating
+pub const ROW_ID_COL_NAME: &str = "row_id";
+
+/// Deletes entries from the sensor data table in the specified time range matching the optional value predicates.
 pub async fn delete_sensor_data(
     sensor_id: uuid::Uuid,
     data: SensorDataDeletionParams,
@@ -31,9 +53,11 @@ pub async fn delete_sensor_data(
     let mut query_builder: QueryBuilder<Postgres> =
         QueryBuilder::new(format!("DELETE FROM {}", sensor.tbl_name));
 
-    query_builder.push(create_timestamp_range_predicate(data.from, data.to, 
-                                                        data.from_inclusive.unwrap_or(true),
-                                                        data.to_inclusive.unwrap_or(true)));
+    let range_predicate = create_timestamp_range_predicate(data.from, data.to, 
+                                                           data.from_inclusive.unwrap_or(true),
+                                                           data.to_inclusive.unwrap_or(true));
+
+    push_data_predicates(&mut query_builder, range_predicate, data.filter.as_ref(), None, &sensor)?;
 
     let query = query_builder.build();
 
@@ -50,12 +74,55 @@ pub async fn delete_sensor_data(
     Ok(())
 }
 
+/// A page of sensor data with the cursor to continue reading after its last row.
+pub struct DataPage {
+    pub data: Value,
+    /// Only present if the page is full and further rows may follow
+    pub next_cursor: Option<String>,
+}
+
+/// Position after the last row of a data page, handed out as opaque base64 string.
+#[derive(Serialize, Deserialize)]
+struct DataCursor {
+    /// Timestamp of the last row
+    t: chrono::NaiveDateTime,
+    /// Row id of the last row
+    r: i64,
+    /// Ordering of the paginated request
+    o: DBOrdering,
+}
+
+impl DataCursor {
+    fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    fn decode(cursor: &str) -> anyhow::Result<DataCursor> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| anyhow::anyhow!("Invalid cursor {}!", cursor))
+    }
+}
+
 /// Fetches data specified by the given predicates in SensorDataRequest.
 pub async fn get_data(
     sensor_id: uuid::Uuid,
     request: DataLoadRequestParams,
     state: &AppState,
 ) -> anyhow::Result<Value> {
+    get_data_page(sensor_id, request, state).await.map(|page| page.data)
+}
+
+/// Fetches data specified by the given predicates in SensorDataRequest.
+/// Non aggregated data ordered by the time column is paginated,
+/// returning a cursor for the next page if the limit is reached.
+pub async fn get_data_page(
+    sensor_id: uuid::Uuid,
+    request: DataLoadRequestParams,
+    state: &AppState,
+) -> anyhow::Result<DataPage> {
     let sensor = cache::request_sensor(sensor_id, &state).await;
 
     if sensor.is_none() {
@@ -64,19 +131,22 @@ pub async fn get_data(
 
     let sensor = sensor.unwrap();
 
+    let time_buckets = TimeBuckets::from_request(&request)?;
+
     // --- Create Select Section ---
 
-    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new("SELECT ");
-    let mut separated = query_builder.separated(", ");
+    let mut select_cols: Vec<String> = Vec::new();
 
     let mut requested_cols: Vec<SensorColumn> = Vec::new();
 
+    // Non aggregated columns of aggregated requests are used for grouping
+    let mut group_keys: Vec<SensorColumn> = Vec::new();
+
     match &request.cols {
         Some(cols) => {
             // Only consider specified data columns / time column
 
             let mut agg_cols = 0;
-            let mut non_agg_cols = 0;
 
             for col in cols.iter() {
                 if col.name == TIME_COL_NAME {
@@ -93,7 +163,8 @@ pub async fn get_data(
                 match &col.aggregation {
                     Some(aggregation) => {
                         let mut res_sens_col = sensor_col.unwrap().clone();
-                        let target_type = aggregation.get_result_type(res_sens_col.val_type);
+                        let source_type = res_sens_col.val_type;
+                        let target_type = aggregation.get_result_type(source_type);
 
                         if target_type == ColumnType::UNKNOWN {
                             anyhow::bail!(
@@ -110,10 +181,9 @@ pub async fn get_data(
                         let type_cast = format!("::{}", res_sens_col.val_type.to_sql_type());
 
                         agg_cols += 1;
-                        separated.push(format!(
-                            "{}({}){} as {}",
-                            aggregation.as_db_op(),
-                            col.name,
+                        select_cols.push(format!(
+                            "{}{} as {}",
+                            aggregation.as_db_expr(&col.name, source_type),
                             type_cast,
                             col.name
                         ));
@@ -121,51 +191,62 @@ pub async fn get_data(
                         requested_cols.push(res_sens_col);
                     }
                     None => {
-                        non_agg_cols += 1;
-                        separated.push(col.name.clone());
+                        select_cols.push(col.name.clone());
                         requested_cols.push(sensor_col.unwrap().to_owned());
+                        group_keys.push(sensor_col.unwrap().to_owned());
                     }
                 };
             }
 
-            if agg_cols > 0 && request.time_grouping.is_none() {
-                anyhow::bail!("Time grouping is missing for aggregated data retrieval!");
+            if agg_cols > 0 && time_buckets.is_none() && group_keys.is_empty() {
+                anyhow::bail!(
+                    "Time grouping or group key columns are missing for aggregated data retrieval!"
+                );
             }
 
-            if request.time_grouping.is_some() && agg_cols == 0 {
+            if time_buckets.is_some() && agg_cols == 0 {
                 anyhow::bail!(
                     "Time grouping was specified but no aggregated data columns where provided!"
                 );
             }
 
-            if agg_cols > 0 && non_agg_cols > 0 {
+            if agg_cols == 0 {
+                group_keys.clear();
+            }
+
+            if let Some(key) = group_keys
+                .iter()
+                .find(|c| c.val_type != ColumnType::STRING && c.val_type != ColumnType::INT)
+            {
                 anyhow::bail!(
-                    "Can't combine aggregated data columns with non aggregated data columns!"
+                    "Column {} with type {:?} can't be combined with aggregated data columns, only STRING and INT columns can be used as group keys!",
+                    key.name,
+                    key.val_type
                 );
             }
 
-            if let Some(time_grouping) = &request.time_grouping {
-                // Modulo-like operation to create time buckets based on grouping interval
-                separated.push(format!(
-                    "to_timestamp(floor(extract(epoch FROM {}) / {}) * {})::timestamp AS {}",
-                    TIME_COL_NAME, time_grouping, time_grouping, GROUPED_TIME_COL_NAME
+            if let Some(time_buckets) = &time_buckets {
+                select_cols.push(format!(
+                    "{} AS {}",
+                    time_buckets.bucket_expression(),
+                    GROUPED_TIME_COL_NAME
                 ));
-            } else {
-                separated.push(TIME_COL_NAME);
+            } else if agg_cols == 0 {
+                select_cols.push(TIME_COL_NAME.to_string());
             }
         }
         None => {
-            if request.time_grouping.is_some() {
+            if time_buckets.is_some() {
                 anyhow::bail!(
                     "Time grouping was specified but no aggregated data columns where provided!"
                 );
             }
 
             // Default, include all data columns + time column
-            separated.push(TIME_COL_NAME);
+            select_cols.push(TIME_COL_NAME.to_string());
 
             for column in sensor.columns.as_slice() {
-                separated.push(column.name.clone());
+                select_cols.push(column.name.clone());
 
                 requested_cols.push(column.to_owned());
             }
@@ -176,6 +257,105 @@ pub async fn get_data(
         anyhow::bail!("No data columns specified to retrieve!");
     }
 
+    // --- Pagination, continues after the cursor ordered by time and row id ---
+
+    let cursor = request.cursor.as_deref().map(DataCursor::decode).transpose()?;
+
+    let ordering = request.ordering.clone().or(cursor.as_ref().map(|c| c.o.clone()));
+
+    let aggregated = request.cols.iter().flatten().any(|c| c.aggregation.is_some());
+    let time_ordered = request.order_col.as_ref().is_none_or(|c| c == TIME_COL_NAME);
+
+    let paginated = !aggregated
+        && time_ordered
+        && matches!(ordering, Some(DBOrdering::ASC) | Some(DBOrdering::DESC));
+
+    if let Some(cursor) = &cursor {
+        if !paginated {
+            anyhow::bail!(
+                "Cursors are only supported for non aggregated data ordered by the time column!"
+            );
+        }
+
+        if ordering.as_ref() != Some(&cursor.o) {
+            anyhow::bail!("Cursor doesn't match the requested ordering {}!", ordering.unwrap());
+        }
+    }
+
+    if paginated {
+        select_cols.push(ROW_ID_COL_NAME.to_string());
+    }
+
+    if request.limit.is_some_and(|l| l < 0) {
+        anyhow::bail!("The limit must not be negative!");
+    }
+
+    // Larger pages have to be read with the returned cursor
+    let limit = match paginated {
+        true => request.limit.map(|l| l.min(get_data_max_page_size(&state.cfg))),
+        false => request.limit,
+    };
+
+    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new("");
+
+    // --- Gap filling, joins the aggregated values onto the full series of time buckets ---
+
+    if let (Some(fill), Some(time_buckets)) = (&request.fill, &time_buckets) {
+        if !group_keys.is_empty() {
+            anyhow::bail!("Gap filling can't be combined with group key columns!");
+        }
+
+        let (Some(from), Some(to)) = (request.from, request.to) else {
+            anyhow::bail!("Gap filling requires the 'from' and 'to' time range!");
+        };
+
+        if *fill == DBFill::ZERO || *fill == DBFill::LINEAR {
+            if let Some(col) = requested_cols
+                .iter()
+                .find(|c| c.val_type != ColumnType::INT && c.val_type != ColumnType::FLOAT)
+            {
+                anyhow::bail!(
+                    "Fill {} is not supported for column {} with type {:?}!",
+                    fill,
+                    col.name,
+                    col.val_type
+                );
+            }
+        }
+
+        let fill_cols: Vec<String> = requested_cols
+            .iter()
+            .map(|c| create_fill_expression(c, fill))
+            .collect();
+
+        // Counts non NULL values up to each bucket to partition the buckets between values
+        let mut fill_counters = vec!["*".to_string()];
+
+        if *fill == DBFill::PREVIOUS || *fill == DBFill::LINEAR {
+            for col in requested_cols.iter() {
+                fill_counters.push(format!(
+                    "count({}) OVER (ORDER BY {}) AS {}_fill_prev",
+                    col.name, GROUPED_TIME_COL_NAME, col.name
+                ));
+                fill_counters.push(format!(
+                    "count({}) OVER (ORDER BY {} DESC) AS {}_fill_next",
+                    col.name, GROUPED_TIME_COL_NAME, col.name
+                ));
+            }
+        }
+
+        query_builder.push(format!(
+            "SELECT {}, {} FROM (SELECT {} FROM {} AS series LEFT JOIN (",
+            GROUPED_TIME_COL_NAME,
+            fill_cols.join(", "),
+            fill_counters.join(", "),
+            time_buckets.series_expression(from, to)
+        ));
+    }
+
+    query_builder.push("SELECT ");
+    query_builder.push(select_cols.join(", "));
+
     // --- Create From Section ---
 
     query_builder.push(" FROM ");
@@ -183,35 +363,64 @@ pub async fn get_data(
 
     // --- Handle predicates based on the request ---
 
-    query_builder.push(create_timestamp_range_predicate(request.from, request.to, 
-                                                        request.from_inclusive.unwrap_or(true), 
-                                                        request.to_inclusive.unwrap_or(true)));
+    let range_predicate = create_timestamp_range_predicate(request.from, request.to, 
+                                                           request.from_inclusive.unwrap_or(true), 
+                                                           request.to_inclusive.unwrap_or(true));
+
+    push_data_predicates(&mut query_builder, range_predicate, request.filter.as_ref(),
+                         cursor.as_ref(), &sensor)?;
 
     // --- Handle grouping ---
 
-    if request.time_grouping.is_some() {
-        query_builder.push(format!(" GROUP BY {}", GROUPED_TIME_COL_NAME));
+    let mut group_by: Vec<String> = group_keys.iter().map(|c| c.name.clone()).collect();
+
+    if time_buckets.is_some() {
+        group_by.insert(0, GROUPED_TIME_COL_NAME.to_string());
+    }
+
+    if !group_by.is_empty() {
+        query_builder.push(format!(" GROUP BY {}", group_by.join(", ")));
+    }
+
+    if request.fill.is_some() {
+        query_builder.push(format!(
+            ") AS data USING ({})) AS filled",
+            GROUPED_TIME_COL_NAME
+        ));
     }
 
     // --- Handle ordering and limit ---
 
-    if request.ordering.is_some() {
-        let mut order_col = request.order_col.unwrap_or(TIME_COL_NAME.to_string());
+    if let Some(ordering) = &ordering {
+        let order_col = request.order_col.unwrap_or(TIME_COL_NAME.to_string());
 
-        // For aggregated queries with use the aggregated time col
-        if request.time_grouping.is_some() && order_col == TIME_COL_NAME {
-            order_col = GROUPED_TIME_COL_NAME.to_string();
-        }
+        // For aggregated queries with use the aggregated time col or the group keys
+        let order_cols = if !group_by.is_empty() && order_col == TIME_COL_NAME {
+            group_by
+        } else if paginated {
+            vec![order_col, ROW_ID_COL_NAME.to_string()]
+        } else {
+            vec![order_col]
+        };
 
-        match request.ordering.unwrap() {
-            DBOrdering::ASC => query_builder.push(format!(" ORDER BY {} ASC", order_col)),
-            DBOrdering::DESC => query_builder.push(format!(" ORDER BY {} DESC", order_col)),
-            DBOrdering::DEFAULT => query_builder.push(""),
+        let direction = match ordering {
+            DBOrdering::ASC => "ASC",
+            DBOrdering::DESC => "DESC",
+            DBOrdering::DEFAULT => "",
         };
+
+        if !direction.is_empty() {
+            let order_by: Vec<String> = order_cols
+                .iter()
+                .map(|c| format!("{} {}", c, direction))
+                .collect();
+
+            query_builder.push(format!(" ORDER BY {}", order_by.join(", ")));
+        }
     }
 
-    if request.limit.is_some() {
-        query_builder.push(format!(" LIMIT {}", request.limit.unwrap()));
+    if let Some(limit) = limit {
+        query_builder.push(format!(" LIMIT {}", limit));
     }
 
     // --- Build and execute query ---
@@ -235,6 +444,18 @@ pub async fn get_data(
 
     let query_result = query_result.unwrap();
 
+    // A full page may be followed by further rows
+    let next_cursor = match (paginated, limit, query_result.last()) {
+        (true, Some(limit), Some(last)) if limit > 0 && query_result.len() == limit as usize => {
+            Some(DataCursor {
+                t: last.try_get(TIME_COL_NAME)?,
+                r: last.try_get(ROW_ID_COL_NAME)?,
+                o: ordering.unwrap(),
+            }.encode())
+        }
+        _ => None,
+    };
+
     let mut array = Vec::<serde_json::Value>::new();
     for row in query_result {
         let mut map = Map::new();
@@ -294,6 +515,49 @@ pub async fn get_data(
                         );
                     }
                 },
+
+                ColumnType::BOOL => match row.try_get::<Option<bool>, _>(col_name.as_str()) {
+                    Ok(val) => {
+                        map.insert(col_name.clone(), serde_json::json!(val));
+                    }
+                    Err(err) => {
+                        eprintln!(
+                            "Error retrieving BOOL value for column '{}': {}",
+                            col_name, err
+                        );
+                    }
+                },
+
+                ColumnType::TIMESTAMP => {
+                    match row.try_get::<Option<chrono::NaiveDateTime>, _>(col_name.as_str()) {
+                        Ok(val) => {
+                            map.insert(
+                                col_name.clone(),
+                                serde_json::json!(
+                                    val.map(|ts| ts.format(TIMESTAMP_FORMAT).to_string())
+                                ),
+                            );
+                        }
+                        Err(err) => {
+                            eprintln!(
+                                "Error retrieving TIMESTAMP value for column '{}': {}",
+                                col_name, err
+                            );
+                        }
+                    }
+                }
+
+                ColumnType::JSON => match row.try_get::<Option<Value>, _>(col_name.as_str()) {
+                    Ok(val) => {
+                        map.insert(col_name.clone(), val.unwrap_or(Value::Null));
+                    }
+                    Err(err) => {
+                        eprintln!(
+                            "Error retrieving JSON value for column '{}': {}",
+                            col_name, err
+                        );
+                    }
+                },
                 _ => {}
             }
         }
@@ -301,7 +565,422 @@ pub async fn get_data(
         array.push(serde_json::json!(map));
     }
 
-    Ok(serde_json::json!(array))
+    Ok(DataPage {
+        data: serde_json::json!(array),
+        next_cursor,
+    })
+}
+
+/// Time buckets of a grouped data request.
+/// Buckets are aligned to the local time of the requested timezone, but returned as UTC timestamps.
+struct TimeBuckets {
+    timezone: Option<&'static str>,
+    interval: BucketInterval,
+}
+
+enum BucketInterval {
+    /// Fixed interval [s] starting at the origin, see date_bin
+    Seconds(u32, chrono::NaiveDateTime),
+    /// Calendar unit shifted by an optional offset [s], see date_trunc
+    Unit(DBTimeUnit, Option<i32>),
+}
+
+impl TimeBuckets {
+    fn from_request(request: &DataLoadRequestParams) -> anyhow::Result<Option<TimeBuckets>> {
+        let timezone = match &request.timezone {
+            Some(tz) => match tz.parse::<chrono_tz::Tz>() {
+                Ok(tz) => Some(tz.name()),
+                Err(_) => anyhow::bail!("Unknown timezone {}!", tz),
+            },
+            None => None,
+        };
+
+        let interval = match (request.time_grouping, request.time_unit) {
+            (Some(_), Some(_)) => anyhow::bail!("Time grouping and time unit can't be combined!"),
+            (Some(0), None) => anyhow::bail!("Time grouping must be larger than 0!"),
+            (Some(time_grouping), None) => {
+                if request.time_offset.is_some() {
+                    anyhow::bail!(
+                        "Time offset is only supported for time units, use the time origin instead!"
+                    );
+                }
+
+                let origin = request
+                    .time_origin
+                    .unwrap_or(chrono::DateTime::UNIX_EPOCH.naive_utc());
+
+                BucketInterval::Seconds(time_grouping, origin)
+            }
+            (None, Some(time_unit)) => {
+                if request.time_origin.is_some() {
+                    anyhow::bail!(
+                        "Time origin is only supported for time groupings, use the time offset instead!"
+                    );
+                }
+
+                BucketInterval::Unit(time_unit, request.time_offset)
+            }
+            (None, None) => {
+                if timezone.is_some()
+                    || request.time_origin.is_some()
+                    || request.time_offset.is_some()
+                    || request.fill.is_some()
+                {
+                    anyhow::bail!(
+                        "Timezone, time origin, time offset and fill require a time grouping or time unit!"
+                    );
+                }
+
+                return Ok(None);
+            }
+        };
+
+        Ok(Some(TimeBuckets { timezone, interval }))
+    }
+
+    fn to_local(&self, time: &str) -> String {
+        match self.timezone {
+            Some(tz) => format!("({} AT TIME ZONE 'UTC' AT TIME ZONE '{}')", time, tz),
+            None => time.to_string(),
+        }
+    }
+
+    fn to_utc(&self, time: &str) -> String {
+        match self.timezone {
+            Some(tz) => format!("({} AT TIME ZONE '{}' AT TIME ZONE 'UTC')", time, tz),
+            None => time.to_string(),
+        }
+    }
+
+    /// Start of the bucket containing the given local time
+    fn bucket_start(&self, local_time: &str) -> String {
+        match &self.interval {
+            BucketInterval::Seconds(secs, origin) => format!(
+                "date_bin('{} seconds', {}, '{}')",
+                secs,
+                local_time,
+                origin.format(TIMESTAMP_FORMAT)
+            ),
+            BucketInterval::Unit(unit, Some(offset)) => format!(
+                "date_trunc('{}', {} - interval '{} seconds') + interval '{} seconds'",
+                unit.as_db_unit(),
+                local_time,
+                offset,
+                offset
+            ),
+            BucketInterval::Unit(unit, None) => {
+                format!("date_trunc('{}', {})", unit.as_db_unit(), local_time)
+            }
+        }
+    }
+
+    /// Expression assigning the time column to its bucket.
+    fn bucket_expression(&self) -> String {
+        self.to_utc(&self.bucket_start(&self.to_local(TIME_COL_NAME)))
+    }
+
+    /// Subquery generating all buckets between from and to as grouped time column.
+    fn series_expression(&self, from: chrono::NaiveDateTime, to: chrono::NaiveDateTime) -> String {
+        let from = self.to_local(&format!("'{}'::timestamp", from.format(TIMESTAMP_FORMAT)));
+        let to = self.to_local(&format!("'{}'::timestamp", to.format(TIMESTAMP_FORMAT)));
+
+        let step = match &self.interval {
+            BucketInterval::Seconds(secs, _) => format!("{} seconds", secs),
+            BucketInterval::Unit(unit, _) => unit.as_db_interval().to_string(),
+        };
+
+        format!(
+            "(SELECT {} AS {} FROM generate_series({}, {}, interval '{}') AS bucket)",
+            self.to_utc("bucket"),
+            GROUPED_TIME_COL_NAME,
+            self.bucket_start(&from),
+            to,
+            step
+        )
+    }
+}
+
+/// Exports the raw data of the sensor in the requested format.
+/// Rows are fetched in chunks from a database cursor and encoded on demand,
+/// so the export is never kept in memory as a whole.
+pub async fn export_data(
+    sensor_id: uuid::Uuid,
+    request: DataExportRequestParams,
+    format: DataExportFormat,
+    permit: OwnedSemaphorePermit,
+    state: &AppState,
+) -> anyhow::Result<impl Stream<Item = anyhow::Result<Bytes>>> {
+    let sensor = cache::request_sensor(sensor_id, state).await;
+
+    if sensor.is_none() {
+        anyhow::bail!("Sensor with id {} not found!", sensor_id);
+    }
+
+    let sensor = sensor.unwrap();
+
+    let columns: Vec<SensorColumn> = match &request.cols {
+        Some(cols) => cols
+            .iter()
+            .map(|col| {
+                if col.aggregation.is_some() {
+                    anyhow::bail!("Aggregated column {} can't be exported!", col.name);
+                }
+
+                sensor.columns.iter().find(|c| c.name == col.name).cloned()
+                    .ok_or_else(|| anyhow::anyhow!("Data column {} does not exist!", col.name))
+            })
+            .collect::<anyhow::Result<_>>()?,
+        None => sensor.columns.clone(),
+    };
+
+    let mut select_cols = vec![TIME_COL_NAME.to_string()];
+    select_cols.extend(columns.iter().map(|c| c.name.clone()));
+
+    let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(format!(
+        "DECLARE {} NO SCROLL CURSOR FOR SELECT {} FROM {}",
+        EXPORT_CURSOR_NAME,
+        select_cols.join(", "),
+        sensor.tbl_name
+    ));
+
+    let range_predicate = create_timestamp_range_predicate(request.from, request.to,
+                                                           request.from_inclusive.unwrap_or(true),
+                                                           request.to_inclusive.unwrap_or(true));
+
+    push_data_predicates(&mut query_builder, range_predicate, request.filter.as_ref(), None,
+                         &sensor)?;
+
+    let direction = match request.ordering {
+        Some(DBOrdering::DESC) => "DESC",
+        _ => "ASC",
+    };
+
+    query_builder.push(format!(" ORDER BY {} {}", TIME_COL_NAME, direction));
+
+    let encoder = DataExportEncoder::new(format, columns)?;
+
+    let timeout_secs = get_data_export_timeout_secs(&state.cfg);
+    let deadline = Instant::now() + Duration::from_secs(timeout_secs);
+
+    // Cursors only exist within a transaction, which is kept open until the export is finished
+    let mut tx = state.db.begin().await?;
+
+    sqlx::query(&format!("SET LOCAL statement_timeout = {}", timeout_secs * 1000))
+        .execute(&mut *tx)
+        .await?;
+
+    let query = query_builder.build();
+    log::debug!("query: {}", query.sql());
+
+    if let Err(err) = query.execute(&mut *tx).await {
+        println!("{:?}", err);
+        anyhow::bail!("Couldn't export sensor data with the specified predicates!");
+    }
+
+    let (sender, mut receiver) = mpsc::channel::<anyhow::Result<Bytes>>(1);
+
+    // The export runs in its own task, so the transaction and the permit are released at the deadline
+    // even if the client stops reading the response
+    tokio::spawn(async move {
+        let _permit = permit;
+
+        let err = match timeout_at(deadline, export_chunks(tx, encoder, &sender)).await {
+            Ok(Ok(())) => return,
+            Ok(Err(err)) => err,
+            Err(_) => anyhow::anyhow!("Export of sensor {} exceeded the time limit of {} seconds!",
+                                      sensor_id, timeout_secs),
+        };
+
+        log::warn!("{:?}", err);
+
+        let _ = sender.try_send(Err(err));
+    });
+
+    Ok(stream::poll_fn(move |cx| receiver.poll_recv(cx)))
+}
+
+/// Fetches the rows of the export cursor and sends them encoded until the cursor is exhausted.
+async fn export_chunks(
+    mut tx: Transaction<'static, Postgres>,
+    mut encoder: DataExportEncoder,
+    sender: &mpsc::Sender<anyhow::Result<Bytes>>,
+) -> anyhow::Result<()> {
+    let fetch = format!("FETCH FORWARD {} FROM {}", EXPORT_FETCH_SIZE, EXPORT_CURSOR_NAME);
+
+    loop {
+        let rows = sqlx::query(&fetch).fetch_all(&mut *tx).await?;
+
+        if rows.is_empty() {
+            tx.commit().await?;
+
+            let _ = sender.send(Ok(Bytes::from(encoder.finish()?))).await;
+
+            return Ok(());
+        }
+
+        // The response was dropped, e.g. because the client disconnected
+        if sender.send(Ok(Bytes::from(encoder.encode(&rows)?))).await.is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// Fetches the data of multiple sensors aligned on the common time buckets of the request.
+/// Access to all sensors must be verified beforehand.
+pub async fn get_aligned_data(
+    request: AlignedDataRequest,
+    state: &AppState,
+) -> anyhow::Result<Value> {
+    if request.sensors.is_empty() {
+        anyhow::bail!("No sensors specified to retrieve data from!");
+    }
+
+    if request.time_grouping.is_none() && request.time_unit.is_none() {
+        anyhow::bail!("Time grouping or time unit is required to align the sensor data!");
+    }
+
+    // The first buckets of each sensor contain the first buckets of the aligned result
+    let ordering = match request.ordering {
+        Some(DBOrdering::DESC) => DBOrdering::DESC,
+        _ => DBOrdering::ASC,
+    };
+
+    if request.limit.is_some_and(|l| l < 0) {
+        anyhow::bail!("The limit of time buckets must not be negative!");
+    }
+
+    let limit = request.limit;
+
+    let mut labels: HashSet<String> = HashSet::new();
+    let mut wide_cols: Vec<String> = Vec::new();
+
+    // Result rows (sensor label, values) of all sensors per time bucket, ordered by time
+    type BucketRows = Vec<(String, Map<String, Value>)>;
+    let mut buckets: BTreeMap<String, BucketRows> = BTreeMap::new();
+
+    for sensor in request.sensors.iter() {
+        if let Some(alias) = &sensor.alias {
+            if !SensorColumn::is_valid_name(alias) {
+                anyhow::bail!("Invalid sensor alias {}!", alias);
+            }
+        }
+
+        let label = sensor.alias.clone().unwrap_or(sensor.id.to_string());
+
+        if !labels.insert(label.clone()) {
+            anyhow::bail!("Sensor {} is specified multiple times!", label);
+        }
+
+        if let Some(col) = sensor.cols.iter().find(|c| c.aggregation.is_none()) {
+            anyhow::bail!("Column {} of sensor {} requires an aggregation!", col.name, label);
+        }
+
+        wide_cols.extend(sensor.cols.iter().map(|c| format!("{}.{}", label, c.name)));
+
+        let params = DataLoadRequestParams {
+            from: request.from,
+            to: request.to,
+            from_inclusive: request.from_inclusive,
+            to_inclusive: request.to_inclusive,
+            filter: sensor.filter.clone(),
+            cols: Some(sensor.cols.clone()),
+            time_grouping: request.time_grouping,
+            time_unit: request.time_unit,
+            timezone: request.timezone.clone(),
+            time_origin: request.time_origin,
+            time_offset: request.time_offset,
+            fill: request.fill.clone(),
+            ordering: Some(ordering.clone()),
+            limit,
+            ..Default::default()
+        };
+
+        let data = get_data(sensor.id, params, state).await?;
+
+        for row in data.as_array().into_iter().flatten() {
+            let mut row = row.as_object().cloned().unwrap_or_default();
+
+            if let Some(Value::String(time)) = row.remove(GROUPED_TIME_COL_NAME) {
+                buckets.entry(time).or_default().push((label.clone(), row));
+            }
+        }
+    }
+
+    let mut ordered: Vec<_> = buckets.into_iter().collect();
+
+    if ordering == DBOrdering::DESC {
+        ordered.reverse();
+    }
+
+    let mut array = Vec::<Value>::new();
+
+    for (time, rows) in ordered.into_iter().take(limit.map_or(usize::MAX, |l| l as usize)) {
+        match request.format.clone().unwrap_or(AlignedDataFormat::WIDE) {
+            AlignedDataFormat::WIDE => {
+                // Columns of sensors without values in the time bucket are NULL
+                let mut map: Map<String, Value> =
+                    wide_cols.iter().map(|c| (c.clone(), Value::Null)).collect();
+
+                map.insert(GROUPED_TIME_COL_NAME.to_string(), Value::String(time));
+
+                for (label, row) in rows {
+                    for (col, val) in row {
+                        map.insert(format!("{}.{}", label, col), val);
+                    }
+                }
+
+                array.push(Value::Object(map));
+            }
+            AlignedDataFormat::LONG => {
+                for (label, mut row) in rows {
+                    row.insert(GROUPED_TIME_COL_NAME.to_string(), Value::String(time.clone()));
+                    row.insert("sensor".to_string(), Value::String(label));
+
+                    array.push(Value::Object(row));
+                }
+            }
+        }
+    }
+
+    Ok(Value::Array(array))
+}
+
+/// Creates the expression filling missing values of the column in the gap filled result.
+fn create_fill_expression(col: &SensorColumn, fill: &DBFill) -> String {
+    let name = &col.name;
+
+    // Closest non NULL values before and after the current bucket, see fill_prev/fill_next counters
+    let prev = |val: &str| {
+        format!(
+            "first_value({}) OVER (PARTITION BY {}_fill_prev ORDER BY {})",
+            val, name, GROUPED_TIME_COL_NAME
+        )
+    };
+    let next = |val: &str| {
+        format!(
+            "first_value({}) OVER (PARTITION BY {}_fill_next ORDER BY {} DESC)",
+            val, name, GROUPED_TIME_COL_NAME
+        )
+    };
+
+    match fill {
+        DBFill::NULL => name.clone(),
+        DBFill::ZERO => format!("COALESCE({}, 0) AS {}", name, name),
+        DBFill::PREVIOUS => format!("{} AS {}", prev(name), name),
+        DBFill::LINEAR => format!(
+            "COALESCE({}, ({} + ({} - {}) * extract(epoch FROM {} - {}) / NULLIF(extract(epoch FROM {} - {}), 0))::{}) AS {}",
+            name,
+            prev(name),
+            next(name),
+            prev(name),
+            GROUPED_TIME_COL_NAME,
+            prev(GROUPED_TIME_COL_NAME),
+            next(GROUPED_TIME_COL_NAME),
+            prev(GROUPED_TIME_COL_NAME),
+            col.val_type.to_sql_type(),
+            name
+        ),
+    }
 }
 
 fn create_timestamp_range_predicate(
@@ -347,17 +1026,63 @@ fn create_timestamp_range_predicate(
     query_builder.into_sql()
 }
 
+/// Appends the time range predicate and the optional value predicates as WHERE clause to the query.
+fn push_data_predicates(
+    query_builder: &mut QueryBuilder<Postgres>,
+    range_predicate: String,
+    filter: Option<&DataFilter>,
+    cursor: Option<&DataCursor>,
+    sensor: &FullSensorInfo,
+) -> anyhow::Result<()> {
+    let mut has_predicate = !range_predicate.is_empty();
+
+    query_builder.push(range_predicate);
+
+    if let Some(filter) = filter {
+        query_builder.push(if has_predicate { " AND " } else { " WHERE " });
+        filter.push_sql(query_builder, sensor)?;
+        has_predicate = true;
+    }
+
+    if let Some(cursor) = cursor {
+        query_builder.push(if has_predicate { " AND " } else { " WHERE " });
+        query_builder.push(format!("({}, {}) ", TIME_COL_NAME, ROW_ID_COL_NAME));
+        query_builder.push(match cursor.o {
+            DBOrdering::DESC => "< (",
+            _ => "> (",
+        });
+        query_builder.push_bind(cursor.t);
+        query_builder.push(", ");
+        query_builder.push_bind(cursor.r);
+        query_builder.push(")");
+    }
+
+    Ok(())
+}
+
 /// Add sensor measurement data given as JSON data to the sensor data table.
 /// The sensor is identified by the 'sensor_id' field, the columns and their values
 /// are also given as fields the JSON object, e.g. "column1": value.
 ///
 /// Insert the sensor data given by the JSON object to the given table.
 /// If any of the entries to insert does not contain any valid values, the whole request will be rejected.
+/// Entries violating the column types or constraints reject the whole request with a ConstraintViolations error.
 pub async fn add_sensor_data(
     sensor: Arc<FullSensorInfo>,
     data: &Vec<SensorDataIngestEntry>,
     state: AppState,
 ) -> anyhow::Result<()> {
+    sensor_col_constraints::validate_entries(&sensor, data)?;
+
+    let mut tx = state.db.begin().await?;
+
+    // Duplicated entries are dropped, if the sensor detects them
+    let data = sensor_data_dedup::filter_duplicates(&sensor, data, &mut tx).await?;
+    if data.is_empty() {
+        let _ = tx.commit().await;
+        return Ok(());
+    }
+
     // INSERT INTO sensor.tbl_name () VALUES ()
     let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new("INSERT INTO ");
     query_builder.push(sensor.tbl_name.clone() + " (");
@@ -395,7 +1120,7 @@ pub async fn add_sensor_data(
             if prov_col_val.is_some() {
                 valid_cols += 1;
 
-                // Column is valid, parse provided value or NULl if invalid
+                // Column is valid, values are type checked by the validation of the entries
                 match &col.val_type {
                     ColumnType::INT => {
                         vals_sep.push_bind(prov_col_val.unwrap().as_i64());
@@ -406,6 +1131,23 @@ pub async fn add_sensor_data(
                     ColumnType::STRING => {
                         vals_sep.push_bind(prov_col_val.unwrap().as_str());
                     }
+                    ColumnType::BOOL => {
+                        vals_sep.push_bind(prov_col_val.unwrap().as_bool());
+                    }
+                    ColumnType::TIMESTAMP => {
+                        // Timestamps are provided as ISO 8601 strings, same as the entry timestamp
+                        vals_sep.push_bind(
+                            serde_json::from_value::<chrono::NaiveDateTime>(
+                                prov_col_val.unwrap().to_owned(),
+                            )
+                            .ok(),
+                        );
+                    }
+                    ColumnType::JSON => {
+                        // Explicit JSON null values are stored as NULL
+                        let val = prov_col_val.unwrap();
+                        vals_sep.push_bind((!val.is_null()).then(|| val.to_owned()));
+                    }
                     _ => {}
                 }
             } else {
@@ -419,6 +1161,15 @@ pub async fn add_sensor_data(
                     ColumnType::STRING => {
                         vals_sep.push_bind(None::<String>);
                     }
+                    ColumnType::BOOL => {
+                        vals_sep.push_bind(None::<bool>);
+                    }
+                    ColumnType::TIMESTAMP => {
+                        vals_sep.push_bind(None::<chrono::NaiveDateTime>);
+                    }
+                    ColumnType::JSON => {
+                        vals_sep.push_bind(None::<Value>);
+                    }
                     _ => {}
                 }
             }
@@ -443,12 +1194,14 @@ pub async fn add_sensor_data(
         }
     }
 
+    if let Some(on_conflict) = sensor_data_dedup::on_conflict_clause(&sensor) {
+        query_builder.push(on_conflict);
+    }
+
     // Execute query
 
     let query = query_builder.build();
 
-    let mut tx = state.db.begin().await?;
-
     let res = query
         .execute(&mut *tx)
         .await
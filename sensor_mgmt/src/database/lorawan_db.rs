@@ -0,0 +1,78 @@
+use sqlx::PgPool;
+use uuid::Uuid;
+
+/// Maps the end device to the sensor, fails if the device is already mapped to any sensor.
+pub async fn register_device(sensor_id: Uuid, dev_eui: &str, db: &PgPool) -> anyhow::Result<()> {
+    sqlx::query("INSERT INTO lorawan_device(dev_eui, sensor_id) VALUES($1, $2)")
+        .bind(dev_eui)
+        .bind(sensor_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Moves the end device to another sensor, fails if the device isn't mapped to the current sensor.
+pub async fn move_device(
+    current_sensor_id: Uuid,
+    sensor_id: Uuid,
+    dev_eui: &str,
+    db: &PgPool,
+) -> anyhow::Result<()> {
+    let res = sqlx::query(
+        "UPDATE lorawan_device SET sensor_id = $1 WHERE dev_eui = $2 AND sensor_id = $3",
+    )
+    .bind(sensor_id)
+    .bind(dev_eui)
+    .bind(current_sensor_id)
+    .execute(db)
+    .await?;
+
+    if res.rows_affected() == 0 {
+        anyhow::bail!(
+            "No device {} registered for sensor {}!",
+            dev_eui,
+            current_sensor_id
+        );
+    }
+
+    Ok(())
+}
+
+/// Removes the mapping of the end device, fails if the device isn't mapped to the sensor.
+pub async fn delete_device(sensor_id: Uuid, dev_eui: &str, db: &PgPool) -> anyhow::Result<()> {
+    let res = sqlx::query("DELETE FROM lorawan_device WHERE dev_eui = $1 AND sensor_id = $2")
+        .bind(dev_eui)
+        .bind(sensor_id)
+        .execute(db)
+        .await?;
+
+    if res.rows_affected() == 0 {
+        anyhow::bail!("No device {} registered for sensor {}!", dev_eui, sensor_id);
+    }
+
+    Ok(())
+}
+
+/// Retrieves the DevEUIs of all end devices mapped to the sensor.
+pub async fn list_devices(sensor_id: Uuid, db: &PgPool) -> anyhow::Result<Vec<String>> {
+    let res = sqlx::query_scalar::<_, String>(
+        "SELECT dev_eui FROM lorawan_device WHERE sensor_id = $1 ORDER BY dev_eui",
+    )
+    .bind(sensor_id)
+    .fetch_all(db)
+    .await?;
+
+    Ok(res)
+}
+
+/// Retrieves the sensor the end device is mapped to.
+pub async fn get_sensor_id(dev_eui: &str, db: &PgPool) -> anyhow::Result<Option<Uuid>> {
+    let res =
+        sqlx::query_scalar::<_, Uuid>("SELECT sensor_id FROM lorawan_device WHERE dev_eui = $1")
+            .bind(dev_eui)
+            .fetch_optional(db)
+            .await?;
+
+    Ok(res)
+}
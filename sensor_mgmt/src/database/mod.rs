@@ -2,7 +2,10 @@ pub mod data_chain_db;
 pub mod data_db;
 pub mod data_transformer_db;
 pub mod event_handler_db;
+pub mod lorawan_db;
+pub mod modbus_source_db;
 pub mod models;
+pub mod pull_source_db;
 pub mod role_db;
 pub mod sensor_db;
 pub mod sensor_events_db;
@@ -0,0 +1,69 @@
+use crate::database::models::modbus_source::{ModbusSource, ModbusSourceInternal};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+pub async fn load(sensor_id: Uuid, db: &PgPool) -> anyhow::Result<Option<ModbusSource>> {
+    let row = sqlx::query("SELECT * FROM sensor_modbus_source WHERE sensor_id = $1")
+        .bind(sensor_id)
+        .fetch_optional(db)
+        .await?;
+
+    match row {
+        Some(r) => Ok(Some(from_row(&r)?.source)),
+        None => Ok(None),
+    }
+}
+
+/// Retrieves the modbus sources of all sensors.
+pub async fn load_all(db: &PgPool) -> anyhow::Result<Vec<ModbusSourceInternal>> {
+    let rows = sqlx::query("SELECT * FROM sensor_modbus_source")
+        .fetch_all(db)
+        .await?;
+
+    rows.iter().map(from_row).collect()
+}
+
+/// Sets the modbus source of the sensor, replacing an existing one.
+pub async fn set(sensor_id: Uuid, source: &ModbusSource, db: &PgPool) -> anyhow::Result<()> {
+    let registers = serde_json::to_string(&source.registers)?;
+
+    sqlx::query(
+        "INSERT INTO sensor_modbus_source(sensor_id, host, port, interval_secs, registers) VALUES($1, $2, $3, $4, $5)
+         ON CONFLICT (sensor_id) DO UPDATE SET host = EXCLUDED.host, port = EXCLUDED.port,
+         interval_secs = EXCLUDED.interval_secs, registers = EXCLUDED.registers",
+    )
+    .bind(sensor_id)
+    .bind(&source.host)
+    .bind(source.port as i32)
+    .bind(source.interval_secs as i32)
+    .bind(registers)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete(sensor_id: Uuid, db: &PgPool) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM sensor_modbus_source WHERE sensor_id = $1")
+        .bind(sensor_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+fn from_row(row: &sqlx::postgres::PgRow) -> anyhow::Result<ModbusSourceInternal> {
+    let port: i32 = row.try_get("port")?;
+    let interval_secs: i32 = row.try_get("interval_secs")?;
+    let registers: String = row.try_get("registers")?;
+
+    Ok(ModbusSourceInternal {
+        sensor_id: row.try_get("sensor_id")?,
+        source: ModbusSource {
+            host: row.try_get("host")?,
+            port: port as u16,
+            interval_secs: interval_secs as u32,
+            registers: serde_json::from_str(&registers)?,
+        },
+    })
+}
@@ -3,6 +3,7 @@ use serde_derive::{Deserialize, Serialize};
 use sqlx::{Decode, Type};
 use sqlx::postgres::{PgTypeInfo, PgValueRef};
 use utoipa::ToSchema;
+use crate::database::data_db::TIME_COL_NAME;
 use crate::database::models::sensor::ColumnType;
 
 /// Modes for database operations - used for access control to sensor measurements.
@@ -57,7 +58,7 @@ impl DBOperation {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Display)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Display, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum DBOrdering {
     DEFAULT = 0,
@@ -65,14 +66,74 @@ pub enum DBOrdering {
     DESC = 2,
 }
 
+/// Calendar units for grouping data values, buckets are aligned like date_trunc (WEEK = ISO week).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, Display, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DBTimeUnit {
+    MINUTE = 0,
+    HOUR = 1,
+    DAY = 2,
+    WEEK = 3,
+    MONTH = 4,
+    QUARTER = 5,
+    YEAR = 6,
+}
+
+impl DBTimeUnit {
+    pub fn as_db_unit(&self) -> &'static str {
+        match self {
+            DBTimeUnit::MINUTE => "minute",
+            DBTimeUnit::HOUR => "hour",
+            DBTimeUnit::DAY => "day",
+            DBTimeUnit::WEEK => "week",
+            DBTimeUnit::MONTH => "month",
+            DBTimeUnit::QUARTER => "quarter",
+            DBTimeUnit::YEAR => "year",
+        }
+    }
+
+    pub fn as_db_interval(&self) -> &'static str {
+        match self {
+            DBTimeUnit::MINUTE => "1 minute",
+            DBTimeUnit::HOUR => "1 hour",
+            DBTimeUnit::DAY => "1 day",
+            DBTimeUnit::WEEK => "1 week",
+            DBTimeUnit::MONTH => "1 month",
+            DBTimeUnit::QUARTER => "3 months",
+            DBTimeUnit::YEAR => "1 year",
+        }
+    }
+}
+
+/// Modes to fill time buckets without data values of grouped data requests.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Display, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DBFill {
+    NULL = 0,     // keep missing values as NULL
+    ZERO = 1,     // use 0 for missing values
+    PREVIOUS = 2, // carry the last value forward (LOCF)
+    LINEAR = 3,   // linear interpolation between the surrounding values
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Display)]
 #[serde(rename_all = "UPPERCASE")]
+#[allow(non_camel_case_types)]
 pub enum DBAggregation {
     MIN = 0,
     MAX = 1,
     SUM = 2,
     AVG = 3,
     COUNT = 4,
+    BOOL_AND = 5,
+    BOOL_OR = 6,
+    P95 = 7,
+    P98 = 8,
+    MEDIAN = 9,
+    STDDEV = 10,
+    VARIANCE = 11,
+    FIRST = 12,
+    LAST = 13,
+    COUNT_DISTINCT = 14,
 }
 
 impl From<String> for DBAggregation {
@@ -83,7 +144,17 @@ impl From<String> for DBAggregation {
             "SUM" => DBAggregation::SUM,
             "AVG" => DBAggregation::AVG,
             "COUNT" => DBAggregation::COUNT,
-            
+            "BOOL_AND" => DBAggregation::BOOL_AND,
+            "BOOL_OR" => DBAggregation::BOOL_OR,
+            "P95" => DBAggregation::P95,
+            "P98" => DBAggregation::P98,
+            "MEDIAN" => DBAggregation::MEDIAN,
+            "STDDEV" => DBAggregation::STDDEV,
+            "VARIANCE" => DBAggregation::VARIANCE,
+            "FIRST" => DBAggregation::FIRST,
+            "LAST" => DBAggregation::LAST,
+            "COUNT_DISTINCT" => DBAggregation::COUNT_DISTINCT,
+
             _ => panic!("Invalid value for DBAggregation: {}", s),
         }
     }
@@ -97,13 +168,29 @@ impl DBAggregation {
             DBAggregation::SUM => "SUM",
             DBAggregation::AVG => "AVG",
             DBAggregation::COUNT => "COUNT",
+            DBAggregation::BOOL_AND => "BOOL_AND",
+            DBAggregation::BOOL_OR => "BOOL_OR",
+            DBAggregation::P95 => "P95",
+            DBAggregation::P98 => "P98",
+            DBAggregation::MEDIAN => "MEDIAN",
+            DBAggregation::STDDEV => "STDDEV",
+            DBAggregation::VARIANCE => "VARIANCE",
+            DBAggregation::FIRST => "FIRST",
+            DBAggregation::LAST => "LAST",
+            DBAggregation::COUNT_DISTINCT => "COUNT_DISTINCT",
         }
     }
     
     pub fn get_result_type(&self, in_type: ColumnType) -> ColumnType {
         match self {
-            DBAggregation::MIN => in_type,
-            DBAggregation::MAX => in_type,
+            // Not defined on JSON values, on BOOL columns mapped to bool_and/bool_or
+            DBAggregation::MIN | DBAggregation::MAX => {
+                if in_type == ColumnType::JSON {
+                    return ColumnType::UNKNOWN;
+                }
+
+                in_type
+            }
             DBAggregation::SUM => {
                 // Only valid for numeric types
                 if in_type == ColumnType::INT || in_type == ColumnType::FLOAT {
@@ -112,19 +199,60 @@ impl DBAggregation {
                 
                 ColumnType::UNKNOWN
             },
-            DBAggregation::AVG => {
-                // Only valid for numeric types
+            DBAggregation::AVG
+            | DBAggregation::P95
+            | DBAggregation::P98
+            | DBAggregation::MEDIAN
+            | DBAggregation::STDDEV
+            | DBAggregation::VARIANCE => {
+                // Only valid for numeric types, percentiles are interpolated
                 if in_type == ColumnType::INT || in_type == ColumnType::FLOAT {
                     return ColumnType::FLOAT;
                 }
 
                 ColumnType::UNKNOWN
             },
-            DBAggregation::COUNT => ColumnType::INT,
+            DBAggregation::COUNT | DBAggregation::COUNT_DISTINCT => ColumnType::INT,
+            DBAggregation::FIRST | DBAggregation::LAST => in_type,
+            DBAggregation::BOOL_AND | DBAggregation::BOOL_OR => {
+                // Only valid for boolean types
+                if in_type == ColumnType::BOOL {
+                    return in_type;
+                }
+
+                ColumnType::UNKNOWN
+            }
         }
     }
     
-    pub fn as_db_op(&self) -> &str {
-        self.as_str()
+    /// Returns the db aggregate expression for the aggregation on a column of the given type.
+    pub fn as_db_expr(&self, col: &str, in_type: ColumnType) -> String {
+        match (self, in_type) {
+            // Postgres has no MIN/MAX for booleans, false < true holds for bool_and/bool_or
+            (DBAggregation::MIN, ColumnType::BOOL) => format!("BOOL_AND({})", col),
+            (DBAggregation::MAX, ColumnType::BOOL) => format!("BOOL_OR({})", col),
+            (DBAggregation::P95 | DBAggregation::P98 | DBAggregation::MEDIAN, _) => {
+                let fraction = match self {
+                    DBAggregation::P95 => 0.95,
+                    DBAggregation::P98 => 0.98,
+                    _ => 0.5,
+                };
+
+                format!("percentile_cont({}) WITHIN GROUP (ORDER BY {})", fraction, col)
+            }
+            (DBAggregation::STDDEV, _) => format!("stddev_samp({})", col),
+            (DBAggregation::VARIANCE, _) => format!("var_samp({})", col),
+            // First/last non NULL value by time
+            (DBAggregation::FIRST, _) => format!(
+                "(array_agg({} ORDER BY {} ASC) FILTER (WHERE {} IS NOT NULL))[1]",
+                col, TIME_COL_NAME, col
+            ),
+            (DBAggregation::LAST, _) => format!(
+                "(array_agg({} ORDER BY {} DESC) FILTER (WHERE {} IS NOT NULL))[1]",
+                col, TIME_COL_NAME, col
+            ),
+            (DBAggregation::COUNT_DISTINCT, _) => format!("COUNT(DISTINCT {})", col),
+            _ => format!("{}({})", self.as_str(), col),
+        }
     }
 }
\ No newline at end of file
@@ -3,6 +3,8 @@ pub mod data_chain;
 pub mod data_transformer;
 pub mod db_structs;
 pub mod events;
+pub mod modbus_source;
+pub mod pull_source;
 pub mod role;
 pub mod sensor;
 pub mod sensor_perm;
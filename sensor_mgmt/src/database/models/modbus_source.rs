@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// Modbus TCP device periodically polled for the data of a sensor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ModbusSource {
+    /// Host of the device or gateway
+    #[schema(example = "192.168.1.20")]
+    pub host: String,
+    /// TCP port of the device
+    #[serde(default = "default_port")]
+    #[schema(example = 502)]
+    pub port: u16,
+    /// Seconds between two polls
+    #[schema(example = 60)]
+    pub interval_secs: u32,
+    /// The registers read on every poll, each mapped to a column of the sensor
+    pub registers: Vec<ModbusRegister>,
+}
+
+/// Register (or consecutive registers for 32 bit values) mapped to a sensor column.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct ModbusRegister {
+    /// The INT or FLOAT column receiving the value
+    #[schema(example = "energy")]
+    pub column: String,
+    /// Unit id of the device behind the gateway
+    #[serde(default = "default_unit_id")]
+    #[schema(example = 1)]
+    pub unit_id: u8,
+    /// Zero based address of the (first) register
+    #[schema(example = 100)]
+    pub address: u16,
+    #[serde(default)]
+    pub register_type: ModbusRegisterType,
+    pub data_type: ModbusDataType,
+    /// Order of the registers of 32 bit values
+    #[serde(default)]
+    pub word_order: ModbusWordOrder,
+    /// Factor applied to the raw value: value = raw * scale + offset
+    #[serde(default = "default_scale")]
+    #[schema(example = 0.1)]
+    pub scale: f64,
+    /// Offset added to the scaled value
+    #[serde(default)]
+    pub offset: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModbusRegisterType {
+    /// Read with function code 0x03
+    #[default]
+    Holding,
+    /// Read with function code 0x04
+    Input,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModbusDataType {
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl ModbusDataType {
+    /// Number of 16 bit registers holding a value of the type
+    pub fn register_count(&self) -> u16 {
+        match self {
+            ModbusDataType::U16 | ModbusDataType::I16 => 1,
+            ModbusDataType::U32 | ModbusDataType::I32 | ModbusDataType::F32 => 2,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ModbusWordOrder {
+    /// The first register holds the high word
+    #[default]
+    Big,
+    /// The first register holds the low word
+    Little,
+}
+
+fn default_port() -> u16 {
+    502
+}
+
+fn default_unit_id() -> u8 {
+    1
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModbusSourceInternal {
+    pub sensor_id: Uuid,
+    pub source: ModbusSource,
+}
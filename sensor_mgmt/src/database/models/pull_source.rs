@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// HTTP endpoint periodically polled for the data of a sensor.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct PullSource {
+    /// The polled url, http or https
+    #[schema(example = "https://api.example.com/weather?station=42")]
+    pub url: String,
+    /// Headers sent with every request, e.g. for authentication
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Seconds between two requests
+    #[schema(example = 300)]
+    pub interval_secs: u32,
+    /// JSON pointer (RFC 6901) selecting the data entries in the response, by default the whole body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "/current")]
+    pub json_pointer: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PullSourceInternal {
+    pub sensor_id: Uuid,
+    pub source: PullSource,
+}
@@ -1,4 +1,5 @@
 use crate::database::models::sensor_perm::SensorPermission;
+use crate::features::sensor_data_dedup::SensorDataDedup;
 use crate::features::sensor_data_storage::SensorDataStorageType;
 use crate::utils::uuid_schema;
 use serde::{Deserialize, Serialize};
@@ -21,6 +22,8 @@ pub struct FullSensorInfo {
     pub permissions: Vec<SensorPermission>,
     pub storage_type: SensorDataStorageType,
     pub storage_params: Option<Map<String, Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_dedup: Option<SensorDataDedup>,
 }
 
 impl FullSensorInfo {
@@ -43,13 +46,17 @@ pub struct ShortSensorInfo {
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Copy, Clone, ToSchema)]
 #[serde(rename_all = "UPPERCASE")]
 #[schema(
-    description = "NOTE: Any INT column will be bound to rust i32. Any FLOAT column will be bound to rust f64. STRING is VARCHAR(50)"
+    description = "NOTE: Any INT column will be bound to rust i32. Any FLOAT column will be bound to rust f64. STRING is VARCHAR(255).<br>\
+    BOOL is BOOLEAN, TIMESTAMP is a TIMESTAMP without timezone (ISO 8601 string) and JSON is JSONB storing arbitrary JSON values."
 )]
 pub enum ColumnType {
     UNKNOWN = 0,
     INT = 1,
     FLOAT = 2,
     STRING = 3,
+    BOOL = 4,
+    TIMESTAMP = 5,
+    JSON = 6,
 }
 
 /// A helper function for converting int values from the database to ColumnType
@@ -60,6 +67,9 @@ impl ColumnType {
             1 => Self::INT,
             2 => Self::FLOAT,
             3 => Self::STRING,
+            4 => Self::BOOL,
+            5 => Self::TIMESTAMP,
+            6 => Self::JSON,
             _ => panic!("Unknown value: {}", v),
         }
     }
@@ -72,9 +82,17 @@ impl ColumnType {
         match &self {
             ColumnType::INT => "INTEGER",
             ColumnType::FLOAT => "FLOAT",
+            ColumnType::BOOL => "BOOLEAN",
+            ColumnType::TIMESTAMP => "TIMESTAMP",
+            ColumnType::JSON => "JSONB",
             _ => "VARCHAR(255)",
         }
     }
+
+    /// Whether the column type supports the INCREMENTAL ingest mode (see add_values in the db).
+    pub fn supports_incremental(&self) -> bool {
+        matches!(self, ColumnType::INT | ColumnType::FLOAT | ColumnType::STRING)
+    }
 }
 
 /// The possible types of ingest data modes for a sensor data column.
@@ -110,4 +128,42 @@ pub struct SensorColumn {
     pub val_type: ColumnType,     // column type
     pub val_unit: String,         // measurement unit
     pub val_ingest: ColumnIngest, // data ingest mode
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraints: Option<ColumnConstraints>, // validation of ingested values
+}
+
+/// Optional validation constraints of a data column, enforced during data ingestion.
+/// Entries violating any constraint are rejected instead of storing NULL values.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, ToSchema)]
+pub struct ColumnConstraints {
+    /// Minimal (inclusive) value, only for INT and FLOAT columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Maximal (inclusive) value, only for INT and FLOAT columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// List of accepted values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed: Option<Vec<Value>>,
+    /// Regular expression the value must match, only for STRING columns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    /// If set, each entry must provide a (non-null) value for the column
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl SensorColumn {
+    /// Column names are used as plain identifiers in the sensor data table.
+    /// Only lowercase alphanumeric names (and '_') that fit into the sensor_schema are accepted.
+    pub fn is_valid_name(name: &str) -> bool {
+        let mut chars = name.chars();
+
+        match chars.next() {
+            Some(c) if c.is_ascii_lowercase() || c == '_' => {}
+            _ => return false,
+        }
+
+        name.len() <= 50 && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    }
 }
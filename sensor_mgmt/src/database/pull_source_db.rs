@@ -0,0 +1,74 @@
+use crate::database::models::pull_source::{PullSource, PullSourceInternal};
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+pub async fn load(sensor_id: Uuid, db: &PgPool) -> anyhow::Result<Option<PullSource>> {
+    let row = sqlx::query("SELECT * FROM sensor_pull_source WHERE sensor_id = $1")
+        .bind(sensor_id)
+        .fetch_optional(db)
+        .await?;
+
+    match row {
+        Some(r) => Ok(Some(from_row(&r)?.source)),
+        None => Ok(None),
+    }
+}
+
+/// Retrieves the pull sources of all sensors.
+pub async fn load_all(db: &PgPool) -> anyhow::Result<Vec<PullSourceInternal>> {
+    let rows = sqlx::query("SELECT * FROM sensor_pull_source")
+        .fetch_all(db)
+        .await?;
+
+    rows.iter().map(from_row).collect()
+}
+
+/// Sets the pull source of the sensor, replacing an existing one.
+pub async fn set(sensor_id: Uuid, source: &PullSource, db: &PgPool) -> anyhow::Result<()> {
+    let headers = match source.headers.is_empty() {
+        true => None,
+        false => Some(serde_json::to_string(&source.headers)?),
+    };
+
+    sqlx::query(
+        "INSERT INTO sensor_pull_source(sensor_id, url, headers, interval_secs, json_pointer) VALUES($1, $2, $3, $4, $5)
+         ON CONFLICT (sensor_id) DO UPDATE SET url = EXCLUDED.url, headers = EXCLUDED.headers,
+         interval_secs = EXCLUDED.interval_secs, json_pointer = EXCLUDED.json_pointer",
+    )
+    .bind(sensor_id)
+    .bind(&source.url)
+    .bind(headers)
+    .bind(source.interval_secs as i32)
+    .bind(&source.json_pointer)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete(sensor_id: Uuid, db: &PgPool) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM sensor_pull_source WHERE sensor_id = $1")
+        .bind(sensor_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+
+fn from_row(row: &sqlx::postgres::PgRow) -> anyhow::Result<PullSourceInternal> {
+    let headers: Option<String> = row.try_get("headers")?;
+    let interval_secs: i32 = row.try_get("interval_secs")?;
+
+    Ok(PullSourceInternal {
+        sensor_id: row.try_get("sensor_id")?,
+        source: PullSource {
+            url: row.try_get("url")?,
+            headers: match headers {
+                Some(h) => serde_json::from_str(&h)?,
+                None => Default::default(),
+            },
+            interval_secs: interval_secs as u32,
+            json_pointer: row.try_get("json_pointer")?,
+        },
+    })
+}
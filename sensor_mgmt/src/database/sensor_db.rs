@@ -1,20 +1,26 @@
+use crate::database::data_db::{ROW_ID_COL_NAME, TIME_COL_NAME};
 use crate::database::models::api_key::ApiKey;
 use crate::database::models::db_structs::DBOperation;
 use crate::database::models::role::ROLE_SYSTEM_GUEST;
 use crate::database::models::sensor::{
-    ColumnIngest, ColumnType, FullSensorInfo, SensorColumn, ShortSensorInfo,
+    ColumnConstraints, ColumnIngest, ColumnType, FullSensorInfo, SensorColumn, ShortSensorInfo,
 };
 use crate::database::models::user::UserInfo;
 use crate::features::cache;
+use crate::features::sensor_col_constraints::validate_col_constraints;
 use crate::features::sensor_col_ingest::{
     register_sensor_col_ingest, unregister_sensor_col_ingest,
 };
+use crate::features::sensor_data_dedup::{
+    register_sensor_data_dedup, unregister_sensor_data_dedup,
+};
 use crate::features::sensor_data_storage::{
     register_sensor_data_storage, unregister_sensor_data_storage,
 };
 use crate::features::user_sens_perm::{UserSensorPerm, UserSensorPermissions};
 use crate::handler::models::requests::{
-    CreateApiKeyRequest, CreateSensorRequest, EditSensorRequest, SensorPermissionRequest,
+    CreateApiKeyRequest, CreateSensorRequest, EditSensorRequest, EditSensorSchemaRequest,
+    SensorPermissionRequest, SensorSchemaOperation,
 };
 use crate::handler::models::responses::GenericUuidResponse;
 use crate::{database::models::sensor_perm::SensorPermission, state::AppState};
@@ -33,6 +39,19 @@ pub async fn get_sensor_overview(state: &AppState) -> anyhow::Result<Vec<ShortSe
     Ok(query_result)
 }
 
+/// Returns the id of the sensor with the given (unique) name.
+pub async fn get_sensor_id_by_name(
+    name: &str,
+    state: &AppState,
+) -> anyhow::Result<Option<uuid::Uuid>> {
+    let query_result = sqlx::query_scalar::<_, uuid::Uuid>("SELECT id FROM sensor WHERE name = $1")
+        .bind(name)
+        .fetch_optional(&state.db)
+        .await?;
+
+    Ok(query_result)
+}
+
 /// Retrieve the complete information about a sensor from the database and return it
 /// in the form of a SensorInfoResponse object. Should only be called from the cache and not directly.
 pub async fn get_full_sensor_info(
@@ -41,7 +60,7 @@ pub async fn get_full_sensor_info(
 ) -> anyhow::Result<FullSensorInfo> {
     // Retrieve the data from the sensor and sensor_schema tables
     let query_result = sqlx::query(r#"
-        SELECT id, s.name AS name, tbl_name, longitude, latitude, description, owner, storage_type, storage_params, col_name, col_type, col_unit, col_ingest
+        SELECT id, s.name AS name, tbl_name, longitude, latitude, description, owner, storage_type, storage_params, storage_dedup, col_name, col_type, col_unit, col_ingest, col_constraints
         FROM sensor s
             JOIN sensor_schema c ON s.id = c.sensor_id
         WHERE s.id = $1"#)
@@ -62,12 +81,14 @@ pub async fn get_full_sensor_info(
     let owner: Option<uuid::Uuid> = query_result[0].get("owner");
     let raw_storage_type: String = query_result[0].get("storage_type");
     let raw_storage_params: String = query_result[0].get("storage_params");
+    let raw_storage_dedup: Option<String> = query_result[0].get("storage_dedup");
 
     let storage_type = serde_json::from_str(&raw_storage_type)?;
     let storage_params = match serde_json::from_str::<Value>(&raw_storage_params) {
         Ok(Value::Object(map)) => Some(map), // If it's a JSON object, return the map
         _ => None,
     };
+    let storage_dedup = raw_storage_dedup.and_then(|d| serde_json::from_str(&d).ok());
 
     let sensor_position: Option<(f64, f64)> = if longitude.is_some() && latitude.is_some() {
         Some((latitude.unwrap(), longitude.unwrap()))
@@ -80,12 +101,21 @@ pub async fn get_full_sensor_info(
     for row in query_result {
         let c_type: i32 = row.get("col_type");
         let c_ingest: i32 = row.get("col_ingest");
+        let c_name: String = row.get("col_name");
+        let c_constraints: Option<String> = row.get("col_constraints");
+
+        let constraints = c_constraints
+            .map(|c| serde_json::from_str::<Option<ColumnConstraints>>(&c))
+            .transpose()
+            .map_err(|err| anyhow::anyhow!("Invalid constraints of column {}: {}", c_name, err))?
+            .flatten();
 
         columns.push(SensorColumn {
-            name: row.get("col_name"),
+            name: c_name,
             val_type: ColumnType::from_integer(c_type),
             val_unit: row.get("col_unit"),
             val_ingest: ColumnIngest::from_integer(c_ingest),
+            constraints,
         });
     }
 
@@ -115,6 +145,7 @@ pub async fn get_full_sensor_info(
         permissions: perm_res?,
         storage_type,
         storage_params,
+        storage_dedup,
     })
 }
 
@@ -244,13 +275,14 @@ pub async fn edit_sensor(
     let mut tx = state.db.begin().await?;
 
     let query_result =
-        sqlx::query(r#"UPDATE sensor SET name=$1, description=$2, longitude=$3, latitude=$4, storage_type=$5, storage_params=$6 WHERE id=$7"#)
+        sqlx::query(r#"UPDATE sensor SET name=$1, description=$2, longitude=$3, latitude=$4, storage_type=$5, storage_params=$6, storage_dedup=$7 WHERE id=$8"#)
             .bind(body.name)
             .bind(body.description)
             .bind(long)
             .bind(lat)
             .bind(serde_json::to_string(&body.storage.variant)?)
             .bind(serde_json::to_string(&body.storage.params)?)
+            .bind(body.storage.dedup.as_ref().map(serde_json::to_string).transpose()?)
             .bind(sensor_id.clone())
             .execute(&mut *tx)
             .await
@@ -285,7 +317,7 @@ pub async fn edit_sensor(
         // Add new strategy
         let res = register_sensor_data_storage(
             body.storage.variant,
-            sensor.tbl_name,
+            sensor.tbl_name.clone(),
             body.storage.params,
             tx.as_mut(),
         )
@@ -301,6 +333,39 @@ pub async fn edit_sensor(
         }
     }
 
+    // Edit data dedup mode (if changed)
+
+    if sensor.storage_dedup != body.storage.dedup {
+        let res = unregister_sensor_data_dedup(
+            sensor_id,
+            &sensor.storage_dedup,
+            &sensor.tbl_name,
+            tx.as_mut(),
+        )
+        .await;
+
+        if let Err(err) = res {
+            let _ = tx.rollback().await;
+            error!(
+                "Couldn't remove old data dedup mode for sensor with id {}!",
+                sensor_id
+            );
+            anyhow::bail!(err);
+        }
+
+        let res =
+            register_sensor_data_dedup(&body.storage.dedup, &sensor.tbl_name, tx.as_mut()).await;
+
+        if let Err(err) = res {
+            let _ = tx.rollback().await;
+            error!(
+                "Couldn't setup new data dedup mode for sensor with id {}!",
+                sensor_id
+            );
+            anyhow::bail!(err);
+        }
+    }
+
     // Edit permissions
 
     let perm_query = update_sensor_access(sensor_id, body.permissions, &state, &mut tx).await;
@@ -318,6 +383,263 @@ pub async fn edit_sensor(
     Ok(())
 }
 
+/// Modifies the data columns of an existing sensor.
+/// All operations are applied in order within a single transaction, altering the sensor data table,
+/// the sensor_schema and the column ingest modes. If any operation fails, the schema remains untouched.
+pub async fn edit_sensor_schema(
+    sensor_id: uuid::Uuid,
+    body: EditSensorSchemaRequest,
+    state: &AppState,
+) -> anyhow::Result<()> {
+    let sensor = cache::request_sensor(sensor_id, state)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("No sensor with id {}!", sensor_id))?;
+
+    if body.operations.is_empty() {
+        anyhow::bail!("No schema operations provided!");
+    }
+
+    // Track the resulting columns to validate subsequent operations
+    let mut columns = sensor.columns.clone();
+
+    let mut tx = state.db.begin().await?;
+
+    for op in body.operations {
+        let res =
+            apply_schema_operation(sensor_id, &sensor.tbl_name, &mut columns, &op, tx.as_mut())
+                .await;
+
+        if let Err(err) = res {
+            let _ = tx.rollback().await;
+            error!("Schema operation {:?} failed for sensor {}!", op, sensor_id);
+            anyhow::bail!(err);
+        }
+    }
+
+    // The sensor info is joined with its schema, thus at least one column must remain
+    if columns.is_empty() {
+        let _ = tx.rollback().await;
+        anyhow::bail!("A sensor requires at least one data column!");
+    }
+
+    let _ = tx.commit().await;
+
+    cache::purge_sensor(sensor_id, state);
+
+    Ok(())
+}
+
+async fn apply_schema_operation(
+    sensor_id: uuid::Uuid,
+    table_name: &str,
+    columns: &mut Vec<SensorColumn>,
+    op: &SensorSchemaOperation,
+    ex: &mut PgConnection,
+) -> anyhow::Result<()> {
+    let col_idx = |name: &str| columns.iter().position(|c| c.name == name);
+
+    match op {
+        SensorSchemaOperation::ADD { column } => {
+            if !SensorColumn::is_valid_name(&column.name)
+                || column.name == TIME_COL_NAME
+                || column.name == ROW_ID_COL_NAME
+            {
+                anyhow::bail!("Invalid column name {}!", column.name);
+            }
+
+            if col_idx(&column.name).is_some() {
+                anyhow::bail!("Column {} already exists!", column.name);
+            }
+
+            if column.val_ingest == ColumnIngest::INCREMENTAL
+                && !column.val_type.supports_incremental()
+            {
+                anyhow::bail!(
+                    "Ingest mode {:?} is not supported for column {} with type {:?}!",
+                    column.val_ingest,
+                    column.name,
+                    column.val_type
+                );
+            }
+
+            validate_col_constraints(column)?;
+
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table_name,
+                column.name,
+                column.val_type.to_sql_type()
+            ))
+            .execute(&mut *ex)
+            .await?;
+
+            sqlx::query(
+                r#"INSERT INTO sensor_schema (sensor_id, col_name, col_type, col_unit, col_ingest, col_constraints)
+                VALUES ($1, $2, $3, $4, $5, $6)"#,
+            )
+            .bind(sensor_id)
+            .bind(column.name.to_string())
+            .bind(column.val_type.to_sql_value())
+            .bind(column.val_unit.to_string())
+            .bind(column.val_ingest.to_sql_value())
+            .bind(column.constraints.as_ref().map(serde_json::to_string).transpose()?)
+            .execute(&mut *ex)
+            .await?;
+
+            register_sensor_col_ingest(
+                column.val_ingest,
+                table_name.to_string(),
+                column.name.clone(),
+                ex,
+            )
+            .await?;
+
+            columns.push(column.clone());
+        }
+        SensorSchemaOperation::DROP { name } => {
+            let idx =
+                col_idx(name).ok_or_else(|| anyhow::anyhow!("Column {} does not exist!", name))?;
+
+            unregister_sensor_col_ingest(
+                columns[idx].val_ingest,
+                table_name.to_string(),
+                name.clone(),
+                ex,
+            )
+            .await?;
+
+            sqlx::query(&format!("ALTER TABLE {} DROP COLUMN {}", table_name, name))
+                .execute(&mut *ex)
+                .await?;
+
+            sqlx::query("DELETE FROM sensor_schema WHERE sensor_id = $1 AND col_name = $2")
+                .bind(sensor_id)
+                .bind(name.to_string())
+                .execute(&mut *ex)
+                .await?;
+
+            columns.remove(idx);
+        }
+        SensorSchemaOperation::RENAME { name, new_name } => {
+            let idx =
+                col_idx(name).ok_or_else(|| anyhow::anyhow!("Column {} does not exist!", name))?;
+
+            if !SensorColumn::is_valid_name(new_name)
+                || new_name == TIME_COL_NAME
+                || new_name == ROW_ID_COL_NAME
+            {
+                anyhow::bail!("Invalid column name {}!", new_name);
+            }
+
+            if col_idx(new_name).is_some() {
+                anyhow::bail!("Column {} already exists!", new_name);
+            }
+
+            // Ingest triggers are bound to the column name, thus they have to be recreated
+            let ingest = columns[idx].val_ingest;
+
+            unregister_sensor_col_ingest(ingest, table_name.to_string(), name.clone(), ex).await?;
+
+            sqlx::query(&format!(
+                "ALTER TABLE {} RENAME COLUMN {} TO {}",
+                table_name, name, new_name
+            ))
+            .execute(&mut *ex)
+            .await?;
+
+            sqlx::query(
+                "UPDATE sensor_schema SET col_name = $1 WHERE sensor_id = $2 AND col_name = $3",
+            )
+            .bind(new_name.to_string())
+            .bind(sensor_id)
+            .bind(name.to_string())
+            .execute(&mut *ex)
+            .await?;
+
+            register_sensor_col_ingest(ingest, table_name.to_string(), new_name.clone(), ex)
+                .await?;
+
+            columns[idx].name = new_name.clone();
+        }
+        SensorSchemaOperation::RETYPE { name, val_type } => {
+            let idx =
+                col_idx(name).ok_or_else(|| anyhow::anyhow!("Column {} does not exist!", name))?;
+            let ingest = columns[idx].val_ingest;
+
+            if *val_type == ColumnType::UNKNOWN {
+                anyhow::bail!("Can't change column {} to type {:?}!", name, val_type);
+            }
+
+            if ingest == ColumnIngest::INCREMENTAL && !val_type.supports_incremental() {
+                anyhow::bail!(
+                    "Ingest mode {:?} is not supported for column {} with type {:?}!",
+                    ingest,
+                    name,
+                    val_type
+                );
+            }
+
+            // Existing constraints must remain applicable to the new type
+            let mut retyped_col = columns[idx].clone();
+            retyped_col.val_type = *val_type;
+            validate_col_constraints(&retyped_col)?;
+
+            // Incremental triggers depend on the column type
+            unregister_sensor_col_ingest(ingest, table_name.to_string(), name.clone(), ex).await?;
+
+            // Existing values are converted by the db, the conversion fails for incompatible values
+            let conversion = match val_type {
+                ColumnType::JSON => format!("to_jsonb({})", name),
+                _ => format!("{}::{}", name, val_type.to_sql_type()),
+            };
+
+            sqlx::query(&format!(
+                "ALTER TABLE {} ALTER COLUMN {} TYPE {} USING {}",
+                table_name,
+                name,
+                val_type.to_sql_type(),
+                conversion
+            ))
+            .execute(&mut *ex)
+            .await?;
+
+            sqlx::query(
+                "UPDATE sensor_schema SET col_type = $1 WHERE sensor_id = $2 AND col_name = $3",
+            )
+            .bind(val_type.to_sql_value())
+            .bind(sensor_id)
+            .bind(name.to_string())
+            .execute(&mut *ex)
+            .await?;
+
+            register_sensor_col_ingest(ingest, table_name.to_string(), name.clone(), ex).await?;
+
+            columns[idx].val_type = *val_type;
+        }
+        SensorSchemaOperation::CONSTRAIN { name, constraints } => {
+            let idx =
+                col_idx(name).ok_or_else(|| anyhow::anyhow!("Column {} does not exist!", name))?;
+
+            let mut constrained_col = columns[idx].clone();
+            constrained_col.constraints = constraints.clone();
+            validate_col_constraints(&constrained_col)?;
+
+            sqlx::query(
+                "UPDATE sensor_schema SET col_constraints = $1 WHERE sensor_id = $2 AND col_name = $3",
+            )
+            .bind(constraints.as_ref().map(serde_json::to_string).transpose()?)
+            .bind(sensor_id)
+            .bind(name.to_string())
+            .execute(&mut *ex)
+            .await?;
+
+            columns[idx] = constrained_col;
+        }
+    }
+
+    Ok(())
+}
+
 /// Register a new sensor by creating the schema information and creating the data table.
 /// All the required information are passed via a CreateSensorRequest object.
 /// If a user is given, it becomes the owner of the sensor.
@@ -340,8 +662,8 @@ pub async fn create_sensor(
     };
 
     let query_result = sqlx::query(
-r#"INSERT INTO sensor (id, name, description, longitude, latitude, tbl_name, owner, storage_type, storage_params)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)"#,
+r#"INSERT INTO sensor (id, name, description, longitude, latitude, tbl_name, owner, storage_type, storage_params, storage_dedup)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"#,
     )
     .bind(sensor_id.clone())
     .bind(body.name.to_string())
@@ -352,6 +674,7 @@ r#"INSERT INTO sensor (id, name, description, longitude, latitude, tbl_name, own
     .bind(user_id.map(|u| u))
     .bind(serde_json::to_string(&body.storage.variant)?)
     .bind(serde_json::to_string(&body.storage.params)?)
+    .bind(body.storage.dedup.as_ref().map(serde_json::to_string).transpose()?)
     .execute(&mut *tx)
     .await
     .map_err(|err: sqlx::Error| err.to_string());
@@ -369,14 +692,33 @@ r#"INSERT INTO sensor (id, name, description, longitude, latitude, tbl_name, own
     // Define created_at timestamp and reject all manually provided future timestamps
     create_stmt.push_str(format!("CREATE TABLE {} (created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, CHECK(created_at <= CURRENT_TIMESTAMP)", table_name).as_str());
 
+    // Stable row id, breaks ties of equal timestamps when paginating
+    create_stmt
+        .push_str(format!(", {} bigint GENERATED ALWAYS AS IDENTITY", ROW_ID_COL_NAME).as_str());
+
     let columns = &body.columns;
     for col in columns.iter() {
+        if col.val_ingest == ColumnIngest::INCREMENTAL && !col.val_type.supports_incremental() {
+            let _ = tx.rollback().await;
+            anyhow::bail!(
+                "Ingest mode {:?} is not supported for column {} with type {:?}!",
+                col.val_ingest,
+                col.name,
+                col.val_type
+            );
+        }
+
+        if let Err(err) = validate_col_constraints(col) {
+            let _ = tx.rollback().await;
+            anyhow::bail!(err);
+        }
+
         create_stmt.push_str(", ");
         create_stmt.push_str(format!("{} {}", &col.name, col.val_type.to_sql_type()).as_str());
 
         let query_result = sqlx::query(
-            r#"INSERT INTO sensor_schema (sensor_id, col_name, col_type, col_unit, col_ingest)
-            VALUES ($1, $2, $3, $4, $5)
+            r#"INSERT INTO sensor_schema (sensor_id, col_name, col_type, col_unit, col_ingest, col_constraints)
+            VALUES ($1, $2, $3, $4, $5, $6)
             "#,
         )
         .bind(sensor_id.clone())
@@ -384,6 +726,7 @@ r#"INSERT INTO sensor (id, name, description, longitude, latitude, tbl_name, own
         .bind(col.val_type.to_sql_value())
         .bind(col.val_unit.to_string())
         .bind(col.val_ingest.to_sql_value())
+        .bind(col.constraints.as_ref().map(serde_json::to_string).transpose()?)
         .execute(&mut *tx)
         .await
         .map_err(|err: sqlx::Error| err.to_string());
@@ -434,7 +777,7 @@ r#"INSERT INTO sensor (id, name, description, longitude, latitude, tbl_name, own
 
     let data_storage_res = register_sensor_data_storage(
         body.storage.variant,
-        table_name,
+        table_name.clone(),
         body.storage.params,
         tx.as_mut(),
     )
@@ -449,6 +792,20 @@ r#"INSERT INTO sensor (id, name, description, longitude, latitude, tbl_name, own
         anyhow::bail!(err);
     }
 
+    // Setup data dedup mode
+
+    let data_dedup_res =
+        register_sensor_data_dedup(&body.storage.dedup, &table_name, tx.as_mut()).await;
+
+    if let Err(err) = data_dedup_res {
+        let _ = tx.rollback().await;
+        error!(
+            "Couldn't setup data dedup mode for sensor with id {}!",
+            sensor_id
+        );
+        anyhow::bail!(err);
+    }
+
     // Setup permissions
 
     let perm_query = update_sensor_access(sensor_id, body.permissions, &state, &mut tx).await;
@@ -711,6 +1068,29 @@ pub async fn get_api_key(key_id: uuid::Uuid, state: &AppState) -> anyhow::Result
     }
 }
 
+/// Checks if the specified sensor has an api key for the operation.
+pub async fn has_api_key(
+    sensor_id: uuid::Uuid,
+    operation: DBOperation,
+    state: &AppState,
+) -> anyhow::Result<bool> {
+    let query_res = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS(SELECT 1 FROM api_keys WHERE sensor_id=$1 AND operation=$2)",
+    )
+    .bind(sensor_id)
+    .bind(operation.as_str())
+    .fetch_one(&state.db)
+    .await;
+
+    match query_res {
+        Ok(exists) => Ok(exists),
+        Err(err) => {
+            error!("Couldn't check api_keys of sensor {}!", sensor_id);
+            anyhow::bail!(err);
+        }
+    }
+}
+
 /// Creates a new api key for the specified user and sensor.
 pub async fn create_api_key(
     sensor_id: uuid::Uuid,
@@ -41,6 +41,8 @@ pub struct CachedData {
     pub users: RwLock<HashMap<Uuid, UserInfo>>,
     pub sensors: RwLock<HashMap<Uuid, FullSensorInfo>>,
     pub api_keys: RwLock<HashMap<Uuid, ApiKey>>,
+    // API keys used by MQTT publishers to log into the broker, by username and client id
+    pub mqtt_logins: RwLock<HashMap<(String, Option<String>), Uuid>>,
 }
 
 pub fn new_cache() -> CachedData {
@@ -49,6 +51,7 @@ pub fn new_cache() -> CachedData {
         users: RwLock::default(),
         sensors: RwLock::default(),
         api_keys: RwLock::default(),
+        mqtt_logins: RwLock::default(),
     }
 }
 
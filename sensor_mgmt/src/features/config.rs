@@ -65,6 +65,8 @@ pub struct ServerConfig {
     pub server: Option<ServerServerConfig>,
     // Authentication options
     pub auth: Option<AuthConfig>,
+    // Connection to the MQTT broker
+    pub mqtt: Option<MqttConfig>,
 }
 
 // Parse the general config file
@@ -83,6 +85,7 @@ pub fn parse_config() -> anyhow::Result<ServerConfig> {
                     Ok(ServerConfig {
                         server: None,
                         auth: None,
+                        mqtt: None,
                     })
                 }
                 _ => Err(err.into()),
@@ -125,6 +128,30 @@ pub struct ServerServerConfig {
 
     //
     ingest_max_size_kb: Option<usize>,
+
+    // Maximum number of rows returned by a single data load request
+    data_max_page_size: Option<i32>,
+
+    // Data exports running at the same time and the time an export may take
+    data_export_max_concurrent: Option<usize>,
+    data_export_timeout_secs: Option<u64>,
+
+    // UDP port of the CoAP ingest listener
+    coap_port: Option<u16>,
+    // CoAP requests handled at the same time and confirmable exchanges kept for retransmissions
+    coap_max_concurrent_requests: Option<usize>,
+    coap_max_exchanges: Option<usize>,
+
+    // Bearer token LoRaWAN network servers must send with their webhooks
+    lorawan_webhook_token: Option<String>,
+
+    // Batched ingest, maximum rows and delay of a batch and number of queued submissions per sensor
+    ingest_batch_max_rows: Option<usize>,
+    ingest_batch_max_delay_ms: Option<u64>,
+    ingest_queue_capacity: Option<usize>,
+
+    // Allows pull and modbus sources on loopback, private and link-local addresses
+    pull_allow_private_hosts: Option<bool>,
 }
 
 const CFG_SERVER_DEFAULT_HOST: &str = "localhost";
@@ -197,6 +224,268 @@ pub fn get_ingest_max_size_kb(cfg: &ServerConfig) -> usize {
     }
 }
 
+const CFG_SERVER_DEFAULT_DATA_MAX_PAGE_SIZE: i32 = 1000;
+pub fn get_data_max_page_size(cfg: &ServerConfig) -> i32 {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.data_max_page_size {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_DATA_MAX_PAGE_SIZE,
+        },
+        None => CFG_SERVER_DEFAULT_DATA_MAX_PAGE_SIZE,
+    }
+}
+
+const CFG_SERVER_DEFAULT_DATA_EXPORT_MAX_CONCURRENT: usize = 4;
+pub fn get_data_export_max_concurrent(cfg: &ServerConfig) -> usize {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.data_export_max_concurrent {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_DATA_EXPORT_MAX_CONCURRENT,
+        },
+        None => CFG_SERVER_DEFAULT_DATA_EXPORT_MAX_CONCURRENT,
+    }
+}
+
+const CFG_SERVER_DEFAULT_DATA_EXPORT_TIMEOUT_SECS: u64 = 300;
+pub fn get_data_export_timeout_secs(cfg: &ServerConfig) -> u64 {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.data_export_timeout_secs {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_DATA_EXPORT_TIMEOUT_SECS,
+        },
+        None => CFG_SERVER_DEFAULT_DATA_EXPORT_TIMEOUT_SECS,
+    }
+}
+
+const CFG_SERVER_DEFAULT_COAP_PORT: u16 = 5683;
+pub fn get_coap_port(cfg: &ServerConfig) -> u16 {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.coap_port {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_COAP_PORT,
+        },
+        None => CFG_SERVER_DEFAULT_COAP_PORT,
+    }
+}
+
+const CFG_SERVER_DEFAULT_COAP_MAX_CONCURRENT_REQUESTS: usize = 64;
+pub fn get_coap_max_concurrent_requests(cfg: &ServerConfig) -> usize {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.coap_max_concurrent_requests {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_COAP_MAX_CONCURRENT_REQUESTS,
+        },
+        None => CFG_SERVER_DEFAULT_COAP_MAX_CONCURRENT_REQUESTS,
+    }
+}
+
+const CFG_SERVER_DEFAULT_COAP_MAX_EXCHANGES: usize = 10000;
+pub fn get_coap_max_exchanges(cfg: &ServerConfig) -> usize {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.coap_max_exchanges {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_COAP_MAX_EXCHANGES,
+        },
+        None => CFG_SERVER_DEFAULT_COAP_MAX_EXCHANGES,
+    }
+}
+
+// Without a token the LoRaWAN webhooks are disabled
+pub fn get_lorawan_webhook_token(cfg: &ServerConfig) -> Option<String> {
+    match &cfg.server {
+        Some(srv_cfg) => srv_cfg.lorawan_webhook_token.clone(),
+        None => None,
+    }
+}
+
+const CFG_SERVER_DEFAULT_INGEST_BATCH_MAX_ROWS: usize = 500;
+pub fn get_ingest_batch_max_rows(cfg: &ServerConfig) -> usize {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.ingest_batch_max_rows {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_INGEST_BATCH_MAX_ROWS,
+        },
+        None => CFG_SERVER_DEFAULT_INGEST_BATCH_MAX_ROWS,
+    }
+}
+
+const CFG_SERVER_DEFAULT_INGEST_BATCH_MAX_DELAY_MS: u64 = 5;
+pub fn get_ingest_batch_max_delay_ms(cfg: &ServerConfig) -> u64 {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.ingest_batch_max_delay_ms {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_INGEST_BATCH_MAX_DELAY_MS,
+        },
+        None => CFG_SERVER_DEFAULT_INGEST_BATCH_MAX_DELAY_MS,
+    }
+}
+
+const CFG_SERVER_DEFAULT_INGEST_QUEUE_CAPACITY: usize = 1000;
+pub fn get_ingest_queue_capacity(cfg: &ServerConfig) -> usize {
+    match &cfg.server {
+        Some(srv_cfg) => match &srv_cfg.ingest_queue_capacity {
+            Some(h) => *h,
+            None => CFG_SERVER_DEFAULT_INGEST_QUEUE_CAPACITY,
+        },
+        None => CFG_SERVER_DEFAULT_INGEST_QUEUE_CAPACITY,
+    }
+}
+
+// Sources may only reach public addresses by default
+pub fn get_pull_allow_private_hosts(cfg: &ServerConfig) -> bool {
+    match &cfg.server {
+        Some(srv_cfg) => srv_cfg.pull_allow_private_hosts.unwrap_or(false),
+        None => false,
+    }
+}
+
+/* ------------------------------------------------ MQTT Options ------------------------------------------------------------ */
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MqttConfig {
+    // Transport used to connect to the broker
+    transport: Option<MqttTransport>,
+
+    // host and port of the broker
+    host: Option<String>,
+    port: Option<u16>,
+
+    // Path of the websocket endpoint (ws and wss)
+    ws_path: Option<String>,
+
+    // Required for the tls and wss transports
+    tls: Option<MqttTlsConfig>,
+
+    // Credentials of the subscriber, granted all topics by the broker auth endpoints
+    username: Option<String>,
+    password: Option<String>,
+
+    // The broker authenticates publishers with the broker auth endpoints
+    broker_auth: Option<bool>,
+
+    // Acknowledge messages without response topic on '<topic>/ack'
+    ack_topics: Option<bool>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MqttTransport {
+    Tcp,
+    Ws,
+    Tls,
+    Wss,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MqttTlsConfig {
+    // PEM bundle of the CAs trusted to sign the broker certificate
+    pub ca_file: String,
+    // PEM client certificate and key, if the broker requires client authentication
+    pub client_cert_file: Option<String>,
+    pub client_key_file: Option<String>,
+    // Protocols offered with ALPN, e.g. "mqtt"
+    pub alpn: Option<Vec<String>>,
+}
+
+// By default we use a WebSocket connection
+const CFG_MQTT_DEFAULT_TRANSPORT: MqttTransport = MqttTransport::Ws;
+pub fn get_mqtt_transport(cfg: &ServerConfig) -> MqttTransport {
+    match &cfg.mqtt {
+        Some(mqtt_cfg) => match &mqtt_cfg.transport {
+            Some(t) => *t,
+            None => CFG_MQTT_DEFAULT_TRANSPORT,
+        },
+        None => CFG_MQTT_DEFAULT_TRANSPORT,
+    }
+}
+
+const CFG_MQTT_DEFAULT_HOST: &str = "mosquitto";
+pub fn get_mqtt_host(cfg: &ServerConfig) -> String {
+    match &cfg.mqtt {
+        Some(mqtt_cfg) => match &mqtt_cfg.host {
+            Some(h) => h.to_string(),
+            None => as_compose_service(CFG_MQTT_DEFAULT_HOST),
+        },
+        None => as_compose_service(CFG_MQTT_DEFAULT_HOST),
+    }
+}
+
+// The default port depends on the transport
+pub fn get_mqtt_port(cfg: &ServerConfig) -> u16 {
+    let default = match get_mqtt_transport(cfg) {
+        MqttTransport::Tcp => 1883,
+        MqttTransport::Ws => 9001,
+        MqttTransport::Tls => 8883,
+        MqttTransport::Wss => 9443,
+    };
+
+    match &cfg.mqtt {
+        Some(mqtt_cfg) => mqtt_cfg.port.unwrap_or(default),
+        None => default,
+    }
+}
+
+const CFG_MQTT_DEFAULT_WS_PATH: &str = "/mqtt";
+pub fn get_mqtt_ws_path(cfg: &ServerConfig) -> String {
+    match &cfg.mqtt {
+        Some(mqtt_cfg) => match &mqtt_cfg.ws_path {
+            Some(p) => p,
+            None => CFG_MQTT_DEFAULT_WS_PATH,
+        },
+        None => CFG_MQTT_DEFAULT_WS_PATH,
+    }
+    .to_string()
+}
+
+pub fn get_mqtt_tls(cfg: &ServerConfig) -> Option<MqttTlsConfig> {
+    match &cfg.mqtt {
+        Some(mqtt_cfg) => mqtt_cfg.tls.clone(),
+        None => None,
+    }
+}
+
+pub fn get_mqtt_credentials(cfg: &ServerConfig) -> Option<(String, String)> {
+    match &cfg.mqtt {
+        Some(mqtt_cfg) => match (&mqtt_cfg.username, &mqtt_cfg.password) {
+            (Some(u), Some(p)) => Some((u.to_string(), p.to_string())),
+            _ => None,
+        },
+        None => None,
+    }
+}
+
+// Without broker auth, publishers must put the api key into the topic
+const CFG_MQTT_DEFAULT_BROKER_AUTH: bool = false;
+pub fn is_mqtt_broker_auth(cfg: &ServerConfig) -> bool {
+    match &cfg.mqtt {
+        Some(mqtt_cfg) => match &mqtt_cfg.broker_auth {
+            Some(v) => *v,
+            None => CFG_MQTT_DEFAULT_BROKER_AUTH,
+        },
+        None => CFG_MQTT_DEFAULT_BROKER_AUTH,
+    }
+}
+
+// MQTT 5 publishers can always request acknowledgements with a response topic
+const CFG_MQTT_DEFAULT_ACK_TOPICS: bool = false;
+pub fn is_mqtt_ack_topics(cfg: &ServerConfig) -> bool {
+    match &cfg.mqtt {
+        Some(mqtt_cfg) => match &mqtt_cfg.ack_topics {
+            Some(v) => *v,
+            None => CFG_MQTT_DEFAULT_ACK_TOPICS,
+        },
+        None => CFG_MQTT_DEFAULT_ACK_TOPICS,
+    }
+}
+
+// Relative paths of files referenced in the config are resolved in the config dir
+pub fn resolve_config_path(path: &str) -> String {
+    match Path::new(path).is_absolute() {
+        true => path.to_string(),
+        false => from_config_dir(path),
+    }
+}
+
 /* ------------------------------------------------ Auth Options ------------------------------------------------------------ */
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
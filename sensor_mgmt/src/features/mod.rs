@@ -3,6 +3,13 @@ pub mod cache;
 pub mod cache_sync;
 pub mod config;
 pub mod event_generation;
+pub mod sensor_col_constraints;
+pub mod sensor_data_batch;
+pub mod sensor_data_dedup;
+pub mod sensor_data_export;
+pub mod sensor_data_filter;
+pub mod sensor_data_modbus;
+pub mod sensor_data_pull;
 pub mod sensor_col_ingest;
 pub mod sensor_data_storage;
 pub mod sensor_data_transform;
@@ -0,0 +1,299 @@
+use crate::database::models::sensor::{
+    ColumnConstraints, ColumnType, FullSensorInfo, SensorColumn,
+};
+use crate::handler::models::requests::SensorDataIngestEntry;
+use anyhow::Result;
+use regex::Regex;
+use serde_json::Value;
+use std::fmt;
+
+/// Error returned by the data ingest if entries violate the constraints of the sensor columns.
+/// Contains one message per offending entry and column.
+#[derive(Debug)]
+pub struct ConstraintViolations(pub Vec<String>);
+
+impl fmt::Display for ConstraintViolations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Data entries violate column constraints: {}",
+            self.0.join("; ")
+        )
+    }
+}
+
+impl std::error::Error for ConstraintViolations {}
+
+// ------------------------------------------ Definition -------------------------------------------
+
+/// Verifies that the constraints of the column are applicable to its type.
+pub fn validate_col_constraints(col: &SensorColumn) -> Result<()> {
+    let Some(cons) = &col.constraints else {
+        return Ok(());
+    };
+
+    let is_numeric = col.val_type == ColumnType::INT || col.val_type == ColumnType::FLOAT;
+
+    if (cons.min.is_some() || cons.max.is_some()) && !is_numeric {
+        anyhow::bail!(
+            "Min/max constraints are not supported for column {} with type {:?}!",
+            col.name,
+            col.val_type
+        );
+    }
+
+    if let (Some(min), Some(max)) = (cons.min, cons.max) {
+        if min > max {
+            anyhow::bail!("Min constraint of column {} is larger than max!", col.name);
+        }
+    }
+
+    if let Some(regex) = &cons.regex {
+        if col.val_type != ColumnType::STRING {
+            anyhow::bail!(
+                "Regex constraints are not supported for column {} with type {:?}!",
+                col.name,
+                col.val_type
+            );
+        }
+
+        if let Err(err) = Regex::new(regex) {
+            anyhow::bail!("Invalid regex constraint for column {}: {}", col.name, err);
+        }
+    }
+
+    if let Some(allowed) = &cons.allowed {
+        if let Some(val) = allowed.iter().find(|v| !is_valid_type(col.val_type, v)) {
+            anyhow::bail!(
+                "Allowed value {} doesn't match type {:?} of column {}!",
+                val,
+                col.val_type,
+                col.name
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// ------------------------------------------ Ingest -----------------------------------------------
+
+/// Checks all data entries against the column types and constraints of the sensor.
+/// Values which don't match the column type or violate a constraint are not converted to NULL, but rejected.
+pub fn validate_entries(
+    sensor: &FullSensorInfo,
+    data: &[SensorDataIngestEntry],
+) -> std::result::Result<(), ConstraintViolations> {
+    let columns: Vec<(&SensorColumn, Option<&ColumnConstraints>, Option<Regex>)> = sensor
+        .columns
+        .iter()
+        .map(|col| {
+            let cons = col.constraints.as_ref();
+            // Definitions are verified on creation, thus the regex is valid
+            let regex = cons
+                .and_then(|c| c.regex.as_ref())
+                .and_then(|r| Regex::new(r).ok());
+            (col, cons, regex)
+        })
+        .collect();
+
+    let mut violations = Vec::new();
+
+    for (idx, entry) in data.iter().enumerate() {
+        for (col, cons, regex) in columns.iter() {
+            let val = entry.data.get(&col.name).filter(|v| !v.is_null());
+
+            if let Some(err) = check_value(col, *cons, regex.as_ref(), val) {
+                violations.push(format!("entry {}: column '{}' {}", idx, col.name, err));
+            }
+        }
+    }
+
+    if !violations.is_empty() {
+        return Err(ConstraintViolations(violations));
+    }
+
+    Ok(())
+}
+
+fn check_value(
+    col: &SensorColumn,
+    cons: Option<&ColumnConstraints>,
+    regex: Option<&Regex>,
+    val: Option<&Value>,
+) -> Option<String> {
+    let Some(val) = val else {
+        let required = cons.is_some_and(|c| c.required);
+        return required.then(|| "requires a value".to_string());
+    };
+
+    if !is_valid_type(col.val_type, val) {
+        return Some(format!("value {} is not of type {:?}", val, col.val_type));
+    }
+
+    let cons = cons?;
+
+    if let Some(min) = cons.min {
+        if val.as_f64().is_some_and(|v| v < min) {
+            return Some(format!("value {} is below the minimum {}", val, min));
+        }
+    }
+
+    if let Some(max) = cons.max {
+        if val.as_f64().is_some_and(|v| v > max) {
+            return Some(format!("value {} is above the maximum {}", val, max));
+        }
+    }
+
+    if let Some(allowed) = &cons.allowed {
+        if !allowed.iter().any(|a| values_equal(a, val)) {
+            return Some(format!("value {} is not allowed", val));
+        }
+    }
+
+    if let Some(regex) = regex {
+        if !regex.is_match(val.as_str().unwrap_or_default()) {
+            return Some(format!("value {} doesn't match {}", val, regex.as_str()));
+        }
+    }
+
+    None
+}
+
+/// Whether the value can be bound to a column of the given type without becoming NULL.
+fn is_valid_type(val_type: ColumnType, val: &Value) -> bool {
+    match val_type {
+        ColumnType::INT => val.as_i64().is_some_and(|v| i32::try_from(v).is_ok()),
+        ColumnType::FLOAT => val.as_f64().is_some(),
+        ColumnType::STRING => val.is_string(),
+        ColumnType::BOOL => val.is_boolean(),
+        ColumnType::TIMESTAMP => {
+            serde_json::from_value::<chrono::NaiveDateTime>(val.to_owned()).is_ok()
+        }
+        ColumnType::JSON => true,
+        ColumnType::UNKNOWN => false,
+    }
+}
+
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a.as_f64(), b.as_f64()) {
+        (Some(a), Some(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::sensor::ColumnIngest;
+    use crate::features::sensor_data_storage::SensorDataStorageType;
+    use serde_json::json;
+
+    fn column(name: &str, val_type: ColumnType, constraints: Value) -> SensorColumn {
+        SensorColumn {
+            name: name.to_string(),
+            val_type,
+            val_unit: "".to_string(),
+            val_ingest: ColumnIngest::LITERAL,
+            constraints: Some(serde_json::from_value(constraints).unwrap()),
+        }
+    }
+
+    fn sensor(columns: Vec<SensorColumn>) -> FullSensorInfo {
+        FullSensorInfo {
+            id: uuid::Uuid::new_v4(),
+            name: "MySensor".to_string(),
+            tbl_name: "".to_string(),
+            position: None,
+            description: None,
+            owner: None,
+            columns,
+            permissions: vec![],
+            storage_type: SensorDataStorageType::Default,
+            storage_params: None,
+            storage_dedup: None,
+        }
+    }
+
+    #[test]
+    fn test_constraint_definitions() {
+        let valid = [
+            column(
+                "c",
+                ColumnType::INT,
+                json!({"min": 0, "max": 10, "required": true}),
+            ),
+            column("c", ColumnType::FLOAT, json!({"allowed": [1.5, 2]})),
+            column(
+                "c",
+                ColumnType::STRING,
+                json!({"regex": "^[A-Z]+$", "allowed": ["A", "B"]}),
+            ),
+            column("c", ColumnType::BOOL, json!({"required": true})),
+        ];
+
+        for col in valid.iter() {
+            assert!(validate_col_constraints(col).is_ok());
+        }
+
+        let invalid = [
+            column("c", ColumnType::STRING, json!({"min": 0})),
+            column("c", ColumnType::INT, json!({"min": 10, "max": 0})),
+            column("c", ColumnType::INT, json!({"regex": "^1$"})),
+            column("c", ColumnType::STRING, json!({"regex": "(unclosed"})),
+            column("c", ColumnType::INT, json!({"allowed": [1, "two"]})),
+        ];
+
+        for col in invalid.iter() {
+            assert!(validate_col_constraints(col).is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_entries() {
+        let sensor = sensor(vec![
+            column("temp", ColumnType::FLOAT, json!({"min": -50, "max": 60})),
+            column(
+                "count",
+                ColumnType::INT,
+                json!({"min": 0, "required": true}),
+            ),
+            column(
+                "state",
+                ColumnType::STRING,
+                json!({"allowed": ["on", "off"]}),
+            ),
+            column("code", ColumnType::STRING, json!({"regex": "^[A-Z]{3}$"})),
+        ]);
+
+        let entry = |v: Value| SensorDataIngestEntry::from_json(v, None);
+
+        // Valid entries, optional columns may be omitted
+        let data = [
+            entry(json!({"temp": 21.5, "count": 1, "state": "on", "code": "ABC"})),
+            entry(json!({"count": 0})),
+        ];
+
+        assert!(validate_entries(&sensor, &data).is_ok());
+
+        // Each violation is reported per entry and column
+        let data = [
+            entry(json!({"temp": -999, "count": 1})),
+            entry(json!({"temp": 20})),
+            entry(json!({"count": -1, "state": "maybe"})),
+            entry(json!({"count": "1", "code": "abc"})),
+        ];
+
+        let errs = validate_entries(&sensor, &data).unwrap_err().0;
+
+        assert_eq!(errs.len(), 6);
+        assert!(errs[0].starts_with("entry 0: column 'temp'"));
+        assert!(errs[1].starts_with("entry 1: column 'count'"));
+        assert!(errs[2].starts_with("entry 2: column 'count'"));
+        assert!(errs[3].starts_with("entry 2: column 'state'"));
+        assert!(errs[4].starts_with("entry 3: column 'count'"));
+        assert!(errs[5].starts_with("entry 3: column 'code'"));
+    }
+}
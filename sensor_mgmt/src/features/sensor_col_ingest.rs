@@ -16,7 +16,7 @@ pub async fn register_sensor_col_ingest(
     }
 }
 
-// Currently, we do not support modification of data columns (only call this function on sensor deletion)
+// Called on sensor deletion and on schema modifications (drop, rename, retype) of a data column
 pub async fn unregister_sensor_col_ingest(
     ingest_mode: ColumnIngest,
     table_name: String,
@@ -31,6 +31,12 @@ pub async fn unregister_sensor_col_ingest(
 
 // ------------------------------------------ Incremental ------------------------------------------
 
+/// Hidden column storing the raw (delta) values of an incremental column, used to re-accumulate
+/// the cumulative values when historical data is inserted, updated or deleted.
+pub fn incremental_delta_col(col_name: &str) -> String {
+    format!("\"incr${}\"", col_name)
+}
+
 async fn on_register_incremental(
     table_name: String,
     col_name: String,
@@ -86,13 +92,14 @@ pub mod tests {
     use std::sync::Arc;
 
     use crate::database::data_db;
-    use crate::database::models::db_structs::DBOperation;
+    use crate::database::models::db_structs::{DBOperation, DBOrdering};
     use crate::database::models::role::ROLE_SYSTEM_GUEST;
     use crate::database::models::sensor::{ColumnIngest, ColumnType, SensorColumn};
     use crate::features::cache;
     use crate::features::sensor_data_storage::{SensorDataStorageCfg, SensorDataStorageType};
     use crate::handler::models::requests::{
-        CreateSensorRequest, DataLoadRequestParams, SensorDataIngestEntry, SensorPermissionRequest,
+        CreateSensorRequest, DataLoadRequestParams, SensorDataDeletionParams,
+        SensorDataIngestEntry, SensorPermissionRequest,
     };
     use crate::handler::models::responses::GenericUuidResponse;
     use crate::state::AppState;
@@ -101,6 +108,7 @@ pub mod tests {
     };
     use actix_http::{Method, StatusCode};
     use async_std::task;
+    use chrono::{NaiveDateTime, TimeDelta};
     use serde_json::{json, Value};
     use sqlx::PgPool;
     use uuid::Uuid;
@@ -210,29 +218,34 @@ pub mod tests {
                     val_type: ColumnType::INT,
                     val_unit: "unit_1".to_string(),
                     val_ingest: ColumnIngest::INCREMENTAL,
+                    constraints: None,
                 },
                 SensorColumn {
                     name: "col2".to_string(),
                     val_type: ColumnType::FLOAT,
                     val_unit: "unit_2".to_string(),
                     val_ingest: ColumnIngest::INCREMENTAL,
+                    constraints: None,
                 },
                 SensorColumn {
                     name: "col3".to_string(),
                     val_type: ColumnType::STRING,
                     val_unit: "unit_3".to_string(),
                     val_ingest: ColumnIngest::INCREMENTAL,
+                    constraints: None,
                 },
                 SensorColumn {
                     name: "col4".to_string(),
                     val_type: ColumnType::INT,
                     val_unit: "unit_4".to_string(),
                     val_ingest: ColumnIngest::LITERAL,
+                    constraints: None,
                 },
             ],
             storage: SensorDataStorageCfg {
                 variant: SensorDataStorageType::Default,
                 params: None,
+                dedup: None,
             },
         };
 
@@ -416,4 +429,168 @@ pub mod tests {
         )
         .await;
     }
+
+    async fn ingest_at(
+        start: NaiveDateTime,
+        sensor_id: Uuid,
+        state: &AppState,
+        entries: &[(i64, i64, &str)],
+    ) {
+        let entries: Vec<SensorDataIngestEntry> = entries
+            .iter()
+            .map(|(secs, col1, col3)| {
+                SensorDataIngestEntry::from_json(
+                    json!({"col1": col1, "col3": col3}),
+                    Some(start + TimeDelta::seconds(*secs)),
+                )
+            })
+            .collect();
+
+        let sensor = Arc::new(cache::request_sensor(sensor_id, state).await.unwrap());
+
+        data_db::add_sensor_data(sensor, &entries, state.clone())
+            .await
+            .unwrap();
+    }
+
+    async fn load_incremental(sensor_id: Uuid, state: &AppState) -> Vec<(i64, String)> {
+        let params = DataLoadRequestParams {
+            ordering: Some(DBOrdering::ASC),
+            ..Default::default()
+        };
+
+        let data = data_db::get_data(sensor_id, params, state).await.unwrap();
+
+        data.as_array()
+            .unwrap()
+            .iter()
+            .map(|elm| {
+                (
+                    elm["col1"].as_i64().unwrap(),
+                    elm["col3"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures("../handler/fixtures/users.sql", "../handler/fixtures/roles.sql")
+    )]
+    async fn test_incremental_backfill(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let token = login(&john(), &state).await;
+
+        let sensor_created = CreateSensorRequest {
+            name: "MyIncrementalSensor".to_string(),
+            description: None,
+            position: None,
+            permissions: vec![],
+            columns: vec![
+                SensorColumn {
+                    name: "col1".to_string(),
+                    val_type: ColumnType::INT,
+                    val_unit: "unit_1".to_string(),
+                    val_ingest: ColumnIngest::INCREMENTAL,
+                    constraints: None,
+                },
+                SensorColumn {
+                    name: "col3".to_string(),
+                    val_type: ColumnType::STRING,
+                    val_unit: "unit_3".to_string(),
+                    val_ingest: ColumnIngest::INCREMENTAL,
+                    constraints: None,
+                },
+            ],
+            storage: SensorDataStorageCfg {
+                variant: SensorDataStorageType::Default,
+                params: None,
+                dedup: None,
+            },
+        };
+
+        let body = execute_request(
+            "/api/sensors/create",
+            Method::POST,
+            None,
+            Some(sensor_created),
+            Some(token.clone()),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let resp: GenericUuidResponse = serde_json::from_value(body).unwrap();
+        let sensor_id = Uuid::parse_str(&resp.uuid).unwrap();
+
+        let start =
+            NaiveDateTime::parse_from_str("2026-01-01T00:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        // Appended, back-filled and unordered batches accumulate in the order of their timestamps
+
+        ingest_at(start, sensor_id, &state, &[(0, 1, "a")]).await;
+        ingest_at(start, sensor_id, &state, &[(20, 4, "d")]).await;
+        ingest_at(start, sensor_id, &state, &[(10, 2, "b")]).await;
+        ingest_at(start, sensor_id, &state, &[(30, 8, "e"), (5, 16, "c")]).await;
+
+        let expected = vec![
+            (1, "a"),
+            (17, "ac"),
+            (19, "acb"),
+            (23, "acbd"),
+            (31, "acbde"),
+        ];
+        let expected: Vec<(i64, String)> = expected
+            .into_iter()
+            .map(|(v, s)| (v, s.to_string()))
+            .collect();
+        assert_eq!(load_incremental(sensor_id, &state).await, expected);
+
+        // Deleting historical data re-accumulates all following rows
+
+        let deletion = SensorDataDeletionParams {
+            from: Some(start + TimeDelta::seconds(5)),
+            to: Some(start + TimeDelta::seconds(5)),
+            ..Default::default()
+        };
+
+        data_db::delete_sensor_data(sensor_id, deletion, &state)
+            .await
+            .unwrap();
+
+        let expected = vec![(1, "a"), (3, "ab"), (7, "abd"), (15, "abde")];
+        let expected: Vec<(i64, String)> = expected
+            .into_iter()
+            .map(|(v, s)| (v, s.to_string()))
+            .collect();
+        assert_eq!(load_incremental(sensor_id, &state).await, expected);
+
+        // Rows with equal timestamps share the same cumulative value
+
+        ingest_at(start, sensor_id, &state, &[(10, 32, "x")]).await;
+
+        let totals: Vec<i64> = load_incremental(sensor_id, &state)
+            .await
+            .into_iter()
+            .map(|(v, _)| v)
+            .collect();
+        assert_eq!(totals, vec![1, 35, 35, 39, 47]);
+
+        // Appended batches accumulate regardless of their order and equal timestamps
+
+        ingest_at(start, sensor_id, &state, &[(40, 64, "f"), (50, 128, "g")]).await;
+        ingest_at(start, sensor_id, &state, &[(70, 1, "i"), (60, 2, "h")]).await;
+        ingest_at(start, sensor_id, &state, &[(80, 4, "j"), (80, 8, "k")]).await;
+
+        let totals: Vec<i64> = load_incremental(sensor_id, &state)
+            .await
+            .into_iter()
+            .map(|(v, _)| v)
+            .collect();
+        assert_eq!(
+            totals,
+            vec![1, 35, 35, 39, 47, 111, 239, 241, 242, 254, 254]
+        );
+    }
 }
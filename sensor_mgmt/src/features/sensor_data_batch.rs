@@ -0,0 +1,514 @@
+use crate::database::data_db::add_sensor_data;
+use crate::database::models::sensor::FullSensorInfo;
+use crate::features::config::{
+    get_ingest_batch_max_delay_ms, get_ingest_batch_max_rows, get_ingest_queue_capacity,
+    ServerConfig,
+};
+use crate::features::sensor_data_dedup;
+use crate::handler::models::requests::SensorDataIngestEntry;
+use crate::state::AppState;
+use anyhow::anyhow;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/*
+    Batched ingest
+
+    Each sensor has a bounded queue of submitted data entries, written by its own writer task.
+    The writer coalesces the queued submissions of all transports into multi-row inserts,
+    a batch is flushed once it reaches the maximum number of rows or the maximum delay
+    since its first submission has passed.
+    When the queue of a sensor is full, submitting waits until the writer catches up,
+    which slows down the HTTP clients and the MQTT subscriber instead of buffering without bounds.
+    If a batch fails, its submissions are inserted one by one, so that each submitter
+    receives the result of its own entries.
+*/
+
+/// Writers without submissions for this duration are stopped
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/* ------------------------------------------------ API ------------------------------------------------------------ */
+
+/// Result of a submission, describing the batch it was written with
+#[derive(Debug, Clone, PartialEq)]
+pub struct IngestBatchResult {
+    /// Number of rows inserted for the submission
+    pub rows: usize,
+    /// Number of rows inserted by the batch
+    pub batch_rows: usize,
+    /// Number of submissions written by the batch
+    pub batch_submissions: usize,
+}
+
+/// Totals of all written batches
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IngestBatchStats {
+    pub batches: u64,
+    pub rows: u64,
+    // Batches that failed and were retried submission by submission
+    pub failed_batches: u64,
+}
+
+/// A submission waiting for its batch to be written
+pub struct PendingIngest(oneshot::Receiver<anyhow::Result<IngestBatchResult>>);
+
+impl PendingIngest {
+    /// Waits until the batch containing the submission has been written.
+    pub async fn result(self) -> anyhow::Result<IngestBatchResult> {
+        self.0
+            .await
+            .map_err(|_| anyhow!("ingest writer stopped before writing the entries"))?
+    }
+}
+
+/// External entrypoint to submit data entries to the per sensor writers
+pub struct IngestBatcher {
+    max_rows: usize,
+    max_delay: Duration,
+    capacity: usize,
+
+    queues: Mutex<HashMap<Uuid, Sender<Submission>>>,
+    stats: Arc<BatchCounters>,
+}
+
+impl IngestBatcher {
+    pub fn new(cfg: &ServerConfig) -> Self {
+        IngestBatcher {
+            max_rows: get_ingest_batch_max_rows(cfg).max(1),
+            max_delay: Duration::from_millis(get_ingest_batch_max_delay_ms(cfg)),
+            capacity: get_ingest_queue_capacity(cfg).max(1),
+            queues: Mutex::new(HashMap::new()),
+            stats: Arc::new(BatchCounters::default()),
+        }
+    }
+
+    /// Queues the entries for the writer of the sensor, waits while its queue is full.
+    /// Submissions of a sensor are written in the order they were queued.
+    pub async fn enqueue(
+        &self,
+        sensor: Arc<FullSensorInfo>,
+        entries: Vec<SensorDataIngestEntry>,
+        state: &AppState,
+    ) -> PendingIngest {
+        let (tx, rx) = oneshot::channel();
+        let mut submission = Submission {
+            sensor,
+            entries,
+            reply: tx,
+        };
+
+        loop {
+            let queue = self.queue(submission.sensor.id, state);
+
+            match queue.send(submission).await {
+                Ok(()) => break,
+                // The writer stopped after being idle, the next try starts a new one
+                Err(mpsc::error::SendError(returned)) => submission = returned,
+            }
+        }
+
+        PendingIngest(rx)
+    }
+
+    /// Queues the entries and waits until they have been written.
+    pub async fn submit(
+        &self,
+        sensor: Arc<FullSensorInfo>,
+        entries: Vec<SensorDataIngestEntry>,
+        state: &AppState,
+    ) -> anyhow::Result<IngestBatchResult> {
+        self.enqueue(sensor, entries, state).await.result().await
+    }
+
+    pub fn stats(&self) -> IngestBatchStats {
+        IngestBatchStats {
+            batches: self.stats.batches.load(Ordering::Relaxed),
+            rows: self.stats.rows.load(Ordering::Relaxed),
+            failed_batches: self.stats.failed_batches.load(Ordering::Relaxed),
+        }
+    }
+
+    // Returns the queue of the sensor, starts its writer if there is none running
+    fn queue(&self, sensor_id: Uuid, state: &AppState) -> Sender<Submission> {
+        let mut queues = self.queues.lock().unwrap();
+
+        if let Some(queue) = queues.get(&sensor_id) {
+            if !queue.is_closed() {
+                return queue.clone();
+            }
+        }
+
+        let (tx, rx) = mpsc::channel(self.capacity);
+        let writer = BatchWriter {
+            sensor_id,
+            max_rows: self.max_rows,
+            max_delay: self.max_delay,
+            stats: self.stats.clone(),
+            state: state.clone(),
+        };
+        tokio::task::spawn(writer.run(rx));
+
+        queues.insert(sensor_id, tx.clone());
+
+        tx
+    }
+}
+
+/* ------------------------------------------------ Writer ------------------------------------------------------------ */
+
+struct Submission {
+    sensor: Arc<FullSensorInfo>,
+    entries: Vec<SensorDataIngestEntry>,
+    reply: oneshot::Sender<anyhow::Result<IngestBatchResult>>,
+}
+
+#[derive(Default)]
+struct BatchCounters {
+    batches: AtomicU64,
+    rows: AtomicU64,
+    failed_batches: AtomicU64,
+}
+
+struct BatchWriter {
+    sensor_id: Uuid,
+    max_rows: usize,
+    max_delay: Duration,
+    stats: Arc<BatchCounters>,
+    state: AppState,
+}
+
+impl BatchWriter {
+    async fn run(self, mut rx: Receiver<Submission>) {
+        // Submission that didn't fit into the previous batch
+        let mut carry: Option<Submission> = None;
+
+        loop {
+            let first = match carry.take() {
+                Some(submission) => submission,
+                None => match tokio::time::timeout(IDLE_TIMEOUT, rx.recv()).await {
+                    Ok(Some(submission)) => submission,
+                    Ok(None) => break,
+                    Err(_) => {
+                        // Already queued submissions are still received after closing
+                        debug!("[BATCH] stopping idle writer of {}", self.sensor_id);
+                        rx.close();
+                        continue;
+                    }
+                },
+            };
+
+            // Collect submissions until the batch is full or the delay has passed
+            let deadline = Instant::now() + self.max_delay;
+            let mut rows = first.entries.len();
+            let mut batch = vec![first];
+
+            while rows < self.max_rows {
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(submission)) if rows + submission.entries.len() > self.max_rows => {
+                        carry = Some(submission);
+                        break;
+                    }
+                    Ok(Some(submission)) => {
+                        rows += submission.entries.len();
+                        batch.push(submission);
+                    }
+                    _ => break,
+                }
+            }
+
+            // Submissions queued before and after a schema change can't share an insert
+            while !batch.is_empty() {
+                let split = batch
+                    .iter()
+                    .position(|s| !same_schema(&s.sensor, &batch[0].sensor))
+                    .unwrap_or(batch.len());
+                let rest = batch.split_off(split);
+
+                self.write(batch).await;
+
+                batch = rest;
+            }
+        }
+    }
+
+    async fn write(&self, batch: Vec<Submission>) {
+        let sensor = batch[0].sensor.clone();
+        let submissions = batch.len();
+
+        let entries: Vec<SensorDataIngestEntry> = batch
+            .iter()
+            .flat_map(|s| s.entries.iter().cloned())
+            .collect();
+
+        let entries_of: Vec<&[SensorDataIngestEntry]> =
+            batch.iter().map(|s| s.entries.as_slice()).collect();
+
+        let res = match submissions {
+            1 => None,
+            // Duplicates of other submissions are only detected one by one
+            _ if !sensor_data_dedup::can_coalesce(&sensor, &entries_of) => None,
+            _ => Some(add_sensor_data(sensor, &entries, self.state.clone()).await),
+        };
+
+        match res {
+            Some(Ok(())) => {
+                self.record(entries.len());
+
+                debug!(
+                    "[BATCH] {} wrote {} rows of {} submissions",
+                    self.sensor_id,
+                    entries.len(),
+                    submissions
+                );
+
+                for s in batch {
+                    let _ = s.reply.send(Ok(IngestBatchResult {
+                        rows: s.entries.len(),
+                        batch_rows: entries.len(),
+                        batch_submissions: submissions,
+                    }));
+                }
+            }
+            // Single submissions and failed batches are written one by one
+            res => {
+                if let Some(Err(err)) = res {
+                    self.stats.failed_batches.fetch_add(1, Ordering::Relaxed);
+                    warn!(
+                        "[BATCH] {} failed to write {} submissions at once: {}",
+                        self.sensor_id, submissions, err
+                    );
+                }
+
+                for s in batch {
+                    let res = add_sensor_data(s.sensor, &s.entries, self.state.clone())
+                        .await
+                        .map(|_| {
+                            self.record(s.entries.len());
+
+                            IngestBatchResult {
+                                rows: s.entries.len(),
+                                batch_rows: s.entries.len(),
+                                batch_submissions: 1,
+                            }
+                        });
+                    let _ = s.reply.send(res);
+                }
+            }
+        }
+    }
+
+    fn record(&self, rows: usize) {
+        self.stats.batches.fetch_add(1, Ordering::Relaxed);
+        self.stats.rows.fetch_add(rows as u64, Ordering::Relaxed);
+    }
+}
+
+/* ------------------------------------------------ Helper functions ------------------------------------------------------------ */
+
+// Entries are bound by column name, validated against the constraints of the columns and deduplicated
+fn same_schema(a: &FullSensorInfo, b: &FullSensorInfo) -> bool {
+    a.tbl_name == b.tbl_name
+        && a.storage_dedup == b.storage_dedup
+        && a.columns.len() == b.columns.len()
+        && a.columns.iter().zip(b.columns.iter()).all(|(a, b)| {
+            a.name == b.name && a.val_type == b.val_type && a.constraints == b.constraints
+        })
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::features::cache;
+    use crate::test_utils::tests::{create_test_app_with_config, create_test_sensors};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    fn entry(col1: i64) -> SensorDataIngestEntry {
+        SensorDataIngestEntry {
+            timestamp: None,
+            data: HashMap::from([("col1".to_string(), json!(col1))]),
+        }
+    }
+
+    async fn test_sensor(state: &AppState) -> Arc<FullSensorInfo> {
+        let test_sens = create_test_sensors(state).await;
+        let sensor_id = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor")
+            .unwrap()
+            .1;
+
+        Arc::new(cache::request_sensor(sensor_id, state).await.unwrap())
+    }
+
+    async fn count_rows(sensor: &FullSensorInfo, state: &AppState) -> i64 {
+        sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", sensor.tbl_name))
+            .fetch_one(&state.db)
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../handler/fixtures/users.sql",
+            "../handler/fixtures/roles.sql",
+            "../handler/fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_ingest_batches(pool: PgPool) {
+        let cfg = serde_yml::from_str(
+            "server:\n  ingest_batch_max_rows: 4\n  ingest_batch_max_delay_ms: 200",
+        )
+        .unwrap();
+        let (_, state) = create_test_app_with_config(pool, cfg).await;
+
+        let sensor = test_sensor(&state).await;
+
+        // --- Submissions are coalesced up to the maximum rows of a batch ---
+
+        let pending = {
+            let mut pending = Vec::new();
+            for i in 0..6 {
+                pending.push(
+                    state
+                        .ingest_batcher
+                        .enqueue(sensor.clone(), vec![entry(i)], &state)
+                        .await,
+                );
+            }
+            pending
+        };
+
+        let mut results = Vec::new();
+        for p in pending {
+            results.push(p.result().await.unwrap());
+        }
+
+        assert!(results.iter().all(|r| r.rows == 1));
+        assert_eq!(results[0].batch_rows, 4);
+        assert_eq!(results[0].batch_submissions, 4);
+        assert_eq!(results[5].batch_rows, 2);
+        assert_eq!(count_rows(&sensor, &state).await, 6);
+
+        // Submissions exceeding the remaining rows of the batch are written with the next one
+        let pending = {
+            let mut pending = Vec::new();
+            for rows in [3, 3] {
+                let entries = (0..rows).map(entry).collect();
+                pending.push(
+                    state
+                        .ingest_batcher
+                        .enqueue(sensor.clone(), entries, &state)
+                        .await,
+                );
+            }
+            pending
+        };
+        for p in pending {
+            let res = p.result().await.unwrap();
+            assert_eq!(res.batch_rows, 3);
+            assert_eq!(res.batch_submissions, 1);
+        }
+
+        // --- Failing submissions don't affect the others of their batch ---
+
+        let invalid = SensorDataIngestEntry {
+            timestamp: None,
+            data: HashMap::from([("unknown".to_string(), json!(1))]),
+        };
+
+        let valid = state
+            .ingest_batcher
+            .enqueue(sensor.clone(), vec![entry(1)], &state)
+            .await;
+        let failing = state
+            .ingest_batcher
+            .enqueue(sensor.clone(), vec![invalid], &state)
+            .await;
+
+        assert_eq!(valid.result().await.unwrap().batch_submissions, 1);
+        assert!(failing.result().await.is_err());
+        assert_eq!(count_rows(&sensor, &state).await, 13);
+
+        let stats = state.ingest_batcher.stats();
+        assert_eq!(stats.failed_batches, 1);
+        assert_eq!(stats.rows, 13);
+    }
+
+    /// Messages per second of concurrent single entry submissions,
+    /// inserted one by one and with the batch writer.
+    /// Run with `cargo test bench_ingest_throughput -- --ignored --nocapture`
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../handler/fixtures/users.sql",
+            "../handler/fixtures/roles.sql",
+            "../handler/fixtures/user_roles.sql"
+        )
+    )]
+    #[ignore]
+    async fn bench_ingest_throughput(pool: PgPool) {
+        const MESSAGES: usize = 5000;
+        const PRODUCERS: usize = 50;
+
+        let cfg = serde_yml::from_str("server:\n  ingest_batch_max_delay_ms: 5").unwrap();
+        let (_, state) = create_test_app_with_config(pool, cfg).await;
+
+        let sensor = test_sensor(&state).await;
+
+        let run = |batched: bool| {
+            let state = state.clone();
+            let sensor = sensor.clone();
+
+            async move {
+                let start = std::time::Instant::now();
+
+                let producers = (0..PRODUCERS).map(|p| {
+                    let state = state.clone();
+                    let sensor = sensor.clone();
+
+                    tokio::spawn(async move {
+                        for i in 0..MESSAGES / PRODUCERS {
+                            let data = vec![entry((p * i) as i64)];
+                            match batched {
+                                true => state
+                                    .ingest_batcher
+                                    .submit(sensor.clone(), data, &state)
+                                    .await
+                                    .map(|_| ()),
+                                false => {
+                                    add_sensor_data(sensor.clone(), &data, state.clone()).await
+                                }
+                            }
+                            .unwrap();
+                        }
+                    })
+                });
+                for producer in futures_util::future::join_all(producers).await {
+                    producer.unwrap();
+                }
+
+                MESSAGES as f64 / start.elapsed().as_secs_f64()
+            }
+        };
+
+        let before = run(false).await;
+        let after = run(true).await;
+
+        println!("single inserts: {:.0} msg/s", before);
+        println!("batched inserts: {:.0} msg/s", after);
+        println!("{:?}", state.ingest_batcher.stats());
+
+        assert_eq!(count_rows(&sensor, &state).await, 2 * MESSAGES as i64);
+    }
+}
@@ -0,0 +1,556 @@
+use crate::database::models::sensor::{ColumnIngest, FullSensorInfo};
+use crate::features::config::TIMESTAMP_FORMAT;
+use crate::features::sensor_col_ingest::incremental_delta_col;
+use crate::handler::models::requests::SensorDataIngestEntry;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgConnection;
+use std::collections::{HashMap, HashSet};
+use utoipa::ToSchema;
+
+/*
+    Duplicate detection
+
+    Gateways retry their requests after timeouts, which would store the same data twice.
+    Sensors may drop duplicated entries in one of two modes:
+    - message_id: clients attach an id to their messages, entries with an id that has been
+      ingested within the window are dropped. The ids are recorded in the same transaction
+      as the data, thus a failed insert can be retried with the same id.
+    - timestamp: the timestamp identifies an entry, a unique index on created_at rejects
+      entries with a stored timestamp, they are either dropped or replace the stored entry.
+*/
+
+/// Field of the data entries holding their message id
+pub const MESSAGE_ID_FIELD: &str = "message_id";
+
+const DEFAULT_WINDOW_SECS: u32 = 3600;
+
+fn default_window_secs() -> u32 {
+    DEFAULT_WINDOW_SECS
+}
+
+/// Duplicate detection of the ingested data entries of a sensor
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum SensorDataDedup {
+    /// Drops entries whose message id has been ingested within the last window_secs seconds (default: 3600)
+    MessageId {
+        #[serde(default = "default_window_secs")]
+        window_secs: u32,
+    },
+    /// Entries with the timestamp of a stored entry are dropped or replace it
+    Timestamp {
+        #[serde(default)]
+        on_conflict: DedupConflict,
+    },
+}
+
+/// Handling of entries with the timestamp of a stored entry
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DedupConflict {
+    /// The entry is dropped
+    #[default]
+    Ignore,
+    /// The entry replaces the stored entry
+    Update,
+}
+
+// ------------------------------------------ Initialize -------------------------------------------
+
+/// Called when the dedup mode is set for a sensor.
+pub async fn register_sensor_data_dedup(
+    dedup: &Option<SensorDataDedup>,
+    table_name: &str,
+    ex: &mut PgConnection,
+) -> Result<()> {
+    match dedup {
+        None => Ok(()),
+        Some(SensorDataDedup::MessageId { window_secs }) => {
+            if *window_secs == 0 {
+                anyhow::bail!("Dedup window must be at least one second!");
+            }
+
+            Ok(())
+        }
+        Some(SensorDataDedup::Timestamp { .. }) => {
+            let res = sqlx::query(&format!(
+                "CREATE UNIQUE INDEX {} ON {} (created_at)",
+                unique_index(table_name),
+                table_name
+            ))
+            .execute(&mut *ex)
+            .await;
+
+            if let Err(err) = res {
+                anyhow::bail!(
+                    "Couldn't enable timestamp dedup, the stored data must not contain duplicated timestamps: {}",
+                    err
+                );
+            }
+
+            Ok(())
+        }
+    }
+}
+
+/// Called when the dedup mode is removed from a sensor.
+pub async fn unregister_sensor_data_dedup(
+    sensor_id: uuid::Uuid,
+    dedup: &Option<SensorDataDedup>,
+    table_name: &str,
+    ex: &mut PgConnection,
+) -> Result<()> {
+    match dedup {
+        None => {}
+        Some(SensorDataDedup::MessageId { .. }) => {
+            sqlx::query("DELETE FROM sensor_ingest_message WHERE sensor_id = $1")
+                .bind(sensor_id)
+                .execute(&mut *ex)
+                .await?;
+        }
+        Some(SensorDataDedup::Timestamp { .. }) => {
+            sqlx::query(&format!(
+                "DROP INDEX IF EXISTS {}",
+                unique_index(table_name)
+            ))
+            .execute(&mut *ex)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// ------------------------------------------- Ingestion -------------------------------------------
+
+/// Assigns the message id of a request to its entries that don't provide their own.
+pub fn assign_message_id(data: &mut [SensorDataIngestEntry], message_id: &str) {
+    for entry in data.iter_mut() {
+        entry
+            .data
+            .entry(MESSAGE_ID_FIELD.to_string())
+            .or_insert_with(|| Value::String(message_id.to_string()));
+    }
+}
+
+/// Returns the entries that are no duplicates, message ids are recorded within the transaction.
+pub async fn filter_duplicates<'a>(
+    sensor: &FullSensorInfo,
+    data: &'a [SensorDataIngestEntry],
+    ex: &mut PgConnection,
+) -> Result<Vec<&'a SensorDataIngestEntry>> {
+    match &sensor.storage_dedup {
+        None => Ok(data.iter().collect()),
+        Some(SensorDataDedup::MessageId { window_secs }) => {
+            let ids: Vec<String> = data
+                .iter()
+                .filter_map(message_id)
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+
+            if ids.is_empty() {
+                return Ok(data.iter().collect());
+            }
+
+            sqlx::query(
+                "DELETE FROM sensor_ingest_message WHERE sensor_id = $1 AND received_at < CURRENT_TIMESTAMP - make_interval(secs => $2)",
+            )
+            .bind(sensor.id)
+            .bind(*window_secs as f64)
+            .execute(&mut *ex)
+            .await?;
+
+            // Ids recorded by concurrent transactions are skipped once these commit
+            let fresh: HashSet<String> = sqlx::query_scalar(
+                "INSERT INTO sensor_ingest_message (sensor_id, message_id) SELECT $1, unnest($2::text[]) ON CONFLICT DO NOTHING RETURNING message_id",
+            )
+            .bind(sensor.id)
+            .bind(&ids)
+            .fetch_all(&mut *ex)
+            .await?
+            .into_iter()
+            .collect();
+
+            Ok(data
+                .iter()
+                .filter(|entry| message_id(entry).is_none_or(|id| fresh.contains(&id)))
+                .collect())
+        }
+        Some(SensorDataDedup::Timestamp { on_conflict }) => {
+            // A row can't be affected twice by one insert, entries without timestamp share the insert time
+            let mut kept: Vec<&SensorDataIngestEntry> = Vec::with_capacity(data.len());
+            let mut positions: HashMap<Option<String>, usize> = HashMap::new();
+
+            for entry in data.iter() {
+                let ts = entry
+                    .timestamp
+                    .map(|ts| ts.format(TIMESTAMP_FORMAT).to_string());
+
+                match positions.get(&ts) {
+                    Some(&pos) if *on_conflict == DedupConflict::Update => kept[pos] = entry,
+                    Some(_) => {}
+                    None => {
+                        positions.insert(ts, kept.len());
+                        kept.push(entry);
+                    }
+                }
+            }
+
+            Ok(kept)
+        }
+    }
+}
+
+/// Returns the ON CONFLICT clause of the data insert.
+pub fn on_conflict_clause(sensor: &FullSensorInfo) -> Option<String> {
+    match &sensor.storage_dedup {
+        Some(SensorDataDedup::Timestamp { on_conflict }) => Some(match on_conflict {
+            DedupConflict::Ignore => " ON CONFLICT (created_at) DO NOTHING".to_string(),
+            DedupConflict::Update => format!(
+                " ON CONFLICT (created_at) DO UPDATE SET {}",
+                sensor
+                    .columns
+                    .iter()
+                    .flat_map(|col| {
+                        let mut set = vec![format!("{0} = EXCLUDED.{0}", col.name)];
+
+                        // The raw values of incremental columns are replaced as well
+                        if col.val_ingest == ColumnIngest::INCREMENTAL {
+                            set.push(format!(
+                                "{0} = EXCLUDED.{0}",
+                                incremental_delta_col(&col.name)
+                            ));
+                        }
+
+                        set
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }),
+        _ => None,
+    }
+}
+
+/// Checks if the submissions can be inserted at once with the same result as one by one.
+pub fn can_coalesce(sensor: &FullSensorInfo, submissions: &[&[SensorDataIngestEntry]]) -> bool {
+    match &sensor.storage_dedup {
+        None => true,
+        // Retries of a message must see the id recorded by the first one
+        Some(SensorDataDedup::MessageId { .. }) => {
+            let mut seen: HashSet<String> = HashSet::new();
+
+            submissions.iter().all(|entries| {
+                let ids: HashSet<String> = entries.iter().filter_map(message_id).collect();
+                let unique = ids.is_disjoint(&seen);
+                seen.extend(ids);
+                unique
+            })
+        }
+        // Entries without timestamp of different submissions would share the insert time
+        Some(SensorDataDedup::Timestamp { .. }) => submissions
+            .iter()
+            .all(|entries| entries.iter().all(|entry| entry.timestamp.is_some())),
+    }
+}
+
+/* ------------------------------------------------ Helper functions ------------------------------------------------------------ */
+
+fn message_id(entry: &SensorDataIngestEntry) -> Option<String> {
+    match entry.data.get(MESSAGE_ID_FIELD) {
+        Some(Value::String(id)) => Some(id.clone()),
+        Some(Value::Number(id)) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn unique_index(table_name: &str) -> String {
+    format!("{}_created_at_key", table_name)
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::db_structs::DBOperation;
+    use crate::database::sensor_db;
+    use crate::features::cache;
+    use crate::features::sensor_data_storage::{SensorDataStorageCfg, SensorDataStorageType};
+    use crate::handler::data_ingest::ingest::insert_entries;
+    use crate::handler::models::requests::EditSensorRequest;
+    use crate::state::AppState;
+    use crate::test_utils::tests::{create_test_api_keys, create_test_app, create_test_sensors};
+    use actix_http::StatusCode;
+    use actix_web::test;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use std::sync::Arc;
+    use uuid::Uuid;
+
+    async fn set_dedup(
+        sensor_id: Uuid,
+        dedup: Option<SensorDataDedup>,
+        state: &AppState,
+    ) -> anyhow::Result<()> {
+        let req = EditSensorRequest {
+            name: "MySensor".to_string(),
+            description: None,
+            position: None,
+            permissions: vec![],
+            storage: SensorDataStorageCfg {
+                variant: SensorDataStorageType::Default,
+                params: None,
+                dedup,
+            },
+        };
+
+        sensor_db::edit_sensor(sensor_id, req, state).await
+    }
+
+    async fn col1_values(sensor_id: Uuid, state: &AppState) -> Vec<Option<i32>> {
+        let sensor = cache::request_sensor(sensor_id, state).await.unwrap();
+
+        sqlx::query_scalar(&format!(
+            "SELECT col1 FROM {} ORDER BY created_at",
+            sensor.tbl_name
+        ))
+        .fetch_all(&state.db)
+        .await
+        .unwrap()
+    }
+
+    fn entries(data: serde_json::Value) -> Vec<SensorDataIngestEntry> {
+        serde_json::from_value(data).unwrap()
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../handler/fixtures/users.sql",
+            "../handler/fixtures/roles.sql",
+            "../handler/fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_message_id_dedup(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let test_keys = create_test_api_keys(&state).await;
+
+        let sensor_id = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor")
+            .unwrap()
+            .1;
+        let key = test_keys
+            .iter()
+            .find(|k| k.sensor_id == sensor_id && k.operation == DBOperation::WRITE)
+            .unwrap()
+            .id;
+
+        assert!(set_dedup(
+            sensor_id,
+            Some(SensorDataDedup::MessageId { window_secs: 0 }),
+            &state
+        )
+        .await
+        .is_err());
+
+        let dedup: SensorDataDedup = serde_json::from_value(json!({"mode": "message_id"})).unwrap();
+        assert_eq!(
+            dedup,
+            SensorDataDedup::MessageId {
+                window_secs: DEFAULT_WINDOW_SECS
+            }
+        );
+        set_dedup(sensor_id, Some(dedup), &state).await.unwrap();
+
+        let ingest = |body: serde_json::Value, message_id: Option<&'static str>| {
+            let app = &app;
+
+            async move {
+                let mut req = test::TestRequest::post()
+                    .uri(&format!(
+                        "/api/sensors/{}/data/ingest?key={}",
+                        sensor_id, key
+                    ))
+                    .set_json(body);
+                if let Some(message_id) = message_id {
+                    req = req.insert_header(("X-Message-Id", message_id));
+                }
+                test::call_service(app, req.to_request()).await.status()
+            }
+        };
+
+        // --- Message id of the request ---
+
+        assert_eq!(
+            ingest(json!([{"col1": 1}]), Some("m1")).await,
+            StatusCode::OK
+        );
+        // Retries are acknowledged but not stored
+        assert_eq!(
+            ingest(json!([{"col1": 1}]), Some("m1")).await,
+            StatusCode::OK
+        );
+        assert_eq!(col1_values(sensor_id, &state).await, vec![Some(1)]);
+
+        // --- Message ids of the entries ---
+
+        ingest(
+            json!([{"col1": 2, "message_id": "a"}, {"col1": 3, "message_id": 7}]),
+            None,
+        )
+        .await;
+        ingest(
+            json!([{"col1": 4, "message_id": "a"}, {"col1": 5, "message_id": "b"}]),
+            Some("m1"),
+        )
+        .await;
+        // Entries without message id are never dropped
+        ingest(json!([{"col1": 6}]), None).await;
+        ingest(json!([{"col1": 6}]), None).await;
+
+        assert_eq!(
+            col1_values(sensor_id, &state).await,
+            vec![Some(1), Some(2), Some(3), Some(5), Some(6), Some(6)]
+        );
+
+        // --- Retries queued in the same batch ---
+
+        let sensor = Arc::new(cache::request_sensor(sensor_id, &state).await.unwrap());
+
+        let first = state
+            .ingest_batcher
+            .enqueue(
+                sensor.clone(),
+                entries(json!([{"col1": 7, "message_id": "c"}])),
+                &state,
+            )
+            .await;
+        let retry = state
+            .ingest_batcher
+            .enqueue(
+                sensor.clone(),
+                entries(json!([{"col1": 7, "message_id": "c"}])),
+                &state,
+            )
+            .await;
+        first.result().await.unwrap();
+        retry.result().await.unwrap();
+
+        assert_eq!(col1_values(sensor_id, &state).await.len(), 7);
+
+        // --- Disabling forgets the message ids ---
+
+        set_dedup(sensor_id, None, &state).await.unwrap();
+
+        let recorded: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM sensor_ingest_message WHERE sensor_id = $1")
+                .bind(sensor_id)
+                .fetch_one(&state.db)
+                .await
+                .unwrap();
+        assert_eq!(recorded, 0);
+
+        ingest(json!([{"col1": 1}]), Some("m1")).await;
+        assert_eq!(col1_values(sensor_id, &state).await.len(), 8);
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../handler/fixtures/users.sql",
+            "../handler/fixtures/roles.sql",
+            "../handler/fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_timestamp_dedup(pool: PgPool) {
+        let (_, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor")
+            .unwrap()
+            .1;
+
+        let insert = |data: serde_json::Value| {
+            let state = &state;
+
+            async move {
+                let sensor = Arc::new(cache::request_sensor(sensor_id, state).await.unwrap());
+                insert_entries(sensor, &entries(data), state).await.unwrap();
+            }
+        };
+
+        // --- Ignore ---
+
+        set_dedup(
+            sensor_id,
+            Some(SensorDataDedup::Timestamp {
+                on_conflict: DedupConflict::Ignore,
+            }),
+            &state,
+        )
+        .await
+        .unwrap();
+
+        insert(json!([{"timestamp": "2025-02-11T08:00:00", "col1": 1}])).await;
+        insert(json!([
+            {"timestamp": "2025-02-11T08:00:00", "col1": 2},
+            {"timestamp": "2025-02-11T08:05:00", "col1": 3},
+            {"timestamp": "2025-02-11T08:05:00", "col1": 4}
+        ]))
+        .await;
+
+        assert_eq!(col1_values(sensor_id, &state).await, vec![Some(1), Some(3)]);
+
+        // --- Update ---
+
+        set_dedup(
+            sensor_id,
+            Some(
+                serde_json::from_value(json!({"mode": "timestamp", "on_conflict": "update"}))
+                    .unwrap(),
+            ),
+            &state,
+        )
+        .await
+        .unwrap();
+
+        insert(json!([
+            {"timestamp": "2025-02-11T08:00:00", "col1": 5},
+            {"timestamp": "2025-02-11T08:00:00", "col1": 6},
+            {"timestamp": "2025-02-11T08:10:00", "col1": 7}
+        ]))
+        .await;
+
+        assert_eq!(
+            col1_values(sensor_id, &state).await,
+            vec![Some(6), Some(3), Some(7)]
+        );
+
+        // --- Disabled ---
+
+        set_dedup(sensor_id, None, &state).await.unwrap();
+
+        insert(json!([{"timestamp": "2025-02-11T08:10:00", "col1": 8}])).await;
+        assert_eq!(col1_values(sensor_id, &state).await.len(), 4);
+
+        // Stored duplicates prevent enabling it again
+        assert!(set_dedup(
+            sensor_id,
+            Some(SensorDataDedup::Timestamp {
+                on_conflict: DedupConflict::Ignore,
+            }),
+            &state
+        )
+        .await
+        .is_err());
+    }
+}
@@ -0,0 +1,298 @@
+use crate::database::data_db::TIME_COL_NAME;
+use crate::database::models::sensor::{ColumnType, SensorColumn};
+use crate::features::config::TIMESTAMP_FORMAT;
+use arrow::array::{
+    ArrayRef, BooleanArray, Float64Array, Int32Array, RecordBatch, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::ipc::writer::StreamWriter;
+use chrono::NaiveDateTime;
+use derive_more::Display;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::postgres::PgRow;
+use sqlx::Row;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// Maximal number of rows buffered by the parquet writer before a row group is written
+const PARQUET_ROW_GROUP_SIZE: usize = 100_000;
+
+/// File formats for exporting sensor data.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, Display, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DataExportFormat {
+    CSV,
+    NDJSON,
+    /// Apache Arrow IPC stream
+    ARROW,
+    /// Apache Parquet file
+    PARQUET,
+}
+
+impl DataExportFormat {
+    const ALL: [DataExportFormat; 4] = [
+        DataExportFormat::CSV,
+        DataExportFormat::NDJSON,
+        DataExportFormat::ARROW,
+        DataExportFormat::PARQUET,
+    ];
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            DataExportFormat::CSV => "text/csv",
+            DataExportFormat::NDJSON => "application/x-ndjson",
+            DataExportFormat::ARROW => "application/vnd.apache.arrow.stream",
+            DataExportFormat::PARQUET => "application/vnd.apache.parquet",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            DataExportFormat::CSV => "csv",
+            DataExportFormat::NDJSON => "ndjson",
+            DataExportFormat::ARROW => "arrows",
+            DataExportFormat::PARQUET => "parquet",
+        }
+    }
+
+    /// Returns the first supported format of the given Accept header.
+    pub fn from_accept_header(accept: &str) -> Option<DataExportFormat> {
+        accept
+            .split(',')
+            .filter_map(|media| media.split(';').next())
+            .find_map(|media| {
+                Self::ALL
+                    .into_iter()
+                    .find(|f| f.content_type().eq_ignore_ascii_case(media.trim()))
+            })
+    }
+}
+
+/// Encodes chunks of sensor data rows into the requested export format.
+/// The rows must contain the time column and the given data columns.
+pub struct DataExportEncoder {
+    columns: Vec<SensorColumn>,
+    schema: SchemaRef,
+    writer: ExportWriter,
+}
+
+enum ExportWriter {
+    Csv(Vec<u8>),
+    Ndjson(Vec<u8>),
+    Arrow(StreamWriter<Vec<u8>>),
+    Parquet(ArrowWriter<Vec<u8>>),
+}
+
+impl DataExportEncoder {
+    pub fn new(format: DataExportFormat, columns: Vec<SensorColumn>) -> anyhow::Result<Self> {
+        let schema = create_schema(&columns);
+
+        let writer = match format {
+            DataExportFormat::CSV => {
+                let mut header = csv::Writer::from_writer(Vec::new());
+                header.write_field(TIME_COL_NAME)?;
+                header.write_record(columns.iter().map(|c| c.name.as_str()))?;
+
+                ExportWriter::Csv(header.into_inner()?)
+            }
+            DataExportFormat::NDJSON => ExportWriter::Ndjson(Vec::new()),
+            DataExportFormat::ARROW => {
+                ExportWriter::Arrow(StreamWriter::try_new(Vec::new(), &schema)?)
+            }
+            DataExportFormat::PARQUET => {
+                let props = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .set_max_row_group_size(PARQUET_ROW_GROUP_SIZE)
+                    .build();
+
+                ExportWriter::Parquet(ArrowWriter::try_new(
+                    Vec::new(),
+                    schema.clone(),
+                    Some(props),
+                )?)
+            }
+        };
+
+        Ok(DataExportEncoder {
+            columns,
+            schema,
+            writer,
+        })
+    }
+
+    /// Encodes the rows, returning the bytes available for sending.
+    pub fn encode(&mut self, rows: &[PgRow]) -> anyhow::Result<Vec<u8>> {
+        match &mut self.writer {
+            ExportWriter::Csv(buf) => {
+                let mut writer = csv::Writer::from_writer(buf);
+
+                for row in rows {
+                    let record = row_to_json(row, &self.columns)?;
+
+                    let names = std::iter::once(TIME_COL_NAME)
+                        .chain(self.columns.iter().map(|c| c.name.as_str()));
+
+                    writer.write_record(names.map(|name| match &record[name] {
+                        Value::Null => String::new(),
+                        Value::String(s) => s.clone(),
+                        v => v.to_string(),
+                    }))?;
+                }
+
+                writer.flush()?;
+            }
+            ExportWriter::Ndjson(buf) => {
+                for row in rows {
+                    serde_json::to_writer(&mut *buf, &row_to_json(row, &self.columns)?)?;
+                    buf.push(b'\n');
+                }
+            }
+            ExportWriter::Arrow(writer) => {
+                writer.write(&create_record_batch(rows, &self.columns, &self.schema)?)?;
+            }
+            ExportWriter::Parquet(writer) => {
+                writer.write(&create_record_batch(rows, &self.columns, &self.schema)?)?;
+            }
+        }
+
+        Ok(self.take())
+    }
+
+    /// Finishes the export, returning the remaining bytes (e.g. the parquet footer).
+    pub fn finish(&mut self) -> anyhow::Result<Vec<u8>> {
+        match &mut self.writer {
+            ExportWriter::Arrow(writer) => writer.finish()?,
+            ExportWriter::Parquet(writer) => {
+                writer.finish()?;
+            }
+            _ => {}
+        }
+
+        Ok(self.take())
+    }
+
+    /// Drains the bytes written so far, the writers only keep track of the written length.
+    fn take(&mut self) -> Vec<u8> {
+        std::mem::take(match &mut self.writer {
+            ExportWriter::Csv(buf) | ExportWriter::Ndjson(buf) => buf,
+            ExportWriter::Arrow(writer) => writer.get_mut(),
+            ExportWriter::Parquet(writer) => writer.inner_mut(),
+        })
+    }
+}
+
+fn create_schema(columns: &[SensorColumn]) -> SchemaRef {
+    let timestamp = DataType::Timestamp(TimeUnit::Microsecond, None);
+
+    let mut fields = vec![Field::new(TIME_COL_NAME, timestamp.clone(), false)];
+
+    fields.extend(columns.iter().map(|col| {
+        let data_type = match col.val_type {
+            ColumnType::INT => DataType::Int32,
+            ColumnType::FLOAT => DataType::Float64,
+            ColumnType::BOOL => DataType::Boolean,
+            ColumnType::TIMESTAMP => timestamp.clone(),
+            // JSON values are exported as serialized strings
+            _ => DataType::Utf8,
+        };
+
+        Field::new(col.name.as_str(), data_type, true)
+    }));
+
+    Arc::new(Schema::new(fields))
+}
+
+fn create_record_batch(
+    rows: &[PgRow],
+    columns: &[SensorColumn],
+    schema: &SchemaRef,
+) -> anyhow::Result<RecordBatch> {
+    let micros = |ts: Option<NaiveDateTime>| ts.map(|ts| ts.and_utc().timestamp_micros());
+
+    let mut arrays: Vec<ArrayRef> = Vec::new();
+
+    let times = rows
+        .iter()
+        .map(|r| Ok(micros(Some(r.try_get(TIME_COL_NAME)?))))
+        .collect::<anyhow::Result<TimestampMicrosecondArray>>()?;
+
+    arrays.push(Arc::new(times));
+
+    for col in columns {
+        let name = col.name.as_str();
+
+        let array: ArrayRef = match col.val_type {
+            ColumnType::INT => Arc::new(
+                rows.iter()
+                    .map(|r| r.try_get::<Option<i32>, _>(name))
+                    .collect::<Result<Int32Array, _>>()?,
+            ),
+            ColumnType::FLOAT => Arc::new(
+                rows.iter()
+                    .map(|r| r.try_get::<Option<f64>, _>(name))
+                    .collect::<Result<Float64Array, _>>()?,
+            ),
+            ColumnType::BOOL => Arc::new(
+                rows.iter()
+                    .map(|r| r.try_get::<Option<bool>, _>(name))
+                    .collect::<Result<BooleanArray, _>>()?,
+            ),
+            ColumnType::TIMESTAMP => Arc::new(
+                rows.iter()
+                    .map(|r| Ok(micros(r.try_get::<Option<NaiveDateTime>, _>(name)?)))
+                    .collect::<anyhow::Result<TimestampMicrosecondArray>>()?,
+            ),
+            ColumnType::JSON => Arc::new(
+                rows.iter()
+                    .map(|r| Ok(r.try_get::<Option<Value>, _>(name)?.map(|v| v.to_string())))
+                    .collect::<anyhow::Result<StringArray>>()?,
+            ),
+            _ => Arc::new(
+                rows.iter()
+                    .map(|r| r.try_get::<Option<String>, _>(name))
+                    .collect::<Result<StringArray, _>>()?,
+            ),
+        };
+
+        arrays.push(array);
+    }
+
+    Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+}
+
+/// Converts the row into the JSON representation of the data load.
+fn row_to_json(row: &PgRow, columns: &[SensorColumn]) -> anyhow::Result<Map<String, Value>> {
+    let mut map = Map::new();
+
+    let created_at: NaiveDateTime = row.try_get(TIME_COL_NAME)?;
+    map.insert(
+        TIME_COL_NAME.to_string(),
+        Value::String(created_at.format(TIMESTAMP_FORMAT).to_string()),
+    );
+
+    for col in columns {
+        let name = col.name.as_str();
+
+        let value = match col.val_type {
+            ColumnType::INT => serde_json::json!(row.try_get::<Option<i32>, _>(name)?),
+            ColumnType::FLOAT => serde_json::json!(row.try_get::<Option<f64>, _>(name)?),
+            ColumnType::BOOL => serde_json::json!(row.try_get::<Option<bool>, _>(name)?),
+            ColumnType::TIMESTAMP => serde_json::json!(row
+                .try_get::<Option<NaiveDateTime>, _>(name)?
+                .map(|ts| ts.format(TIMESTAMP_FORMAT).to_string())),
+            ColumnType::JSON => row
+                .try_get::<Option<Value>, _>(name)?
+                .unwrap_or(Value::Null),
+            _ => serde_json::json!(row.try_get::<Option<String>, _>(name)?),
+        };
+
+        map.insert(col.name.clone(), value);
+    }
+
+    Ok(map)
+}
@@ -0,0 +1,472 @@
+use crate::database::data_db::TIME_COL_NAME;
+use crate::database::models::sensor::{ColumnType, FullSensorInfo};
+use anyhow::Result;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sqlx::{Postgres, QueryBuilder};
+use std::fmt;
+
+/// Value predicates on the data columns of a sensor, used to restrict loaded or deleted data.
+///
+/// Serialized to a query param string in the form of:
+///
+/// - `col.op.value` with op in [eq, neq, lt, lte, gt, gte], e.g. `temp.gt.20.5`
+/// - `col.in.(v1,v2,...)` to match a list of values
+/// - `col.is.null` or `col.is.notnull`
+/// - `and(f1,f2,...)` or `or(f1,f2,...)` to combine predicates
+///
+/// Predicates on the top level are combined with AND. Values containing special characters
+/// (`,`, `(`, `)`, `"`) must be quoted, e.g. `state.eq."a,b"`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataFilter {
+    And(Vec<DataFilter>),
+    Or(Vec<DataFilter>),
+    Cond {
+        col: String,
+        op: FilterOp,
+        values: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterOp {
+    EQ,
+    NEQ,
+    LT,
+    LTE,
+    GT,
+    GTE,
+    IN,
+    ISNULL,
+    NOTNULL,
+}
+
+impl FilterOp {
+    fn as_db_op(&self) -> &'static str {
+        match self {
+            FilterOp::EQ => "=",
+            FilterOp::NEQ => "!=",
+            FilterOp::LT => "<",
+            FilterOp::LTE => "<=",
+            FilterOp::GT => ">",
+            FilterOp::GTE => ">=",
+            FilterOp::IN => "IN",
+            FilterOp::ISNULL => "IS NULL",
+            FilterOp::NOTNULL => "IS NOT NULL",
+        }
+    }
+
+    fn as_param(&self) -> &'static str {
+        match self {
+            FilterOp::EQ => "eq",
+            FilterOp::NEQ => "neq",
+            FilterOp::LT => "lt",
+            FilterOp::LTE => "lte",
+            FilterOp::GT => "gt",
+            FilterOp::GTE => "gte",
+            FilterOp::IN => "in",
+            FilterOp::ISNULL | FilterOp::NOTNULL => "is",
+        }
+    }
+}
+
+impl DataFilter {
+    /// Parses the filter from its query param representation.
+    pub fn parse(input: &str) -> Result<DataFilter> {
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            pos: 0,
+        };
+
+        let mut filters = parser.parse_list()?;
+
+        if parser.pos < parser.chars.len() {
+            anyhow::bail!(
+                "Unexpected '{}' at position {} of filter!",
+                parser.chars[parser.pos],
+                parser.pos
+            );
+        }
+
+        if filters.len() == 1 {
+            return Ok(filters.remove(0));
+        }
+
+        Ok(DataFilter::And(filters))
+    }
+
+    /// Appends the filter as SQL predicate to the query.
+    /// Columns are verified against the columns of the sensor, values are bound according to the column type.
+    pub fn push_sql(
+        &self,
+        query_builder: &mut QueryBuilder<Postgres>,
+        sensor: &FullSensorInfo,
+    ) -> Result<()> {
+        match self {
+            DataFilter::And(filters) | DataFilter::Or(filters) => {
+                let sep = if matches!(self, DataFilter::And(_)) {
+                    " AND "
+                } else {
+                    " OR "
+                };
+
+                query_builder.push("(");
+
+                for (idx, filter) in filters.iter().enumerate() {
+                    if idx != 0 {
+                        query_builder.push(sep);
+                    }
+
+                    filter.push_sql(query_builder, sensor)?;
+                }
+
+                query_builder.push(")");
+            }
+            DataFilter::Cond { col, op, values } => {
+                let col_type = if col == TIME_COL_NAME {
+                    ColumnType::TIMESTAMP
+                } else {
+                    match sensor.columns.iter().find(|c| &c.name == col) {
+                        Some(c) => c.val_type,
+                        None => anyhow::bail!("Data column {} does not exist!", col),
+                    }
+                };
+
+                query_builder.push(format!("{} {}", col, op.as_db_op()));
+
+                match op {
+                    FilterOp::ISNULL | FilterOp::NOTNULL => {}
+                    FilterOp::IN => {
+                        query_builder.push(" (");
+
+                        for (idx, val) in values.iter().enumerate() {
+                            if idx != 0 {
+                                query_builder.push(", ");
+                            }
+
+                            push_value(query_builder, col, col_type, val)?;
+                        }
+
+                        query_builder.push(")");
+                    }
+                    _ => {
+                        query_builder.push(" ");
+                        push_value(query_builder, col, col_type, &values[0])?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Binds the raw filter value with the type of the column.
+fn push_value(
+    query_builder: &mut QueryBuilder<Postgres>,
+    col: &str,
+    col_type: ColumnType,
+    val: &str,
+) -> Result<()> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "Filter value {} doesn't match type {:?} of column {}!",
+            val,
+            col_type,
+            col
+        )
+    };
+
+    match col_type {
+        ColumnType::INT => {
+            query_builder.push_bind(val.parse::<i64>().map_err(|_| invalid())?);
+        }
+        ColumnType::FLOAT => {
+            query_builder.push_bind(val.parse::<f64>().map_err(|_| invalid())?);
+        }
+        ColumnType::STRING => {
+            query_builder.push_bind(val.to_string());
+        }
+        ColumnType::BOOL => {
+            query_builder.push_bind(val.parse::<bool>().map_err(|_| invalid())?);
+        }
+        ColumnType::TIMESTAMP => {
+            // Same notation as for the ingest of timestamps
+            let ts = serde_json::from_value::<chrono::NaiveDateTime>(serde_json::json!(val))
+                .map_err(|_| invalid())?;
+            query_builder.push_bind(ts);
+        }
+        _ => anyhow::bail!(
+            "Column {} with type {:?} only supports the is.null and is.notnull filters!",
+            col,
+            col_type
+        ),
+    }
+
+    Ok(())
+}
+
+// ------------------------------------------ Parsing ----------------------------------------------
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        if self.peek() != Some(c) {
+            anyhow::bail!("Expected '{}' at position {} of filter!", c, self.pos);
+        }
+
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Comma separated list of filters
+    fn parse_list(&mut self) -> Result<Vec<DataFilter>> {
+        let mut filters = vec![self.parse_filter()?];
+
+        while self.peek() == Some(',') {
+            self.pos += 1;
+            filters.push(self.parse_filter()?);
+        }
+
+        Ok(filters)
+    }
+
+    fn parse_filter(&mut self) -> Result<DataFilter> {
+        let ident = self.parse_ident();
+
+        if self.peek() == Some('(') && (ident == "and" || ident == "or") {
+            self.pos += 1;
+            let filters = self.parse_list()?;
+            self.expect(')')?;
+
+            return Ok(if ident == "and" {
+                DataFilter::And(filters)
+            } else {
+                DataFilter::Or(filters)
+            });
+        }
+
+        if ident.is_empty() {
+            anyhow::bail!("Missing column name at position {} of filter!", self.pos);
+        }
+
+        self.expect('.')?;
+        let op_pos = self.pos;
+        let op = self.parse_ident();
+        self.expect('.')?;
+
+        let (op, values) = match op.as_str() {
+            "eq" => (FilterOp::EQ, vec![self.parse_value()?]),
+            "neq" => (FilterOp::NEQ, vec![self.parse_value()?]),
+            "lt" => (FilterOp::LT, vec![self.parse_value()?]),
+            "lte" => (FilterOp::LTE, vec![self.parse_value()?]),
+            "gt" => (FilterOp::GT, vec![self.parse_value()?]),
+            "gte" => (FilterOp::GTE, vec![self.parse_value()?]),
+            "in" => {
+                self.expect('(')?;
+                let mut values = vec![self.parse_value()?];
+
+                while self.peek() == Some(',') {
+                    self.pos += 1;
+                    values.push(self.parse_value()?);
+                }
+
+                self.expect(')')?;
+                (FilterOp::IN, values)
+            }
+            "is" => match self.parse_ident().as_str() {
+                "null" => (FilterOp::ISNULL, vec![]),
+                "notnull" => (FilterOp::NOTNULL, vec![]),
+                _ => anyhow::bail!("Expected null or notnull at position {} of filter!", op_pos),
+            },
+            _ => anyhow::bail!("Unknown filter operation '{}' at position {}!", op, op_pos),
+        };
+
+        Ok(DataFilter::Cond {
+            col: ident,
+            op,
+            values,
+        })
+    }
+
+    fn parse_ident(&mut self) -> String {
+        let start = self.pos;
+
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Raw value until the next separator or a quoted value
+    fn parse_value(&mut self) -> Result<String> {
+        let mut val = String::new();
+
+        if self.peek() == Some('"') {
+            self.pos += 1;
+
+            loop {
+                match self.peek() {
+                    Some('"') => break,
+                    Some('\\') if self.chars.get(self.pos + 1).is_some() => {
+                        val.push(self.chars[self.pos + 1]);
+                        self.pos += 2;
+                    }
+                    Some(c) => {
+                        val.push(c);
+                        self.pos += 1;
+                    }
+                    None => anyhow::bail!("Unterminated quoted value in filter!"),
+                }
+            }
+
+            self.pos += 1;
+            return Ok(val);
+        }
+
+        while let Some(c) = self.peek().filter(|c| !matches!(c, ',' | '(' | ')' | '"')) {
+            val.push(c);
+            self.pos += 1;
+        }
+
+        if val.is_empty() {
+            anyhow::bail!("Missing filter value at position {}!", self.pos);
+        }
+
+        Ok(val)
+    }
+}
+
+// ------------------------------------------ Query Param -------------------------------------------
+
+fn fmt_value(val: &str) -> String {
+    if val.is_empty() || val.contains([',', '(', ')', '"', '\\']) {
+        format!("\"{}\"", val.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        val.to_string()
+    }
+}
+
+impl fmt::Display for DataFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataFilter::And(filters) | DataFilter::Or(filters) => {
+                let list = filters
+                    .iter()
+                    .map(|f| f.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
+                let name = if matches!(self, DataFilter::And(_)) {
+                    "and"
+                } else {
+                    "or"
+                };
+
+                write!(f, "{}({})", name, list)
+            }
+            DataFilter::Cond { col, op, values } => match op {
+                FilterOp::ISNULL => write!(f, "{}.is.null", col),
+                FilterOp::NOTNULL => write!(f, "{}.is.notnull", col),
+                FilterOp::IN => {
+                    let list = values
+                        .iter()
+                        .map(|v| fmt_value(v))
+                        .collect::<Vec<String>>()
+                        .join(",");
+                    write!(f, "{}.in.({})", col, list)
+                }
+                _ => write!(f, "{}.{}.{}", col, op.as_param(), fmt_value(&values[0])),
+            },
+        }
+    }
+}
+
+impl Serialize for DataFilter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DataFilter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        DataFilter::parse(&s).map_err(de::Error::custom)
+    }
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cond(col: &str, op: FilterOp, values: &[&str]) -> DataFilter {
+        DataFilter::Cond {
+            col: col.to_string(),
+            op,
+            values: values.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_parse_filter() {
+        let filter = DataFilter::parse("temp.gte.-20.5").unwrap();
+        assert_eq!(filter, cond("temp", FilterOp::GTE, &["-20.5"]));
+
+        let filter =
+            DataFilter::parse("created_at.lt.2025-02-11T08:27:17,state.is.notnull").unwrap();
+        assert_eq!(
+            filter,
+            DataFilter::And(vec![
+                cond("created_at", FilterOp::LT, &["2025-02-11T08:27:17"]),
+                cond("state", FilterOp::NOTNULL, &[]),
+            ])
+        );
+
+        let filter =
+            DataFilter::parse(r#"or(state.in.(on,"a,\"b\""),and(temp.neq.1,temp.is.null))"#)
+                .unwrap();
+        assert_eq!(
+            filter,
+            DataFilter::Or(vec![
+                cond("state", FilterOp::IN, &["on", "a,\"b\""]),
+                DataFilter::And(vec![
+                    cond("temp", FilterOp::NEQ, &["1"]),
+                    cond("temp", FilterOp::ISNULL, &[])
+                ]),
+            ])
+        );
+
+        // Query param representation can be parsed again
+        assert_eq!(DataFilter::parse(&filter.to_string()).unwrap(), filter);
+
+        let invalid = [
+            "",
+            "temp",
+            "temp.gt",
+            "temp.gt.",
+            "temp.like.x",
+            "temp.is.zero",
+            "or(temp.eq.1",
+            "temp.in.(1,2",
+            "temp.eq.1)",
+            "s.eq.\"open",
+        ];
+
+        for input in invalid.iter() {
+            assert!(DataFilter::parse(input).is_err(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,504 @@
+use crate::database::models::modbus_source::{
+    ModbusDataType, ModbusRegister, ModbusRegisterType, ModbusSource, ModbusWordOrder,
+};
+use crate::database::models::sensor::{ColumnType, FullSensorInfo};
+use crate::features::cache;
+use crate::features::sensor_data_pull::{resolve_source_host, MIN_PULL_INTERVAL_SECS};
+use crate::handler::data_ingest::ingest::insert_entries;
+use crate::handler::models::requests::SensorDataIngestEntry;
+use crate::state::AppState;
+use crate::utils::AppError;
+use actix_http::StatusCode;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio_modbus::client::{tcp, Client, Context, Reader};
+use tokio_modbus::slave::{Slave, SlaveContext};
+use uuid::Uuid;
+
+/*
+    Modbus TCP sources
+
+    Devices speaking Modbus TCP are polled by the pull scheduler in the interval of their source.
+    Every poll reads the configured registers, scales the raw values and inserts them as a single
+    data entry, mapped to the INT and FLOAT columns of the sensor.
+*/
+
+/// Upper bound for connecting to the device and reading all registers
+const MAX_POLL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/* ------------------------------------------------ API ------------------------------------------------------------ */
+
+/// Checks that the modbus source can be polled for the columns of the sensor.
+pub async fn validate_modbus_source(
+    source: &ModbusSource,
+    sensor: &FullSensorInfo,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+
+    if source.host.is_empty() {
+        errors.push("host must not be empty".to_string());
+    } else if let Err(err) = resolve_source_host(&source.host, source.port, state).await {
+        errors.push(err);
+    }
+
+    if source.interval_secs < MIN_PULL_INTERVAL_SECS {
+        errors.push(format!(
+            "interval must be at least {} seconds",
+            MIN_PULL_INTERVAL_SECS
+        ));
+    }
+
+    if source.registers.is_empty() {
+        errors.push("at least one register must be mapped".to_string());
+    }
+
+    let mut mapped = HashSet::new();
+
+    for reg in &source.registers {
+        match sensor.columns.iter().find(|c| c.name == reg.column) {
+            Some(col) if matches!(col.val_type, ColumnType::INT | ColumnType::FLOAT) => (),
+            Some(_) => errors.push(format!(
+                "column '{}' is not an INT or FLOAT column",
+                reg.column
+            )),
+            None => errors.push(format!("column '{}' doesn't exist", reg.column)),
+        }
+
+        if !mapped.insert(reg.column.as_str()) {
+            errors.push(format!("column '{}' is mapped multiple times", reg.column));
+        }
+
+        if reg
+            .address
+            .checked_add(reg.data_type.register_count() - 1)
+            .is_none()
+        {
+            errors.push(format!(
+                "address {} is out of range for the data type",
+                reg.address
+            ));
+        }
+
+        if !reg.scale.is_finite() || !reg.offset.is_finite() {
+            errors.push(format!("invalid scaling of column '{}'", reg.column));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(AppError::validation("invalid modbus source", errors));
+    }
+
+    Ok(())
+}
+
+/// Reads the registers of the source and ingests them as a single entry into the sensor.
+/// The returned boolean value indicates wether an entry has been produced
+pub async fn poll_sensor_data(
+    sensor_id: Uuid,
+    source: &ModbusSource,
+    state: &AppState,
+) -> anyhow::Result<bool, AppError> {
+    let sensor_opt = cache::request_sensor(sensor_id, state).await;
+    if sensor_opt.is_none() {
+        return AppError::internal(format!("could not find sensor with id: '{}'", sensor_id));
+    }
+    let sensor = Arc::new(sensor_opt.unwrap());
+
+    let timeout = Duration::from_secs(source.interval_secs as u64).min(MAX_POLL_TIMEOUT);
+
+    let values = tokio::time::timeout(timeout, read_registers(source, state))
+        .await
+        .map_err(|_| bad_gateway(format!("polling {}:{} timed out", source.host, source.port)))??;
+
+    let mut data = HashMap::new();
+
+    for (reg, value) in source.registers.iter().zip(values) {
+        let col_type = sensor
+            .columns
+            .iter()
+            .find(|c| c.name == reg.column)
+            .map(|c| c.val_type);
+
+        let value = match col_type {
+            Some(ColumnType::INT) => Value::from(value.round() as i64),
+            Some(ColumnType::FLOAT) => serde_json::Number::from_f64(value)
+                .map(Value::Number)
+                .ok_or_else(|| {
+                    bad_gateway(format!(
+                        "register of column '{}' is not a number",
+                        reg.column
+                    ))
+                })?,
+            _ => {
+                return Err(AppError::validation(
+                    "invalid modbus source",
+                    vec![format!(
+                        "column '{}' is not an INT or FLOAT column",
+                        reg.column
+                    )],
+                ))
+            }
+        };
+
+        data.insert(reg.column.clone(), value);
+    }
+
+    if data.is_empty() {
+        return Ok(false);
+    }
+
+    let entries = vec![SensorDataIngestEntry {
+        timestamp: None,
+        data,
+    }];
+
+    insert_entries(sensor, &entries, state).await?;
+
+    Ok(true)
+}
+
+/* ------------------------------------------------ Helper functions ------------------------------------------------------------ */
+
+fn bad_gateway(msg: String) -> AppError {
+    AppError::InternalError {
+        status: Some(StatusCode::BAD_GATEWAY),
+        msg: Some(msg),
+    }
+}
+
+// Reads the scaled values of all registers of the source, in order of the registers
+async fn read_registers(source: &ModbusSource, state: &AppState) -> Result<Vec<f64>, AppError> {
+    let addr: SocketAddr = resolve_source_host(&source.host, source.port, state)
+        .await
+        .map_err(bad_gateway)?[0];
+
+    let mut ctx = tcp::connect(addr)
+        .await
+        .map_err(|err| bad_gateway(format!("failed to connect to {}: {}", addr, err)))?;
+
+    let mut values = Vec::with_capacity(source.registers.len());
+    for reg in &source.registers {
+        let words = read_register(&mut ctx, reg).await?;
+        values.push(decode(reg, &words) * reg.scale + reg.offset);
+    }
+
+    let _ = ctx.disconnect().await;
+
+    Ok(values)
+}
+
+async fn read_register(ctx: &mut Context, reg: &ModbusRegister) -> Result<Vec<u16>, AppError> {
+    ctx.set_slave(Slave(reg.unit_id));
+
+    let count = reg.data_type.register_count();
+    let res = match reg.register_type {
+        ModbusRegisterType::Holding => ctx.read_holding_registers(reg.address, count).await,
+        ModbusRegisterType::Input => ctx.read_input_registers(reg.address, count).await,
+    };
+
+    match res {
+        Ok(Ok(words)) if words.len() == count as usize => Ok(words),
+        Ok(Ok(words)) => Err(bad_gateway(format!(
+            "expected {} registers at address {}, got {}",
+            count,
+            reg.address,
+            words.len()
+        ))),
+        Ok(Err(exception)) => Err(bad_gateway(format!(
+            "device responded with exception for address {}: {}",
+            reg.address, exception
+        ))),
+        Err(err) => Err(bad_gateway(format!(
+            "failed to read address {}: {}",
+            reg.address, err
+        ))),
+    }
+}
+
+// Converts the raw registers into the value of the data type
+fn decode(reg: &ModbusRegister, words: &[u16]) -> f64 {
+    let raw32 = || match reg.word_order {
+        ModbusWordOrder::Big => ((words[0] as u32) << 16) | words[1] as u32,
+        ModbusWordOrder::Little => ((words[1] as u32) << 16) | words[0] as u32,
+    };
+
+    match reg.data_type {
+        ModbusDataType::U16 => words[0] as f64,
+        ModbusDataType::I16 => words[0] as i16 as f64,
+        ModbusDataType::U32 => raw32() as f64,
+        ModbusDataType::I32 => raw32() as i32 as f64,
+        ModbusDataType::F32 => f32::from_bits(raw32()) as f64,
+    }
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::tests::login;
+    use crate::test_utils::tests::{
+        create_test_app_with_config, create_test_sensors, execute_request, john,
+    };
+    use actix_http::Method;
+    use actix_web::ResponseError;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use std::future;
+    use tokio::net::TcpListener;
+    use tokio_modbus::prelude::{ExceptionCode, Request, Response, SlaveRequest};
+    use tokio_modbus::server::tcp::{accept_tcp_connection, Server};
+    use tokio_modbus::server::Service;
+
+    /// Simulated device serving fixed holding and input registers per unit id.
+    struct Simulator {
+        holding: HashMap<(u8, u16), u16>,
+        input: HashMap<(u8, u16), u16>,
+    }
+
+    impl Service for Simulator {
+        type Request = SlaveRequest<'static>;
+        type Response = Response;
+        type Exception = ExceptionCode;
+        type Future = future::Ready<Result<Self::Response, Self::Exception>>;
+
+        fn call(&self, req: Self::Request) -> Self::Future {
+            let read = |registers: &HashMap<(u8, u16), u16>, addr: u16, cnt: u16| {
+                (addr..addr + cnt)
+                    .map(|a| registers.get(&(req.slave, a)).copied())
+                    .collect::<Option<Vec<u16>>>()
+                    .ok_or(ExceptionCode::IllegalDataAddress)
+            };
+
+            let res = match req.request {
+                Request::ReadHoldingRegisters(addr, cnt) => {
+                    read(&self.holding, addr, cnt).map(Response::ReadHoldingRegisters)
+                }
+                Request::ReadInputRegisters(addr, cnt) => {
+                    read(&self.input, addr, cnt).map(Response::ReadInputRegisters)
+                }
+                _ => Err(ExceptionCode::IllegalFunction),
+            };
+
+            future::ready(res)
+        }
+    }
+
+    async fn start_simulator(sim: Simulator) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Server::new(listener);
+        let sim = Arc::new(sim);
+
+        tokio::spawn(async move {
+            let new_service = |_| Ok(Some(sim.clone()));
+            let on_connected = |stream, socket_addr| async move {
+                accept_tcp_connection(stream, socket_addr, new_service)
+            };
+            let _ = server.serve(&on_connected, |_| {}).await;
+        });
+
+        addr
+    }
+
+    fn register(column: &str, address: u16, data_type: ModbusDataType) -> ModbusRegister {
+        ModbusRegister {
+            column: column.to_string(),
+            unit_id: 1,
+            address,
+            register_type: ModbusRegisterType::Holding,
+            data_type,
+            word_order: ModbusWordOrder::Big,
+            scale: 1.0,
+            offset: 0.0,
+        }
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../handler/fixtures/users.sql",
+            "../handler/fixtures/roles.sql",
+            "../handler/fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_modbus_source(pool: PgPool) {
+        // The simulated device runs on the loopback interface
+        let cfg = serde_yml::from_str("server:\n  pull_allow_private_hosts: true").unwrap();
+        let (app, state) = create_test_app_with_config(pool, cfg).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor")
+            .unwrap()
+            .1;
+
+        let f = 21.5f32.to_bits();
+        let addr = start_simulator(Simulator {
+            // col1: signed 16 bit with scaling, col2: 32 bit float in little word order
+            holding: HashMap::from([((1, 100), (-120i16) as u16)]),
+            input: HashMap::from([((2, 10), (f & 0xFFFF) as u16), ((2, 11), (f >> 16) as u16)]),
+        })
+        .await;
+
+        let source = ModbusSource {
+            host: addr.ip().to_string(),
+            port: addr.port(),
+            interval_secs: 3600,
+            registers: vec![
+                ModbusRegister {
+                    scale: 0.5,
+                    offset: 100.0,
+                    ..register("col1", 100, ModbusDataType::I16)
+                },
+                ModbusRegister {
+                    unit_id: 2,
+                    register_type: ModbusRegisterType::Input,
+                    word_order: ModbusWordOrder::Little,
+                    ..register("col2", 10, ModbusDataType::F32)
+                },
+            ],
+        };
+
+        let set_url = format!("/api/sensors/{}/modbus_source/set", sensor_id);
+        let load_url = format!("/api/sensors/{}/modbus_source/load", sensor_id);
+
+        // --- Set modbus source without permission - Should fail ---
+
+        execute_request(
+            &set_url,
+            Method::POST,
+            None,
+            Some(&source),
+            None,
+            StatusCode::UNAUTHORIZED,
+            &app,
+        )
+        .await;
+
+        // --- Set invalid modbus source - Should fail ---
+
+        let token = login(&john(), &state).await;
+
+        let invalid = ModbusSource {
+            interval_secs: 1,
+            registers: vec![
+                register("col3", 0, ModbusDataType::U16),
+                register("unknown", 0, ModbusDataType::U16),
+                register("col1", u16::MAX, ModbusDataType::U32),
+            ],
+            ..source.clone()
+        };
+        let body = execute_request(
+            &set_url,
+            Method::POST,
+            None,
+            Some(&invalid),
+            Some(token.clone()),
+            StatusCode::BAD_REQUEST,
+            &app,
+        )
+        .await;
+        assert_eq!(body["fields"].as_array().unwrap().len(), 4);
+
+        // --- Set modbus source - Should succeed and be polled by the scheduler ---
+
+        execute_request(
+            &set_url,
+            Method::POST,
+            None,
+            Some(&source),
+            Some(token.clone()),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let body = execute_request(
+            &load_url,
+            Method::GET,
+            None,
+            None::<Value>,
+            Some(token.clone()),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+        assert_eq!(body, serde_json::to_value(&source).unwrap());
+
+        let tbl_name = cache::request_sensor(sensor_id, &state)
+            .await
+            .unwrap()
+            .tbl_name;
+        let query = format!("SELECT col1, col2 FROM {}", tbl_name);
+
+        let mut rows = Vec::new();
+        for _ in 0..50 {
+            rows = sqlx::query_as::<_, (Option<i32>, Option<f64>)>(&query)
+                .fetch_all(&state.db)
+                .await
+                .unwrap();
+            if !rows.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        assert_eq!(rows, vec![(Some(40), Some(21.5))]);
+
+        // --- Failing polls ---
+
+        let missing_register = ModbusSource {
+            registers: vec![register("col1", 200, ModbusDataType::U16)],
+            ..source.clone()
+        };
+        let err = poll_sensor_data(sensor_id, &missing_register, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
+
+        let unreachable = ModbusSource {
+            port: TcpListener::bind("127.0.0.1:0")
+                .await
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port(),
+            ..source.clone()
+        };
+        let err = poll_sensor_data(sensor_id, &unreachable, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
+
+        // --- Delete modbus source ---
+
+        let delete_url = format!("/api/sensors/{}/modbus_source/delete", sensor_id);
+        execute_request(
+            &delete_url,
+            Method::DELETE,
+            None,
+            None::<Value>,
+            Some(token.clone()),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let body = execute_request(
+            &load_url,
+            Method::GET,
+            None,
+            None::<Value>,
+            Some(token),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+        assert_eq!(body, json!({}));
+    }
+}
@@ -0,0 +1,815 @@
+use crate::database::models::events::LogEvent;
+use crate::database::models::modbus_source::ModbusSource;
+use crate::database::models::pull_source::PullSource;
+use crate::database::{modbus_source_db, pull_source_db};
+use crate::features::cache;
+use crate::features::config::{get_ingest_max_size_kb, get_pull_allow_private_hosts};
+use crate::features::sensor_data_modbus::poll_sensor_data;
+use crate::handler::data_ingest::format::IngestFormat;
+use crate::handler::data_ingest::ingest::{insert_entries, transform_entries};
+use crate::handler::models::requests::TransportProto;
+use crate::handler::models::telelmetry::OTelData;
+use crate::state::AppState;
+use crate::utils::AppError;
+use actix_http::StatusCode;
+use actix_web::ResponseError;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+/*
+    Pull sources
+
+    The scheduler polls the HTTP endpoints and Modbus devices configured for sensors in their interval.
+    The fetched HTTP body runs through the data chain of the sensor like pushed data, the
+    results of the requests are recorded as log events of the sensor.
+    The configured sources are reloaded when they are modified and periodically, to pick up
+    changes made by other instances.
+    Sources may only reach public addresses, unless private hosts are allowed in the config.
+    Hosts are checked when a source is set and on every request, redirects aren't followed.
+*/
+
+/// Smallest allowed interval between two requests to a source
+pub const MIN_PULL_INTERVAL_SECS: u32 = 5;
+
+/// Upper bound for the duration of a single request
+const MAX_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Interval in which the sources are reloaded from the database
+const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Interval in which the scheduler checks for due sources
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/* ------------------------------------------------ API ------------------------------------------------------------ */
+
+/// External entrypoint to interact with the scheduler
+pub struct PullScheduler {
+    reload_tx: UnboundedSender<()>,
+}
+
+impl PullScheduler {
+    /// Reloads the sources after they were modified.
+    pub fn reload(&self) {
+        if let Err(err) = self.reload_tx.send(()) {
+            error!("[PULL] failed to request reload: {}", err);
+        }
+    }
+}
+
+/// Starts the scheduler task polling the configured pull sources.
+pub fn start_pull_scheduler(state: AppState) -> PullScheduler {
+    let (tx, rx) = mpsc::unbounded_channel::<()>();
+
+    tokio::task::spawn(pull_scheduler(state, rx));
+
+    PullScheduler { reload_tx: tx }
+}
+
+/// Checks that the pull source can be executed.
+pub async fn validate_pull_source(source: &PullSource, state: &AppState) -> Result<(), AppError> {
+    let mut errors = Vec::new();
+
+    match Url::parse(&source.url) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+            if let Err(err) = resolve_url_host(&url, state).await {
+                errors.push(err);
+            }
+        }
+        Ok(url) => errors.push(format!("unsupported url scheme '{}'", url.scheme())),
+        Err(err) => errors.push(format!("invalid url: {}", err)),
+    }
+
+    if source.interval_secs < MIN_PULL_INTERVAL_SECS {
+        errors.push(format!(
+            "interval must be at least {} seconds",
+            MIN_PULL_INTERVAL_SECS
+        ));
+    }
+
+    if let Err(err) = header_map(&source.headers) {
+        errors.push(err);
+    }
+
+    if let Some(pointer) = &source.json_pointer {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            errors.push(format!("invalid JSON pointer '{}'", pointer));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(AppError::validation("invalid pull source", errors));
+    }
+
+    Ok(())
+}
+
+/// Resolves the host of a source, addresses that aren't publicly routable
+/// (loopback, private networks, link-local, ...) are rejected unless allowed in the config.
+pub async fn resolve_source_host(
+    host: &str,
+    port: u16,
+    state: &AppState,
+) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|err| format!("could not resolve host '{}': {}", host, err))?
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(format!("could not resolve host '{}'", host));
+    }
+
+    if !get_pull_allow_private_hosts(&state.cfg) {
+        if let Some(addr) = addrs.iter().find(|addr| !is_global_ip(addr.ip())) {
+            return Err(format!(
+                "host '{}' resolves to the non-public address {}",
+                host,
+                addr.ip()
+            ));
+        }
+    }
+
+    Ok(addrs)
+}
+
+/// Fetches the data of the source and ingests it into the sensor.
+/// The returned boolean value indicates wether an entry has been produced
+pub async fn pull_sensor_data(
+    sensor_id: Uuid,
+    source: &PullSource,
+    state: &AppState,
+) -> anyhow::Result<bool, AppError> {
+    let sensor_opt = cache::request_sensor(sensor_id, state).await;
+    if sensor_opt.is_none() {
+        return AppError::internal(format!("could not find sensor with id: '{}'", sensor_id));
+    }
+    let sensor = Arc::new(sensor_opt.unwrap());
+
+    let body = fetch(source, state).await?;
+
+    let data = match &source.json_pointer {
+        Some(pointer) => select_entries(&body, pointer)?,
+        None => body,
+    };
+
+    // Transform data into ingestable format
+    let data = transform_entries(sensor.clone(), data, &IngestFormat::JSON, state).await?;
+
+    if data.is_empty() {
+        return Ok(false);
+    }
+
+    insert_entries(sensor, &data, state).await?;
+
+    Ok(true)
+}
+
+/* ------------------------------------------------ Helper functions ------------------------------------------------------------ */
+
+fn header_map(headers: &HashMap<String, String>) -> Result<HeaderMap, String> {
+    let mut map = HeaderMap::new();
+
+    for (name, value) in headers {
+        let name = HeaderName::try_from(name.as_str())
+            .map_err(|_| format!("invalid header name '{}'", name))?;
+        let value = HeaderValue::try_from(value.as_str())
+            .map_err(|_| format!("invalid value of header '{}'", name))?;
+        map.insert(name, value);
+    }
+
+    Ok(map)
+}
+
+async fn fetch(source: &PullSource, state: &AppState) -> Result<bytes::Bytes, AppError> {
+    let bad_gateway = |msg: String| AppError::InternalError {
+        status: Some(StatusCode::BAD_GATEWAY),
+        msg: Some(msg),
+    };
+
+    let headers = header_map(&source.headers).map_err(bad_gateway)?;
+    let timeout = Duration::from_secs(source.interval_secs as u64).min(MAX_REQUEST_TIMEOUT);
+
+    let url =
+        Url::parse(&source.url).map_err(|err| bad_gateway(format!("invalid url: {}", err)))?;
+    let (host, addrs) = resolve_url_host(&url, state).await.map_err(bad_gateway)?;
+
+    // The connection is pinned to the checked addresses, the host can't be rebound in between
+    let client = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .resolve_to_addrs(&host, &addrs)
+        .build()
+        .map_err(|err| bad_gateway(format!("failed to create client: {}", err)))?;
+
+    let mut res = client
+        .get(url)
+        .headers(headers)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|err| bad_gateway(format!("request failed: {}", err)))?;
+
+    if !res.status().is_success() {
+        return Err(bad_gateway(format!(
+            "source responded with {}",
+            res.status()
+        )));
+    }
+
+    // Responses are limited like pushed data
+    let max_size = get_ingest_max_size_kb(&state.cfg) * 1024;
+    let too_large = || bad_gateway(format!("response exceeds {} bytes", max_size));
+
+    if res
+        .content_length()
+        .is_some_and(|len| len > max_size as u64)
+    {
+        return Err(too_large());
+    }
+
+    let mut body = Vec::new();
+    while let Some(chunk) = res
+        .chunk()
+        .await
+        .map_err(|err| bad_gateway(format!("failed to read response: {}", err)))?
+    {
+        if body.len() + chunk.len() > max_size {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes::Bytes::from(body))
+}
+
+// Resolves the host of the url, returns the host name along its addresses
+async fn resolve_url_host(
+    url: &Url,
+    state: &AppState,
+) -> Result<(String, Vec<SocketAddr>), String> {
+    // IPv6 hosts are enclosed in brackets
+    let host = match url.host_str() {
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string(),
+        None => return Err("url has no host".to_string()),
+    };
+    let port = url.port_or_known_default().unwrap_or(80);
+
+    let addrs = resolve_source_host(&host, port, state).await?;
+
+    Ok((host, addrs))
+}
+
+// Whether the address is publicly routable, see the (unstable) IpAddr::is_global
+fn is_global_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                // Shared address space (carrier-grade NAT)
+                || (a == 100 && (64..128).contains(&b))
+                // IETF protocol assignments
+                || (a == 192 && b == 0 && c == 0)
+                // Benchmarking
+                || (a == 198 && (18..20).contains(&b))
+                // Reserved
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_global_ip(IpAddr::V4(ip));
+            }
+
+            let segments = ip.segments();
+
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Unique local
+                || (segments[0] & 0xfe00) == 0xfc00
+                // Link-local and deprecated site-local
+                || (segments[0] & 0xffc0) == 0xfe80
+                || (segments[0] & 0xffc0) == 0xfec0
+                // Documentation
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8))
+        }
+    }
+}
+
+// Selects the entries in the JSON body, a single object is treated as one entry
+fn select_entries(body: &[u8], pointer: &str) -> Result<bytes::Bytes, AppError> {
+    let value = serde_json::from_slice::<Value>(body)
+        .map_err(|err| AppError::validation("malformed data entries", vec![err.to_string()]))?;
+
+    let selected = match value.pointer(pointer) {
+        Some(Value::Object(entry)) => Value::Array(vec![Value::Object(entry.clone())]),
+        Some(entries) => entries.clone(),
+        None => {
+            return Err(AppError::validation(
+                "malformed data entries",
+                vec![format!(
+                    "JSON pointer '{}' doesn't match the response",
+                    pointer
+                )],
+            ))
+        }
+    };
+
+    serde_json::to_vec(&selected)
+        .map(bytes::Bytes::from)
+        .or_else(|err| AppError::internal(err.to_string()))
+}
+
+/* ------------------------------------------------ Scheduler ------------------------------------------------------------ */
+
+/// A source polled by the scheduler, a sensor may have one of each kind
+#[derive(Debug, Clone, PartialEq)]
+enum Source {
+    Http(PullSource),
+    Modbus(ModbusSource),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum SourceKind {
+    Http,
+    Modbus,
+}
+
+impl Source {
+    fn kind(&self) -> SourceKind {
+        match self {
+            Source::Http(_) => SourceKind::Http,
+            Source::Modbus(_) => SourceKind::Modbus,
+        }
+    }
+
+    fn interval_secs(&self) -> u32 {
+        match self {
+            Source::Http(s) => s.interval_secs,
+            Source::Modbus(s) => s.interval_secs,
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Source::Http(s) => s.url.clone(),
+            Source::Modbus(s) => format!("modbus://{}:{}", s.host, s.port),
+        }
+    }
+}
+
+struct ScheduledSource {
+    source: Source,
+    next_run: Instant,
+    task: Option<JoinHandle<()>>,
+}
+
+async fn pull_scheduler(state: AppState, mut reload_rx: UnboundedReceiver<()>) {
+    info!("[PULL] starting scheduler");
+
+    let mut scheduled: HashMap<(Uuid, SourceKind), ScheduledSource> = HashMap::new();
+
+    let mut reload = tokio::time::interval(RELOAD_INTERVAL);
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = reload.tick() => reload_sources(&mut scheduled, &state).await,
+            res = reload_rx.recv() => match res {
+                Some(_) => reload_sources(&mut scheduled, &state).await,
+                None => {
+                    info!("[PULL] channel closed");
+                    break;
+                }
+            },
+            _ = tick.tick() => {
+                let now = Instant::now();
+
+                for ((sensor_id, _), s) in scheduled.iter_mut() {
+                    // Skip sources that aren't due or still running
+                    if s.next_run > now || s.task.as_ref().is_some_and(|t| !t.is_finished()) {
+                        continue;
+                    }
+
+                    s.next_run = now + Duration::from_secs(s.source.interval_secs() as u64);
+                    s.task = Some(tokio::task::spawn(run_source(
+                        *sensor_id,
+                        s.source.clone(),
+                        state.clone(),
+                    )));
+                }
+            }
+        }
+    }
+}
+
+async fn load_sources(state: &AppState) -> anyhow::Result<Vec<(Uuid, Source)>> {
+    let http = pull_source_db::load_all(&state.db).await?;
+    let modbus = modbus_source_db::load_all(&state.db).await?;
+
+    let http = http
+        .into_iter()
+        .map(|s| (s.sensor_id, Source::Http(s.source)));
+    let modbus = modbus
+        .into_iter()
+        .map(|s| (s.sensor_id, Source::Modbus(s.source)));
+
+    Ok(http.chain(modbus).collect())
+}
+
+async fn reload_sources(
+    scheduled: &mut HashMap<(Uuid, SourceKind), ScheduledSource>,
+    state: &AppState,
+) {
+    let sources = match load_sources(state).await {
+        Ok(s) => s,
+        Err(err) => {
+            error!("[PULL] failed to load sources: {}", err);
+            return;
+        }
+    };
+
+    debug!("[PULL] loaded {} sources", sources.len());
+
+    let mut previous = std::mem::take(scheduled);
+
+    for (sensor_id, source) in sources {
+        let key = (sensor_id, source.kind());
+
+        // Unchanged sources keep their schedule
+        let entry = match previous.remove(&key) {
+            Some(s) if s.source == source => s,
+            _ => ScheduledSource {
+                source,
+                next_run: Instant::now(),
+                task: None,
+            },
+        };
+
+        scheduled.insert(key, entry);
+    }
+}
+
+async fn run_source(sensor_id: Uuid, source: Source, state: AppState) {
+    let start = Instant::now();
+
+    let res = match &source {
+        Source::Http(s) => pull_sensor_data(sensor_id, s, &state).await,
+        Source::Modbus(s) => poll_sensor_data(sensor_id, s, &state).await,
+    };
+
+    let (status, payload) = match res {
+        Ok(true) => (StatusCode::OK, None),
+        Ok(false) => (StatusCode::NO_CONTENT, None),
+        Err(err) => {
+            error!(
+                "[PULL] failed to pull {} for {}: '{}'",
+                source.name(),
+                sensor_id,
+                err
+            );
+            (err.status_code(), Some(err.to_string()))
+        }
+    };
+
+    let mut e = LogEvent::new(
+        OTelData::generate(),
+        start.elapsed(),
+        TransportProto::PULL,
+        format!("/api/sensors/{}", sensor_id),
+        status,
+    );
+    if let Some(payload) = payload {
+        e.with_payload(payload);
+    }
+    if let Some(events) = state.events.clone() {
+        let _ = events.les_chan.send(e);
+    }
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::tests::{
+        anne, create_test_app, create_test_app_with_config, create_test_sensors, execute_request,
+        john, login,
+    };
+    use actix_http::Method;
+    use serde_json::json;
+    use sqlx::PgPool;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves the JSON body on '/data' if the api key header is sent, redirects '/redirect' to it,
+    /// serves an oversized body on '/large' and responds with 404 otherwise.
+    async fn start_test_source(body: Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    break;
+                };
+
+                let mut buf = vec![0u8; 4096];
+                let len = stream.read(&mut buf).await.unwrap_or(0);
+                let req = String::from_utf8_lossy(&buf[..len]).to_lowercase();
+
+                let path = req.split(' ').nth(1).unwrap_or_default();
+
+                let (status, body) = match path {
+                    "/data" if req.contains("x-api-key: secret") => ("200 OK", body.to_string()),
+                    "/data" => ("401 Unauthorized", String::new()),
+                    "/redirect" => ("302 Found\r\nLocation: /data", String::new()),
+                    "/large" => ("200 OK", format!("[\"{}\"]", "x".repeat(2048))),
+                    _ => ("404 Not Found", String::new()),
+                };
+
+                let res = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(res.as_bytes()).await;
+            }
+        });
+
+        format!("http://{}", addr)
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../handler/fixtures/users.sql",
+            "../handler/fixtures/roles.sql",
+            "../handler/fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_pull_source(pool: PgPool) {
+        // The test source runs on the loopback interface, the body size is limited to 1 kB
+        let cfg = serde_yml::from_str(
+            "server:\n  pull_allow_private_hosts: true\n  ingest_max_size_kb: 1",
+        )
+        .unwrap();
+        let (app, state) = create_test_app_with_config(pool.clone(), cfg).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor")
+            .unwrap()
+            .1;
+
+        let base_url = start_test_source(json!({
+            "station": "lab",
+            "current": {"col1": 7, "col2": 1.5, "col3": "pulled"}
+        }))
+        .await;
+
+        let source = PullSource {
+            url: format!("{}/data", base_url),
+            headers: HashMap::from([("X-Api-Key".to_string(), "secret".to_string())]),
+            interval_secs: 3600,
+            json_pointer: Some("/current".to_string()),
+        };
+
+        let set_url = format!("/api/sensors/{}/pull_source/set", sensor_id);
+        let load_url = format!("/api/sensors/{}/pull_source/load", sensor_id);
+
+        // --- Set pull source without permission - Should fail ---
+
+        execute_request(
+            &set_url,
+            Method::POST,
+            None,
+            Some(&source),
+            None,
+            StatusCode::UNAUTHORIZED,
+            &app,
+        )
+        .await;
+
+        let token = login(&anne(), &state).await;
+        execute_request(
+            &set_url,
+            Method::POST,
+            None,
+            Some(&source),
+            Some(token),
+            StatusCode::UNAUTHORIZED,
+            &app,
+        )
+        .await;
+
+        // --- Set invalid pull source - Should fail ---
+
+        let token = login(&john(), &state).await;
+
+        let invalid = PullSource {
+            url: "ftp://localhost/data".to_string(),
+            interval_secs: 1,
+            ..source.clone()
+        };
+        let body = execute_request(
+            &set_url,
+            Method::POST,
+            None,
+            Some(&invalid),
+            Some(token.clone()),
+            StatusCode::BAD_REQUEST,
+            &app,
+        )
+        .await;
+        assert_eq!(body["fields"].as_array().unwrap().len(), 2);
+
+        // --- Set pull source on a private host without permission in the config - Should fail ---
+
+        let (strict_app, _) = create_test_app(pool).await;
+
+        for url in [
+            source.url.clone(),
+            "http://169.254.169.254/latest/meta-data".to_string(),
+            "http://[::ffff:10.0.0.1]/data".to_string(),
+        ] {
+            let private = PullSource {
+                url,
+                ..source.clone()
+            };
+            execute_request(
+                &set_url,
+                Method::POST,
+                None,
+                Some(&private),
+                Some(token.clone()),
+                StatusCode::BAD_REQUEST,
+                &strict_app,
+            )
+            .await;
+        }
+
+        // --- Set pull source - Should succeed and be polled by the scheduler ---
+
+        execute_request(
+            &set_url,
+            Method::POST,
+            None,
+            Some(&source),
+            Some(token.clone()),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let body = execute_request(
+            &load_url,
+            Method::GET,
+            None,
+            None::<Value>,
+            Some(token.clone()),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+        assert_eq!(body, serde_json::to_value(&source).unwrap());
+
+        let tbl_name = cache::request_sensor(sensor_id, &state)
+            .await
+            .unwrap()
+            .tbl_name;
+        let query = format!("SELECT col1, col3 FROM {}", tbl_name);
+
+        let mut rows = Vec::new();
+        for _ in 0..50 {
+            rows = sqlx::query_as::<_, (Option<i32>, Option<String>)>(&query)
+                .fetch_all(&state.db)
+                .await
+                .unwrap();
+            if !rows.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        assert_eq!(rows, vec![(Some(7), Some("pulled".to_string()))]);
+
+        // --- Failing sources ---
+
+        let unknown = PullSource {
+            url: format!("{}/other", base_url),
+            ..source.clone()
+        };
+        let err = pull_sensor_data(sensor_id, &unknown, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
+
+        let no_key = PullSource {
+            headers: HashMap::new(),
+            ..source.clone()
+        };
+        let err = pull_sensor_data(sensor_id, &no_key, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
+
+        // Redirects aren't followed, they could lead to any host
+        let redirect = PullSource {
+            url: format!("{}/redirect", base_url),
+            ..source.clone()
+        };
+        let err = pull_sensor_data(sensor_id, &redirect, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
+
+        let large = PullSource {
+            url: format!("{}/large", base_url),
+            json_pointer: None,
+            ..source.clone()
+        };
+        let err = pull_sensor_data(sensor_id, &large, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_GATEWAY);
+
+        let invalid_pointer = PullSource {
+            json_pointer: Some("/missing".to_string()),
+            ..source.clone()
+        };
+        let err = pull_sensor_data(sensor_id, &invalid_pointer, &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
+
+        // --- Delete pull source ---
+
+        let delete_url = format!("/api/sensors/{}/pull_source/delete", sensor_id);
+        execute_request(
+            &delete_url,
+            Method::DELETE,
+            None,
+            None::<Value>,
+            Some(token.clone()),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let body = execute_request(
+            &load_url,
+            Method::GET,
+            None,
+            None::<Value>,
+            Some(token),
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+        assert_eq!(body, json!({}));
+    }
+
+    #[test]
+    fn test_global_ips() {
+        for ip in ["8.8.8.8", "2606:4700::1111", "::ffff:1.1.1.1"] {
+            assert!(is_global_ip(ip.parse().unwrap()), "{}", ip);
+        }
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_global_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+}
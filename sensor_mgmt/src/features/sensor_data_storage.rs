@@ -1,3 +1,4 @@
+use crate::features::sensor_data_dedup::SensorDataDedup;
 use anyhow::Result;
 use serde_derive::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -76,6 +77,9 @@ trait SensorDataStorageStrategy {
 pub struct SensorDataStorageCfg {
     pub variant: SensorDataStorageType,
     pub params: Option<Map<String, Value>>,
+    /// Optional detection of duplicated data entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedup: Option<SensorDataDedup>,
 }
 
 // -------------------------------------------- Default --------------------------------------------
@@ -279,7 +283,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::Default,
-                params: json!({"ff": 10}).as_object().cloned()
+                params: json!({"ff": 10}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -291,7 +296,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::Default,
-                params: json!({}).as_object().cloned()
+                params: json!({}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -301,7 +307,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::Default,
-                params: None
+                params: None,
+                dedup: None,
             }),
             &state
         )
@@ -315,6 +322,7 @@ pub mod tests {
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::Default,
                 params: None,
+                dedup: None,
             }),
             &state,
         )
@@ -372,7 +380,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferCount,
-                params: json!({"asd": 10}).as_object().cloned()
+                params: json!({"asd": 10}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -384,7 +393,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferCount,
-                params: json!({"count": "123"}).as_object().cloned()
+                params: json!({"count": "123"}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -394,7 +404,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferCount,
-                params: json!({"count": 1234.1234}).as_object().cloned()
+                params: json!({"count": 1234.1234}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -406,7 +417,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferCount,
-                params: json!({"count": 10, "prop": "value"}).as_object().cloned()
+                params: json!({"count": 10, "prop": "value"}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -420,6 +432,7 @@ pub mod tests {
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferCount,
                 params: json!({"count": 10}).as_object().cloned(),
+                dedup: None,
             }),
             &state,
         )
@@ -465,6 +478,7 @@ pub mod tests {
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferCount,
                 params: json!({"count": 15}).as_object().cloned(),
+                dedup: None,
             }),
             &state,
         )
@@ -519,7 +533,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferInterval,
-                params: json!({"asdc": 10}).as_object().cloned()
+                params: json!({"asdc": 10}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -531,7 +546,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferInterval,
-                params: json!({"interval": "123"}).as_object().cloned()
+                params: json!({"interval": "123"}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -543,7 +559,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferInterval,
-                params: json!({"interval": 123}).as_object().cloned()
+                params: json!({"interval": 123}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -553,7 +570,8 @@ pub mod tests {
             sensor_id,
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferInterval,
-                params: json!({"interval": 123.123}).as_object().cloned()
+                params: json!({"interval": 123.123}).as_object().cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -567,7 +585,8 @@ pub mod tests {
                 variant: SensorDataStorageType::RingBufferInterval,
                 params: json!({"interval": 10, "prop": "value"})
                     .as_object()
-                    .cloned()
+                    .cloned(),
+                dedup: None,
             }),
             &state
         )
@@ -581,6 +600,7 @@ pub mod tests {
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferInterval,
                 params: json!({"interval": 600.0 / 60_000.0}).as_object().cloned(),
+                dedup: None,
             }),
             &state,
         )
@@ -634,6 +654,7 @@ pub mod tests {
                 })
                 .as_object()
                 .cloned(),
+                dedup: None,
             }),
             &state,
         )
@@ -691,6 +712,7 @@ pub mod tests {
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferCount,
                 params: json!({"count": 10}).as_object().cloned(),
+                dedup: None,
             }),
             &state,
         )
@@ -726,6 +748,7 @@ pub mod tests {
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::RingBufferInterval,
                 params: json!({"interval": 600.0 / 60_000.0}).as_object().cloned(),
+                dedup: None,
             }),
             &state,
         )
@@ -771,6 +794,7 @@ pub mod tests {
             edit_sensor_req(SensorDataStorageCfg {
                 variant: SensorDataStorageType::Default,
                 params: json!({}).as_object().cloned(),
+                dedup: None,
             }),
             &state,
         )
@@ -1,6 +1,7 @@
 use crate::database::data_chain_db::load_inbound;
 use crate::database::data_transformer_db::{self};
 use crate::features::config::as_compose_service;
+use crate::handler::data_ingest::format::IngestFormat;
 use crate::{
     database::models::sensor::FullSensorInfo, handler::models::requests::SensorDataIngestEntry,
     state::AppState,
@@ -23,7 +24,8 @@ use uuid::Uuid;
 
 /* ------------------------------------------------ Public API ------------------------------------------------------------ */
 
-/// Takes input JSON data and transforms it into a specific output
+/// Takes input data and transforms it into a specific output.
+/// Without an inbound transformer, the data is decoded according to its format.
 /// Request flow
 /// Send Transform Request
 ///     -> Transform Service
@@ -35,6 +37,7 @@ use uuid::Uuid;
 pub async fn transform(
     sensor: Arc<FullSensorInfo>,
     data: bytes::Bytes,
+    format: &IngestFormat,
     state: &AppState,
 ) -> anyhow::Result<Vec<SensorDataIngestEntry>> {
     match load_inbound(sensor.id, &state.db).await? {
@@ -44,7 +47,7 @@ pub async fn transform(
 
             let res = get_transformed_data(
                 &transformer_id,
-                String::from_utf8(data.to_vec())?,
+                String::from_utf8(format.binary_to_json(data)?.to_vec())?,
                 &state.data_transform,
             )
             .await?;
@@ -53,7 +56,7 @@ pub async fn transform(
         }
         None => {
             debug!("No inbound transformer found");
-            Ok(serde_json::from_slice::<Vec<SensorDataIngestEntry>>(&data)?)
+            format.decode(&sensor, &data)
         }
     }
 }
@@ -1,17 +1,30 @@
-use crate::database::data_db::{delete_sensor_data, get_data};
+use crate::database::data_db::{delete_sensor_data, export_data, get_aligned_data, get_data_page};
 use crate::database::models::db_structs::DBOperation;
 use crate::features::cache;
+use crate::features::config::get_data_max_page_size;
+use crate::features::sensor_data_export::DataExportFormat;
 use crate::features::user_sens_perm::UserSensorPerm;
-use crate::handler::models::requests::{DataLoadRequestParams, SensorDataDeletionParams};
+use crate::handler::models::requests::{
+    AlignedDataRequest, DataExportRequestParams, DataLoadRequestParams, SensorDataDeletionParams,
+};
 use crate::handler::{main_hdl, policy};
 use crate::state::AppState;
-use actix_web::{delete, get, web, Responder};
+use crate::utils::AppError;
+use actix_web::http::header::{self, ContentDisposition, HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
 use chrono::Utc;
 
 /* ------------------------------------------------ Sensor Data ------------------------------------------------------------ */
 
 pub(crate) const COMMON_TAG: &str = "Sensor / Data";
 
+/// Response header containing the cursor to request the next page of sensor data
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+
+/// Maximum number of sensors of a single multi-sensor query
+pub const ALIGNED_MAX_SENSORS: usize = 20;
+
 // NOTE:
 // HTTP data ingest has been moved to data_ingest/http.rs
 // MQTT data ingest is in data_ingest/mqtt.rs
@@ -20,7 +33,7 @@ pub(crate) const COMMON_TAG: &str = "Sensor / Data";
     delete,
     path = "/api/sensors/{id}/data/delete",
     description = "Deletes entries from the sensor data table for the specified sensor and the time range.<br>\
-    Deleting all sensor data by omitting the 'from' and 'to' parameter can only be executed by also specifying the 'purge' flag.",
+    Deleting all sensor data by omitting the 'from', 'to' and 'filter' parameter can only be executed by also specifying the 'purge' flag.",
     params(
         ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string())),
         ("key" = String, Query, description = "The provided API key for writing data.", example = json!(uuid::Uuid::new_v4().to_string())),
@@ -28,11 +41,15 @@ pub(crate) const COMMON_TAG: &str = "Sensor / Data";
         ("to" = Option<String>, Query, description = "Upper border for deleting data entries, RFC3339 without timezone information", example="2006-01-02T15:04:05"),
         ("from_inclusive" = Option<bool>, Query, description = "If the 'from' range border should be considered inclusive, default=true"),
         ("to_inclusive" = Option<bool>, Query, description = "If the 'to' range border should be considered inclusive, default=true"),
+        ("filter" = Option<String>, Query, description = "Value predicates on the data columns, combined with AND on the top level.<br>\
+        Predicates: col.eq.val, col.neq.val, col.lt.val, col.lte.val, col.gt.val, col.gte.val, col.in.(v1,v2), col.is.null, col.is.notnull<br>\
+        Predicates may be nested with and(...) and or(...). Values containing special characters must be double-quoted.", example="temp.gt.20,or(state.eq.on,state.is.null)"),
         ("purge" = Option<bool>, Query, description = "If the entire dataset should be deleted when omitting 'to and 'from' attributes")
     ),
     tag = COMMON_TAG,
     responses(
         (status = 200, description = "Returns ok if the deletion was successful."),
+        (status = 400, description= "Returns a bad request error if the query parameters are malformed."),
         (status = 401, description= "Returns an unauthorized error if access is not permitted."),
         (status = 500, description= "Returns an error if the sensor does not exist or data couldn't be deleted."),
     ),
@@ -67,6 +84,7 @@ async fn delete_sensor_data_handler(
 
     if params.from.is_none()
         && params.to.is_none()
+        && params.filter.is_none()
         && (params.purge.is_none() || !params.purge.unwrap())
     {
         return policy::internal_error(
@@ -87,28 +105,48 @@ async fn delete_sensor_data_handler(
     path = "/api/sensors/{id}/data/load",
     description = "Retrieves values from the specified sensor based on various predicates.<br>\
     In addition to the data columns, a time column is added to the result rows. By default, this time column is called 'created_at'. \
-    For requests with a time_grouping, the time column is called 'grouped_time'.",
+    For requests with a time_grouping, the time column is called 'grouped_time'.<br>\
+    Non aggregated data ordered by the time column is paginated, full pages return the cursor for the next page in the 'X-Next-Cursor' header.",
     params(
         ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string())),
         ("key" = String, Query, description = "The provided API key for reading data.", example = json!(uuid::Uuid::new_v4().to_string())),
-        ("limit" = Option<String>, Query, description = "If this parameter is not present a limit of 10 entries will be assumed as the default.", example = "10"),
+        ("limit" = Option<String>, Query, description = "If this parameter is not present a limit of 10 entries will be assumed as the default.<br>\
+        Limits of non aggregated requests ordered by the time column are capped to the maximum page size of the server (default 1000), \
+        further pages are read with the returned cursor. Negative limits are rejected.", example = "10"),
         ("ordering" = Option<String>, Query, description = "DESC or ASC", example = "DESC"),
         ("order_col" = Option<String>, Query, description = "The column name to order to result values. By default the time column is used.", example = "col1"),
+        ("cursor" = Option<String>, Query, description = "Cursor returned in the 'X-Next-Cursor' header of the previous page to continue reading after its last row.<br>\
+        Requests must keep the ordering and the predicates of the previous page, the ordering may be omitted."),
         ("from" = Option<String>, Query, description = "Lower border for data retrieval, RFC3339 without timezone information", example="2006-01-02T15:04:05"),
         ("to" = Option<String>, Query, description = "Upper border for data retrieval, RFC3339 without timezone information", example="2006-01-02T15:04:05"),
         ("from_inclusive" = Option<bool>, Query, description = "If the 'from' range border should be considered inclusive, default=true"),
         ("to_inclusive" = Option<bool>, Query, description = "If the 'to' range border should be considered inclusive, default=true"),
+        ("filter" = Option<String>, Query, description = "Value predicates on the data columns, combined with AND on the top level.<br>\
+        Predicates: col.eq.val, col.neq.val, col.lt.val, col.lte.val, col.gt.val, col.gte.val, col.in.(v1,v2), col.is.null, col.is.notnull<br>\
+        Predicates may be nested with and(...) and or(...). Values containing special characters must be double-quoted.", example="temp.gt.20,or(state.eq.on,state.is.null)"),
         ("cols" = Option<String>, Query, description = "Comma separated list of columns to retrieve, e.g. col1,col2<br>\
         In case of time grouping, an aggregation has to be specified for each column, e.g. col1.COUNT,col2.SUM<br>\
+        Non aggregated STRING or INT columns are used as group keys, returning one row per key (and time interval), e.g. col1.AVG,col3<br>\
         By default, all columns + the time column are retrieved.", example="col1,col2"),
         ("time_grouping" = Option<u32>, Query, description = "Optional time interval in seconds used for grouping the result values.<br>\
         E.g. grouping values in 1 hour intervals (3.600s=1hour).<br>\
-        If a time grouping is used, each retrieved column must specify a data aggregation [SUM, COUNT, MAX, MIN, AVG].<br>\
+        If a time grouping is used, each retrieved column must specify a data aggregation or be a group key column.<br>\
+        Aggregations: [SUM, COUNT, MAX, MIN, AVG, BOOL_AND, BOOL_OR, P95, P98, MEDIAN, STDDEV, VARIANCE, FIRST, LAST, COUNT_DISTINCT]<br>\
         Result values will contain a 'grouped_time' field defining the grouped time value", example="3600"),
+        ("time_unit" = Option<String>, Query, description = "Optional calendar unit used for grouping the result values instead of a fixed time_grouping interval.<br>\
+        One of [MINUTE, HOUR, DAY, WEEK, MONTH, QUARTER, YEAR], weeks start on Monday (ISO week).", example="DAY"),
+        ("timezone" = Option<String>, Query, description = "IANA timezone the time buckets are aligned to, default=UTC.<br>\
+        The 'grouped_time' values are returned as UTC timestamps.", example="Europe/Berlin"),
+        ("time_origin" = Option<String>, Query, description = "Origin of the time_grouping buckets in local time, default=1970-01-01T00:00:00", example="2006-01-02T06:00:00"),
+        ("time_offset" = Option<i32>, Query, description = "Offset in seconds to shift the borders of time_unit buckets, e.g. 21600 for days starting at 06:00", example="21600"),
+        ("fill" = Option<String>, Query, description = "Optional mode to fill time buckets without data values [NULL, ZERO, PREVIOUS, LINEAR].<br>\
+        Returns the full series of time buckets between 'from' and 'to', which must both be specified. Not supported in combination with group key columns.", example="LINEAR"),
     ),
     tag = COMMON_TAG,
     responses(
-        (status = 200, description = "Returns the retrieved key/value data.", body=Vec<Value>, example=json!([{"created_at": Utc::now().naive_utc(), "col1": 42, "col2": 51.234, "col3": "Hello"}])),
+        (status = 200, description = "Returns the retrieved key/value data.", body=Vec<Value>,
+            headers(("X-Next-Cursor" = String, description = "Cursor for the next page, only present for full pages of non aggregated data ordered by the time column.")),
+            example=json!([{"created_at": Utc::now().naive_utc(), "col1": 42, "col2": 51.234, "col3": "Hello"}])),
         (status = 401, description= "Returns an unauthorized error if access is not permitted."),
         (status = 500, description= "Returns an error if the sensor does not exist or the data couldn't be retrieved."),
     ),
@@ -121,23 +159,7 @@ async fn get_sensor_data_handler(
 ) -> impl Responder {
     let sensor_id = path.into_inner();
 
-    // Retrieves the api key if it exists and is valid
-
-    let api_key = match params.key {
-        Some(key) => cache::request_api_key(key, &state).await,
-        None => None,
-    };
-
-    // Verifies key or guest access
-
-    let has_access = match api_key {
-        Some(key) => key.sensor_id == sensor_id && key.operation == DBOperation::READ,
-        None => policy::require_sensor_permission(None, sensor_id, UserSensorPerm::Read, &state)
-            .await
-            .is_none(),
-    };
-
-    if !has_access {
+    if !has_read_access(sensor_id, params.key, &state).await {
         return policy::unauthorized("No permissions to read sensor data!".to_string()).unwrap();
     }
 
@@ -147,24 +169,205 @@ async fn get_sensor_data_handler(
         query_params.limit = Some(10);
     }
 
-    let result = get_data(sensor_id, query_params, &state).await;
+    if query_params.limit.is_some_and(|limit| limit < 0) {
+        let fields = vec!["limit".to_string()];
+        return AppError::validation("The limit must not be negative!", fields).into();
+    }
+
+    let result = get_data_page(sensor_id, query_params, &state).await;
+
+    let next_cursor = result.as_ref().ok().and_then(|page| page.next_cursor.clone());
+
+    let mut response = main_hdl::send_result(&result.map(|page| page.data));
+
+    if let Some(cursor) = next_cursor {
+        if let Ok(value) = HeaderValue::from_str(&cursor) {
+            response.headers_mut().insert(HeaderName::from_static(NEXT_CURSOR_HEADER), value);
+        }
+    }
+
+    response
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sensors/{id}/data/export",
+    description = "Exports the raw data of the specified sensor as file download.<br>\
+    The rows are streamed from the database, so the whole time range of a sensor can be exported at once.<br>\
+    The format is taken from the 'format' parameter or the Accept header [text/csv, application/x-ndjson, \
+    application/vnd.apache.arrow.stream, application/vnd.apache.parquet], by default CSV is used. \
+    Each row contains the time column 'created_at' and the data columns. JSON columns are exported as serialized strings for ARROW and PARQUET.",
+    params(
+        ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string())),
+        ("key" = String, Query, description = "The provided API key for reading data.", example = json!(uuid::Uuid::new_v4().to_string())),
+        ("format" = Option<String>, Query, description = "CSV, NDJSON, ARROW (IPC stream) or PARQUET", example = "CSV"),
+        ("ordering" = Option<String>, Query, description = "Ordering of the rows by time, DESC or ASC, default=ASC", example = "ASC"),
+        ("from" = Option<String>, Query, description = "Lower border for data retrieval, RFC3339 without timezone information", example="2006-01-02T15:04:05"),
+        ("to" = Option<String>, Query, description = "Upper border for data retrieval, RFC3339 without timezone information", example="2006-01-02T15:04:05"),
+        ("from_inclusive" = Option<bool>, Query, description = "If the 'from' range border should be considered inclusive, default=true"),
+        ("to_inclusive" = Option<bool>, Query, description = "If the 'to' range border should be considered inclusive, default=true"),
+        ("filter" = Option<String>, Query, description = "Value predicates on the data columns, see data load.", example="temp.gt.20"),
+        ("cols" = Option<String>, Query, description = "Comma separated list of data columns to export, by default all data columns are exported.", example="col1,col2"),
+    ),
+    tag = COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns the exported data as file.", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson"),
+            (Vec<u8> = "application/vnd.apache.arrow.stream"),
+            (Vec<u8> = "application/vnd.apache.parquet"),
+        )),
+        (status = 401, description= "Returns an unauthorized error if access is not permitted."),
+        (status = 500, description= "Returns an error if the sensor does not exist or the data couldn't be exported."),
+        (status = 503, description= "Returns an error if the maximum number of concurrent data exports is reached."),
+    ),
+)]
+#[get("/sensors/{id}/data/export")]
+async fn export_sensor_data_handler(
+    path: web::Path<uuid::Uuid>,
+    params: web::Query<DataExportRequestParams>,
+    req: HttpRequest,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let sensor_id = path.into_inner();
+
+    if !has_read_access(sensor_id, params.key, &state).await {
+        return policy::unauthorized("No permissions to read sensor data!".to_string()).unwrap();
+    }
+
+    let params = params.into_inner();
+
+    let format = params.format.or_else(|| {
+        req.headers()
+            .get(header::ACCEPT)
+            .and_then(|accept| accept.to_str().ok())
+            .and_then(DataExportFormat::from_accept_header)
+    });
+
+    let format = format.unwrap_or(DataExportFormat::CSV);
+
+    let Ok(permit) = state.export_permits.clone().try_acquire_owned() else {
+        return AppError::InternalError {
+            status: Some(StatusCode::SERVICE_UNAVAILABLE),
+            msg: Some("Too many data exports running, try again later!".to_string()),
+        }
+        .into();
+    };
+
+    match export_data(sensor_id, params, format, permit, &state).await {
+        Ok(stream) => HttpResponse::Ok()
+            .content_type(format.content_type())
+            .insert_header(ContentDisposition::attachment(format!(
+                "{}.{}",
+                sensor_id,
+                format.file_extension()
+            )))
+            .streaming(stream),
+        Err(err) => main_hdl::send_result::<()>(&Err(err)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sensors/data/query",
+    description = "Retrieves aggregated values of multiple sensors aligned on common time buckets.<br>\
+    Each sensor specifies its data columns with an aggregation, an optional filter and the API key for reading its data. \
+    Either a 'time_grouping' or a 'time_unit' is required, the remaining time parameters behave as for the data load of a single sensor.<br>\
+    The WIDE format returns one row per time bucket with a column 'alias.col' per sensor column, missing values are null. \
+    The LONG format returns one row per time bucket and sensor with a 'sensor' column containing the alias.<br>\
+    The limit is applied to the number of time buckets, if not present a limit of 10 time buckets will be assumed as the default. \
+    Limits are capped to the maximum page size of the server and at most 20 sensors can be queried at once.",
+    request_body(
+        content_type = "application/json",
+        content = AlignedDataRequest,
+        description = "The sensors, their data columns and the time buckets to align the data on.",
+    ),
+    tag = COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns the aligned data of the sensors.", body=Vec<Value>, example=json!([{"grouped_time": Utc::now().naive_utc(), "outdoor.temp": 12.5, "indoor.temp": 21.3}])),
+        (status = 400, description= "Returns an error if the limit is negative or too many sensors are specified."),
+        (status = 401, description= "Returns an unauthorized error if access to one of the sensors is not permitted."),
+        (status = 500, description= "Returns an error if a sensor does not exist or the data couldn't be retrieved."),
+    ),
+)]
+#[post("/sensors/data/query")]
+async fn get_aligned_sensor_data_handler(
+    body: web::Json<AlignedDataRequest>,
+    state: web::Data<AppState>,
+) -> impl Responder {
+    let mut request = body.into_inner();
+
+    if request.sensors.len() > ALIGNED_MAX_SENSORS {
+        let msg = format!("At most {} sensors can be queried at once!", ALIGNED_MAX_SENSORS);
+        return AppError::validation(msg, vec!["sensors".to_string()]).into();
+    }
+
+    if request.limit.is_some_and(|limit| limit < 0) {
+        let fields = vec!["limit".to_string()];
+        return AppError::validation("The limit must not be negative!", fields).into();
+    }
+
+    for sensor in request.sensors.iter() {
+        if !has_read_access(sensor.id, sensor.key, &state).await {
+            let msg = format!("No permissions to read data of sensor {}!", sensor.id);
+            return policy::unauthorized(msg).unwrap();
+        }
+    }
+
+    // By default, we enforce a limit of 10 time buckets if no limit is set
+    let limit = request.limit.unwrap_or(10);
+    request.limit = Some(limit.min(get_data_max_page_size(&state.cfg)));
+
+    let result = get_aligned_data(request, &state).await;
 
     main_hdl::send_result(&result)
 }
 
+/// Verifies that the sensor data can be read with the provided API key or by guests.
+async fn has_read_access(
+    sensor_id: uuid::Uuid,
+    key: Option<uuid::Uuid>,
+    state: &AppState,
+) -> bool {
+    // Retrieves the api key if it exists and is valid
+
+    let api_key = match key {
+        Some(key) => cache::request_api_key(key, state).await,
+        None => None,
+    };
+
+    // Verifies key or guest access
+
+    match api_key {
+        Some(key) => key.sensor_id == sensor_id && key.operation == DBOperation::READ,
+        None => policy::require_sensor_permission(None, sensor_id, UserSensorPerm::Read, state)
+            .await
+            .is_none(),
+    }
+}
+
 /* ------------------------------------------------ Tests ------------------------------------------------------------ */
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
-    use crate::database::data_db::{GROUPED_TIME_COL_NAME, TIME_COL_NAME};
-    use crate::database::models::db_structs::{DBAggregation, DBOrdering};
+    use crate::database::data_db::{self, GROUPED_TIME_COL_NAME, TIME_COL_NAME};
+    use crate::database::models::db_structs::{DBAggregation, DBFill, DBOrdering, DBTimeUnit};
+    use crate::database::models::role::ROLE_SYSTEM_GUEST;
+    use crate::database::models::sensor::{ColumnIngest, ColumnType, SensorColumn};
+    use crate::database::sensor_db;
     use crate::features::config::TIMESTAMP_FORMAT;
+    use crate::features::sensor_data_filter::DataFilter;
     use crate::handler::models::requests::DataLoadRequestColumns;
-    use crate::handler::models::requests::SensorDataIngestEntry;
+    use crate::handler::models::requests::{
+        AlignedDataFormat, AlignedDataRequestSensor, CreateSensorRequest, SensorDataIngestEntry,
+        SensorPermissionRequest,
+    };
     use crate::test_utils::tests::{
-        create_test_api_keys, create_test_app, create_test_sensors, execute_request, john,
+        create_guest_sensor, create_test_api_keys, create_test_app, create_test_app_with_config,
+        create_test_sensors, guest_sensor_request, test_column, anne, execute_request, john, login,
     };
+    use crate::utils::QueryParam;
     use actix_http::body::BoxBody;
     use actix_http::{Method, Request};
     use actix_web::dev::{Service, ServiceResponse};
@@ -175,6 +378,7 @@ pub mod tests {
     use serde_json::{json, Value};
     use sqlx::PgPool;
     use std::collections::HashMap;
+    use std::sync::Arc;
     use uuid::Uuid;
 
     #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
@@ -379,7 +583,7 @@ pub mod tests {
         )
         .await;
 
-        // --- Ingest various invalid data to public sensor - Should succeed ---
+        // --- Ingest various invalid data to public sensor ---
 
         fn compare_payload(payload: &Value, entry: &Map<String, Value>) -> bool {
             match (payload, entry) {
@@ -394,7 +598,7 @@ pub mod tests {
         req_payload.limit = Some(1);
         req_payload.ordering = Some(DBOrdering::DESC);
 
-        // --- Some columns invalid - should be inserted as NULLs
+        // --- Some columns invalid - Should fail with the offending columns
 
         let payload = {
             json!({
@@ -404,42 +608,24 @@ pub mod tests {
             })
         };
 
-        let _ = execute_request(
+        let body = execute_request(
             &format!("/api/sensors/{}/data/ingest", public_sensor.1),
             Method::POST,
             None,
             Some(vec![SensorDataIngestEntry::from_json(payload, None)]),
             None,
-            StatusCode::OK,
-            &app,
-        )
-        .await;
-
-        // Check result
-
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", public_sensor.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::OK,
+            StatusCode::BAD_REQUEST,
             &app,
         )
         .await;
 
-        let expected_result = {
-            json!({
-                "col1": null,
-                "col2": null,
-                "col3": "42",
-            })
-        };
+        let fields: Vec<String> = serde_json::from_value(body["fields"].clone()).unwrap();
 
-        let elm = body.as_array().unwrap().first().unwrap();
-        assert!(compare_payload(&expected_result, elm.as_object().unwrap()));
+        assert_eq!(fields.len(), 2);
+        assert!(fields[0].starts_with("entry 0: column 'col1'"));
+        assert!(fields[1].starts_with("entry 0: column 'col2'"));
 
-        // --- All columns invalid - should be inserted as NULLs
+        // --- All columns invalid - Should fail with all columns
 
         let payload = {
             json!({
@@ -449,40 +635,20 @@ pub mod tests {
             })
         };
 
-        let _ = execute_request(
+        let body = execute_request(
             &format!("/api/sensors/{}/data/ingest", public_sensor.1),
             Method::POST,
             None,
             Some(vec![SensorDataIngestEntry::from_json(payload, None)]),
             None,
-            StatusCode::OK,
-            &app,
-        )
-        .await;
-
-        // Check result
-
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", public_sensor.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::OK,
+            StatusCode::BAD_REQUEST,
             &app,
         )
         .await;
 
-        let expected_result = {
-            json!({
-                "col1": null,
-                "col2": null,
-                "col3": null,
-            })
-        };
+        let fields: Vec<String> = serde_json::from_value(body["fields"].clone()).unwrap();
 
-        let elm = body.as_array().unwrap().first().unwrap();
-        assert!(compare_payload(&expected_result, elm.as_object().unwrap()));
+        assert_eq!(fields.len(), 3);
 
         // --- Some invalid col names - Only col2 should be inserted correctly, rest NULL
 
@@ -690,125 +856,102 @@ pub mod tests {
         }
     }
 
-    async fn add_bulk_dummy_data(
-        amount: u32,
-        delay_per_tup: u32,
-        sensor_id: Uuid,
-        write_key: Uuid,
-        app: &impl Service<Request, Response = ServiceResponse<BoxBody>, Error = actix_web::Error>,
-    ) {
-        let mut data_entries: Vec<SensorDataIngestEntry> = Vec::new();
-
-        for i in 0..amount {
-            let time: NaiveDateTime =
-                Utc::now().naive_utc() + chrono::Duration::milliseconds((i * delay_per_tup) as i64);
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_ingest_column_constraints(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
 
-            let payload = json!({
-                "col1": i,
-                "col2": 56.789,
-                "col3": "Hello",
-            });
+        let sensor_req = |constraints: Value| {
+            guest_sensor_request(
+                "MyConstrainedSensor",
+                vec![
+                    SensorColumn {
+                        val_unit: "celsius".to_string(),
+                        constraints: Some(serde_json::from_value(constraints).unwrap()),
+                        ..test_column("temp", ColumnType::FLOAT)
+                    },
+                    SensorColumn {
+                        constraints: Some(serde_json::from_value(json!({"allowed": ["on", "off"]})).unwrap()),
+                        ..test_column("state", ColumnType::STRING)
+                    },
+                ],
+            )
+        };
 
-            data_entries.push(SensorDataIngestEntry::from_json(payload, Some(time)));
-        }
+        // --- Constraints not applicable to the column type - Should fail ---
 
-        // Make sure the wait before pushing the data, or it will be rejected by DB (timestamps in future)
-        task::sleep(core::time::Duration::from_millis(
-            (amount * delay_per_tup) as u64,
-        ))
-        .await;
+        let res = sensor_db::create_sensor(sensor_req(json!({"regex": "^1$"})), None, &state).await;
+        assert!(res.is_err());
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/ingest?key={}", sensor_id, write_key),
-            Method::POST,
-            None,
-            Some(data_entries),
+        let sensor_id = sensor_db::create_sensor(
+            sensor_req(json!({"min": -50, "max": 60, "required": true})),
             None,
-            StatusCode::OK,
-            &app,
+            &state,
         )
-        .await;
-    }
+        .await
+        .unwrap()
+        .uuid;
 
-    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
-    async fn test_delete_sensor(pool: PgPool) {
-        let (app, state) = create_test_app(pool).await;
-
-        let test_sens = create_test_sensors(&state).await;
-        let test_keys = create_test_api_keys(&state).await;
-
-        let target_sensor_own = test_sens
-            .iter()
-            .find(|(name, _)| name == "MySensor")
-            .unwrap();
-        let target_sensor_allowed = test_sens
-            .iter()
-            .find(|(name, _)| name == "MySensor2")
-            .unwrap();
-        let target_sensor_not_allowed = test_sens
-            .iter()
-            .find(|(name, _)| name == "MySensor4")
-            .unwrap();
-        let public_sensor = test_sens
-            .iter()
-            .find(|(name, _)| name == "MySensor5")
+        // Constraints are persisted with the sensor schema
+        let sensor = cache::request_sensor(Uuid::parse_str(&sensor_id).unwrap(), &state)
+            .await
             .unwrap();
+        let temp = sensor.columns.iter().find(|c| c.name == "temp").unwrap();
+        assert_eq!(temp.constraints.as_ref().unwrap().max, Some(60.0));
+        assert!(temp.constraints.as_ref().unwrap().required);
 
-        // --- Delete ALL allowed (his own) sensor data as john with key - Should succeed ---
-
-        let api_key_read = test_keys
-            .iter()
-            .find(|k| {
-                k.user_id == john().id
-                    && k.sensor_id == target_sensor_own.1
-                    && k.operation == DBOperation::READ
-            })
-            .unwrap()
-            .id;
-        let api_key_write = test_keys
-            .iter()
-            .find(|k| {
-                k.user_id == john().id
-                    && k.sensor_id == target_sensor_own.1
-                    && k.operation == DBOperation::WRITE
-            })
-            .unwrap()
-            .id;
-
-        let amount = 10;
-        let tup_delay = 100;
+        let ingest_url = format!("/api/sensors/{}/data/ingest", sensor_id);
 
-        add_bulk_dummy_data(amount, 5, target_sensor_own.1, api_key_write, &app).await;
+        let ingest = |payloads: Vec<Value>, status: StatusCode| {
+            let entries: Vec<SensorDataIngestEntry> = payloads
+                .into_iter()
+                .map(|p| SensorDataIngestEntry::from_json(p, None))
+                .collect();
 
-        let delete_request = SensorDataDeletionParams {
-            key: Some(api_key_write),
-            from: None,
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(true),
+            execute_request(
+                &ingest_url,
+                Method::POST,
+                None,
+                Some(entries),
+                None,
+                status,
+                &app,
+            )
         };
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", target_sensor_own.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
-            None::<Value>,
-            None,
+        // --- Valid entries - Should succeed ---
+
+        let _ = ingest(
+            vec![json!({"temp": 21.5, "state": "on"}), json!({"temp": -50})],
             StatusCode::OK,
-            &app,
         )
         .await;
 
-        // Check if all values were deleted
+        // --- Offending entries reject the whole request with per-entry errors ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
+        let body = ingest(
+            vec![
+                json!({"temp": 20.0, "state": "on"}),
+                json!({"temp": -999}),
+                json!({"state": "maybe"}),
+                json!({"temp": "warm"}),
+            ],
+            StatusCode::BAD_REQUEST,
+        )
+        .await;
+
+        let fields: Vec<String> = serde_json::from_value(body["fields"].clone()).unwrap();
 
+        assert_eq!(fields.len(), 4);
+        assert!(fields[0].starts_with("entry 1: column 'temp'"));
+        assert!(fields[1].starts_with("entry 2: column 'temp'"));
+        assert!(fields[2].starts_with("entry 2: column 'state'"));
+        assert!(fields[3].starts_with("entry 3: column 'temp'"));
+
+        // Nothing of the rejected request was stored
         let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            &format!("/api/sensors/{}/data/load", sensor_id),
             Method::GET,
-            Some(req_payload.to_vector()),
+            None,
             None::<Value>,
             None,
             StatusCode::OK,
@@ -816,105 +959,143 @@ pub mod tests {
         )
         .await;
 
-        assert_eq!(body.as_array().unwrap().len(), 0);
+        assert_eq!(body.as_array().unwrap().len(), 2);
 
-        // --- Delete SOME [open range] allowed (his own) sensor data as john with key - Should succeed ---
+        // --- Unreadable stored constraints are reported instead of being dropped ---
 
-        let time_start: NaiveDateTime = Utc::now().naive_utc();
+        let sensor_id = Uuid::parse_str(&sensor_id).unwrap();
 
-        add_bulk_dummy_data(amount, tup_delay, target_sensor_own.1, api_key_write, &app).await;
+        sqlx::query("UPDATE sensor_schema SET col_constraints = '{' WHERE sensor_id = $1 AND col_name = 'temp'")
+            .bind(sensor_id)
+            .execute(&state.db)
+            .await
+            .unwrap();
 
-        let delete_threshold: NaiveDateTime =
-            time_start + chrono::Duration::milliseconds((amount / 2 * tup_delay) as i64);
+        let mut conn = state.db.acquire().await.unwrap();
+        assert!(sensor_db::get_full_sensor_info(sensor_id, &mut conn).await.is_err());
+    }
 
-        let delete_request = SensorDataDeletionParams {
-            key: Some(api_key_write),
-            from: Some(delete_threshold),
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(false),
-        };
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_ingest_column_types(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", target_sensor_own.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::OK,
-            &app,
+        let sensor_id = create_guest_sensor(
+            "MyTypedSensor",
+            vec![test_column("count", ColumnType::INT), test_column("label", ColumnType::STRING)],
+            &state,
         )
         .await;
 
-        // Check results - Get all values and check if only the specified values where deleted! [half values from end]
-
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.ordering = Some(DBOrdering::DESC);
+        let ingest_url = format!("/api/sensors/{}/data/ingest", sensor_id);
 
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::OK,
-            &app,
-        )
-        .await;
+        let ingest = |payload: Value, status: StatusCode| {
+            execute_request(
+                &ingest_url,
+                Method::POST,
+                None,
+                Some(vec![SensorDataIngestEntry::from_json(payload, None)]),
+                None,
+                status,
+                &app,
+            )
+        };
 
-        let res_arr = body.as_array().unwrap();
+        // --- Matching types and omitted columns - Should succeed ---
 
-        assert_eq!(res_arr.len(), (amount / 2) as usize);
+        let _ = ingest(json!({"count": 1, "label": "a"}), StatusCode::OK).await;
+        let _ = ingest(json!({"count": 2}), StatusCode::OK).await;
 
-        for (index, elm) in body.as_array().unwrap().iter().enumerate() {
-            assert_eq!(
-                elm.as_object()
-                    .unwrap()
-                    .get("col1")
-                    .unwrap()
-                    .as_i64()
-                    .unwrap(),
-                (amount - 1 - amount / 2 - index as u32) as i64
-            );
-        }
+        // --- Values not matching unconstrained columns - Should fail instead of inserting NULL ---
 
-        // --- Delete SOME [closed range] allowed (his own) sensor data as john with key - Should succeed ---
+        let body = ingest(json!({"count": "3", "label": "b"}), StatusCode::BAD_REQUEST).await;
 
-        let time_start: NaiveDateTime = Utc::now().naive_utc();
+        let fields: Vec<String> = serde_json::from_value(body["fields"].clone()).unwrap();
 
-        add_bulk_dummy_data(10, tup_delay, target_sensor_own.1, api_key_write, &app).await;
+        assert_eq!(fields.len(), 1);
+        assert!(fields[0].starts_with("entry 0: column 'count'"));
 
-        // to/from are inclusive (here)!
-        let delete_to: NaiveDateTime = time_start
-            + chrono::Duration::milliseconds(((amount - 2 - 1) * tup_delay) as i64)
-            + chrono::Duration::milliseconds((tup_delay / 2) as i64); // Additional delay for consistency
+        let _ = ingest(json!({"count": 4, "label": 5}), StatusCode::BAD_REQUEST).await;
+    }
 
-        let delete_request = SensorDataDeletionParams {
-            key: Some(api_key_write),
-            from: Some(time_start), // Does only remove new values
-            to: Some(delete_to),
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(false),
-        };
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_value_filter(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", target_sensor_own.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::OK,
-            &app,
+        let sensor_id = create_guest_sensor(
+            "MyFilteredSensor",
+            vec![
+                test_column("temp", ColumnType::FLOAT),
+                test_column("state", ColumnType::STRING),
+                test_column("meta", ColumnType::JSON),
+            ],
+            &state,
         )
         .await;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let data: Vec<SensorDataIngestEntry> = [
+            (json!({"temp": 10.0, "state": "on"}), "2025-02-11T08:00:00"),
+            (json!({"temp": 20.0, "state": "off"}), "2025-02-11T09:00:00"),
+            (json!({"temp": 30.0, "state": "a,b"}), "2025-02-11T10:00:00"),
+            (json!({"temp": -999.0}), "2025-02-11T11:00:00"),
+            (json!({"temp": 999.0, "state": "on"}), "2025-02-11T12:00:00"),
+        ]
+        .into_iter()
+        .map(|(v, t)| SensorDataIngestEntry::from_json(v, Some(ts(t))))
+        .collect();
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let load_url = format!("/api/sensors/{}/data/load", sensor_id);
+
+        let load = |filter: &str, status: StatusCode| {
+            let req_payload = DataLoadRequestParams {
+                ordering: Some(DBOrdering::ASC),
+                filter: Some(DataFilter::parse(filter).unwrap()),
+                ..Default::default()
+            };
 
-        // Check results - Get all values and check if only the specified values where deleted!
+            execute_request(
+                &load_url,
+                Method::GET,
+                Some(req_payload.to_vector()),
+                None::<Value>,
+                None,
+                status,
+                &app,
+            )
+        };
+
+        let temps = |body: Value| -> Vec<f64> {
+            body.as_array().unwrap().iter().map(|r| r["temp"].as_f64().unwrap()).collect()
+        };
+
+        // --- Value predicates - Should succeed ---
+
+        let body = load("temp.gte.20,temp.lt.500", StatusCode::OK).await;
+        assert_eq!(temps(body), vec![20.0, 30.0]);
+
+        let body = load("or(state.in.(off,\"a,b\"),state.is.null)", StatusCode::OK).await;
+        assert_eq!(temps(body), vec![20.0, 30.0, -999.0]);
+
+        let body = load("and(state.neq.on,created_at.gt.2025-02-11T09:00:00)", StatusCode::OK).await;
+        assert_eq!(temps(body), vec![30.0]);
+
+        let body = load("meta.is.null", StatusCode::OK).await;
+        assert_eq!(body.as_array().unwrap().len(), 5);
+
+        // --- Combined with the time range ---
+
+        let req_payload = DataLoadRequestParams {
+            from: Some(ts("2025-02-11T09:30:00")),
+            filter: Some(DataFilter::parse("state.eq.on").unwrap()),
+            ..Default::default()
+        };
 
         let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            &load_url,
             Method::GET,
             Some(req_payload.to_vector()),
             None::<Value>,
@@ -924,59 +1105,28 @@ pub mod tests {
         )
         .await;
 
-        let res_arr = body.as_array().unwrap();
-
-        assert_eq!(res_arr.len(), 2 + (amount / 2) as usize); // Old and new values
+        assert_eq!(temps(body), vec![999.0]);
 
-        for (index, elm) in res_arr.iter().enumerate() {
-            if index < 2 {
-                //New values (starting from last idx)
-                assert_eq!(
-                    elm.as_object()
-                        .unwrap()
-                        .get("col1")
-                        .unwrap()
-                        .as_i64()
-                        .unwrap(),
-                    (amount - 1 - index as u32) as i64
-                );
-            } else {
-                // Old values starting from amount/2 index
-                assert_eq!(
-                    elm.as_object()
-                        .unwrap()
-                        .get("col1")
-                        .unwrap()
-                        .as_i64()
-                        .unwrap(),
-                    (amount / 2 - 1 + 2 - index as u32) as i64
-                );
-            }
-        }
+        // --- Invalid predicates - Should fail ---
 
-        // --- Delete allowed sensor data as john with key - Should succeed ---
+        let _ = load("unknown.eq.1", StatusCode::INTERNAL_SERVER_ERROR).await;
+        let _ = load("temp.eq.warm", StatusCode::INTERNAL_SERVER_ERROR).await;
+        let _ = load("meta.eq.1", StatusCode::INTERNAL_SERVER_ERROR).await;
 
-        let api_key_write = test_keys
-            .iter()
-            .find(|k| {
-                k.user_id == john().id
-                    && k.sensor_id == target_sensor_allowed.1
-                    && k.operation == DBOperation::WRITE
-            })
-            .unwrap()
-            .id;
+        // --- Delete only the bad readings ---
 
         let delete_request = SensorDataDeletionParams {
-            key: Some(api_key_write),
+            key: None,
             from: None,
             to: None,
             to_inclusive: None,
             from_inclusive: None,
-            purge: Some(true),
+            filter: Some(DataFilter::parse("or(temp.lt.-50,temp.gt.60)").unwrap()),
+            purge: None,
         };
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", target_sensor_allowed.1),
+            &format!("/api/sensors/{}/data/delete", sensor_id),
             Method::DELETE,
             Some(delete_request.to_vector()),
             None::<Value>,
@@ -986,186 +1136,751 @@ pub mod tests {
         )
         .await;
 
-        // --- Delete allowed sensor data as john with valid key but wrong op (READ) - Should fail ---
+        let body = load("temp.is.notnull", StatusCode::OK).await;
+        assert_eq!(temps(body), vec![10.0, 20.0, 30.0]);
+    }
 
-        let api_key_read = test_keys
-            .iter()
-            .find(|k| {
-                k.user_id == john().id
-                    && k.sensor_id == target_sensor_allowed.1
-                    && k.operation == DBOperation::READ
-            })
-            .unwrap()
-            .id;
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_group_keys(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
 
-        let delete_request = SensorDataDeletionParams {
-            key: Some(api_key_read),
-            from: None,
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(false),
+        let sensor_id = create_guest_sensor(
+            "MyBinSensor",
+            vec![
+                test_column("bin_type", ColumnType::STRING),
+                test_column("bin_id", ColumnType::INT),
+                test_column("level", ColumnType::FLOAT),
+            ],
+            &state,
+        )
+        .await;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let data: Vec<SensorDataIngestEntry> = [
+            (json!({"bin_type": "glass", "bin_id": 1, "level": 10.0}), "2025-02-11T08:10:00"),
+            (json!({"bin_type": "glass", "bin_id": 2, "level": 20.0}), "2025-02-11T08:20:00"),
+            (json!({"bin_type": "paper", "bin_id": 3, "level": 50.0}), "2025-02-11T08:30:00"),
+            (json!({"bin_type": "glass", "bin_id": 1, "level": 40.0}), "2025-02-11T09:10:00"),
+            (json!({"bin_type": "paper", "bin_id": 3, "level": 70.0}), "2025-02-11T09:20:00"),
+            (json!({"bin_type": "paper", "bin_id": 4, "level": 90.0}), "2025-02-11T09:30:00"),
+        ]
+        .into_iter()
+        .map(|(v, t)| SensorDataIngestEntry::from_json(v, Some(ts(t))))
+        .collect();
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let load_url = format!("/api/sensors/{}/data/load", sensor_id);
+
+        let load = |cols: &[(&str, Option<DBAggregation>)],
+                    time_grouping: Option<u32>,
+                    status: StatusCode| {
+            let req_payload = DataLoadRequestParams {
+                ordering: Some(DBOrdering::ASC),
+                time_grouping,
+                cols: Some(
+                    cols.iter()
+                        .map(|(name, aggregation)| DataLoadRequestColumns {
+                            name: name.to_string(),
+                            aggregation: aggregation.clone(),
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            };
+
+            execute_request(
+                &load_url,
+                Method::GET,
+                Some(req_payload.to_vector()),
+                None::<Value>,
+                None,
+                status,
+                &app,
+            )
         };
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", target_sensor_allowed.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::UNAUTHORIZED,
-            &app,
+        // --- Average level per bin type per hour ---
+
+        let body = load(
+            &[("level", Some(DBAggregation::AVG)), ("bin_type", None)],
+            Some(3600),
+            StatusCode::OK,
         )
         .await;
 
-        // --- Delete un-allowed sensor data with valid key (john) of wrong sensor - Should fail ---
+        let rows: Vec<(String, String, f64)> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                assert!(!r.as_object().unwrap().contains_key(TIME_COL_NAME));
+                (
+                    r[GROUPED_TIME_COL_NAME].as_str().unwrap().to_string(),
+                    r["bin_type"].as_str().unwrap().to_string(),
+                    r["level"].as_f64().unwrap(),
+                )
+            })
+            .collect();
 
-        let delete_request = SensorDataDeletionParams {
-            key: Some(api_key_write),
-            from: None,
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(false),
-        };
+        let expected = [
+            ("2025-02-11T08:00:00.000", "glass", 15.0),
+            ("2025-02-11T08:00:00.000", "paper", 50.0),
+            ("2025-02-11T09:00:00.000", "glass", 40.0),
+            ("2025-02-11T09:00:00.000", "paper", 80.0),
+        ];
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", target_sensor_not_allowed.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
-            None::<Value>,
+        assert_eq!(rows.len(), expected.len());
+
+        for (row, exp) in rows.iter().zip(expected.iter()) {
+            assert_eq!((row.0.as_str(), row.1.as_str(), row.2), *exp);
+        }
+
+        // --- Multiple keys without time grouping ---
+
+        let body = load(
+            &[("bin_type", None), ("bin_id", None), ("level", Some(DBAggregation::MAX))],
             None,
-            StatusCode::UNAUTHORIZED,
-            &app,
+            StatusCode::OK,
         )
         .await;
 
-        // --- Delete sensor data with invalid key - Should fail ---
+        let rows: Vec<(String, i64, f64)> = body
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| {
+                assert!(!r.as_object().unwrap().contains_key(GROUPED_TIME_COL_NAME));
+                (
+                    r["bin_type"].as_str().unwrap().to_string(),
+                    r["bin_id"].as_i64().unwrap(),
+                    r["level"].as_f64().unwrap(),
+                )
+            })
+            .collect();
 
-        let delete_request = SensorDataDeletionParams {
-            key: Some(Uuid::new_v4()),
-            from: None,
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(false),
-        };
+        assert_eq!(
+            rows,
+            vec![
+                ("glass".to_string(), 1, 40.0),
+                ("glass".to_string(), 2, 20.0),
+                ("paper".to_string(), 3, 70.0),
+                ("paper".to_string(), 4, 90.0),
+            ]
+        );
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", target_sensor_not_allowed.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
-            None::<Value>,
+        // --- Keys must be STRING or INT columns ---
+
+        let _ = load(
+            &[("level", None), ("bin_id", Some(DBAggregation::COUNT))],
             None,
-            StatusCode::UNAUTHORIZED,
-            &app,
+            StatusCode::INTERNAL_SERVER_ERROR,
         )
         .await;
+    }
 
-        // --- Delete non-existing sensor data without key - Should fail ---
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_calendar_grouping(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", Uuid::new_v4()),
-            Method::DELETE,
-            Some(SensorDataDeletionParams::default().to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::INTERNAL_SERVER_ERROR,
-            &app,
+        let sensor_id = create_guest_sensor(
+            "MyCounterSensor",
+            vec![test_column("count", ColumnType::INT)],
+            &state,
         )
         .await;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        // Europe/Berlin is UTC+1 in February, 2025-02-10 is a Monday
+        let data: Vec<SensorDataIngestEntry> = [
+            (1, "2025-02-10T22:30:00"),
+            (2, "2025-02-10T23:30:00"),
+            (4, "2025-02-11T12:00:00"),
+            (8, "2025-02-17T08:00:00"),
+            (16, "2025-03-01T00:30:00"),
+        ]
+        .into_iter()
+        .map(|(v, t)| SensorDataIngestEntry::from_json(json!({"count": v}), Some(ts(t))))
+        .collect();
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let load_url = format!("/api/sensors/{}/data/load", sensor_id);
+
+        let load = |req: DataLoadRequestParams, status: StatusCode| {
+            let req_payload = DataLoadRequestParams {
+                ordering: Some(DBOrdering::ASC),
+                cols: Some(vec![DataLoadRequestColumns {
+                    name: "count".to_string(),
+                    aggregation: Some(DBAggregation::SUM),
+                }]),
+                ..req
+            };
 
-        // --- Delete public sensor data without key - Should succeed ---
+            execute_request(
+                &load_url,
+                Method::GET,
+                Some(req_payload.to_vector()),
+                None::<Value>,
+                None,
+                status,
+                &app,
+            )
+        };
 
-        let delete_request = SensorDataDeletionParams {
-            key: None,
-            from: None,
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(true),
+        let buckets = |body: Value| -> Vec<(String, i64)> {
+            body.as_array()
+                .unwrap()
+                .iter()
+                .map(|r| {
+                    (
+                        r[GROUPED_TIME_COL_NAME].as_str().unwrap().to_string(),
+                        r["count"].as_i64().unwrap(),
+                    )
+                })
+                .collect()
         };
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", public_sensor.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::OK,
-            &app,
+        let expect = |expected: &[(&str, i64)]| -> Vec<(String, i64)> {
+            expected.iter().map(|(t, v)| (format!("{}.000", t), *v)).collect()
+        };
+
+        // --- UTC days ---
+
+        let req = DataLoadRequestParams {
+            time_unit: Some(DBTimeUnit::DAY),
+            ..Default::default()
+        };
+
+        let body = load(req, StatusCode::OK).await;
+        assert_eq!(
+            buckets(body),
+            expect(&[
+                ("2025-02-10T00:00:00", 3),
+                ("2025-02-11T00:00:00", 4),
+                ("2025-02-17T00:00:00", 8),
+                ("2025-03-01T00:00:00", 16),
+            ])
+        );
+
+        // --- Local days, buckets are returned in UTC ---
+
+        let req = DataLoadRequestParams {
+            time_unit: Some(DBTimeUnit::DAY),
+            timezone: Some("Europe/Berlin".to_string()),
+            ..Default::default()
+        };
+
+        let body = load(req, StatusCode::OK).await;
+        assert_eq!(
+            buckets(body),
+            expect(&[
+                ("2025-02-09T23:00:00", 1),
+                ("2025-02-10T23:00:00", 6),
+                ("2025-02-16T23:00:00", 8),
+                ("2025-02-28T23:00:00", 16),
+            ])
+        );
+
+        // --- Local ISO weeks and UTC months ---
+
+        let req = DataLoadRequestParams {
+            time_unit: Some(DBTimeUnit::WEEK),
+            timezone: Some("Europe/Berlin".to_string()),
+            ..Default::default()
+        };
+
+        let body = load(req, StatusCode::OK).await;
+        assert_eq!(
+            buckets(body),
+            expect(&[
+                ("2025-02-09T23:00:00", 7),
+                ("2025-02-16T23:00:00", 8),
+                ("2025-02-23T23:00:00", 16),
+            ])
+        );
+
+        let req = DataLoadRequestParams {
+            time_unit: Some(DBTimeUnit::MONTH),
+            ..Default::default()
+        };
+
+        let body = load(req, StatusCode::OK).await;
+        assert_eq!(
+            buckets(body),
+            expect(&[("2025-02-01T00:00:00", 15), ("2025-03-01T00:00:00", 16)])
+        );
+
+        // --- Days starting at 06:00 ---
+
+        let req = DataLoadRequestParams {
+            time_unit: Some(DBTimeUnit::DAY),
+            time_offset: Some(6 * 3600),
+            ..Default::default()
+        };
+
+        let body = load(req, StatusCode::OK).await;
+        assert_eq!(
+            buckets(body),
+            expect(&[
+                ("2025-02-10T06:00:00", 3),
+                ("2025-02-11T06:00:00", 4),
+                ("2025-02-17T06:00:00", 8),
+                ("2025-02-28T06:00:00", 16),
+            ])
+        );
+
+        // --- Fixed intervals with custom origin ---
+
+        let req = DataLoadRequestParams {
+            time_grouping: Some(24 * 3600),
+            time_origin: Some(ts("2025-01-01T12:00:00")),
+            ..Default::default()
+        };
+
+        let body = load(req, StatusCode::OK).await;
+        assert_eq!(
+            buckets(body),
+            expect(&[
+                ("2025-02-10T12:00:00", 3),
+                ("2025-02-11T12:00:00", 4),
+                ("2025-02-16T12:00:00", 8),
+                ("2025-02-28T12:00:00", 16),
+            ])
+        );
+
+        // --- Invalid combinations - Should fail ---
+
+        let invalid = [
+            DataLoadRequestParams {
+                time_unit: Some(DBTimeUnit::DAY),
+                timezone: Some("Mars/Olympus_Mons".to_string()),
+                ..Default::default()
+            },
+            DataLoadRequestParams {
+                time_unit: Some(DBTimeUnit::DAY),
+                time_grouping: Some(3600),
+                ..Default::default()
+            },
+            DataLoadRequestParams {
+                time_grouping: Some(3600),
+                time_offset: Some(60),
+                ..Default::default()
+            },
+            DataLoadRequestParams {
+                time_unit: Some(DBTimeUnit::DAY),
+                time_origin: Some(ts("2025-01-01T12:00:00")),
+                ..Default::default()
+            },
+            DataLoadRequestParams {
+                timezone: Some("Europe/Berlin".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        for req in invalid {
+            let _ = load(req, StatusCode::INTERNAL_SERVER_ERROR).await;
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_gap_filling(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let sensor_id = create_guest_sensor(
+            "MySilentSensor",
+            vec![
+                test_column("level", ColumnType::FLOAT),
+                test_column("count", ColumnType::INT),
+                test_column("state", ColumnType::STRING),
+            ],
+            &state,
         )
         .await;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let data: Vec<SensorDataIngestEntry> = [
+            (json!({"level": 10.0, "count": 1, "state": "on"}), "2025-02-11T00:15:00"),
+            (json!({"level": 40.0, "count": 4, "state": "off"}), "2025-02-11T03:45:00"),
+        ]
+        .into_iter()
+        .map(|(v, t)| SensorDataIngestEntry::from_json(v, Some(ts(t))))
+        .collect();
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let load_url = format!("/api/sensors/{}/data/load", sensor_id);
+
+        let load = |req: DataLoadRequestParams, status: StatusCode| {
+            let req_payload = DataLoadRequestParams {
+                cols: req.cols.clone().or(Some(vec![
+                    DataLoadRequestColumns {
+                        name: "level".to_string(),
+                        aggregation: Some(DBAggregation::AVG),
+                    },
+                    DataLoadRequestColumns {
+                        name: "count".to_string(),
+                        aggregation: Some(DBAggregation::SUM),
+                    },
+                ])),
+                ordering: req.ordering.clone().or(Some(DBOrdering::ASC)),
+                time_unit: Some(DBTimeUnit::HOUR),
+                from: Some(ts("2025-02-10T23:00:00")),
+                to: Some(ts("2025-02-11T05:00:00")),
+                ..req
+            };
 
-        // --- Delete public sensor data with valid key of wrong sensor - Should fail ---
+            execute_request(
+                &load_url,
+                Method::GET,
+                Some(req_payload.to_vector()),
+                None::<Value>,
+                None,
+                status,
+                &app,
+            )
+        };
 
-        let delete_request = SensorDataDeletionParams {
-            key: Some(api_key_write),
-            from: None,
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(false),
+        let values = |body: Value| -> (Vec<String>, Vec<Value>, Vec<Value>) {
+            let rows = body.as_array().unwrap();
+
+            (
+                rows.iter()
+                    .map(|r| r[GROUPED_TIME_COL_NAME].as_str().unwrap()[11..16].to_string())
+                    .collect(),
+                rows.iter().map(|r| r["level"].clone()).collect(),
+                rows.iter().map(|r| r["count"].clone()).collect(),
+            )
+        };
+
+        // --- Missing buckets are NULL ---
+
+        let req = DataLoadRequestParams {
+            fill: Some(DBFill::NULL),
+            ..Default::default()
+        };
+
+        let (times, level, count) = values(load(req, StatusCode::OK).await);
+
+        assert_eq!(times, vec!["23:00", "00:00", "01:00", "02:00", "03:00", "04:00", "05:00"]);
+        assert_eq!(json!(level), json!([null, 10.0, null, null, 40.0, null, null]));
+        assert_eq!(json!(count), json!([null, 1, null, null, 4, null, null]));
+
+        // --- Zero ---
+
+        let req = DataLoadRequestParams {
+            fill: Some(DBFill::ZERO),
+            ..Default::default()
+        };
+
+        let (_, level, count) = values(load(req, StatusCode::OK).await);
+
+        assert_eq!(json!(level), json!([0.0, 10.0, 0.0, 0.0, 40.0, 0.0, 0.0]));
+        assert_eq!(json!(count), json!([0, 1, 0, 0, 4, 0, 0]));
+
+        // --- Previous value (LOCF) ---
+
+        let req = DataLoadRequestParams {
+            fill: Some(DBFill::PREVIOUS),
+            ..Default::default()
+        };
+
+        let (_, level, _) = values(load(req, StatusCode::OK).await);
+
+        assert_eq!(json!(level), json!([null, 10.0, 10.0, 10.0, 40.0, 40.0, 40.0]));
+
+        // Ordering and limit are applied to the filled series
+
+        let req = DataLoadRequestParams {
+            fill: Some(DBFill::PREVIOUS),
+            ordering: Some(DBOrdering::DESC),
+            limit: Some(3),
+            ..Default::default()
+        };
+
+        let (times, level, _) = values(load(req, StatusCode::OK).await);
+
+        assert_eq!(times, vec!["05:00", "04:00", "03:00"]);
+        assert_eq!(json!(level), json!([40.0, 40.0, 40.0]));
+
+        // --- Linear interpolation ---
+
+        let req = DataLoadRequestParams {
+            fill: Some(DBFill::LINEAR),
+            ..Default::default()
+        };
+
+        let (_, level, count) = values(load(req, StatusCode::OK).await);
+
+        assert_eq!(json!(level), json!([null, 10.0, 20.0, 30.0, 40.0, null, null]));
+        assert_eq!(json!(count), json!([null, 1, 2, 3, 4, null, null]));
+
+        // --- Invalid requests - Should fail ---
+
+        let string_cols = Some(vec![DataLoadRequestColumns {
+            name: "state".to_string(),
+            aggregation: Some(DBAggregation::MAX),
+        }]);
+
+        let group_key_cols = Some(vec![
+            DataLoadRequestColumns {
+                name: "level".to_string(),
+                aggregation: Some(DBAggregation::AVG),
+            },
+            DataLoadRequestColumns {
+                name: "state".to_string(),
+                aggregation: None,
+            },
+        ]);
+
+        let invalid = [
+            DataLoadRequestParams {
+                fill: Some(DBFill::LINEAR),
+                cols: string_cols.clone(),
+                ..Default::default()
+            },
+            DataLoadRequestParams {
+                fill: Some(DBFill::ZERO),
+                cols: string_cols.clone(),
+                ..Default::default()
+            },
+            DataLoadRequestParams {
+                fill: Some(DBFill::NULL),
+                cols: group_key_cols,
+                ..Default::default()
+            },
+        ];
+
+        for req in invalid {
+            let _ = load(req, StatusCode::INTERNAL_SERVER_ERROR).await;
+        }
+
+        // Strings can be carried forward
+        let req = DataLoadRequestParams {
+            fill: Some(DBFill::PREVIOUS),
+            cols: string_cols,
+            ..Default::default()
+        };
+
+        let body = load(req, StatusCode::OK).await;
+        let states: Vec<Value> =
+            body.as_array().unwrap().iter().map(|r| r["state"].clone()).collect();
+
+        assert_eq!(json!(states), json!([null, "on", "on", "on", "off", "off", "off"]));
+
+        // Fill requires a time range and a time grouping
+        let req_payload = DataLoadRequestParams {
+            fill: Some(DBFill::NULL),
+            time_unit: Some(DBTimeUnit::HOUR),
+            cols: Some(vec![DataLoadRequestColumns {
+                name: "level".to_string(),
+                aggregation: Some(DBAggregation::AVG),
+            }]),
+            ..Default::default()
         };
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", public_sensor.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
+            &load_url,
+            Method::GET,
+            Some(req_payload.to_vector()),
             None::<Value>,
             None,
-            StatusCode::UNAUTHORIZED,
+            StatusCode::INTERNAL_SERVER_ERROR,
             &app,
         )
         .await;
 
-        // --- Delete public sensor data with invalid key - Should succeed ---
-
-        let delete_request = SensorDataDeletionParams {
-            key: Some(Uuid::new_v4()),
-            from: None,
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: Some(true),
+        let req_payload = DataLoadRequestParams {
+            fill: Some(DBFill::NULL),
+            ..Default::default()
         };
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", public_sensor.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
+            &load_url,
+            Method::GET,
+            Some(req_payload.to_vector()),
             None::<Value>,
             None,
-            StatusCode::OK,
+            StatusCode::INTERNAL_SERVER_ERROR,
             &app,
         )
         .await;
+    }
 
-        // --- Delete all public sensor data without purge flag - Should fail ---
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_extended_aggregations(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
 
-        let delete_request = SensorDataDeletionParams {
-            key: None,
-            from: None,
-            to: None,
-            to_inclusive: None,
-            from_inclusive: None,
-            purge: None,
+        let sensor_id = create_guest_sensor(
+            "MyAirQualitySensor",
+            vec![
+                test_column("pm10", ColumnType::FLOAT),
+                test_column("station", ColumnType::STRING),
+            ],
+            &state,
+        )
+        .await;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let start =
+            NaiveDateTime::parse_from_str("2025-02-11T00:00:00", "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        // Permutation of 1..=100 ordered by time, followed by an entry without pm10 value
+        let mut data: Vec<SensorDataIngestEntry> = (0..100)
+            .map(|i| {
+                SensorDataIngestEntry::from_json(
+                    json!({"pm10": (i * 37 % 100 + 1) as f64, "station": format!("s{}", i % 3)}),
+                    Some(start + chrono::Duration::minutes(i)),
+                )
+            })
+            .collect();
+
+        data.push(SensorDataIngestEntry::from_json(
+            json!({"station": "x"}),
+            Some(start + chrono::Duration::minutes(100)),
+        ));
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let load_url = format!("/api/sensors/{}/data/load", sensor_id);
+
+        let aggregate = |name: &str, aggregation: DBAggregation, status: StatusCode| {
+            let req_payload = DataLoadRequestParams {
+                time_unit: Some(DBTimeUnit::DAY),
+                cols: Some(vec![DataLoadRequestColumns {
+                    name: name.to_string(),
+                    aggregation: Some(aggregation),
+                }]),
+                ..Default::default()
+            };
+
+            execute_request(
+                &load_url,
+                Method::GET,
+                Some(req_payload.to_vector()),
+                None::<Value>,
+                None,
+                status,
+                &app,
+            )
         };
 
+        let assert_float = |body: Value, col: &str, expected: f64| {
+            let rows = body.as_array().unwrap();
+            assert_eq!(rows.len(), 1);
+
+            let val = rows[0][col].as_f64().unwrap();
+            assert!((val - expected).abs() < 1e-6, "{} != {}", val, expected);
+        };
+
+        // --- Percentiles are interpolated ---
+
+        let body = aggregate("pm10", DBAggregation::P95, StatusCode::OK).await;
+        assert_float(body, "pm10", 95.05);
+
+        let body = aggregate("pm10", DBAggregation::P98, StatusCode::OK).await;
+        assert_float(body, "pm10", 98.02);
+
+        let body = aggregate("pm10", DBAggregation::MEDIAN, StatusCode::OK).await;
+        assert_float(body, "pm10", 50.5);
+
+        // --- Sample standard deviation and variance ---
+
+        let body = aggregate("pm10", DBAggregation::VARIANCE, StatusCode::OK).await;
+        assert_float(body, "pm10", 100.0 * 101.0 / 12.0);
+
+        let body = aggregate("pm10", DBAggregation::STDDEV, StatusCode::OK).await;
+        assert_float(body, "pm10", (100.0_f64 * 101.0 / 12.0).sqrt());
+
+        // --- First and last non NULL value by time ---
+
+        let body = aggregate("pm10", DBAggregation::FIRST, StatusCode::OK).await;
+        assert_float(body, "pm10", 1.0);
+
+        let body = aggregate("pm10", DBAggregation::LAST, StatusCode::OK).await;
+        assert_float(body, "pm10", 64.0);
+
+        let body = aggregate("station", DBAggregation::FIRST, StatusCode::OK).await;
+        assert_eq!(body[0]["station"], json!("s0"));
+
+        let body = aggregate("station", DBAggregation::LAST, StatusCode::OK).await;
+        assert_eq!(body[0]["station"], json!("x"));
+
+        // --- Count distinct ---
+
+        let body = aggregate("station", DBAggregation::COUNT_DISTINCT, StatusCode::OK).await;
+        assert_eq!(body[0]["station"], json!(4));
+
+        let body = aggregate("pm10", DBAggregation::COUNT_DISTINCT, StatusCode::OK).await;
+        assert_eq!(body[0]["pm10"], json!(100));
+
+        // --- Statistical aggregations are restricted to numeric columns ---
+
+        for aggregation in [
+            DBAggregation::P95,
+            DBAggregation::P98,
+            DBAggregation::MEDIAN,
+            DBAggregation::STDDEV,
+            DBAggregation::VARIANCE,
+        ] {
+            let _ = aggregate("station", aggregation, StatusCode::INTERNAL_SERVER_ERROR).await;
+        }
+
+        // --- Query param notation ---
+
+        let col = DataLoadRequestColumns::from_query_param("pm10.COUNT_DISTINCT".to_string());
+        assert_eq!(col.name, "pm10");
+        assert_eq!(col.to_query_param(), "pm10.COUNT_DISTINCT");
+    }
+
+    async fn add_bulk_dummy_data(
+        amount: u32,
+        delay_per_tup: u32,
+        sensor_id: Uuid,
+        write_key: Uuid,
+        app: &impl Service<Request, Response = ServiceResponse<BoxBody>, Error = actix_web::Error>,
+    ) {
+        let mut data_entries: Vec<SensorDataIngestEntry> = Vec::new();
+
+        for i in 0..amount {
+            let time: NaiveDateTime =
+                Utc::now().naive_utc() + chrono::Duration::milliseconds((i * delay_per_tup) as i64);
+
+            let payload = json!({
+                "col1": i,
+                "col2": 56.789,
+                "col3": "Hello",
+            });
+
+            data_entries.push(SensorDataIngestEntry::from_json(payload, Some(time)));
+        }
+
+        // Make sure the wait before pushing the data, or it will be rejected by DB (timestamps in future)
+        task::sleep(core::time::Duration::from_millis(
+            (amount * delay_per_tup) as u64,
+        ))
+        .await;
+
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/delete", public_sensor.1),
-            Method::DELETE,
-            Some(delete_request.to_vector()),
-            None::<Value>,
+            &format!("/api/sensors/{}/data/ingest?key={}", sensor_id, write_key),
+            Method::POST,
             None,
-            StatusCode::INTERNAL_SERVER_ERROR,
+            Some(data_entries),
+            None,
+            StatusCode::OK,
             &app,
         )
         .await;
     }
 
     #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
-    async fn test_get_data_sensor(pool: PgPool) {
+    async fn test_delete_sensor(pool: PgPool) {
         let (app, state) = create_test_app(pool).await;
 
         let test_sens = create_test_sensors(&state).await;
@@ -1188,66 +1903,17 @@ pub mod tests {
             .find(|(name, _)| name == "MySensor5")
             .unwrap();
 
-        let mut req_payload = DataLoadRequestParams::default();
-
-        fn compare_payload(payload: &Value, entry: &Map<String, Value>) -> bool {
-            let epsilon = 1e-5;
-
-            fn compare_floats_with_epsilon(a: f64, b: f64, epsilon: f64) -> bool {
-                (a - b).abs() < epsilon
-            }
-
-            match (payload, entry) {
-                // When payload is an Object (Map), compare recursively
-                (Value::Object(sub_map), super_map) => {
-                    sub_map.iter().all(|(k, v)| {
-                        if let Some(super_val) = super_map.get(k) {
-                            // Compare as floats with epsilon if both are f64
-                            if let (Value::Number(v_num), Value::Number(super_num)) = (v, super_val)
-                            {
-                                if let (Some(v_float), Some(super_float)) =
-                                    (v_num.as_f64(), super_num.as_f64())
-                                {
-                                    return compare_floats_with_epsilon(
-                                        v_float,
-                                        super_float,
-                                        epsilon,
-                                    );
-                                }
-                            }
-                            // Otherwise, compare directly (e.g., strings, booleans, etc.)
-                            return super_val == v;
-                        }
-                        false // If the key is not found, return false
-                    })
-                }
-                // If it's not an object, just return false
-                _ => false,
-            }
-        }
-
-        // --- Ingest allowed (his own) sensor dummy data as john - Should succeed ---
-
-        // Ingest 10 dummy data entries
-
-        let mut payloads: Vec<Value> = Vec::new();
-
-        for i in 0..10 {
-            let payload = {
-                json!({
-                    "col1": i,
-                    "col2": 1.11 * i as f64,
-                    "col3": format!("Hallo_{}", "o".repeat(10 - i)),
-                })
-            };
-
-            payloads.push(payload);
-        }
-
-        let time_start: NaiveDateTime = Utc::now().naive_utc();
-
-        let mut time_between: NaiveDateTime = Default::default();
+        // --- Delete ALL allowed (his own) sensor data as john with key - Should succeed ---
 
+        let api_key_read = test_keys
+            .iter()
+            .find(|k| {
+                k.user_id == john().id
+                    && k.sensor_id == target_sensor_own.1
+                    && k.operation == DBOperation::READ
+            })
+            .unwrap()
+            .id;
         let api_key_write = test_keys
             .iter()
             .find(|k| {
@@ -1258,48 +1924,25 @@ pub mod tests {
             .unwrap()
             .id;
 
-        for (index, payload) in payloads.iter().enumerate() {
-            let entry = SensorDataIngestEntry::from_json(payload.clone(), None);
-
-            execute_request(
-                &format!("/api/sensors/{}/data/ingest", target_sensor_own.1),
-                Method::POST,
-                Some(vec![("key".to_string(), api_key_write.to_string())]),
-                Some(vec![entry]),
-                None,
-                StatusCode::OK,
-                &app,
-            )
-            .await;
-
-            task::sleep(core::time::Duration::from_millis(50)).await; // A little bit of time for time tracking
-
-            if index == 4 {
-                time_between = Utc::now().naive_utc() + core::time::Duration::from_millis(100); // Inconsistent rounding issues in db?
-
-                task::sleep(core::time::Duration::from_millis(200)).await; // More sleep to make test more consistent
-            }
-        }
-
-        let time_end: NaiveDateTime = Utc::now().naive_utc();
+        let amount = 10;
+        let tup_delay = 100;
 
-        // --- Get own sensor data with valid key (john) - Should succeed ---
+        add_bulk_dummy_data(amount, 5, target_sensor_own.1, api_key_write, &app).await;
 
-        let api_key_read = test_keys
-            .iter()
-            .find(|key| {
-                key.sensor_id == target_sensor_own.1
-                    && key.user_id == john().id
-                    && key.operation == DBOperation::READ
-            })
-            .unwrap()
-            .id;
-        req_payload.key = Some(api_key_read);
+        let delete_request = SensorDataDeletionParams {
+            key: Some(api_key_write),
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(true),
+        };
 
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/delete", target_sensor_own.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
             StatusCode::OK,
@@ -1307,24 +1950,10 @@ pub mod tests {
         )
         .await;
 
-        let elms = body.as_array().unwrap();
-
-        assert_eq!(elms.len(), payloads.len()); // We retrieved all data
-
-        // Check if data is correct
-
-        for (index, elm) in elms.iter().enumerate() {
-            assert!(compare_payload(
-                payloads.get(index).unwrap(),
-                elm.as_object().unwrap()
-            ));
-        }
-
-        // --- Perform various allowed data retrievals to check correctness of data ---
+        // Check if all values were deleted
 
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.limit = Some(5);
 
         let body = execute_request(
             &format!("/api/sensors/{}/data/load", target_sensor_own.1),
@@ -1337,19 +1966,31 @@ pub mod tests {
         )
         .await;
 
-        assert_eq!(body.as_array().unwrap().len(), 5);
+        assert_eq!(body.as_array().unwrap().len(), 0);
 
-        // Get the first (oldest) element
+        // --- Delete SOME [open range] allowed (his own) sensor data as john with key - Should succeed ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.limit = Some(1);
-        req_payload.ordering = Some(DBOrdering::ASC);
+        let time_start: NaiveDateTime = Utc::now().naive_utc();
 
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+        add_bulk_dummy_data(amount, tup_delay, target_sensor_own.1, api_key_write, &app).await;
+
+        let delete_threshold: NaiveDateTime =
+            time_start + chrono::Duration::milliseconds((amount / 2 * tup_delay) as i64);
+
+        let delete_request = SensorDataDeletionParams {
+            key: Some(api_key_write),
+            from: Some(delete_threshold),
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(false),
+        };
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/delete", target_sensor_own.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
             StatusCode::OK,
@@ -1357,21 +1998,10 @@ pub mod tests {
         )
         .await;
 
-        assert!(compare_payload(
-            payloads.first().unwrap(),
-            body.as_array()
-                .unwrap()
-                .first()
-                .unwrap()
-                .as_object()
-                .unwrap()
-        ));
-
-        // Get the last (newest) element
+        // Check results - Get all values and check if only the specified values where deleted! [half values from end]
 
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.limit = Some(1);
         req_payload.ordering = Some(DBOrdering::DESC);
 
         let body = execute_request(
@@ -1385,28 +2015,47 @@ pub mod tests {
         )
         .await;
 
-        assert!(compare_payload(
-            payloads.last().unwrap(),
-            body.as_array()
-                .unwrap()
-                .first()
-                .unwrap()
-                .as_object()
-                .unwrap()
-        ));
+        let res_arr = body.as_array().unwrap();
 
-        // Get last elements in custom order [col3]
+        assert_eq!(res_arr.len(), (amount / 2) as usize);
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.limit = Some(1);
-        req_payload.ordering = Some(DBOrdering::DESC);
-        req_payload.order_col = Some("col3".to_string());
+        for (index, elm) in body.as_array().unwrap().iter().enumerate() {
+            assert_eq!(
+                elm.as_object()
+                    .unwrap()
+                    .get("col1")
+                    .unwrap()
+                    .as_i64()
+                    .unwrap(),
+                (amount - 1 - amount / 2 - index as u32) as i64
+            );
+        }
 
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+        // --- Delete SOME [closed range] allowed (his own) sensor data as john with key - Should succeed ---
+
+        let time_start: NaiveDateTime = Utc::now().naive_utc();
+
+        add_bulk_dummy_data(10, tup_delay, target_sensor_own.1, api_key_write, &app).await;
+
+        // to/from are inclusive (here)!
+        let delete_to: NaiveDateTime = time_start
+            + chrono::Duration::milliseconds(((amount - 2 - 1) * tup_delay) as i64)
+            + chrono::Duration::milliseconds((tup_delay / 2) as i64); // Additional delay for consistency
+
+        let delete_request = SensorDataDeletionParams {
+            key: Some(api_key_write),
+            from: Some(time_start), // Does only remove new values
+            to: Some(delete_to),
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(false),
+        };
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/delete", target_sensor_own.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
             StatusCode::OK,
@@ -1414,22 +2063,7 @@ pub mod tests {
         )
         .await;
 
-        assert!(compare_payload(
-            payloads.first().unwrap(),
-            body.as_array()
-                .unwrap()
-                .first()
-                .unwrap()
-                .as_object()
-                .unwrap()
-        ));
-
-        // Get the first 5 elements based on time
-
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.from = Some(time_start);
-        req_payload.to = Some(time_between);
+        // Check results - Get all values and check if only the specified values where deleted!
 
         let body = execute_request(
             &format!("/api/sensors/{}/data/load", target_sensor_own.1),
@@ -1442,27 +2076,62 @@ pub mod tests {
         )
         .await;
 
-        let elms = body.as_array().unwrap();
-
-        assert_eq!(elms.len(), 5);
+        let res_arr = body.as_array().unwrap();
 
-        for (index, elm) in elms.iter().enumerate() {
-            assert!(compare_payload(
-                payloads.get(index).unwrap(),
-                elm.as_object().unwrap()
-            ));
-        }
+        assert_eq!(res_arr.len(), 2 + (amount / 2) as usize); // Old and new values
 
-        // Get the last 5 elements based on time
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.from = Some(time_between);
-        req_payload.to = Some(time_end);
+        for (index, elm) in res_arr.iter().enumerate() {
+            if index < 2 {
+                //New values (starting from last idx)
+                assert_eq!(
+                    elm.as_object()
+                        .unwrap()
+                        .get("col1")
+                        .unwrap()
+                        .as_i64()
+                        .unwrap(),
+                    (amount - 1 - index as u32) as i64
+                );
+            } else {
+                // Old values starting from amount/2 index
+                assert_eq!(
+                    elm.as_object()
+                        .unwrap()
+                        .get("col1")
+                        .unwrap()
+                        .as_i64()
+                        .unwrap(),
+                    (amount / 2 - 1 + 2 - index as u32) as i64
+                );
+            }
+        }
 
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+        // --- Delete allowed sensor data as john with key - Should succeed ---
+
+        let api_key_write = test_keys
+            .iter()
+            .find(|k| {
+                k.user_id == john().id
+                    && k.sensor_id == target_sensor_allowed.1
+                    && k.operation == DBOperation::WRITE
+            })
+            .unwrap()
+            .id;
+
+        let delete_request = SensorDataDeletionParams {
+            key: Some(api_key_write),
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(true),
+        };
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/delete", target_sensor_allowed.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
             StatusCode::OK,
@@ -1470,107 +2139,91 @@ pub mod tests {
         )
         .await;
 
-        let elms = body.as_array().unwrap();
-
-        assert_eq!(elms.len(), 5);
-
-        for (index, elm) in elms.iter().enumerate() {
-            // 5 last elements are ASC sorted
-            assert!(compare_payload(
-                payloads.get(5 + index).unwrap(),
-                elm.as_object().unwrap()
-            ));
-        }
+        // --- Delete allowed sensor data as john with valid key but wrong op (READ) - Should fail ---
 
-        // All together, last 3 elements (of 5) desc
+        let api_key_read = test_keys
+            .iter()
+            .find(|k| {
+                k.user_id == john().id
+                    && k.sensor_id == target_sensor_allowed.1
+                    && k.operation == DBOperation::READ
+            })
+            .unwrap()
+            .id;
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.limit = Some(3);
-        req_payload.ordering = Some(DBOrdering::DESC);
-        req_payload.from = Some(time_between);
-        req_payload.to = Some(time_end);
+        let delete_request = SensorDataDeletionParams {
+            key: Some(api_key_read),
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(false),
+        };
 
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/delete", target_sensor_allowed.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
-            StatusCode::OK,
+            StatusCode::UNAUTHORIZED,
             &app,
         )
         .await;
 
-        let elms = body.as_array().unwrap();
-
-        assert_eq!(elms.len(), 3);
-
-        for (index, elm) in elms.iter().enumerate() {
-            assert!(compare_payload(
-                payloads.get(9 - index).unwrap(),
-                elm.as_object().unwrap()
-            ));
-        }
-
-        // Only return col1 as result (+time)
+        // --- Delete un-allowed sensor data with valid key (john) of wrong sensor - Should fail ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.cols = Some(vec![DataLoadRequestColumns {
-            name: "col1".to_string(),
-            aggregation: None,
-        }]);
+        let delete_request = SensorDataDeletionParams {
+            key: Some(api_key_write),
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(false),
+        };
 
-        let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/delete", target_sensor_not_allowed.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
-            StatusCode::OK,
+            StatusCode::UNAUTHORIZED,
             &app,
         )
         .await;
 
-        for elm in body.as_array().unwrap().iter() {
-            assert!(
-                elm.as_object().unwrap().contains_key("col1")
-                    && elm.as_object().unwrap().contains_key(TIME_COL_NAME)
-                    && elm.as_object().unwrap().len() == 2
-            );
-        }
-
-        // Return invalid col -> Error
+        // --- Delete sensor data with invalid key - Should fail ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.cols = Some(vec![DataLoadRequestColumns {
-            name: "col5".to_string(),
-            aggregation: None,
-        }]);
+        let delete_request = SensorDataDeletionParams {
+            key: Some(Uuid::new_v4()),
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(false),
+        };
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+            &format!("/api/sensors/{}/data/delete", target_sensor_not_allowed.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::UNAUTHORIZED,
             &app,
         )
         .await;
 
-        // Return no cols -> Error
-
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.cols = Some(Vec::new());
+        // --- Delete non-existing sensor data without key - Should fail ---
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+            &format!("/api/sensors/{}/data/delete", Uuid::new_v4()),
+            Method::DELETE,
+            Some(SensorDataDeletionParams::default().to_vector()),
             None::<Value>,
             None,
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1578,137 +2231,280 @@ pub mod tests {
         )
         .await;
 
-        // Return aggregated col but not specifying time grouping -> Error
+        // --- Delete public sensor data without key - Should succeed ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.cols = Some(vec![DataLoadRequestColumns {
-            name: "col1".to_string(),
-            aggregation: Some(DBAggregation::SUM),
-        }]);
+        let delete_request = SensorDataDeletionParams {
+            key: None,
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(true),
+        };
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+            &format!("/api/sensors/{}/data/delete", public_sensor.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::OK,
             &app,
         )
         .await;
 
-        // Specify time grouping but no aggregated cols -> Error
+        // --- Delete public sensor data with valid key of wrong sensor - Should fail ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.time_grouping = Some(1);
-        req_payload.cols = Some(vec![DataLoadRequestColumns {
-            name: "col1".to_string(),
-            aggregation: None,
-        }]);
+        let delete_request = SensorDataDeletionParams {
+            key: Some(api_key_write),
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(false),
+        };
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+            &format!("/api/sensors/{}/data/delete", public_sensor.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::UNAUTHORIZED,
             &app,
         )
         .await;
 
-        // Specify time grouping but no aggregated cols (default cols) -> Error
+        // --- Delete public sensor data with invalid key - Should succeed ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.time_grouping = Some(1);
+        let delete_request = SensorDataDeletionParams {
+            key: Some(Uuid::new_v4()),
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: Some(true),
+        };
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+            &format!("/api/sensors/{}/data/delete", public_sensor.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::OK,
             &app,
         )
         .await;
 
-        // Mixed aggregated and non-aggregated cols -> Error
+        // --- Delete all public sensor data without purge flag - Should fail ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(api_key_read);
-        req_payload.cols = Some(vec![
-            DataLoadRequestColumns {
-                name: "col1".to_string(),
-                aggregation: Some(DBAggregation::SUM),
-            },
-            DataLoadRequestColumns {
-                name: "col2".to_string(),
-                aggregation: None,
-            },
-        ]);
+        let delete_request = SensorDataDeletionParams {
+            key: None,
+            from: None,
+            to: None,
+            to_inclusive: None,
+            from_inclusive: None,
+            filter: None,
+            purge: None,
+        };
 
         let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
+            &format!("/api/sensors/{}/data/delete", public_sensor.1),
+            Method::DELETE,
+            Some(delete_request.to_vector()),
             None::<Value>,
             None,
             StatusCode::INTERNAL_SERVER_ERROR,
             &app,
         )
         .await;
+    }
 
-        // Invalid aggregations -> Error
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_get_data_sensor(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let test_keys = create_test_api_keys(&state).await;
+
+        let target_sensor_own = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor")
+            .unwrap();
+        let target_sensor_allowed = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor2")
+            .unwrap();
+        let target_sensor_not_allowed = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor4")
+            .unwrap();
+        let public_sensor = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor5")
+            .unwrap();
 
         let mut req_payload = DataLoadRequestParams::default();
+
+        fn compare_payload(payload: &Value, entry: &Map<String, Value>) -> bool {
+            let epsilon = 1e-5;
+
+            fn compare_floats_with_epsilon(a: f64, b: f64, epsilon: f64) -> bool {
+                (a - b).abs() < epsilon
+            }
+
+            match (payload, entry) {
+                // When payload is an Object (Map), compare recursively
+                (Value::Object(sub_map), super_map) => {
+                    sub_map.iter().all(|(k, v)| {
+                        if let Some(super_val) = super_map.get(k) {
+                            // Compare as floats with epsilon if both are f64
+                            if let (Value::Number(v_num), Value::Number(super_num)) = (v, super_val)
+                            {
+                                if let (Some(v_float), Some(super_float)) =
+                                    (v_num.as_f64(), super_num.as_f64())
+                                {
+                                    return compare_floats_with_epsilon(
+                                        v_float,
+                                        super_float,
+                                        epsilon,
+                                    );
+                                }
+                            }
+                            // Otherwise, compare directly (e.g., strings, booleans, etc.)
+                            return super_val == v;
+                        }
+                        false // If the key is not found, return false
+                    })
+                }
+                // If it's not an object, just return false
+                _ => false,
+            }
+        }
+
+        // --- Ingest allowed (his own) sensor dummy data as john - Should succeed ---
+
+        // Ingest 10 dummy data entries
+
+        let mut payloads: Vec<Value> = Vec::new();
+
+        for i in 0..10 {
+            let payload = {
+                json!({
+                    "col1": i,
+                    "col2": 1.11 * i as f64,
+                    "col3": format!("Hallo_{}", "o".repeat(10 - i)),
+                })
+            };
+
+            payloads.push(payload);
+        }
+
+        let time_start: NaiveDateTime = Utc::now().naive_utc();
+
+        let mut time_between: NaiveDateTime = Default::default();
+
+        let api_key_write = test_keys
+            .iter()
+            .find(|k| {
+                k.user_id == john().id
+                    && k.sensor_id == target_sensor_own.1
+                    && k.operation == DBOperation::WRITE
+            })
+            .unwrap()
+            .id;
+
+        for (index, payload) in payloads.iter().enumerate() {
+            let entry = SensorDataIngestEntry::from_json(payload.clone(), None);
+
+            execute_request(
+                &format!("/api/sensors/{}/data/ingest", target_sensor_own.1),
+                Method::POST,
+                Some(vec![("key".to_string(), api_key_write.to_string())]),
+                Some(vec![entry]),
+                None,
+                StatusCode::OK,
+                &app,
+            )
+            .await;
+
+            task::sleep(core::time::Duration::from_millis(50)).await; // A little bit of time for time tracking
+
+            if index == 4 {
+                time_between = Utc::now().naive_utc() + core::time::Duration::from_millis(100); // Inconsistent rounding issues in db?
+
+                task::sleep(core::time::Duration::from_millis(200)).await; // More sleep to make test more consistent
+            }
+        }
+
+        let time_end: NaiveDateTime = Utc::now().naive_utc();
+
+        // --- Get own sensor data with valid key (john) - Should succeed ---
+
+        let api_key_read = test_keys
+            .iter()
+            .find(|key| {
+                key.sensor_id == target_sensor_own.1
+                    && key.user_id == john().id
+                    && key.operation == DBOperation::READ
+            })
+            .unwrap()
+            .id;
         req_payload.key = Some(api_key_read);
-        req_payload.cols = Some(vec![DataLoadRequestColumns {
-            name: "col3".to_string(),
-            aggregation: Some(DBAggregation::SUM),
-        }]);
 
-        let _ = execute_request(
+        let body = execute_request(
             &format!("/api/sensors/{}/data/load", target_sensor_own.1),
             Method::GET,
             Some(req_payload.to_vector()),
             None::<Value>,
             None,
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::OK,
             &app,
         )
         .await;
 
+        let elms = body.as_array().unwrap();
+
+        assert_eq!(elms.len(), payloads.len()); // We retrieved all data
+
+        // Check if data is correct
+
+        for (index, elm) in elms.iter().enumerate() {
+            assert!(compare_payload(
+                payloads.get(index).unwrap(),
+                elm.as_object().unwrap()
+            ));
+        }
+
+        // --- Perform various allowed data retrievals to check correctness of data ---
+
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.cols = Some(vec![DataLoadRequestColumns {
-            name: "col3".to_string(),
-            aggregation: Some(DBAggregation::AVG),
-        }]);
+        req_payload.limit = Some(5);
 
-        let _ = execute_request(
+        let body = execute_request(
             &format!("/api/sensors/{}/data/load", target_sensor_own.1),
             Method::GET,
             Some(req_payload.to_vector()),
             None::<Value>,
             None,
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::OK,
             &app,
         )
         .await;
 
-        // Only return col1 aggregated + time col
+        assert_eq!(body.as_array().unwrap().len(), 5);
+
+        // Get the first (oldest) element
 
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.time_grouping = Some(100);
-        req_payload.cols = Some(vec![DataLoadRequestColumns {
-            name: "col1".to_string(),
-            aggregation: Some(DBAggregation::SUM),
-        }]);
+        req_payload.limit = Some(1);
+        req_payload.ordering = Some(DBOrdering::ASC);
 
         let body = execute_request(
             &format!("/api/sensors/{}/data/load", target_sensor_own.1),
@@ -1721,45 +2517,22 @@ pub mod tests {
         )
         .await;
 
-        let elm = body.as_array().unwrap();
-
-        let col1_sum: i64 = payloads
-            .iter()
-            .filter_map(|item| item.get("col1").and_then(Value::as_i64))
-            .sum();
-
-        assert_eq!(elm.len(), 1);
-        assert_eq!(
-            elm.first()
+        assert!(compare_payload(
+            payloads.first().unwrap(),
+            body.as_array()
                 .unwrap()
-                .as_object()
+                .first()
                 .unwrap()
-                .get("col1")
+                .as_object()
                 .unwrap()
-                .as_i64()
-                .unwrap(),
-            col1_sum
-        );
+        ));
 
-        // Test various aggregations
+        // Get the last (newest) element
 
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.time_grouping = Some(100);
-        req_payload.cols = Some(vec![
-            DataLoadRequestColumns {
-                name: "col1".to_string(),
-                aggregation: Some(DBAggregation::MIN),
-            },
-            DataLoadRequestColumns {
-                name: "col2".to_string(),
-                aggregation: Some(DBAggregation::MIN),
-            },
-            DataLoadRequestColumns {
-                name: "col3".to_string(),
-                aggregation: Some(DBAggregation::MIN),
-            },
-        ]);
+        req_payload.limit = Some(1);
+        req_payload.ordering = Some(DBOrdering::DESC);
 
         let body = execute_request(
             &format!("/api/sensors/{}/data/load", target_sensor_own.1),
@@ -1772,53 +2545,23 @@ pub mod tests {
         )
         .await;
 
-        let elm = body
-            .as_array()
-            .unwrap()
-            .first()
-            .unwrap()
-            .as_object()
-            .unwrap();
+        assert!(compare_payload(
+            payloads.last().unwrap(),
+            body.as_array()
+                .unwrap()
+                .first()
+                .unwrap()
+                .as_object()
+                .unwrap()
+        ));
 
-        let col1_min: i64 = payloads
-            .iter()
-            .filter_map(|item| item.get("col1").and_then(Value::as_i64))
-            .min()
-            .unwrap();
-        let col2_min: f64 = payloads
-            .iter()
-            .filter_map(|item| item.get("col2").and_then(Value::as_f64))
-            .filter(|&val| !val.is_nan()) // Exclude NaN values
-            .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Greater))
-            .unwrap();
-        let col3_min: String = payloads
-            .iter()
-            .filter_map(|item| item.get("col3").and_then(Value::as_str))
-            .map(|s| s.to_string())
-            .min()
-            .unwrap();
-
-        assert_eq!(elm.get("col1").unwrap().as_i64().unwrap(), col1_min);
-        assert_eq!(elm.get("col2").unwrap().as_f64().unwrap(), col2_min);
-        assert_eq!(elm.get("col3").unwrap().as_str().unwrap(), col3_min);
+        // Get last elements in custom order [col3]
 
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.time_grouping = Some(100);
-        req_payload.cols = Some(vec![
-            DataLoadRequestColumns {
-                name: "col1".to_string(),
-                aggregation: Some(DBAggregation::AVG),
-            },
-            DataLoadRequestColumns {
-                name: "col2".to_string(),
-                aggregation: Some(DBAggregation::SUM),
-            },
-            DataLoadRequestColumns {
-                name: "col3".to_string(),
-                aggregation: Some(DBAggregation::COUNT),
-            },
-        ]);
+        req_payload.limit = Some(1);
+        req_payload.ordering = Some(DBOrdering::DESC);
+        req_payload.order_col = Some("col3".to_string());
 
         let body = execute_request(
             &format!("/api/sensors/{}/data/load", target_sensor_own.1),
@@ -1831,57 +2574,50 @@ pub mod tests {
         )
         .await;
 
-        let elm = body
-            .as_array()
-            .unwrap()
-            .first()
-            .unwrap()
-            .as_object()
-            .unwrap();
+        assert!(compare_payload(
+            payloads.first().unwrap(),
+            body.as_array()
+                .unwrap()
+                .first()
+                .unwrap()
+                .as_object()
+                .unwrap()
+        ));
 
-        let (sum, count): (f64, i32) = payloads
-            .iter()
-            .filter_map(|item| item.get("col1").and_then(Value::as_f64))
-            .fold((0.0, 0), |(sum, count), val| (sum + val, count + 1));
-        let col1_avg = sum / count as f64;
-        let (col2_sum, _): (f64, i32) = payloads
-            .iter()
-            .filter_map(|item| item.get("col2").and_then(Value::as_f64))
-            .fold((0.0, 0), |(sum, count), val| (sum + val, count + 1));
-        let col3_count = payloads.len();
+        // Get the first 5 elements based on time
 
-        assert_eq!(elm.get("col1").unwrap().as_f64().unwrap(), col1_avg);
-        assert_eq!(elm.get("col2").unwrap().as_f64().unwrap(), col2_sum);
-        assert_eq!(
-            elm.get("col3").unwrap().as_i64().unwrap(),
-            col3_count as i64
-        );
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.from = Some(time_start);
+        req_payload.to = Some(time_between);
 
-        // Test multiple time buckets
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
 
-        // Insert some more values after a delay to test multiple buckets
-        task::sleep(core::time::Duration::from_millis(1500)).await;
+        let elms = body.as_array().unwrap();
 
-        for payload in payloads.iter() {
-            let entry = SensorDataIngestEntry::from_json(payload.clone(), None);
+        assert_eq!(elms.len(), 5);
 
-            execute_request(
-                &format!("/api/sensors/{}/data/ingest", target_sensor_own.1),
-                Method::POST,
-                Some(vec![("key".to_string(), api_key_write.to_string())]),
-                Some(vec![entry]),
-                None,
-                StatusCode::OK,
-                &app,
-            )
-            .await;
+        for (index, elm) in elms.iter().enumerate() {
+            assert!(compare_payload(
+                payloads.get(index).unwrap(),
+                elm.as_object().unwrap()
+            ));
         }
 
-        // First, get all data to check expected bucket count
-
+        // Get the last 5 elements based on time
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.limit = Some(100);
+        req_payload.from = Some(time_between);
+        req_payload.to = Some(time_end);
 
         let body = execute_request(
             &format!("/api/sensors/{}/data/load", target_sensor_own.1),
@@ -1894,45 +2630,56 @@ pub mod tests {
         )
         .await;
 
-        let full_data = body.as_array().unwrap();
-
-        let mut buckets = HashMap::new();
-        let mut half_buckets = HashMap::new(); // Counts only half of the values before second insert
+        let elms = body.as_array().unwrap();
 
-        let half_threshold = time_end.and_utc().timestamp();
+        assert_eq!(elms.len(), 5);
 
-        fn json_timestamp_to_epoch(data: &Value) -> i64 {
-            let timestamp = data.as_str().unwrap();
-            let time = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).unwrap();
+        for (index, elm) in elms.iter().enumerate() {
+            // 5 last elements are ASC sorted
+            assert!(compare_payload(
+                payloads.get(5 + index).unwrap(),
+                elm.as_object().unwrap()
+            ));
+        }
 
-            let epoch = time.and_utc().timestamp();
+        // All together, last 3 elements (of 5) desc
 
-            epoch
-        }
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.limit = Some(3);
+        req_payload.ordering = Some(DBOrdering::DESC);
+        req_payload.from = Some(time_between);
+        req_payload.to = Some(time_end);
 
-        for data in full_data.iter() {
-            let epoch =
-                json_timestamp_to_epoch(data.as_object().unwrap().get("created_at").unwrap());
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
 
-            *buckets.entry(epoch).or_insert(0) += 1;
+        let elms = body.as_array().unwrap();
 
-            if epoch > half_threshold {
-                continue;
-            }
+        assert_eq!(elms.len(), 3);
 
-            *half_buckets.entry(epoch).or_insert(0) += 1;
+        for (index, elm) in elms.iter().enumerate() {
+            assert!(compare_payload(
+                payloads.get(9 - index).unwrap(),
+                elm.as_object().unwrap()
+            ));
         }
 
-        // Now get aggregated data
+        // Only return col1 as result (+time)
 
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.ordering = Some(DBOrdering::DESC);
-        req_payload.order_col = Some("col1".to_string());
-        req_payload.time_grouping = Some(1);
         req_payload.cols = Some(vec![DataLoadRequestColumns {
             name: "col1".to_string(),
-            aggregation: Some(DBAggregation::COUNT),
+            aggregation: None,
         }]);
 
         let body = execute_request(
@@ -1946,58 +2693,1688 @@ pub mod tests {
         )
         .await;
 
-        let arr = body.as_array().unwrap();
-
-        assert_eq!(arr.len(), buckets.len()); // Check bucket count
-
-        // Check if ordering is correct (DESC for col1)
-        assert_eq!(
-            arr.last()
-                .unwrap()
-                .as_object()
-                .unwrap()
-                .get("col1")
-                .unwrap(),
-            buckets
-                .iter()
-                .min_by_key(|entry| entry.1)
-                .unwrap()
-                .1
-                .clone()
-        );
+        for elm in body.as_array().unwrap().iter() {
+            assert!(
+                elm.as_object().unwrap().contains_key("col1")
+                    && elm.as_object().unwrap().contains_key(TIME_COL_NAME)
+                    && elm.as_object().unwrap().len() == 2
+            );
+        }
 
-        // Check if correct number of elements where counted across all buckets
-        let mut total_elms = 0;
+        // Return invalid col -> Error
 
-        for elm in arr.iter() {
-            let obj = elm.as_object().unwrap();
-            let count = obj.get("col1").unwrap().as_i64().unwrap();
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.cols = Some(vec![DataLoadRequestColumns {
+            name: "col5".to_string(),
+            aggregation: None,
+        }]);
 
-            total_elms += count;
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
 
-            let epoch = json_timestamp_to_epoch(obj.get(GROUPED_TIME_COL_NAME).unwrap());
+        // Return no cols -> Error
 
-            assert_eq!(buckets.get(&epoch).unwrap().clone(), count);
-        }
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.cols = Some(Vec::new());
 
-        assert_eq!(total_elms, 2 * payloads.len() as i64);
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
 
-        // Check if time predicate still works with aggregation [only consider half of the values]
+        // Return aggregated col but not specifying time grouping -> Error
 
         let mut req_payload = DataLoadRequestParams::default();
         req_payload.key = Some(api_key_read);
-        req_payload.to = Some(time_end); // This contains only half of the values
-        req_payload.ordering = Some(DBOrdering::DESC);
-        req_payload.time_grouping = Some(1);
         req_payload.cols = Some(vec![DataLoadRequestColumns {
             name: "col1".to_string(),
-            aggregation: Some(DBAggregation::COUNT),
+            aggregation: Some(DBAggregation::SUM),
         }]);
 
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
+
+        // Specify time grouping but no aggregated cols -> Error
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.time_grouping = Some(1);
+        req_payload.cols = Some(vec![DataLoadRequestColumns {
+            name: "col1".to_string(),
+            aggregation: None,
+        }]);
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
+
+        // Specify time grouping but no aggregated cols (default cols) -> Error
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.time_grouping = Some(1);
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
+
+        // Mixed aggregated and non-aggregated FLOAT cols -> Error
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.cols = Some(vec![
+            DataLoadRequestColumns {
+                name: "col1".to_string(),
+                aggregation: Some(DBAggregation::SUM),
+            },
+            DataLoadRequestColumns {
+                name: "col2".to_string(),
+                aggregation: None,
+            },
+        ]);
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
+
+        // Invalid aggregations -> Error
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.cols = Some(vec![DataLoadRequestColumns {
+            name: "col3".to_string(),
+            aggregation: Some(DBAggregation::SUM),
+        }]);
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.cols = Some(vec![DataLoadRequestColumns {
+            name: "col3".to_string(),
+            aggregation: Some(DBAggregation::AVG),
+        }]);
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
+
+        // Only return col1 aggregated + time col
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.time_grouping = Some(100);
+        req_payload.cols = Some(vec![DataLoadRequestColumns {
+            name: "col1".to_string(),
+            aggregation: Some(DBAggregation::SUM),
+        }]);
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let elm = body.as_array().unwrap();
+
+        let col1_sum: i64 = payloads
+            .iter()
+            .filter_map(|item| item.get("col1").and_then(Value::as_i64))
+            .sum();
+
+        assert_eq!(elm.len(), 1);
+        assert_eq!(
+            elm.first()
+                .unwrap()
+                .as_object()
+                .unwrap()
+                .get("col1")
+                .unwrap()
+                .as_i64()
+                .unwrap(),
+            col1_sum
+        );
+
+        // Test various aggregations
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.time_grouping = Some(100);
+        req_payload.cols = Some(vec![
+            DataLoadRequestColumns {
+                name: "col1".to_string(),
+                aggregation: Some(DBAggregation::MIN),
+            },
+            DataLoadRequestColumns {
+                name: "col2".to_string(),
+                aggregation: Some(DBAggregation::MIN),
+            },
+            DataLoadRequestColumns {
+                name: "col3".to_string(),
+                aggregation: Some(DBAggregation::MIN),
+            },
+        ]);
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let elm = body
+            .as_array()
+            .unwrap()
+            .first()
+            .unwrap()
+            .as_object()
+            .unwrap();
+
+        let col1_min: i64 = payloads
+            .iter()
+            .filter_map(|item| item.get("col1").and_then(Value::as_i64))
+            .min()
+            .unwrap();
+        let col2_min: f64 = payloads
+            .iter()
+            .filter_map(|item| item.get("col2").and_then(Value::as_f64))
+            .filter(|&val| !val.is_nan()) // Exclude NaN values
+            .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Greater))
+            .unwrap();
+        let col3_min: String = payloads
+            .iter()
+            .filter_map(|item| item.get("col3").and_then(Value::as_str))
+            .map(|s| s.to_string())
+            .min()
+            .unwrap();
+
+        assert_eq!(elm.get("col1").unwrap().as_i64().unwrap(), col1_min);
+        assert_eq!(elm.get("col2").unwrap().as_f64().unwrap(), col2_min);
+        assert_eq!(elm.get("col3").unwrap().as_str().unwrap(), col3_min);
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.time_grouping = Some(100);
+        req_payload.cols = Some(vec![
+            DataLoadRequestColumns {
+                name: "col1".to_string(),
+                aggregation: Some(DBAggregation::AVG),
+            },
+            DataLoadRequestColumns {
+                name: "col2".to_string(),
+                aggregation: Some(DBAggregation::SUM),
+            },
+            DataLoadRequestColumns {
+                name: "col3".to_string(),
+                aggregation: Some(DBAggregation::COUNT),
+            },
+        ]);
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let elm = body
+            .as_array()
+            .unwrap()
+            .first()
+            .unwrap()
+            .as_object()
+            .unwrap();
+
+        let (sum, count): (f64, i32) = payloads
+            .iter()
+            .filter_map(|item| item.get("col1").and_then(Value::as_f64))
+            .fold((0.0, 0), |(sum, count), val| (sum + val, count + 1));
+        let col1_avg = sum / count as f64;
+        let (col2_sum, _): (f64, i32) = payloads
+            .iter()
+            .filter_map(|item| item.get("col2").and_then(Value::as_f64))
+            .fold((0.0, 0), |(sum, count), val| (sum + val, count + 1));
+        let col3_count = payloads.len();
+
+        assert_eq!(elm.get("col1").unwrap().as_f64().unwrap(), col1_avg);
+        assert_eq!(elm.get("col2").unwrap().as_f64().unwrap(), col2_sum);
+        assert_eq!(
+            elm.get("col3").unwrap().as_i64().unwrap(),
+            col3_count as i64
+        );
+
+        // Test multiple time buckets
+
+        // Insert some more values after a delay to test multiple buckets
+        task::sleep(core::time::Duration::from_millis(1500)).await;
+
+        for payload in payloads.iter() {
+            let entry = SensorDataIngestEntry::from_json(payload.clone(), None);
+
+            execute_request(
+                &format!("/api/sensors/{}/data/ingest", target_sensor_own.1),
+                Method::POST,
+                Some(vec![("key".to_string(), api_key_write.to_string())]),
+                Some(vec![entry]),
+                None,
+                StatusCode::OK,
+                &app,
+            )
+            .await;
+        }
+
+        // First, get all data to check expected bucket count
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.limit = Some(100);
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let full_data = body.as_array().unwrap();
+
+        let mut buckets = HashMap::new();
+        let mut half_buckets = HashMap::new(); // Counts only half of the values before second insert
+
+        let half_threshold = time_end.and_utc().timestamp();
+
+        fn json_timestamp_to_epoch(data: &Value) -> i64 {
+            let timestamp = data.as_str().unwrap();
+            let time = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).unwrap();
+
+            let epoch = time.and_utc().timestamp();
+
+            epoch
+        }
+
+        for data in full_data.iter() {
+            let epoch =
+                json_timestamp_to_epoch(data.as_object().unwrap().get("created_at").unwrap());
+
+            *buckets.entry(epoch).or_insert(0) += 1;
+
+            if epoch > half_threshold {
+                continue;
+            }
+
+            *half_buckets.entry(epoch).or_insert(0) += 1;
+        }
+
+        // Now get aggregated data
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.ordering = Some(DBOrdering::DESC);
+        req_payload.order_col = Some("col1".to_string());
+        req_payload.time_grouping = Some(1);
+        req_payload.cols = Some(vec![DataLoadRequestColumns {
+            name: "col1".to_string(),
+            aggregation: Some(DBAggregation::COUNT),
+        }]);
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let arr = body.as_array().unwrap();
+
+        assert_eq!(arr.len(), buckets.len()); // Check bucket count
+
+        // Check if ordering is correct (DESC for col1)
+        assert_eq!(
+            arr.last()
+                .unwrap()
+                .as_object()
+                .unwrap()
+                .get("col1")
+                .unwrap(),
+            buckets
+                .iter()
+                .min_by_key(|entry| entry.1)
+                .unwrap()
+                .1
+                .clone()
+        );
+
+        // Check if correct number of elements where counted across all buckets
+        let mut total_elms = 0;
+
+        for elm in arr.iter() {
+            let obj = elm.as_object().unwrap();
+            let count = obj.get("col1").unwrap().as_i64().unwrap();
+
+            total_elms += count;
+
+            let epoch = json_timestamp_to_epoch(obj.get(GROUPED_TIME_COL_NAME).unwrap());
+
+            assert_eq!(buckets.get(&epoch).unwrap().clone(), count);
+        }
+
+        assert_eq!(total_elms, 2 * payloads.len() as i64);
+
+        // Check if time predicate still works with aggregation [only consider half of the values]
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(api_key_read);
+        req_payload.to = Some(time_end); // This contains only half of the values
+        req_payload.ordering = Some(DBOrdering::DESC);
+        req_payload.time_grouping = Some(1);
+        req_payload.cols = Some(vec![DataLoadRequestColumns {
+            name: "col1".to_string(),
+            aggregation: Some(DBAggregation::COUNT),
+        }]);
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let arr = body.as_array().unwrap();
+
+        assert_eq!(arr.len(), half_buckets.len()); // Check (half) bucket count
+                                                   // Check if ordering is correct [DESC for time]
+        assert_eq!(
+            json_timestamp_to_epoch(
+                arr.last()
+                    .unwrap()
+                    .as_object()
+                    .unwrap()
+                    .get(GROUPED_TIME_COL_NAME)
+                    .unwrap()
+            ),
+            half_buckets
+                .iter()
+                .min_by_key(|entry| entry.0)
+                .unwrap()
+                .0
+                .clone()
+        );
+
+        // --- Get allowed sensor data with valid key (john) - Should succeed ---
+
+        let mut req_payload = DataLoadRequestParams::default();
+        req_payload.key = Some(
+            test_keys
+                .iter()
+                .find(|k| {
+                    k.user_id == john().id
+                        && k.sensor_id == target_sensor_allowed.1
+                        && k.operation == DBOperation::READ
+                })
+                .unwrap()
+                .id,
+        );
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_allowed.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        // --- Get not allowed sensor data with valid key (john) of wrong sensor - Should fail ---
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_not_allowed.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::UNAUTHORIZED,
+            &app,
+        )
+        .await;
+
+        // --- Get allowed sensor data with valid key (john) but wrong op (write) - Should fail ---
+
+        req_payload.key = Some(
+            test_keys
+                .iter()
+                .find(|k| {
+                    k.user_id == john().id
+                        && k.sensor_id == target_sensor_allowed.1
+                        && k.operation == DBOperation::WRITE
+                })
+                .unwrap()
+                .id,
+        );
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_allowed.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::UNAUTHORIZED,
+            &app,
+        )
+        .await;
+
+        // --- Get non-existing sensor data without key - Should fail ---
+
+        req_payload.key = None;
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", Uuid::new_v4()),
+            Method::GET,
+            Some(DataLoadRequestParams::default().to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &app,
+        )
+        .await;
+
+        // --- Get non-public sensor data without key - Should fail ---
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", target_sensor_not_allowed.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::UNAUTHORIZED,
+            &app,
+        )
+        .await;
+
+        // --- Get public sensor data without key - Should succeed ---
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", public_sensor.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        // --- Get public sensor data with valid key of wrong sensor - Should fail ---
+
+        req_payload.key = Some(
+            test_keys
+                .iter()
+                .find(|k| {
+                    k.user_id == john().id
+                        && k.sensor_id == target_sensor_allowed.1
+                        && k.operation == DBOperation::WRITE
+                })
+                .unwrap()
+                .id,
+        );
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", public_sensor.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::UNAUTHORIZED,
+            &app,
+        )
+        .await;
+
+        // --- Get public sensor data with invalid key - Should succeed ---
+
+        req_payload.key = Some(Uuid::new_v4());
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/load", public_sensor.1),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_extended_column_types(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        // --- Incremental ingest is not supported for the new types - Should fail ---
+
+        let incremental_flag = SensorColumn {
+            val_ingest: ColumnIngest::INCREMENTAL,
+            ..test_column("flag", ColumnType::BOOL)
+        };
+
+        let res = sensor_db::create_sensor(
+            guest_sensor_request("MyTypedSensor", vec![incremental_flag]),
+            None,
+            &state,
+        )
+        .await;
+
+        assert!(res.is_err());
+
+        // --- Create sensor with BOOL, TIMESTAMP and JSON columns - Should succeed ---
+
+        let sensor_id = create_guest_sensor(
+            "MyTypedSensor",
+            vec![
+                test_column("flag", ColumnType::BOOL),
+                test_column("event", ColumnType::TIMESTAMP),
+                test_column("meta", ColumnType::JSON),
+            ],
+            &state,
+        )
+        .await;
+
+        let payloads = [
+            json!({"flag": true, "event": "2025-02-11T08:27:17", "meta": {"fw": "1.2", "errs": [1, 2]}}),
+            json!({"flag": false, "event": "2025-02-12T10:00:00.500", "meta": [1, 2, 3]}),
+            json!({"flag": null, "event": null, "meta": null}),
+        ];
+
+        let data_entries: Vec<SensorDataIngestEntry> = payloads
+            .iter()
+            .map(|p| SensorDataIngestEntry::from_json(p.clone(), None))
+            .collect();
+
+        let _ = execute_request(
+            &format!("/api/sensors/{}/data/ingest", sensor_id),
+            Method::POST,
+            None,
+            Some(data_entries),
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        // --- Values not matching the column types - Should fail ---
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/ingest", sensor_id),
+            Method::POST,
+            None,
+            Some(vec![SensorDataIngestEntry::from_json(
+                json!({"flag": "invalid", "event": "invalid", "meta": null}),
+                None,
+            )]),
+            None,
+            StatusCode::BAD_REQUEST,
+            &app,
+        )
+        .await;
+
+        let fields: Vec<String> = serde_json::from_value(body["fields"].clone()).unwrap();
+
+        assert_eq!(fields.len(), 2);
+
+        // --- Values are decoded with their native json types ---
+
+        let req_payload = DataLoadRequestParams {
+            ordering: Some(DBOrdering::ASC),
+            order_col: Some("event".to_string()),
+            ..Default::default()
+        };
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", sensor_id),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let rows = body.as_array().unwrap();
+        assert_eq!(rows.len(), 3);
+
+        assert_eq!(rows[0]["flag"], json!(true));
+        assert_eq!(rows[0]["event"], json!("2025-02-11T08:27:17.000"));
+        assert_eq!(rows[0]["meta"], json!({"fw": "1.2", "errs": [1, 2]}));
+
+        assert_eq!(rows[1]["flag"], json!(false));
+        assert_eq!(rows[1]["event"], json!("2025-02-12T10:00:00.500"));
+        assert_eq!(rows[1]["meta"], json!([1, 2, 3]));
+
+        // NULL values are stored as NULL
+        assert_eq!(rows[2]["flag"], Value::Null);
+        assert_eq!(rows[2]["event"], Value::Null);
+        assert_eq!(rows[2]["meta"], Value::Null);
+
+        // --- Aggregations on the new types ---
+
+        let mut req_payload = DataLoadRequestParams {
+            time_grouping: Some(3600),
+            cols: Some(vec![
+                DataLoadRequestColumns {
+                    name: "flag".to_string(),
+                    aggregation: Some(DBAggregation::BOOL_AND),
+                },
+                DataLoadRequestColumns {
+                    name: "event".to_string(),
+                    aggregation: Some(DBAggregation::MAX),
+                },
+                DataLoadRequestColumns {
+                    name: "meta".to_string(),
+                    aggregation: Some(DBAggregation::COUNT),
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", sensor_id),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let row = body.as_array().unwrap().first().unwrap();
+        assert_eq!(row["flag"], json!(false));
+        assert_eq!(row["event"], json!("2025-02-12T10:00:00.500"));
+        assert_eq!(row["meta"], json!(2));
+
+        // MIN/MAX on booleans are mapped to bool_and/bool_or
+        req_payload.cols = Some(vec![DataLoadRequestColumns {
+            name: "flag".to_string(),
+            aggregation: Some(DBAggregation::MAX),
+        }]);
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", sensor_id),
+            Method::GET,
+            Some(req_payload.to_vector()),
+            None::<Value>,
+            None,
+            StatusCode::OK,
+            &app,
+        )
+        .await;
+
+        let row = body.as_array().unwrap().first().unwrap();
+        assert_eq!(row["flag"], json!(true));
+
+        // --- Invalid aggregations - Should fail ---
+
+        for (name, aggregation) in [
+            ("event", DBAggregation::SUM),
+            ("flag", DBAggregation::AVG),
+            ("meta", DBAggregation::MAX),
+            ("event", DBAggregation::BOOL_OR),
+        ] {
+            req_payload.cols = Some(vec![DataLoadRequestColumns {
+                name: name.to_string(),
+                aggregation: Some(aggregation),
+            }]);
+
+            let _ = execute_request(
+                &format!("/api/sensors/{}/data/load", sensor_id),
+                Method::GET,
+                Some(req_payload.to_vector()),
+                None::<Value>,
+                None,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &app,
+            )
+            .await;
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_aligned_query(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let test_keys = create_test_api_keys(&state).await;
+
+        let private_sensor = test_sens.iter().find(|(name, _)| name == "MySensor").unwrap().1;
+        let public_sensor = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+
+        let api_key_read = test_keys
+            .iter()
+            .find(|k| {
+                k.user_id == john().id
+                    && k.sensor_id == private_sensor
+                    && k.operation == DBOperation::READ
+            })
+            .unwrap()
+            .id;
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let add_data = |sensor_id: Uuid, values: Vec<(i64, &str)>| {
+            let state = state.clone();
+            let data: Vec<SensorDataIngestEntry> = values
+                .into_iter()
+                .map(|(v, t)| SensorDataIngestEntry::from_json(json!({"col1": v}), Some(ts(t))))
+                .collect();
+
+            async move {
+                let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+                data_db::add_sensor_data(Arc::new(sensor), &data, state).await.unwrap();
+            }
+        };
+
+        add_data(
+            private_sensor,
+            vec![(1, "2025-02-11T08:10:00"), (2, "2025-02-11T08:20:00"), (4, "2025-02-11T10:00:00")],
+        )
+        .await;
+
+        add_data(public_sensor, vec![(10, "2025-02-11T08:30:00"), (20, "2025-02-11T09:30:00")])
+            .await;
+
+        let sensor = |id: Uuid, key: Option<Uuid>, alias: &str| AlignedDataRequestSensor {
+            id,
+            key,
+            alias: Some(alias.to_string()),
+            cols: vec![DataLoadRequestColumns {
+                name: "col1".to_string(),
+                aggregation: Some(DBAggregation::SUM),
+            }],
+            filter: None,
+        };
+
+        let query = |req: AlignedDataRequest, status: StatusCode| {
+            execute_request(
+                "/api/sensors/data/query",
+                Method::POST,
+                None,
+                Some(req),
+                None,
+                status,
+                &app,
+            )
+        };
+
+        let base_req = AlignedDataRequest {
+            sensors: vec![
+                sensor(private_sensor, Some(api_key_read), "a"),
+                sensor(public_sensor, None, "b"),
+            ],
+            time_grouping: Some(3600),
+            ..Default::default()
+        };
+
+        // --- Wide format, missing values are null ---
+
+        let body = query(base_req.clone(), StatusCode::OK).await;
+
+        assert_eq!(
+            body,
+            json!([
+                {GROUPED_TIME_COL_NAME: "2025-02-11T08:00:00.000", "a.col1": 3, "b.col1": 10},
+                {GROUPED_TIME_COL_NAME: "2025-02-11T09:00:00.000", "a.col1": null, "b.col1": 20},
+                {GROUPED_TIME_COL_NAME: "2025-02-11T10:00:00.000", "a.col1": 4, "b.col1": null},
+            ])
+        );
+
+        // --- Long format with descending order and limit ---
+
+        let req = AlignedDataRequest {
+            format: Some(AlignedDataFormat::LONG),
+            ordering: Some(DBOrdering::DESC),
+            limit: Some(2),
+            ..base_req.clone()
+        };
+
+        let body = query(req, StatusCode::OK).await;
+
+        assert_eq!(
+            body,
+            json!([
+                {GROUPED_TIME_COL_NAME: "2025-02-11T10:00:00.000", "sensor": "a", "col1": 4},
+                {GROUPED_TIME_COL_NAME: "2025-02-11T09:00:00.000", "sensor": "b", "col1": 20},
+            ])
+        );
+
+        // --- Limit spans the first buckets of different sensors ---
+
+        let req = AlignedDataRequest {
+            limit: Some(2),
+            ..base_req.clone()
+        };
+
+        let body = query(req, StatusCode::OK).await;
+
+        assert_eq!(
+            body,
+            json!([
+                {GROUPED_TIME_COL_NAME: "2025-02-11T08:00:00.000", "a.col1": 3, "b.col1": 10},
+                {GROUPED_TIME_COL_NAME: "2025-02-11T09:00:00.000", "a.col1": null, "b.col1": 20},
+            ])
+        );
+
+        // --- Private sensor without key - Should fail ---
+
+        let req = AlignedDataRequest {
+            sensors: vec![sensor(private_sensor, None, "a"), sensor(public_sensor, None, "b")],
+            ..base_req.clone()
+        };
+
+        let _ = query(req, StatusCode::UNAUTHORIZED).await;
+
+        // --- Duplicate alias - Should fail ---
+
+        let req = AlignedDataRequest {
+            sensors: vec![sensor(public_sensor, None, "b"), sensor(public_sensor, None, "b")],
+            ..base_req.clone()
+        };
+
+        let _ = query(req, StatusCode::INTERNAL_SERVER_ERROR).await;
+
+        // --- Invalid alias - Should fail ---
+
+        let req = AlignedDataRequest {
+            sensors: vec![sensor(public_sensor, None, "b\"; DROP TABLE sensor")],
+            ..base_req.clone()
+        };
+
+        let _ = query(req, StatusCode::INTERNAL_SERVER_ERROR).await;
+
+        // --- Missing time grouping - Should fail ---
+
+        let req = AlignedDataRequest {
+            time_grouping: None,
+            ..base_req.clone()
+        };
+
+        let _ = query(req, StatusCode::INTERNAL_SERVER_ERROR).await;
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_aligned_query_limits(pool: PgPool) {
+        let cfg = serde_yml::from_str("server:\n  data_max_page_size: 1").unwrap();
+        let (app, state) = create_test_app_with_config(pool, cfg).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let public_sensor = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+        let sensor = cache::request_sensor(public_sensor, &state).await.unwrap();
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let data = vec![
+            SensorDataIngestEntry::from_json(json!({"col1": 1}), Some(ts("2025-02-11T08:00:00"))),
+            SensorDataIngestEntry::from_json(json!({"col1": 2}), Some(ts("2025-02-11T09:00:00"))),
+        ];
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let sensor = |alias: String| AlignedDataRequestSensor {
+            id: public_sensor,
+            key: None,
+            alias: Some(alias),
+            cols: vec![DataLoadRequestColumns {
+                name: "col1".to_string(),
+                aggregation: Some(DBAggregation::SUM),
+            }],
+            filter: None,
+        };
+
+        let query = |req: AlignedDataRequest, status: StatusCode| {
+            execute_request(
+                "/api/sensors/data/query",
+                Method::POST,
+                None,
+                Some(req),
+                None,
+                status,
+                &app,
+            )
+        };
+
+        let base_req = AlignedDataRequest {
+            sensors: vec![sensor("a".to_string())],
+            time_grouping: Some(3600),
+            ..Default::default()
+        };
+
+        // --- Limit is capped to the maximum page size ---
+
+        let req = AlignedDataRequest {
+            limit: Some(5),
+            ..base_req.clone()
+        };
+
+        let body = query(req, StatusCode::OK).await;
+
+        assert_eq!(body, json!([{GROUPED_TIME_COL_NAME: "2025-02-11T08:00:00.000", "a.col1": 1}]));
+
+        // --- Negative limit - Should fail ---
+
+        let req = AlignedDataRequest {
+            limit: Some(-1),
+            ..base_req.clone()
+        };
+
+        let _ = query(req, StatusCode::BAD_REQUEST).await;
+
+        // --- Too many sensors - Should fail ---
+
+        let req = AlignedDataRequest {
+            sensors: (0..=ALIGNED_MAX_SENSORS).map(|i| sensor(format!("s{}", i))).collect(),
+            ..base_req.clone()
+        };
+
+        let _ = query(req, StatusCode::BAD_REQUEST).await;
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_pagination(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        // Rows 2 and 3 share the same timestamp
+        let data: Vec<SensorDataIngestEntry> = [
+            (1, "2025-02-11T08:00:00"),
+            (2, "2025-02-11T09:00:00"),
+            (3, "2025-02-11T09:00:00"),
+            (4, "2025-02-11T10:00:00"),
+            (5, "2025-02-11T11:00:00"),
+        ]
+        .into_iter()
+        .map(|(v, t)| SensorDataIngestEntry::from_json(json!({"col1": v}), Some(ts(t))))
+        .collect();
+
+        let tbl_name = sensor.tbl_name.clone();
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let load_url = format!("/api/sensors/{}/data/load", sensor_id);
+
+        // Returns the status, the loaded col1 values and the next cursor
+        let load = |req: DataLoadRequestParams| {
+            let uri = format!(
+                "{}?{}",
+                load_url,
+                serde_urlencoded::to_string(req.to_vector()).unwrap()
+            );
+
+            let app = &app;
+
+            async move {
+                let req = actix_web::test::TestRequest::get().uri(&uri).to_request();
+                let resp = actix_web::test::call_service(app, req).await;
+
+                let status = resp.status();
+                let cursor = resp
+                    .headers()
+                    .get(NEXT_CURSOR_HEADER)
+                    .map(|c| c.to_str().unwrap().to_string());
+
+                let body: Value = actix_web::test::read_body_json(resp).await;
+                let values: Vec<i64> = body
+                    .as_array()
+                    .map(|rows| rows.iter().map(|r| r["col1"].as_i64().unwrap()).collect())
+                    .unwrap_or_default();
+
+                (status, values, cursor)
+            }
+        };
+
+        let cols = Some(vec![DataLoadRequestColumns {
+            name: "col1".to_string(),
+            aggregation: None,
+        }]);
+
+        // --- Ascending pages, the tie is split across pages ---
+
+        let req = DataLoadRequestParams {
+            limit: Some(2),
+            ordering: Some(DBOrdering::ASC),
+            cols: cols.clone(),
+            ..Default::default()
+        };
+
+        let mut values = Vec::new();
+        let mut cursor = None;
+        let mut pages = 0;
+
+        loop {
+            let (status, page, next) = load(DataLoadRequestParams {
+                cursor: cursor.clone(),
+                ..req.clone()
+            })
+            .await;
+
+            assert_eq!(status, StatusCode::OK);
+
+            pages += 1;
+            values.extend(page);
+            cursor = next;
+
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        assert_eq!(pages, 3);
+        assert_eq!(values, vec![1, 2, 3, 4, 5]);
+
+        // --- Rows updated between pages keep their position ---
+
+        let (_, page, next) = load(req.clone()).await;
+        assert_eq!(page, vec![1, 2]);
+
+        sqlx::query(format!("UPDATE {} SET col1 = col1", tbl_name).as_str())
+            .execute(&state.db)
+            .await
+            .unwrap();
+
+        let (status, page, _) = load(DataLoadRequestParams {
+            limit: Some(5),
+            cursor: next,
+            ..req.clone()
+        })
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page, vec![3, 4, 5]);
+
+        // --- Descending pages, the ordering is taken from the cursor ---
+
+        let req = DataLoadRequestParams {
+            limit: Some(3),
+            ordering: Some(DBOrdering::DESC),
+            ..Default::default()
+        };
+
+        let (status, page, next) = load(req.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page, vec![5, 4, 3]);
+        assert!(next.is_some());
+
+        let (status, page, next) = load(DataLoadRequestParams {
+            ordering: None,
+            cursor: next.clone(),
+            ..req.clone()
+        })
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(page, vec![2, 1]);
+        assert!(next.is_none());
+
+        // --- Exactly full last page returns a cursor to an empty page ---
+
+        let (_, _, next) = load(DataLoadRequestParams {
+            limit: Some(5),
+            ..req.clone()
+        })
+        .await;
+
+        let (status, page, next) = load(DataLoadRequestParams {
+            limit: Some(5),
+            cursor: next,
+            ..req.clone()
+        })
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(page.is_empty());
+        assert!(next.is_none());
+
+        // --- No cursor without time ordering or for aggregated data ---
+
+        let (_, _, next) = load(DataLoadRequestParams {
+            ordering: None,
+            ..req.clone()
+        })
+        .await;
+        assert!(next.is_none());
+
+        let (_, _, next) = load(DataLoadRequestParams {
+            order_col: Some("col1".to_string()),
+            ..req.clone()
+        })
+        .await;
+        assert!(next.is_none());
+
+        // --- Invalid cursors - Should fail ---
+
+        let (_, _, cursor) = load(req.clone()).await;
+
+        let (status, _, _) = load(DataLoadRequestParams {
+            ordering: Some(DBOrdering::ASC),
+            cursor: cursor.clone(),
+            ..req.clone()
+        })
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        let (status, _, _) = load(DataLoadRequestParams {
+            cursor: cursor.clone(),
+            cols: Some(vec![DataLoadRequestColumns {
+                name: "col1".to_string(),
+                aggregation: Some(DBAggregation::SUM),
+            }]),
+            time_grouping: Some(3600),
+            ..req.clone()
+        })
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        let (status, _, _) = load(DataLoadRequestParams {
+            cursor: Some("invalid".to_string()),
+            ..req.clone()
+        })
+        .await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_max_page_size(pool: PgPool) {
+        let cfg = serde_yml::from_str("server:\n  data_max_page_size: 2").unwrap();
+        let (app, state) = create_test_app_with_config(pool, cfg).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let data: Vec<SensorDataIngestEntry> = [
+            (1, "2025-02-11T08:00:00"),
+            (2, "2025-02-11T09:00:00"),
+            (3, "2025-02-11T10:00:00"),
+            (4, "2025-02-11T11:00:00"),
+        ]
+        .into_iter()
+        .map(|(v, t)| SensorDataIngestEntry::from_json(json!({"col1": v}), Some(ts(t))))
+        .collect();
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let load_url = format!("/api/sensors/{}/data/load", sensor_id);
+
+        // Returns the status, the number of loaded rows and if a cursor was returned
+        let load = |req: DataLoadRequestParams| {
+            let uri = format!(
+                "{}?{}",
+                load_url,
+                serde_urlencoded::to_string(req.to_vector()).unwrap()
+            );
+
+            let app = &app;
+
+            async move {
+                let req = actix_web::test::TestRequest::get().uri(&uri).to_request();
+                let resp = actix_web::test::call_service(app, req).await;
+
+                let status = resp.status();
+                let has_cursor = resp.headers().contains_key(NEXT_CURSOR_HEADER);
+
+                let body: Value = actix_web::test::read_body_json(resp).await;
+                let rows = body.as_array().map(|rows| rows.len()).unwrap_or_default();
+
+                (status, rows, has_cursor)
+            }
+        };
+
+        let req = DataLoadRequestParams {
+            limit: Some(5),
+            ordering: Some(DBOrdering::ASC),
+            ..Default::default()
+        };
+
+        // --- Paginated reads are capped and continue with the cursor ---
+
+        assert_eq!(load(req.clone()).await, (StatusCode::OK, 2, true));
+
+        // --- Aggregated and not time ordered reads return no cursor and are not capped ---
+
+        let aggregated = DataLoadRequestParams {
+            cols: Some(vec![DataLoadRequestColumns {
+                name: "col1".to_string(),
+                aggregation: Some(DBAggregation::SUM),
+            }]),
+            time_grouping: Some(3600),
+            ..req.clone()
+        };
+
+        assert_eq!(load(aggregated).await, (StatusCode::OK, 4, false));
+
+        let col_ordered = DataLoadRequestParams {
+            order_col: Some("col1".to_string()),
+            ..req.clone()
+        };
+
+        assert_eq!(load(col_ordered).await, (StatusCode::OK, 4, false));
+
+        // --- Negative limit - Should fail ---
+
+        let (status, _, _) = load(DataLoadRequestParams {
+            limit: Some(-1),
+            ..req.clone()
+        })
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_export(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+        let private_sensor = test_sens.iter().find(|(name, _)| name == "MySensor").unwrap().1;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let ts = |s: &str| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap();
+
+        let data = vec![
+            SensorDataIngestEntry::from_json(
+                json!({"col1": 1, "col2": 1.5, "col3": "a,b"}),
+                Some(ts("2025-02-11T08:00:00")),
+            ),
+            SensorDataIngestEntry::from_json(
+                json!({"col1": 2, "col3": "c"}),
+                Some(ts("2025-02-11T09:00:00")),
+            ),
+            SensorDataIngestEntry::from_json(
+                json!({"col1": 3, "col2": 3.5}),
+                Some(ts("2025-02-11T10:00:00")),
+            ),
+        ];
+
+        data_db::add_sensor_data(Arc::new(sensor.clone()), &data, state.clone()).await.unwrap();
+
+        // Returns the status, the content type and the body of the export
+        let export = |sensor_id: Uuid, req: DataExportRequestParams, accept: Option<&str>| {
+            let uri = format!(
+                "/api/sensors/{}/data/export?{}",
+                sensor_id,
+                serde_urlencoded::to_string(req.to_vector()).unwrap()
+            );
+
+            let accept = accept.map(|a| a.to_string());
+            let app = &app;
+
+            async move {
+                let mut req = actix_web::test::TestRequest::get().uri(&uri);
+
+                if let Some(accept) = accept {
+                    req = req.insert_header((header::ACCEPT, accept));
+                }
+
+                let resp = actix_web::test::call_service(app, req.to_request()).await;
+
+                let status = resp.status();
+                let content_type = resp
+                    .headers()
+                    .get(header::CONTENT_TYPE)
+                    .map(|c| c.to_str().unwrap().to_string())
+                    .unwrap_or_default();
+
+                (status, content_type, actix_web::test::read_body(resp).await)
+            }
+        };
+
+        // --- CSV by default ---
+
+        let (status, content_type, body) =
+            export(sensor_id, DataExportRequestParams::default(), None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "text/csv");
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            "created_at,col1,col2,col3\n\
+            2025-02-11T08:00:00.000,1,1.5,\"a,b\"\n\
+            2025-02-11T09:00:00.000,2,,c\n\
+            2025-02-11T10:00:00.000,3,3.5,\n"
+        );
+
+        // --- NDJSON with filter and descending order ---
+
+        let req = DataExportRequestParams {
+            format: Some(DataExportFormat::NDJSON),
+            ordering: Some(DBOrdering::DESC),
+            filter: Some(DataFilter::parse("col1.gt.1").unwrap()),
+            ..Default::default()
+        };
+
+        let (status, content_type, body) = export(sensor_id, req, None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/x-ndjson");
+
+        let rows: Vec<Value> = String::from_utf8(body.to_vec())
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                json!({"created_at": "2025-02-11T10:00:00.000", "col1": 3, "col2": 3.5, "col3": null}),
+                json!({"created_at": "2025-02-11T09:00:00.000", "col1": 2, "col2": null, "col3": "c"}),
+            ]
+        );
+
+        // --- Arrow IPC stream by Accept header ---
+
+        let (status, content_type, body) = export(
+            sensor_id,
+            DataExportRequestParams::default(),
+            Some("application/vnd.apache.arrow.stream"),
+        )
+        .await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/vnd.apache.arrow.stream");
+
+        let reader = arrow::ipc::reader::StreamReader::try_new(body.as_ref(), None).unwrap();
+        let batches: Vec<arrow::array::RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        let batch = arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap();
+
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), 4);
+
+        let col2 = batch.column_by_name("col2").unwrap();
+        let col2 = col2.as_any().downcast_ref::<arrow::array::Float64Array>().unwrap();
+        assert_eq!(col2.iter().collect::<Vec<_>>(), vec![Some(1.5), None, Some(3.5)]);
+
+        // --- Parquet with selected columns ---
+
+        let req = DataExportRequestParams {
+            format: Some(DataExportFormat::PARQUET),
+            cols: Some(vec![DataLoadRequestColumns {
+                name: "col1".to_string(),
+                aggregation: None,
+            }]),
+            ..Default::default()
+        };
+
+        let (status, content_type, body) = export(sensor_id, req, None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type, "application/vnd.apache.parquet");
+
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(body)
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<arrow::array::RecordBatch> = reader.map(|b| b.unwrap()).collect();
+
+        assert_eq!(batches[0].num_columns(), 2);
+
+        let col1: Vec<Option<i32>> = batches
+            .iter()
+            .flat_map(|b| {
+                let col1 = b.column_by_name("col1").unwrap();
+                let col1 = col1.as_any().downcast_ref::<arrow::array::Int32Array>().unwrap();
+                col1.iter().collect::<Vec<_>>()
+            })
+            .collect();
+
+        assert_eq!(col1, vec![Some(1), Some(2), Some(3)]);
+
+        // --- Exports exceeding a single fetch from the cursor ---
+
+        sqlx::query(&format!(
+            "INSERT INTO {} (created_at, col1) SELECT TIMESTAMP '2025-01-01' + i * INTERVAL '1 second', i \
+            FROM generate_series(1, 12000) AS i",
+            sensor.tbl_name
+        ))
+        .execute(&state.db)
+        .await
+        .unwrap();
+
+        let (status, _, body) = export(sensor_id, DataExportRequestParams::default(), None).await;
+
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(String::from_utf8(body.to_vec()).unwrap().lines().count(), 1 + 12003);
+
+        // --- Private sensor without key - Should fail ---
+
+        let (status, _, _) =
+            export(private_sensor, DataExportRequestParams::default(), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // --- Aggregated or unknown columns - Should fail ---
+
+        for col in [("col1", Some(DBAggregation::SUM)), ("unknown", None)] {
+            let req = DataExportRequestParams {
+                cols: Some(vec![DataLoadRequestColumns {
+                    name: col.0.to_string(),
+                    aggregation: col.1,
+                }]),
+                ..Default::default()
+            };
+
+            let (status, _, _) = export(sensor_id, req, None).await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_data_export_limits(pool: PgPool) {
+        let cfg = serde_yml::from_str(
+            "server:\n  data_export_max_concurrent: 1\n  data_export_timeout_secs: 1",
+        )
+        .unwrap();
+        let (app, state) = create_test_app_with_config(pool, cfg).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+        let sensor = cache::request_sensor(sensor_id, &state).await.unwrap();
+
+        let data = vec![SensorDataIngestEntry::from_json(json!({"col1": 1}), None)];
+
+        data_db::add_sensor_data(Arc::new(sensor), &data, state.clone()).await.unwrap();
+
+        let export = || {
+            let req = actix_web::test::TestRequest::get()
+                .uri(&format!("/api/sensors/{}/data/export", sensor_id))
+                .to_request();
+
+            actix_web::test::call_service(&app, req)
+        };
+
+        // --- A running export occupies the only permit ---
+
+        let running = export().await;
+        assert_eq!(running.status(), StatusCode::OK);
+
+        let rejected = export().await;
+        assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // --- Dropping the response releases the permit ---
+
+        drop(running);
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        let resp = export().await;
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = actix_web::test::read_body(resp).await;
+        assert_eq!(String::from_utf8(body.to_vec()).unwrap().lines().count(), 2);
+
+        // --- A stalled export is aborted at the time limit ---
+
+        let stalled = export().await;
+        assert_eq!(stalled.status(), StatusCode::OK);
+
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+
+        let resp = export().await;
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_ingest_csv(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+
+        let ingest_url = format!("/api/sensors/{}/data/ingest", sensor_id);
+
+        // Returns the status and body of the CSV ingest
+        let ingest = |params: &str, csv: &str| {
+            let uri = format!("{}?{}", ingest_url, params);
+            let csv = csv.to_string();
+            let app = &app;
+
+            async move {
+                let req = actix_web::test::TestRequest::post()
+                    .uri(&uri)
+                    .insert_header((header::CONTENT_TYPE, "text/csv"))
+                    .set_payload(csv)
+                    .to_request();
+
+                let resp = actix_web::test::call_service(app, req).await;
+                let status = resp.status();
+                let body: Value = actix_web::test::read_body_json(resp).await;
+
+                (status, body)
+            }
+        };
+
+        // --- Default options, unknown columns are ignored and empty fields are NULL ---
+
+        let (status, _) = ingest(
+            "",
+            "timestamp,col1,col2,col3,unknown\n\
+            2025-02-11T08:00:00,1,1.5,\"a,b\",x\n\
+            2025-02-11T09:00:00+01:00,2,,c,y\n",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // --- Custom delimiter, timestamp column and format ---
+
+        let (status, _) = ingest(
+            "delimiter=;&timestamp_col=time&timestamp_format=%25d.%25m.%25Y%20%25H:%25M",
+            "time;col2;col1\n11.02.2025 10:00;3.5;3\n",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // --- Epoch timestamps ---
+
+        let (status, _) =
+            ingest("timestamp_format=unix", "timestamp,col1\n1739275200,4\n").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let load_url = format!("/api/sensors/{}/data/load", sensor_id);
+
+        let req = DataLoadRequestParams {
+            ordering: Some(DBOrdering::ASC),
+            ..Default::default()
+        };
+
         let body = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_own.1),
+            &load_url,
             Method::GET,
-            Some(req_payload.to_vector()),
+            Some(req.to_vector()),
             None::<Value>,
             None,
             StatusCode::OK,
@@ -2005,46 +4382,44 @@ pub mod tests {
         )
         .await;
 
-        let arr = body.as_array().unwrap();
-
-        assert_eq!(arr.len(), half_buckets.len()); // Check (half) bucket count
-                                                   // Check if ordering is correct [DESC for time]
         assert_eq!(
-            json_timestamp_to_epoch(
-                arr.last()
-                    .unwrap()
-                    .as_object()
-                    .unwrap()
-                    .get(GROUPED_TIME_COL_NAME)
-                    .unwrap()
-            ),
-            half_buckets
-                .iter()
-                .min_by_key(|entry| entry.0)
-                .unwrap()
-                .0
-                .clone()
+            body,
+            json!([
+                {"created_at": "2025-02-11T08:00:00.000", "col1": 1, "col2": 1.5, "col3": "a,b"},
+                {"created_at": "2025-02-11T08:00:00.000", "col1": 2, "col2": null, "col3": "c"},
+                {"created_at": "2025-02-11T10:00:00.000", "col1": 3, "col2": 3.5, "col3": null},
+                {"created_at": "2025-02-11T12:00:00.000", "col1": 4, "col2": null, "col3": null},
+            ])
         );
 
-        // --- Get allowed sensor data with valid key (john) - Should succeed ---
+        // --- Invalid values reject the whole request ---
 
-        let mut req_payload = DataLoadRequestParams::default();
-        req_payload.key = Some(
-            test_keys
-                .iter()
-                .find(|k| {
-                    k.user_id == john().id
-                        && k.sensor_id == target_sensor_allowed.1
-                        && k.operation == DBOperation::READ
-                })
-                .unwrap()
-                .id,
+        let (status, body) = ingest(
+            "",
+            "timestamp,col1,col2\n2025-02-11T11:00:00,5,x\nyesterday,6,6.5\n",
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["fields"],
+            json!([
+                "line 2: invalid FLOAT value 'x' for column col2",
+                "line 3: invalid timestamp 'yesterday'"
+            ])
         );
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_allowed.1),
+        // --- Missing columns - Should fail ---
+
+        let (status, _) = ingest("timestamp_col=time", "timestamp,col1\n2025-02-11T11:00:00,5\n").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let (status, _) = ingest("", "timestamp,other\n2025-02-11T11:00:00,5\n").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let body = execute_request(
+            &load_url,
             Method::GET,
-            Some(req_payload.to_vector()),
+            Some(req.to_vector()),
             None::<Value>,
             None,
             StatusCode::OK,
@@ -2052,77 +4427,213 @@ pub mod tests {
         )
         .await;
 
-        // --- Get not allowed sensor data with valid key (john) of wrong sensor - Should fail ---
+        assert_eq!(body.as_array().unwrap().len(), 4);
+    }
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_not_allowed.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::UNAUTHORIZED,
-            &app,
-        )
-        .await;
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_ingest_line_protocol(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
 
-        // --- Get allowed sensor data with valid key (john) but wrong op (write) - Should fail ---
+        let test_sens = create_test_sensors(&state).await;
+        let test_keys = create_test_api_keys(&state).await;
 
-        req_payload.key = Some(
+        let private_sensor = test_sens.iter().find(|(name, _)| name == "MySensor").unwrap().1;
+        let public_sensor = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+
+        let key = |operation: DBOperation| {
             test_keys
                 .iter()
-                .find(|k| {
-                    k.user_id == john().id
-                        && k.sensor_id == target_sensor_allowed.1
-                        && k.operation == DBOperation::WRITE
-                })
+                .find(|k| k.sensor_id == private_sensor && k.operation == operation)
                 .unwrap()
-                .id,
+                .id
+        };
+        let write_key = key(DBOperation::WRITE);
+        let read_key = key(DBOperation::READ);
+
+        // Returns the status and body of the line protocol ingest
+        let ingest = |params: String, lines: &str| {
+            let uri = format!("/api/sensors/data/ingest/influx?{}", params);
+            let lines = lines.to_string();
+            let app = &app;
+
+            async move {
+                let req = actix_web::test::TestRequest::post()
+                    .uri(&uri)
+                    .insert_header((header::CONTENT_TYPE, "text/plain"))
+                    .set_payload(lines)
+                    .to_request();
+
+                let resp = actix_web::test::call_service(app, req).await;
+                let status = resp.status();
+                let body = actix_web::test::read_body(resp).await;
+
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or_default())
+            }
+        };
+
+        let load = |sensor_id: Uuid, key: Option<Uuid>| {
+            let app = &app;
+
+            async move {
+                let req = DataLoadRequestParams {
+                    key,
+                    ordering: Some(DBOrdering::ASC),
+                    ..Default::default()
+                };
+
+                execute_request(
+                    &format!("/api/sensors/{}/data/load", sensor_id),
+                    Method::GET,
+                    Some(req.to_vector()),
+                    None::<Value>,
+                    None,
+                    StatusCode::OK,
+                    &app,
+                )
+                .await
+            }
+        };
+
+        // --- Public sensor by name, nanosecond timestamps, unknown fields and tags are ignored ---
+
+        let (status, _) = ingest(
+            String::new(),
+            "MySensor5,site=lab col1=1i,col2=1.5,col3=\"a b\",other=3 1739260800000000000\n\
+            MySensor5 col1=2,col2=2i 1739264400000000000\n",
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        assert_eq!(
+            load(public_sensor, None).await,
+            json!([
+                {"created_at": "2025-02-11T08:00:00.000", "col1": 1, "col2": 1.5, "col3": "a b"},
+                {"created_at": "2025-02-11T09:00:00.000", "col1": 2, "col2": 2.0, "col3": null},
+            ])
         );
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_allowed.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::UNAUTHORIZED,
-            &app,
+        // --- Private sensor by id in a tag with key and precision ---
+
+        let (status, _) = ingest(
+            format!("key={}&precision=ms&sensor_tag=device", write_key),
+            &format!("telegraf,device={} col1=3i 1739268000000", private_sensor),
         )
         .await;
+        assert_eq!(status, StatusCode::OK);
 
-        // --- Get non-existing sensor data without key - Should fail ---
+        assert_eq!(
+            load(private_sensor, Some(read_key)).await.as_array().unwrap().last().unwrap()["created_at"],
+            json!("2025-02-11T10:00:00.000")
+        );
 
-        req_payload.key = None;
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", Uuid::new_v4()),
-            Method::GET,
-            Some(DataLoadRequestParams::default().to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::INTERNAL_SERVER_ERROR,
-            &app,
+        // --- Private sensor without key or mixed with another sensor - Should fail ---
+
+        let (status, _) = ingest(String::new(), "MySensor col1=4i").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let (status, _) = ingest(
+            format!("key={}", write_key),
+            &format!("{} col1=4i\nMySensor5 col1=4i", private_sensor),
         )
         .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
 
-        // --- Get non-public sensor data without key - Should fail ---
+        // --- Malformed lines, invalid values and unknown sensors reject the whole request ---
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", target_sensor_not_allowed.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::UNAUTHORIZED,
-            &app,
+        let (status, body) = ingest(
+            String::new(),
+            "MySensor5 col1=5i\nMySensor5 col1=1.5\nMySensor5 col1\nUnknown col1=5i\n",
         )
         .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["fields"].as_array().unwrap().len(), 1);
+        assert!(body["fields"][0].as_str().unwrap().starts_with("line 3:"));
 
-        // --- Get public sensor data without key - Should succeed ---
+        let (status, body) = ingest(
+            String::new(),
+            "MySensor5 col1=5i\nMySensor5 col1=1.5\nUnknown col1=5i\nMySensor5 unknown=1\n",
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["fields"],
+            json!([
+                "line 2: invalid INT value Float(1.5) for column col1",
+                "line 4: no field matches a column of sensor MySensor5",
+                "line 3: unknown sensor 'Unknown'"
+            ])
+        );
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", public_sensor.1),
+        assert_eq!(load(public_sensor, None).await.as_array().unwrap().len(), 2);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_ingest_senml(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+
+        let ingest_url = format!("/api/sensors/{}/data/ingest", sensor_id);
+
+        // Returns the status and body of the SenML ingest
+        let ingest = |content_type: &'static str, payload: Vec<u8>| {
+            let uri = ingest_url.clone();
+            let app = &app;
+
+            async move {
+                let req = actix_web::test::TestRequest::post()
+                    .uri(&uri)
+                    .insert_header((header::CONTENT_TYPE, content_type))
+                    .set_payload(payload)
+                    .to_request();
+
+                let resp = actix_web::test::call_service(app, req).await;
+                let status = resp.status();
+                let body = actix_web::test::read_body(resp).await;
+
+                (status, serde_json::from_slice::<Value>(&body).unwrap_or_default())
+            }
+        };
+
+        // --- JSON pack with base fields, records of the same time form one entry ---
+
+        let pack = json!([
+            {"bn": "device-1/", "bt": 1739260800, "bu": "unit_1", "n": "col1", "v": 1},
+            {"n": "col2", "u": "unit_2", "v": 1.5},
+            {"n": "col1", "t": 3600, "v": 2},
+            {"n": "battery", "u": "%", "v": 80},
+        ]);
+
+        let (status, _) = ingest("application/senml+json", serde_json::to_vec(&pack).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // --- CBOR pack with integer labels ---
+
+        let record = |fields: Vec<(i64, ciborium::Value)>| {
+            ciborium::Value::Map(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+        };
+
+        let pack = ciborium::Value::Array(vec![
+            record(vec![(-3, 1739268000.into()), (0, "col3".into()), (3, "cbor".into())]),
+            record(vec![(0, "col1".into()), (2, 3.into())]),
+        ]);
+
+        let mut payload = Vec::new();
+        ciborium::into_writer(&pack, &mut payload).unwrap();
+
+        let (status, _) = ingest("application/senml+cbor", payload).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let req = DataLoadRequestParams {
+            ordering: Some(DBOrdering::ASC),
+            ..Default::default()
+        };
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", sensor_id),
             Method::GET,
-            Some(req_payload.to_vector()),
+            Some(req.to_vector()),
             None::<Value>,
             None,
             StatusCode::OK,
@@ -2130,43 +4641,250 @@ pub mod tests {
         )
         .await;
 
-        // --- Get public sensor data with valid key of wrong sensor - Should fail ---
+        assert_eq!(
+            body,
+            json!([
+                {"created_at": "2025-02-11T08:00:00.000", "col1": 1, "col2": 1.5, "col3": null},
+                {"created_at": "2025-02-11T09:00:00.000", "col1": 2, "col2": null, "col3": null},
+                {"created_at": "2025-02-11T10:00:00.000", "col1": 3, "col2": null, "col3": "cbor"},
+            ])
+        );
 
-        req_payload.key = Some(
-            test_keys
-                .iter()
-                .find(|k| {
-                    k.user_id == john().id
-                        && k.sensor_id == target_sensor_allowed.1
-                        && k.operation == DBOperation::WRITE
-                })
-                .unwrap()
-                .id,
+        // --- Mismatching units and invalid values reject the whole pack ---
+
+        let pack = json!([
+            {"n": "col1", "u": "m", "v": 1},
+            {"n": "col1", "v": 1.5},
+            {"n": "col2", "v": 1, "x_": 1},
+        ]);
+
+        let (status, body) = ingest("application/senml+json", serde_json::to_vec(&pack).unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body["fields"],
+            json!([
+                "record 0: unit 'm' doesn't match unit 'unit_1' of column col1",
+                "record 1: invalid INT value for column col1",
+                "record 2: unsupported label 'x_'"
+            ])
         );
 
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", public_sensor.1),
-            Method::GET,
-            Some(req_payload.to_vector()),
-            None::<Value>,
-            None,
-            StatusCode::UNAUTHORIZED,
-            &app,
-        )
-        .await;
+        // --- Pack without records of the sensor columns - Should fail ---
 
-        // --- Get public sensor data with invalid key - Should succeed ---
+        let pack = json!([{"n": "other", "v": 1}]);
 
-        req_payload.key = Some(Uuid::new_v4());
-        let _ = execute_request(
-            &format!("/api/sensors/{}/data/load", public_sensor.1),
+        let (status, _) = ingest("application/senml+json", serde_json::to_vec(&pack).unwrap()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_ingest_binary(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let sensor_id = test_sens.iter().find(|(name, _)| name == "MySensor5").unwrap().1;
+
+        let ingest_url = format!("/api/sensors/{}/data/ingest", sensor_id);
+
+        // Returns the status of the ingest
+        let ingest = |content_type: &'static str, payload: Vec<u8>| {
+            let uri = ingest_url.clone();
+            let app = &app;
+
+            async move {
+                let req = actix_web::test::TestRequest::post()
+                    .uri(&uri)
+                    .insert_header((header::CONTENT_TYPE, content_type))
+                    .set_payload(payload)
+                    .to_request();
+
+                actix_web::test::call_service(app, req).await.status()
+            }
+        };
+
+        let entry = |ts: &str, payload: Value| {
+            SensorDataIngestEntry::from_json(
+                payload,
+                Some(NaiveDateTime::parse_from_str(ts, "%Y-%m-%dT%H:%M:%S").unwrap()),
+            )
+        };
+
+        // --- CBOR ---
+
+        let entries = vec![entry("2025-02-11T08:00:00", json!({"col1": 1, "col2": 1.5, "col3": "cbor"}))];
+
+        let mut payload = Vec::new();
+        ciborium::into_writer(&entries, &mut payload).unwrap();
+
+        assert_eq!(ingest("application/cbor", payload).await, StatusCode::OK);
+
+        // --- MessagePack ---
+
+        let entries = vec![entry("2025-02-11T09:00:00", json!({"col1": 2, "col3": "msgpack"}))];
+        let payload = rmp_serde::to_vec_named(&entries).unwrap();
+
+        assert_eq!(ingest("application/msgpack", payload).await, StatusCode::OK);
+
+        // --- Invalid payloads - Should fail ---
+
+        assert_eq!(ingest("application/cbor", vec![0xff, 0x00]).await, StatusCode::BAD_REQUEST);
+        assert_eq!(ingest("application/msgpack", vec![0xc1]).await, StatusCode::BAD_REQUEST);
+
+        let req = DataLoadRequestParams {
+            ordering: Some(DBOrdering::ASC),
+            ..Default::default()
+        };
+
+        let body = execute_request(
+            &format!("/api/sensors/{}/data/load", sensor_id),
             Method::GET,
-            Some(req_payload.to_vector()),
+            Some(req.to_vector()),
             None::<Value>,
             None,
             StatusCode::OK,
             &app,
         )
         .await;
+
+        assert_eq!(
+            body,
+            json!([
+                {"created_at": "2025-02-11T08:00:00.000", "col1": 1, "col2": 1.5, "col3": "cbor"},
+                {"created_at": "2025-02-11T09:00:00.000", "col1": 2, "col2": null, "col3": "msgpack"},
+            ])
+        );
+    }
+
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_ingest_lorawan(pool: PgPool) {
+        let cfg = serde_yml::from_str("server:\n  lorawan_webhook_token: 'ns-secret'").unwrap();
+        let (app, state) = create_test_app_with_config(pool, cfg).await;
+
+        let column = |name: &str| test_column(name, ColumnType::FLOAT);
+
+        // The sensor isn't writable without key, uplinks are authorized by the device registration
+        let sensor_req = CreateSensorRequest {
+            permissions: vec![SensorPermissionRequest {
+                role_id: ROLE_SYSTEM_GUEST,
+                operations: vec![DBOperation::INFO, DBOperation::READ],
+            }],
+            ..guest_sensor_request("MyLoRaSensor", vec![column("temp"), column("rssi"), column("snr")])
+        };
+
+        let sensor_id = sensor_db::create_sensor(sensor_req, Some(john().id), &state).await.unwrap().uuid;
+        let sensor_id = Uuid::parse_str(&sensor_id).unwrap();
+
+        let token = login(&john(), &state).await;
+
+        // --- Register end device ---
+
+        let register_url = format!("/api/sensors/{}/lorawan/register", sensor_id);
+
+        execute_request(&register_url, Method::POST, None, Some(json!({"dev_eui": "70b3d57ed005a1b2"})),
+                        None, StatusCode::UNAUTHORIZED, &app).await;
+
+        execute_request(&register_url, Method::POST, None, Some(json!({"dev_eui": "70b3"})),
+                        Some(token.clone()), StatusCode::BAD_REQUEST, &app).await;
+
+        execute_request(&register_url, Method::POST, None, Some(json!({"dev_eui": "70b3d57ed005a1b2"})),
+                        Some(token.clone()), StatusCode::OK, &app).await;
+
+        let body = execute_request(&format!("/api/sensors/{}/lorawan/list", sensor_id), Method::GET, None, None::<Value>,
+                                   Some(token.clone()), StatusCode::OK, &app).await;
+        assert_eq!(body, json!(["70B3D57ED005A1B2"]));
+
+        // --- Device of another owner - Should fail ---
+
+        let anne_sensor_req = CreateSensorRequest {
+            permissions: vec![],
+            ..guest_sensor_request("AnnesLoRaSensor", vec![column("temp")])
+        };
+
+        let anne_sensor_id = sensor_db::create_sensor(anne_sensor_req, Some(anne().id), &state).await.unwrap().uuid;
+        let anne_token = login(&anne(), &state).await;
+
+        execute_request(&format!("/api/sensors/{}/lorawan/register", anne_sensor_id), Method::POST, None,
+                        Some(json!({"dev_eui": "70-B3-D5-7E-D0-05-A1-B2"})), Some(anne_token), StatusCode::CONFLICT, &app).await;
+
+        let body = execute_request(&format!("/api/sensors/{}/lorawan/list", sensor_id), Method::GET, None, None::<Value>,
+                                   Some(token.clone()), StatusCode::OK, &app).await;
+        assert_eq!(body, json!(["70B3D57ED005A1B2"]));
+
+        // Returns the status of the webhook
+        let webhook = |server: &'static str, query: &'static str, auth: Option<&'static str>, body: Value| {
+            let app = &app;
+
+            async move {
+                let mut req = actix_web::test::TestRequest::post()
+                    .uri(&format!("/api/sensors/data/ingest/lorawan/{}{}", server, query))
+                    .set_json(body);
+                if let Some(auth) = auth {
+                    req = req.insert_header((header::AUTHORIZATION, format!("Bearer {}", auth)));
+                }
+
+                actix_web::test::call_service(app, req.to_request()).await.status()
+            }
+        };
+
+        let ttn_uplink = json!({
+            "end_device_ids": {"device_id": "dev1", "dev_eui": "70B3D57ED005A1B2"},
+            "received_at": "2025-02-11T08:00:00Z",
+            "uplink_message": {
+                "frm_payload": "AQI=",
+                "decoded_payload": {"temp": 21.5},
+                "rx_metadata": [{"rssi": -110, "snr": -3.5}, {"rssi": -42, "snr": 9.25}]
+            }
+        });
+
+        // --- Network server not authenticated - Should fail ---
+
+        assert_eq!(webhook("ttn", "", None, ttn_uplink.clone()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(webhook("ttn", "", Some("wrong"), ttn_uplink.clone()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(webhook("ttn", "", Some("ns-secre"), ttn_uplink.clone()).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(webhook("ttn", "", Some("ns-secret2"), ttn_uplink.clone()).await, StatusCode::UNAUTHORIZED);
+
+        // --- TTN uplink with decoded payload - Should succeed ---
+
+        assert_eq!(webhook("ttn", "", Some("ns-secret"), ttn_uplink.clone()).await, StatusCode::OK);
+
+        // --- ChirpStack uplink with raw payload - Should succeed ---
+
+        let chirpstack_uplink = json!({
+            "time": "2025-02-11T10:00:00+01:00",
+            "deviceInfo": {"devEui": "70b3d57ed005a1b2"},
+            // [{"temp": 22.0}]
+            "data": "W3sidGVtcCI6IDIyLjB9XQ==",
+            "rxInfo": [{"rssi": -60, "snr": 10.5}]
+        });
+
+        assert_eq!(webhook("chirpstack", "?event=up", Some("ns-secret"), chirpstack_uplink.clone()).await, StatusCode::OK);
+
+        // --- Other events are ignored ---
+
+        assert_eq!(webhook("chirpstack", "?event=join", Some("ns-secret"), chirpstack_uplink.clone()).await, StatusCode::NO_CONTENT);
+
+        // --- Malformed uplinks - Should fail ---
+
+        assert_eq!(webhook("ttn", "", Some("ns-secret"), json!({"uplink_message": {}})).await, StatusCode::BAD_REQUEST);
+        assert_eq!(webhook("chirpstack", "?format=xml", Some("ns-secret"), chirpstack_uplink.clone()).await, StatusCode::BAD_REQUEST);
+
+        let body = execute_request(&format!("/api/sensors/{}/data/load", sensor_id), Method::GET,
+                                   Some(DataLoadRequestParams { ordering: Some(DBOrdering::ASC), ..Default::default() }.to_vector()),
+                                   None::<Value>, None, StatusCode::OK, &app).await;
+
+        assert_eq!(
+            body,
+            json!([
+                {"created_at": "2025-02-11T08:00:00.000", "temp": 21.5, "rssi": -42.0, "snr": 9.25},
+                {"created_at": "2025-02-11T09:00:00.000", "temp": 22.0, "rssi": -60.0, "snr": 10.5},
+            ])
+        );
+
+        // --- Removed end device - Should fail ---
+
+        execute_request(&format!("/api/sensors/{}/lorawan/70-b3-d5-7e-d0-05-a1-b2/delete", sensor_id), Method::DELETE, None, None::<Value>,
+                        Some(token.clone()), StatusCode::OK, &app).await;
+
+        assert_eq!(webhook("ttn", "", Some("ns-secret"), ttn_uplink).await, StatusCode::NOT_FOUND);
     }
 }
@@ -0,0 +1,894 @@
+use crate::database::models::events::LogEvent;
+use crate::features::config::{get_coap_max_concurrent_requests, get_coap_max_exchanges};
+use crate::handler::data_ingest::format::IngestFormat;
+use crate::handler::data_ingest::ingest::ingest_data_buisness_logic;
+use crate::handler::data_ingest::senml::SenmlEncoding;
+use crate::handler::models::requests::TransportProto;
+use crate::handler::models::telelmetry::OTelData;
+use crate::state::AppState;
+use actix_http::StatusCode;
+use actix_web::ResponseError;
+use coap_lite::option_value::OptionValueU16;
+use coap_lite::{CoapOption, CoapRequest, MessageType, Packet, RequestType, ResponseType};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::Semaphore;
+use tracing::{debug, error, info, info_span};
+use uuid::Uuid;
+
+/*
+
+CoAP
+
+listener:
+    UDP socket accepting POST requests on 'PATH_PREFIX<sensor_id>[/<api_key>]'
+    Responses are piggybacked on the ACK of confirmable requests.
+    Each request is handled in its own task, retransmitted confirmable requests
+    are answered with the response of the first transmission (RFC 7252 §4.5).
+    The number of concurrently handled requests and of remembered exchanges is limited,
+    requests beyond the limit are answered with 5.03 Service Unavailable.
+
+*/
+
+// Same path as for the HTTP handler and MQTT topics
+pub const PATH_PREFIX: &str = "api/sensors/";
+
+// Largest datagram we accept, block-wise transfers are not supported
+const MAX_DATAGRAM_SIZE: usize = 64 * 1024;
+
+// Time responses are kept for retransmissions, EXCHANGE_LIFETIME of RFC 7252 §4.8.2
+const EXCHANGE_LIFETIME: Duration = Duration::from_secs(247);
+
+// Registered CoAP Content-Formats
+const CONTENT_FORMAT_JSON: u16 = 50;
+const CONTENT_FORMAT_CBOR: u16 = 60;
+const CONTENT_FORMAT_SENML_JSON: u16 = 110;
+const CONTENT_FORMAT_SENML_CBOR: u16 = 112;
+
+/* ------------------------------------------------ API ------------------------------------------------------------ */
+
+/// External entrypoint to interact with the service
+#[derive(Clone)]
+pub struct COAP {
+    // Address the listener is bound to
+    addr: SocketAddr,
+}
+
+impl COAP {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+/// Binds the UDP socket and starts a tokio task that runs the CoAP listener.
+/// Returns None if the socket can't be bound.
+pub fn coap_service_init(state: AppState) -> Option<COAP> {
+    // Tests run in parallel and each need their own listener
+    #[cfg(not(test))]
+    let addr = (
+        crate::features::config::get_host(&state.cfg),
+        crate::features::config::get_coap_port(&state.cfg),
+    );
+    #[cfg(test)]
+    let addr = ("127.0.0.1".to_string(), 0);
+
+    let socket = std::net::UdpSocket::bind((addr.0.as_str(), addr.1))
+        .and_then(|s| s.set_nonblocking(true).map(|_| s))
+        .and_then(|s| UdpSocket::from_std(s));
+
+    let socket = match socket {
+        Ok(s) => s,
+        Err(err) => {
+            error!("[CoAP] failed to bind {}:{}: '{}'", addr.0, addr.1, err);
+            return None;
+        }
+    };
+
+    let local_addr = socket.local_addr().ok()?;
+
+    tokio::spawn(async move {
+        info!("[CoAP] listening on {}", local_addr);
+
+        start_coap_listener(state, socket).await;
+
+        info!("[CoAP] listener stopped.");
+    });
+
+    Some(COAP { addr: local_addr })
+}
+
+/* ------------------------------------------------ Helper functions ------------------------------------------------------------ */
+
+/// Source and message id of a confirmable request
+type ExchangeKey = (SocketAddr, u16);
+
+/// State of a confirmable request
+enum Exchange {
+    /// First transmission of the request
+    New,
+    /// The request is still being handled
+    Pending,
+    /// Encoded response of the handled request
+    Done(Vec<u8>),
+}
+
+/// Recent confirmable exchanges for the deduplication of retransmitted requests,
+/// bounded to a maximum number of entries by evicting the oldest exchanges.
+struct ExchangeCache {
+    // Start of the exchange and its response once handled
+    exchanges: HashMap<ExchangeKey, (Instant, Option<Vec<u8>>)>,
+    // Exchanges in the order of their start, may refer to already removed exchanges
+    order: VecDeque<(ExchangeKey, Instant)>,
+    max_entries: usize,
+}
+
+impl ExchangeCache {
+    fn new(max_entries: usize) -> Self {
+        ExchangeCache {
+            exchanges: HashMap::new(),
+            order: VecDeque::new(),
+            max_entries,
+        }
+    }
+
+    /// Starts the exchange unless it is already known
+    fn begin(&mut self, key: ExchangeKey) -> Exchange {
+        self.purge();
+
+        match self.exchanges.get(&key) {
+            Some((_, Some(response))) => Exchange::Done(response.clone()),
+            Some((_, None)) => Exchange::Pending,
+            None => {
+                // Evicts the oldest exchanges to make room for the new one
+                while self.order.len() >= self.max_entries.max(1) {
+                    self.pop_oldest();
+                }
+
+                let start = Instant::now();
+                self.exchanges.insert(key, (start, None));
+                self.order.push_back((key, start));
+                Exchange::New
+            }
+        }
+    }
+
+    /// Stores the response of the exchange, exchanges without response are forgotten
+    fn complete(&mut self, key: ExchangeKey, response: Option<Vec<u8>>) {
+        match response {
+            Some(response) => {
+                if let Some(exchange) = self.exchanges.get_mut(&key) {
+                    exchange.1 = Some(response);
+                }
+            }
+            None => {
+                self.exchanges.remove(&key);
+            }
+        }
+    }
+
+    // Removes expired exchanges
+    fn purge(&mut self) {
+        while self
+            .order
+            .front()
+            .is_some_and(|(_, start)| start.elapsed() >= EXCHANGE_LIFETIME)
+        {
+            self.pop_oldest();
+        }
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some((key, start)) = self.order.pop_front() {
+            // The key may have been reused by a newer exchange
+            if self.exchanges.get(&key).is_some_and(|(s, _)| *s == start) {
+                self.exchanges.remove(&key);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct KeyPair {
+    sensor_id: Uuid,
+    api_key: Option<Uuid>,
+}
+
+// takes 'PATH_PREFIX<sensor_id>[/<api_key>]' and returns the ids
+fn split_path(path: &str) -> Result<KeyPair, ResponseType> {
+    let rest = path
+        .strip_prefix(PATH_PREFIX)
+        .ok_or(ResponseType::NotFound)?;
+
+    let segments: Vec<&str> = rest.split('/').collect();
+
+    let (sensor_id, api_key) = match segments.as_slice() {
+        [sensor_id] => (sensor_id, None),
+        [sensor_id, ""] => (sensor_id, None),
+        [sensor_id, key] => (sensor_id, Some(key)),
+        _ => return Err(ResponseType::NotFound),
+    };
+
+    let sensor_id = Uuid::parse_str(sensor_id).map_err(|_| ResponseType::BadRequest)?;
+    let api_key = match api_key {
+        Some(key) => Some(Uuid::parse_str(key).map_err(|_| ResponseType::BadRequest)?),
+        None => None,
+    };
+
+    Ok(KeyPair { sensor_id, api_key })
+}
+
+// Payload format from the Content-Format option, JSON if not specified
+fn content_format(packet: &Packet) -> Result<IngestFormat, ResponseType> {
+    let cf = match packet.get_first_option_as::<OptionValueU16>(CoapOption::ContentFormat) {
+        None => return Ok(IngestFormat::JSON),
+        Some(cf) => cf.map_err(|_| ResponseType::BadOption)?.0,
+    };
+
+    match cf {
+        CONTENT_FORMAT_JSON => Ok(IngestFormat::JSON),
+        CONTENT_FORMAT_CBOR => Ok(IngestFormat::CBOR),
+        CONTENT_FORMAT_SENML_JSON => Ok(IngestFormat::SENML(SenmlEncoding::JSON)),
+        CONTENT_FORMAT_SENML_CBOR => Ok(IngestFormat::SENML(SenmlEncoding::CBOR)),
+        _ => Err(ResponseType::UnsupportedContentFormat),
+    }
+}
+
+// Maps the status of the ingest to the CoAP response code
+fn response_type(status: StatusCode) -> ResponseType {
+    match status {
+        StatusCode::OK => ResponseType::Created,
+        StatusCode::NO_CONTENT => ResponseType::Changed,
+        StatusCode::BAD_REQUEST => ResponseType::BadRequest,
+        StatusCode::UNAUTHORIZED => ResponseType::Unauthorized,
+        StatusCode::FORBIDDEN => ResponseType::Forbidden,
+        StatusCode::NOT_FOUND => ResponseType::NotFound,
+        StatusCode::PAYLOAD_TOO_LARGE => ResponseType::RequestEntityTooLarge,
+        _ => ResponseType::InternalServerError,
+    }
+}
+
+// Maps the CoAP response code to the status used for events
+fn status_code(response: ResponseType) -> StatusCode {
+    match response {
+        ResponseType::Created | ResponseType::Changed => StatusCode::OK,
+        ResponseType::BadRequest | ResponseType::BadOption => StatusCode::BAD_REQUEST,
+        ResponseType::Unauthorized => StatusCode::UNAUTHORIZED,
+        ResponseType::Forbidden => StatusCode::FORBIDDEN,
+        ResponseType::NotFound => StatusCode::NOT_FOUND,
+        ResponseType::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+        ResponseType::RequestEntityTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+        ResponseType::UnsupportedContentFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        ResponseType::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/* ------------------------------------------------ CoAP Listener ------------------------------------------------------------ */
+
+///
+/// Receives CoAP requests on the socket and ingests the payload of POST requests.
+/// Every request must be to a path in the form of 'PATH_PREFIX<sensor_id>[/<api_key>]'
+///
+async fn start_coap_listener(state: AppState, socket: UdpSocket) {
+    let socket = Arc::new(socket);
+    let exchanges = Arc::new(Mutex::new(ExchangeCache::new(get_coap_max_exchanges(
+        &state.cfg,
+    ))));
+    let permits = Arc::new(Semaphore::new(get_coap_max_concurrent_requests(&state.cfg)));
+
+    let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+
+    loop {
+        let (len, src) = match socket.recv_from(&mut buf).await {
+            Ok(v) => v,
+            Err(err) => {
+                error!("[CoAP] failed to receive: '{}'", err);
+                continue;
+            }
+        };
+
+        // Malformed datagrams are silently ignored
+        let packet = match Packet::from_bytes(&buf[..len]) {
+            Ok(p) => p,
+            Err(err) => {
+                debug!("[CoAP] ignoring malformed message from {}: '{}'", src, err);
+                continue;
+            }
+        };
+
+        let request = CoapRequest::from_packet(packet, src);
+
+        // Only requests expect a response
+        if request.response.is_none() {
+            continue;
+        }
+
+        // Retransmissions of confirmable requests are answered with the cached response
+        let key = (request.message.header.get_type() == MessageType::Confirmable)
+            .then_some((src, request.message.header.message_id));
+
+        if let Some(key) = key {
+            let exchange = exchanges.lock().unwrap().begin(key);
+
+            match exchange {
+                Exchange::New => {}
+                Exchange::Pending => {
+                    debug!(
+                        "[CoAP] ignoring retransmission from {} while handling it",
+                        src
+                    );
+                    continue;
+                }
+                Exchange::Done(response) => {
+                    send_response(&socket, &response, src).await;
+                    continue;
+                }
+            }
+        }
+
+        // Requests exceeding the concurrency limit are rejected without being handled
+        let Ok(permit) = permits.clone().try_acquire_owned() else {
+            debug!("[CoAP] rejecting request from {}, too many requests", src);
+
+            if let Some(key) = key {
+                exchanges.lock().unwrap().complete(key, None);
+            }
+
+            if let Some(response) = encode_response(request, ResponseType::ServiceUnavailable, None)
+            {
+                send_response(&socket, &response, src).await;
+            }
+            continue;
+        };
+
+        let state = state.clone();
+        let socket = socket.clone();
+        let exchanges = exchanges.clone();
+
+        tokio::spawn(async move {
+            let response = respond(request, &state).await;
+            drop(permit);
+
+            if let Some(key) = key {
+                exchanges.lock().unwrap().complete(key, response.clone());
+            }
+
+            if let Some(response) = response {
+                send_response(&socket, &response, src).await;
+            }
+        });
+    }
+}
+
+// Handles the request and returns the encoded response
+async fn respond(request: CoapRequest<SocketAddr>, state: &AppState) -> Option<Vec<u8>> {
+    let (code, diagnostic) = handle_request(&request, state).await;
+
+    encode_response(request, code, diagnostic)
+}
+
+// Encodes the response to the request with the code and optional diagnostic payload
+fn encode_response(
+    mut request: CoapRequest<SocketAddr>,
+    code: ResponseType,
+    diagnostic: Option<String>,
+) -> Option<Vec<u8>> {
+    let response = request.response.as_mut()?;
+
+    response.set_status(code);
+    if let Some(msg) = diagnostic {
+        response.message.payload = msg.into_bytes();
+    }
+
+    match response.message.to_bytes() {
+        Ok(bytes) => Some(bytes),
+        Err(err) => {
+            error!("[CoAP] failed to encode response: '{}'", err);
+            None
+        }
+    }
+}
+
+async fn send_response(socket: &UdpSocket, response: &[u8], src: SocketAddr) {
+    if let Err(err) = socket.send_to(response, src).await {
+        error!("[CoAP] failed to respond to {}: '{}'", src, err);
+    }
+}
+
+// Handles the request, returns the response code and an optional diagnostic payload
+async fn handle_request(
+    request: &CoapRequest<SocketAddr>,
+    state: &AppState,
+) -> (ResponseType, Option<String>) {
+    let start = Instant::now();
+    let path = request.get_path();
+
+    info_span!("CoAP ingest handler", path);
+
+    if *request.get_method() != RequestType::Post {
+        return (ResponseType::MethodNotAllowed, None);
+    }
+
+    let parsed = split_path(&path).and_then(|keys| {
+        let format = content_format(&request.message)?;
+        Ok((keys, format))
+    });
+
+    let (keys, format) = match parsed {
+        Ok(v) => v,
+        Err(code) => {
+            log_event(start.elapsed(), state, &path, code, None);
+            return (code, None);
+        }
+    };
+
+    let payload = bytes::Bytes::copy_from_slice(&request.message.payload);
+
+    let res = ingest_data_buisness_logic(
+        keys.sensor_id,
+        keys.api_key,
+        payload.clone(),
+        format,
+        None,
+        state,
+    )
+    .await;
+
+    match res {
+        Ok(ingested) => {
+            let code = match ingested {
+                true => ResponseType::Created,
+                false => ResponseType::Changed,
+            };
+
+            log_event(
+                start.elapsed(),
+                state,
+                &path,
+                code,
+                Some(String::from_utf8_lossy(&payload).into_owned()),
+            );
+
+            (code, None)
+        }
+        Err(err) => {
+            error!("[CoAP] failed to ingest into db: '{}' ({keys:?})", err);
+
+            let code = response_type(err.status_code());
+            log_event(start.elapsed(), state, &path, code, None);
+
+            (code, Some(err.to_string()))
+        }
+    }
+}
+
+fn log_event(
+    dur: Duration,
+    state: &AppState,
+    path: &str,
+    code: ResponseType,
+    payload: Option<String>,
+) {
+    let mut e = LogEvent::new(
+        OTelData::generate(),
+        dur,
+        TransportProto::COAP,
+        format!("/{}", path),
+        status_code(code),
+    );
+    if let Some(payload) = payload {
+        e.with_payload(payload);
+    }
+    state.events.clone().unwrap().les_chan.send(e).unwrap();
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::database::data_db;
+    use crate::database::models::db_structs::DBOperation;
+    use crate::features::cache;
+    use crate::handler::models::requests::DataLoadRequestParams;
+    use crate::test_utils::tests::{
+        create_test_api_keys, create_test_app, create_test_app_with_config, create_test_sensors,
+        john,
+    };
+    use serde::Serialize;
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    /// Builds a confirmable CoAP request with a random message id.
+    fn build_request(
+        path: &str,
+        method: RequestType,
+        content_format: Option<u16>,
+        payload: Vec<u8>,
+    ) -> CoapRequest<SocketAddr> {
+        let mut request: CoapRequest<SocketAddr> = CoapRequest::new();
+        request.message.header.set_type(MessageType::Confirmable);
+        request.message.header.message_id = fastrand::u16(..);
+        request.message.set_token(vec![1, 2, 3, 4]);
+        request.set_method(method);
+        request.set_path(path);
+        if let Some(cf) = content_format {
+            request
+                .message
+                .add_option(CoapOption::ContentFormat, cf.to_be_bytes().to_vec());
+        }
+        request.message.payload = payload;
+
+        request
+    }
+
+    /// Sends the request from the socket to the listener and returns the response code and payload.
+    async fn send_request(
+        socket: &UdpSocket,
+        request: &CoapRequest<SocketAddr>,
+        state: &AppState,
+    ) -> (ResponseType, Vec<u8>) {
+        let addr = state.coap_listener.clone().unwrap().local_addr();
+
+        socket
+            .send_to(&request.message.to_bytes().unwrap(), addr)
+            .await
+            .unwrap();
+
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+        let (len, _) = tokio::time::timeout(Duration::from_secs(5), socket.recv_from(&mut buf))
+            .await
+            .expect("no CoAP response")
+            .unwrap();
+
+        let response = Packet::from_bytes(&buf[..len]).unwrap();
+        assert_eq!(response.header.get_type(), MessageType::Acknowledgement);
+        assert_eq!(
+            response.header.message_id,
+            request.message.header.message_id
+        );
+
+        let code = match response.header.code {
+            coap_lite::MessageClass::Response(code) => code,
+            _ => ResponseType::UnKnown,
+        };
+
+        (code, response.payload)
+    }
+
+    /// Sends a confirmable CoAP request to the listener and returns the response code and payload.
+    pub async fn coap_request(
+        path: &str,
+        method: RequestType,
+        content_format: Option<u16>,
+        payload: Vec<u8>,
+        state: &AppState,
+    ) -> (ResponseType, Vec<u8>) {
+        let request = build_request(path, method, content_format, payload);
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        send_request(&socket, &request, state).await
+    }
+
+    /// Posts the JSON payload to the sensor and checks the expected status of the ingest.
+    pub async fn coap_client_post<T>(
+        sensor_id: Uuid,
+        api_key: Option<Uuid>,
+        payload: Option<T>,
+        expected_status: StatusCode,
+        state: &AppState,
+    ) where
+        T: Serialize,
+    {
+        let path = match api_key {
+            Some(key) => format!("/{PATH_PREFIX}{sensor_id}/{key}"),
+            None => format!("/{PATH_PREFIX}{sensor_id}"),
+        };
+
+        let payload = payload.map_or(Vec::new(), |p| serde_json::to_vec(&p).unwrap());
+
+        let (code, _) = coap_request(&path, RequestType::Post, None, payload, state).await;
+
+        assert_eq!(status_code(code), expected_status);
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../fixtures/users.sql",
+            "../fixtures/roles.sql",
+            "../fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_coap_ingest(pool: PgPool) {
+        let (_app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let test_keys = create_test_api_keys(&state).await;
+
+        let private_sensor = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor")
+            .unwrap()
+            .1;
+        let public_sensor = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor5")
+            .unwrap()
+            .1;
+
+        let write_key = test_keys
+            .iter()
+            .find(|k| {
+                k.user_id == john().id
+                    && k.sensor_id == private_sensor
+                    && k.operation == DBOperation::WRITE
+            })
+            .unwrap()
+            .id;
+
+        let payload = json!([{"col1": 42, "col2": 56.789, "col3": "Hello"}]);
+
+        // --- Ingest with key and into public sensor - Should succeed ---
+
+        let path = format!("/{PATH_PREFIX}{private_sensor}/{write_key}");
+        let body = serde_json::to_vec(&payload).unwrap();
+        let (code, _) = coap_request(&path, RequestType::Post, None, body, &state).await;
+        assert_eq!(code, ResponseType::Created);
+
+        coap_client_post(public_sensor, None, Some(&payload), StatusCode::OK, &state).await;
+
+        // --- Ingest private sensor without key - Should fail ---
+
+        coap_client_post(
+            private_sensor,
+            None,
+            Some(&payload),
+            StatusCode::UNAUTHORIZED,
+            &state,
+        )
+        .await;
+
+        // --- CBOR Content-Format ---
+
+        let mut body = Vec::new();
+        ciborium::into_writer(&payload, &mut body).unwrap();
+
+        let path = format!("/{PATH_PREFIX}{public_sensor}");
+        let (code, _) = coap_request(
+            &path,
+            RequestType::Post,
+            Some(CONTENT_FORMAT_CBOR),
+            body,
+            &state,
+        )
+        .await;
+        assert_eq!(code, ResponseType::Created);
+
+        // --- Invalid requests ---
+
+        let (code, _) =
+            coap_request(&path, RequestType::Post, Some(0), b"col1".to_vec(), &state).await;
+        assert_eq!(code, ResponseType::UnsupportedContentFormat);
+
+        let (code, _) = coap_request(&path, RequestType::Get, None, Vec::new(), &state).await;
+        assert_eq!(code, ResponseType::MethodNotAllowed);
+
+        let (code, _) =
+            coap_request("/api/other", RequestType::Post, None, Vec::new(), &state).await;
+        assert_eq!(code, ResponseType::NotFound);
+
+        let (code, _) = coap_request(
+            &format!("/{PATH_PREFIX}no-uuid"),
+            RequestType::Post,
+            None,
+            Vec::new(),
+            &state,
+        )
+        .await;
+        assert_eq!(code, ResponseType::BadRequest);
+
+        // Malformed entries are rejected with a diagnostic payload
+        let (code, diagnostic) = coap_request(
+            &path,
+            RequestType::Post,
+            Some(CONTENT_FORMAT_JSON),
+            b"{".to_vec(),
+            &state,
+        )
+        .await;
+        assert_eq!(code, ResponseType::InternalServerError);
+        assert!(!diagnostic.is_empty());
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../fixtures/users.sql",
+            "../fixtures/roles.sql",
+            "../fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_coap_retransmission(pool: PgPool) {
+        let (_app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+
+        let public_sensor = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor5")
+            .unwrap()
+            .1;
+
+        let path = format!("/{PATH_PREFIX}{public_sensor}");
+        let payload = serde_json::to_vec(&json!([{"col1": 42}])).unwrap();
+        let request = build_request(&path, RequestType::Post, None, payload);
+
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        // --- Retransmission is answered with the cached response, the data is ingested once ---
+
+        let first = send_request(&socket, &request, &state).await;
+        assert_eq!(first.0, ResponseType::Created);
+
+        let second = send_request(&socket, &request, &state).await;
+        assert_eq!(second, first);
+
+        let data = data_db::get_data(
+            public_sensor,
+            DataLoadRequestParams {
+                limit: Some(10),
+                ..Default::default()
+            },
+            &state,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(data.as_array().unwrap().len(), 1);
+
+        // --- Same message id from another endpoint is a new request ---
+
+        let other_socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        let (code, _) = send_request(&other_socket, &request, &state).await;
+        assert_eq!(code, ResponseType::Created);
+
+        let data = data_db::get_data(
+            public_sensor,
+            DataLoadRequestParams {
+                limit: Some(10),
+                ..Default::default()
+            },
+            &state,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(data.as_array().unwrap().len(), 2);
+    }
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../fixtures/users.sql",
+            "../fixtures/roles.sql",
+            "../fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_coap_limits(pool: PgPool) {
+        let cfg = serde_yml::from_str(
+            "server:\n  coap_max_concurrent_requests: 1\n  coap_max_exchanges: 4",
+        )
+        .unwrap();
+        let (_app, state) = create_test_app_with_config(pool, cfg).await;
+
+        let test_sens = create_test_sensors(&state).await;
+
+        let public_sensor = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor5")
+            .unwrap()
+            .1;
+        let tbl_name = cache::request_sensor(public_sensor, &state)
+            .await
+            .unwrap()
+            .tbl_name;
+
+        let count = || {
+            let query = format!("SELECT count(*) FROM {}", tbl_name);
+            let db = state.db.clone();
+
+            async move {
+                sqlx::query_scalar::<_, i64>(&query)
+                    .fetch_one(&db)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let path = format!("/{PATH_PREFIX}{public_sensor}");
+        let payload = serde_json::to_vec(&json!([{"col1": 42}])).unwrap();
+
+        let request = |message_id: u16| {
+            let mut request = build_request(&path, RequestType::Post, None, payload.clone());
+            request.message.header.message_id = message_id;
+            request
+        };
+
+        let addr = state.coap_listener.clone().unwrap().local_addr();
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+        // --- Flood exceeding the concurrency limit is answered with 5.03 ---
+
+        let flood: Vec<_> = (0..50).map(request).collect();
+
+        for req in flood.iter() {
+            socket
+                .send_to(&req.message.to_bytes().unwrap(), addr)
+                .await
+                .unwrap();
+        }
+
+        let mut codes: HashMap<u16, ResponseType> = HashMap::new();
+        let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+
+        while codes.len() < flood.len() {
+            let (len, _) = tokio::time::timeout(Duration::from_secs(5), socket.recv_from(&mut buf))
+                .await
+                .expect("no CoAP response")
+                .unwrap();
+
+            let response = Packet::from_bytes(&buf[..len]).unwrap();
+            let code = match response.header.code {
+                coap_lite::MessageClass::Response(code) => code,
+                _ => ResponseType::UnKnown,
+            };
+
+            codes.insert(response.header.message_id, code);
+        }
+
+        let rejected: Vec<u16> = codes
+            .iter()
+            .filter(|(_, code)| **code == ResponseType::ServiceUnavailable)
+            .map(|(id, _)| *id)
+            .collect();
+
+        assert!(!rejected.is_empty());
+        assert!(codes
+            .values()
+            .all(|c| *c == ResponseType::Created || *c == ResponseType::ServiceUnavailable));
+        assert_eq!(count().await, (flood.len() - rejected.len()) as i64);
+
+        // Rejected requests aren't remembered, their retransmission is handled
+        let (code, _) = send_request(&socket, &flood[rejected[0] as usize], &state).await;
+        assert_eq!(code, ResponseType::Created);
+
+        // --- Exchanges beyond the limit evict the oldest ---
+
+        let before = count().await;
+
+        let exchanges: Vec<_> = (100..105).map(request).collect();
+
+        for req in exchanges.iter() {
+            let (code, _) = send_request(&socket, req, &state).await;
+            assert_eq!(code, ResponseType::Created);
+        }
+
+        assert_eq!(count().await, before + 5);
+
+        // The latest exchange is still answered from the cache
+        let (code, _) = send_request(&socket, &exchanges[4], &state).await;
+        assert_eq!(code, ResponseType::Created);
+        assert_eq!(count().await, before + 5);
+
+        // The oldest exchange was evicted and is handled again
+        let (code, _) = send_request(&socket, &exchanges[0], &state).await;
+        assert_eq!(code, ResponseType::Created);
+        assert_eq!(count().await, before + 6);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::database::models::sensor::{ColumnType, FullSensorInfo};
+use crate::features::config::TIMESTAMP_FORMAT;
+use crate::handler::data_ingest::senml::{self, SenmlEncoding};
+use crate::handler::models::requests::SensorDataIngestEntry;
+use chrono::{DateTime, NaiveDateTime};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+/* ------------------------------------------------ Formats ------------------------------------------------------------ */
+
+/// Payload formats accepted for the data ingest.
+#[derive(Debug, Clone, Default)]
+pub enum IngestFormat {
+    /// JSON array of SensorDataIngestEntry
+    #[default]
+    JSON,
+    /// CSV with a header row naming the sensor columns
+    CSV(CsvIngestOptions),
+    /// SenML (RFC 8428) pack of records
+    SENML(SenmlEncoding),
+    /// CBOR encoded array of SensorDataIngestEntry
+    CBOR,
+    /// MessagePack encoded array of SensorDataIngestEntry
+    MSGPACK,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CsvIngestOptions {
+    /// Header of the column containing the timestamps of the entries, by default "timestamp"
+    pub timestamp_col: Option<String>,
+    /// chrono format of the timestamps, 'unix' or 'unix_ms' for epoch timestamps, by default ISO 8601
+    pub timestamp_format: Option<String>,
+    /// Field delimiter, by default ','
+    pub delimiter: Option<char>,
+}
+
+/// Error for payloads that can't be decoded into data entries, rejected with BAD_REQUEST.
+#[derive(Debug)]
+pub struct MalformedIngestData(pub Vec<String>);
+
+impl fmt::Display for MalformedIngestData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Malformed data entries: {}", self.0.join("; "))
+    }
+}
+
+impl std::error::Error for MalformedIngestData {}
+
+impl IngestFormat {
+    /// Payload formats selectable by name, e.g. in MQTT topics or query parameters.
+    /// CSV needs further options and can't be selected by name.
+    pub fn from_name(name: &str) -> Option<IngestFormat> {
+        match name {
+            "json" => Some(IngestFormat::JSON),
+            "senml" | "senml+json" => Some(IngestFormat::SENML(SenmlEncoding::JSON)),
+            "senml+cbor" => Some(IngestFormat::SENML(SenmlEncoding::CBOR)),
+            "cbor" => Some(IngestFormat::CBOR),
+            "msgpack" => Some(IngestFormat::MSGPACK),
+            _ => None,
+        }
+    }
+
+    /// Decodes the payload into data entries for the sensor.
+    pub fn decode(
+        &self,
+        sensor: &FullSensorInfo,
+        data: &[u8],
+    ) -> anyhow::Result<Vec<SensorDataIngestEntry>> {
+        match self {
+            IngestFormat::JSON => Ok(serde_json::from_slice::<Vec<SensorDataIngestEntry>>(data)?),
+            IngestFormat::CSV(options) => Ok(decode_csv(sensor, data, options)?),
+            IngestFormat::SENML(encoding) => Ok(senml::decode(sensor, data, *encoding)?),
+            IngestFormat::CBOR | IngestFormat::MSGPACK => {
+                let value = self.decode_binary(data)?.unwrap_or_default();
+                Ok(serde_json::from_value::<Vec<SensorDataIngestEntry>>(value)?)
+            }
+        }
+    }
+
+    /// Converts compact binary payloads (including SenML CBOR packs) into their JSON representation,
+    /// e.g. to be processed by data transformers. Other payloads are returned unchanged.
+    pub fn binary_to_json(&self, data: bytes::Bytes) -> Result<bytes::Bytes, MalformedIngestData> {
+        match self.decode_binary(&data)? {
+            Some(value) => Ok(bytes::Bytes::from(value.to_string())),
+            None => Ok(data),
+        }
+    }
+
+    /// Decodes CBOR, MessagePack and SenML CBOR payloads, returns None for other formats.
+    fn decode_binary(&self, data: &[u8]) -> Result<Option<Value>, MalformedIngestData> {
+        let value = match self {
+            IngestFormat::SENML(SenmlEncoding::CBOR) => senml::cbor_pack_to_json(data)
+                .map(|records| Value::Array(records.into_iter().map(Value::Object).collect())),
+            IngestFormat::CBOR => ciborium::from_reader::<Value, _>(data)
+                .map_err(|err| format!("Invalid CBOR payload: {}", err)),
+            IngestFormat::MSGPACK => rmp_serde::from_slice::<Value>(data)
+                .map_err(|err| format!("Invalid MessagePack payload: {}", err)),
+            _ => return Ok(None),
+        };
+
+        value
+            .map(Some)
+            .map_err(|err| MalformedIngestData(vec![err]))
+    }
+}
+
+/* ------------------------------------------------ CSV ------------------------------------------------------------ */
+
+const CSV_DEFAULT_TIMESTAMP_COL: &str = "timestamp";
+
+/// Parses the CSV rows into data entries, the values are parsed according to the type of the sensor column.
+/// Empty fields are inserted as NULL, columns without a matching sensor column are ignored.
+fn decode_csv(
+    sensor: &FullSensorInfo,
+    data: &[u8],
+    options: &CsvIngestOptions,
+) -> Result<Vec<SensorDataIngestEntry>, MalformedIngestData> {
+    let malformed = |msg: String| MalformedIngestData(vec![msg]);
+
+    let delimiter = options.delimiter.unwrap_or(',');
+
+    if !delimiter.is_ascii() {
+        return Err(malformed(format!(
+            "Delimiter '{}' must be an ASCII character",
+            delimiter
+        )));
+    }
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter as u8)
+        .trim(csv::Trim::All)
+        .from_reader(data);
+
+    let headers = reader
+        .headers()
+        .map_err(|err| malformed(format!("Invalid header row: {}", err)))?
+        .clone();
+
+    let timestamp_col = options
+        .timestamp_col
+        .as_deref()
+        .unwrap_or(CSV_DEFAULT_TIMESTAMP_COL);
+    let timestamp_idx = headers.iter().position(|h| h == timestamp_col);
+
+    if timestamp_idx.is_none() && options.timestamp_col.is_some() {
+        return Err(malformed(format!(
+            "Timestamp column '{}' is missing",
+            timestamp_col
+        )));
+    }
+
+    // Header index -> sensor column
+    let columns: Vec<(usize, &str, ColumnType)> = headers
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, header)| {
+            let col = sensor.columns.iter().find(|c| c.name == header)?;
+            Some((idx, col.name.as_str(), col.val_type))
+        })
+        .collect();
+
+    if columns.is_empty() {
+        return Err(malformed(
+            "Header row doesn't contain any sensor column".to_string(),
+        ));
+    }
+
+    let format = options.timestamp_format.as_deref();
+
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+
+    for (row, record) in reader.records().enumerate() {
+        // Header is line 1
+        let line = row + 2;
+
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                errors.push(format!("line {}: {}", line, err));
+                continue;
+            }
+        };
+
+        let timestamp = match timestamp_idx.and_then(|idx| record.get(idx)) {
+            Some(field) if !field.is_empty() => match parse_timestamp(field, format) {
+                Some(ts) => Some(ts),
+                None => {
+                    errors.push(format!("line {}: invalid timestamp '{}'", line, field));
+                    continue;
+                }
+            },
+            _ => None,
+        };
+
+        let mut data = HashMap::new();
+
+        for (idx, name, val_type) in columns.iter() {
+            let field = record.get(*idx).unwrap_or_default();
+
+            match parse_value(field, *val_type, format) {
+                Some(value) => {
+                    data.insert(name.to_string(), value);
+                }
+                None => errors.push(format!(
+                    "line {}: invalid {:?} value '{}' for column {}",
+                    line, val_type, field, name
+                )),
+            }
+        }
+
+        entries.push(SensorDataIngestEntry { timestamp, data });
+    }
+
+    if !errors.is_empty() {
+        return Err(MalformedIngestData(errors));
+    }
+
+    Ok(entries)
+}
+
+/// Parses the field into the JSON value expected for the column type, empty fields are NULL.
+fn parse_value(field: &str, val_type: ColumnType, ts_format: Option<&str>) -> Option<Value> {
+    if field.is_empty() {
+        return Some(Value::Null);
+    }
+
+    match val_type {
+        ColumnType::INT => field.parse::<i32>().ok().map(Value::from),
+        ColumnType::FLOAT => field.parse::<f64>().ok().map(Value::from),
+        ColumnType::BOOL => match field.to_lowercase().as_str() {
+            "true" | "1" => Some(Value::Bool(true)),
+            "false" | "0" => Some(Value::Bool(false)),
+            _ => None,
+        },
+        ColumnType::TIMESTAMP => parse_timestamp(field, ts_format)
+            .map(|ts| Value::String(ts.format(TIMESTAMP_FORMAT).to_string())),
+        ColumnType::JSON => {
+            Some(serde_json::from_str(field).unwrap_or_else(|_| Value::String(field.to_string())))
+        }
+        _ => Some(Value::String(field.to_string())),
+    }
+}
+
+/// Parses timestamps with the given chrono format or epoch unit, by default ISO 8601.
+/// Timestamps with timezone information are converted to UTC.
+pub fn parse_timestamp(value: &str, format: Option<&str>) -> Option<NaiveDateTime> {
+    match format {
+        Some("unix") => {
+            let secs = value.parse::<f64>().ok()?;
+            DateTime::from_timestamp_micros((secs * 1_000_000.0).round() as i64)
+                .map(|ts| ts.naive_utc())
+        }
+        Some("unix_ms") => {
+            DateTime::from_timestamp_millis(value.parse().ok()?).map(|ts| ts.naive_utc())
+        }
+        Some(format) => NaiveDateTime::parse_from_str(value, format)
+            .ok()
+            .or_else(|| {
+                DateTime::parse_from_str(value, format)
+                    .ok()
+                    .map(|ts| ts.naive_utc())
+            }),
+        None => DateTime::parse_from_rfc3339(value)
+            .map(|ts| ts.naive_utc())
+            .ok()
+            .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok())
+            .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").ok()),
+    }
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_binary_to_json() {
+        let entries = json!([{"col1": 42, "col3": "Hello"}]);
+
+        // --- CBOR and MessagePack are converted to the JSON entries ---
+
+        let mut cbor = Vec::new();
+        ciborium::into_writer(&entries, &mut cbor).unwrap();
+        let msgpack = rmp_serde::to_vec_named(&entries).unwrap();
+
+        for (format, payload) in [(IngestFormat::CBOR, cbor), (IngestFormat::MSGPACK, msgpack)] {
+            let json = format.binary_to_json(bytes::Bytes::from(payload)).unwrap();
+            assert_eq!(serde_json::from_slice::<Value>(&json).unwrap(), entries);
+        }
+
+        // --- SenML CBOR packs are converted to the SenML JSON pack ---
+
+        let record = |fields: Vec<(i64, ciborium::Value)>| {
+            ciborium::Value::Map(fields.into_iter().map(|(k, v)| (k.into(), v)).collect())
+        };
+
+        let pack = ciborium::Value::Array(vec![
+            record(vec![
+                (-2, "device-1/".into()),
+                (-3, 1739260800.into()),
+                (0, "col1".into()),
+                (2, 42.into()),
+            ]),
+            record(vec![
+                (0, "col3".into()),
+                (3, "Hello".into()),
+                (6, 60.into()),
+            ]),
+        ]);
+
+        let mut payload = Vec::new();
+        ciborium::into_writer(&pack, &mut payload).unwrap();
+
+        let format = IngestFormat::SENML(SenmlEncoding::CBOR);
+        let json = format.binary_to_json(bytes::Bytes::from(payload)).unwrap();
+
+        assert_eq!(
+            serde_json::from_slice::<Value>(&json).unwrap(),
+            json!([
+                {"bn": "device-1/", "bt": 1739260800, "n": "col1", "v": 42},
+                {"n": "col3", "vs": "Hello", "t": 60},
+            ])
+        );
+
+        let res = format.binary_to_json(bytes::Bytes::from_static(b"\xff"));
+        assert!(res.is_err());
+
+        // --- Text formats are returned unchanged ---
+
+        let payload = bytes::Bytes::from(entries.to_string());
+        let json = IngestFormat::JSON.binary_to_json(payload.clone()).unwrap();
+        assert_eq!(json, payload);
+
+        let json = IngestFormat::SENML(SenmlEncoding::JSON)
+            .binary_to_json(payload.clone())
+            .unwrap();
+        assert_eq!(json, payload);
+    }
+}
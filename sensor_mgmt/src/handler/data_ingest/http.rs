@@ -1,11 +1,19 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{post, web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use chrono::Utc;
 use serde_json::json;
 use crate::handler::data_hdl;
+use crate::handler::data_ingest::format::{CsvIngestOptions, IngestFormat};
 use crate::handler::data_ingest::ingest::ingest_data_buisness_logic;
-use crate::handler::models::requests::{SensorDataIngestEntry, DataIngestRequestParams};
+use crate::handler::data_ingest::line_protocol::ingest_line_protocol_buisness_logic;
+use crate::handler::data_ingest::lorawan::ingest_lorawan_buisness_logic;
+use crate::handler::data_ingest::mqtt_auth;
+use crate::handler::data_ingest::senml::SenmlEncoding;
+use crate::handler::models::requests::{SensorDataIngestEntry, DataIngestRequestParams, LineProtocolIngestRequestParams, LineProtocolPrecision, LoRaWANIngestRequestParams, LoRaWANNetworkServer, MqttAuthAclRequest, MqttAuthSuperuserRequest, MqttAuthUserRequest};
 use crate::state::AppState;
+use crate::utils::AppError;
 
+/// Header with the id of an ingested message, used for duplicate detection
+const MESSAGE_ID_HEADER: &str = "X-Message-Id";
 
 /* ------------------------------------------------Data Management ------------------------------------------------------------ */
 
@@ -13,32 +21,66 @@ use crate::state::AppState;
     post,
     path = "/api/sensors/{id}/data/ingest",
     request_body(
-        content_type = "application/json",
-        content = Vec<SensorDataIngestEntry>,
         description = "Data entries with column names and values to insert for the specified sensor.<br>\
-        If invalid data is provided for the columns, NULLs will be inserted. The timestamp of the data tuple \
+        Values which don't match the type of their column reject the request, omitted columns are inserted as NULL. The timestamp of the data tuple \
         may be provided in ISO 8601 format. Timestamps in the future will be rejected.<br>\
-        Care: Inserting multiple values without specifying a custom timestamp will result in the same timestamp for all entries.",
-        example = json!([{"timestamp": Utc::now().naive_utc(), "col1": 1, "col2": 4.21, "col3": "hello"}])
+        Care: Inserting multiple values without specifying a custom timestamp will result in the same timestamp for all entries.<br>\
+        With the content type text/csv, the header row names the sensor columns and the values are parsed according to the column types. \
+        Empty fields are inserted as NULL, invalid values reject the request.<br>\
+        With the content types application/senml+json and application/senml+cbor, the SenML (RFC 8428) records are mapped to the columns by their \
+        resolved name, records with the same resolved time form one entry. Units must match the units of the columns.<br>\
+        With the content types application/cbor and application/msgpack, the body contains the same entries as the JSON body encoded as CBOR or MessagePack. \
+        They are converted to JSON before data transformers are applied.",
+        content(
+            (Vec<SensorDataIngestEntry> = "application/json", example = json!([{"timestamp": Utc::now().naive_utc(), "col1": 1, "col2": 4.21, "col3": "hello"}])),
+            (String = "text/csv", example = "timestamp,col1,col2,col3\n2025-02-11T08:27:17,1,4.21,hello"),
+            (Vec<serde_json::Value> = "application/senml+json", example = json!([{"bn": "device-1/", "bt": 1739262437, "n": "col1", "v": 1}, {"n": "col3", "vs": "hello"}])),
+            (Vec<u8> = "application/senml+cbor"),
+            (Vec<u8> = "application/cbor"),
+            (Vec<u8> = "application/msgpack"),
+        ),
     ),
     params( 
         ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string())),
-        ("key" = String, Query, description = "The provided API key for writing data.", example = json!(uuid::Uuid::new_v4().to_string()))
+        ("key" = String, Query, description = "The provided API key for writing data.", example = json!(uuid::Uuid::new_v4().to_string())),
+        ("timestamp_col" = Option<String>, Query, description = "CSV only: Header of the column containing the timestamps, default=timestamp", example = "time"),
+        ("timestamp_format" = Option<String>, Query, description = "CSV only: chrono format of the timestamps (e.g. %d.%m.%Y %H:%M:%S), \
+        'unix' or 'unix_ms' for epoch timestamps. By default ISO 8601 / RFC 3339, timestamps with timezone are converted to UTC.", example = "unix"),
+        ("delimiter" = Option<String>, Query, description = "CSV only: Field delimiter, default=,", example = ";"),
+        ("X-Message-Id" = Option<String>, Header, description = "Id of the message, assigned to all entries without message_id field. \
+        Sensors with message id dedup drop entries whose id has already been ingested.", example = "gw-1-4711"),
     ),
     tag = data_hdl::COMMON_TAG,
     responses(
         (status = 200, description = "Returns OK if the insertion into the DB was successful."),
         (status = 204, description = "Returns NO_CONTENT if the entry didnt produce an insertion into the DB but also didnt produce an error."),
-        (status = 400, description = "Returns the BAD_REQUEST status if the input parameters are malformed."),
+        (status = 400, description = "Returns the BAD_REQUEST status if the input parameters are malformed or data entries violate column constraints."),
         (status = 401, description= "Returns the unauthorized status if access is not permitted."),
         (status = 500, description= "Returns the generic error status if something unexpected went wrong"),
     ),
 )]
 
 #[post("/sensors/{id}/data/ingest")]
-async fn ingest_sensor_data_handler(sensor_id: web::Path<uuid::Uuid>, data: web::Bytes, params: web::Query<DataIngestRequestParams>, state: web::Data<AppState>) -> impl Responder  {
+async fn ingest_sensor_data_handler(sensor_id: web::Path<uuid::Uuid>, data: web::Bytes, params: web::Query<DataIngestRequestParams>, req: HttpRequest, state: web::Data<AppState>) -> impl Responder  {
 
-    let res = ingest_data_buisness_logic(sensor_id.into_inner(), params.key, data, &state).await;
+    let params = params.into_inner();
+
+    let format = match req.content_type() {
+        "text/csv" => IngestFormat::CSV(CsvIngestOptions {
+            timestamp_col: params.timestamp_col,
+            timestamp_format: params.timestamp_format,
+            delimiter: params.delimiter,
+        }),
+        "application/senml+json" => IngestFormat::SENML(SenmlEncoding::JSON),
+        "application/senml+cbor" => IngestFormat::SENML(SenmlEncoding::CBOR),
+        "application/cbor" => IngestFormat::CBOR,
+        "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => IngestFormat::MSGPACK,
+        _ => IngestFormat::JSON,
+    };
+
+    let message_id = req.headers().get(MESSAGE_ID_HEADER).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+
+    let res = ingest_data_buisness_logic(sensor_id.into_inner(), params.key, data, format, message_id, &state).await;
     let r: HttpResponse = match res {
         Err(err) => err.into(),
         Ok(r) => {
@@ -49,4 +91,167 @@ async fn ingest_sensor_data_handler(sensor_id: web::Path<uuid::Uuid>, data: web:
         },
     };
     r
-}
\ No newline at end of file
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sensors/data/ingest/influx",
+    request_body(
+        description = "Points in InfluxDB line protocol, one per line.<br>\
+        Each point is mapped to a sensor by its measurement (or the tag specified by sensor_tag), holding either the uuid or the name of the sensor. \
+        The fields are mapped to the sensor columns of the same name, other fields and tags are ignored. \
+        Points without timestamp get the time of insertion.<br>\
+        All points are validated before insertion, malformed lines or unknown sensors reject the request.",
+        content(
+            (String = "text/plain", example = "MySensor,site=lab col1=42i,col2=4.21,col3=\"hello\" 1739262437000000000"),
+        ),
+    ),
+    params(
+        ("key" = Option<String>, Query, description = "The provided API key for writing data.", example = json!(uuid::Uuid::new_v4().to_string())),
+        ("precision" = Option<LineProtocolPrecision>, Query, description = "Precision of the point timestamps, default=ns", example = "ms"),
+        ("sensor_tag" = Option<String>, Query, description = "Tag identifying the sensor of a point, by default the measurement is used", example = "device"),
+    ),
+    tag = data_hdl::COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns OK if the insertion into the DB was successful."),
+        (status = 204, description = "Returns NO_CONTENT if the payload didnt contain any points."),
+        (status = 400, description = "Returns the BAD_REQUEST status if lines are malformed, reference unknown sensors or data entries violate column constraints."),
+        (status = 401, description= "Returns the unauthorized status if access to one of the sensors is not permitted."),
+        (status = 500, description= "Returns the generic error status if something unexpected went wrong"),
+    ),
+)]
+
+#[post("/sensors/data/ingest/influx")]
+async fn ingest_line_protocol_handler(data: web::Bytes, params: web::Query<LineProtocolIngestRequestParams>, state: web::Data<AppState>) -> impl Responder {
+    let res = ingest_line_protocol_buisness_logic(data, params.into_inner(), &state).await;
+    let r: HttpResponse = match res {
+        Err(err) => err.into(),
+        Ok(true) => HttpResponse::Ok().json(json!({})),
+        Ok(false) => HttpResponse::NoContent().finish(),
+    };
+    r
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sensors/data/ingest/lorawan/{server}",
+    request_body(
+        description = "Uplink webhook of a LoRaWAN network server, the TTN v3 uplink message or the ChirpStack v4 up event. \
+        The network server must send the configured lorawan_webhook_token as 'Authorization: Bearer' header.<br>\
+        The DevEUI of the end device is mapped to the sensor it was registered for. The payload decoded by the network server \
+        (decoded_payload / object) is ingested as single data entry, otherwise the base64 decoded frm_payload (data) is passed \
+        through the data chain of the sensor in the given format.<br>\
+        Entries without timestamp get the time the uplink was received, the RSSI and SNR of the best gateway are stored in the columns \
+        'rssi' and 'snr' if the sensor has them. Other messages and events are ignored.",
+        content(
+            (serde_json::Value = "application/json", example = json!({"end_device_ids": {"dev_eui": "70B3D57ED005A1B2"}, "received_at": Utc::now(), "uplink_message": {"frm_payload": "AQI=", "decoded_payload": {"col1": 42}, "rx_metadata": [{"rssi": -42, "snr": 9.25}]}})),
+        ),
+    ),
+    params(
+        ("server" = LoRaWANNetworkServer, Path, description = "The network server sending the webhook", example = "ttn"),
+        ("format" = Option<String>, Query, description = "Format of the raw frm_payload (json, cbor, msgpack, senml+json, senml+cbor), default=json", example = "cbor"),
+        ("event" = Option<String>, Query, description = "ChirpStack only: The type of the event, only 'up' events are ingested", example = "up"),
+    ),
+    tag = data_hdl::COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns OK if the insertion into the DB was successful."),
+        (status = 204, description = "Returns NO_CONTENT if the message didnt contain any data, e.g. non uplink events."),
+        (status = 400, description = "Returns the BAD_REQUEST status if the uplink or its payload is malformed or data entries violate column constraints."),
+        (status = 401, description= "Returns the unauthorized status if the bearer token of the network server is invalid."),
+        (status = 404, description= "Returns the NOT_FOUND status if the end device isn't registered for a sensor."),
+        (status = 500, description= "Returns the generic error status if something unexpected went wrong"),
+    ),
+)]
+
+#[post("/sensors/data/ingest/lorawan/{server}")]
+async fn ingest_lorawan_handler(req: HttpRequest, path: web::Path<LoRaWANNetworkServer>, data: web::Bytes, params: web::Query<LoRaWANIngestRequestParams>, state: web::Data<AppState>) -> impl Responder {
+    let token = req.headers()
+        .get(actix_web::http::header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "));
+
+    let res = ingest_lorawan_buisness_logic(path.into_inner(), token, data, params.into_inner(), &state).await;
+    let r: HttpResponse = match res {
+        Err(err) => err.into(),
+        Ok(true) => HttpResponse::Ok().json(json!({})),
+        Ok(false) => HttpResponse::NoContent().finish(),
+    };
+    r
+}
+
+/* ------------------------------------------------MQTT Broker Auth ------------------------------------------------------------ */
+
+const MQTT_AUTH_OPENAPI_COMMON_TAG: &str = "MQTT Broker Auth";
+
+// The broker sends the checks either as JSON or as form body
+type MqttAuthBody<T> = web::Either<web::Json<T>, web::Form<T>>;
+
+fn send_mqtt_auth_result(res: Result<bool, AppError>) -> HttpResponse {
+    match res {
+        Ok(true) => HttpResponse::Ok().json(json!({"ok": true, "error": ""})),
+        Ok(false) => HttpResponse::Forbidden().json(json!({"ok": false, "error": "access denied"})),
+        Err(err) => err.into(),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/mqtt/auth/user",
+    request_body(
+        content = MqttAuthUserRequest,
+        description = "Login of a MQTT client forwarded by the broker (e.g. the HTTP backend of mosquitto-go-auth) as JSON or form body. \
+        Publishers log in with the sensor id as username and a write API key of the sensor as password.",
+    ),
+    tag = MQTT_AUTH_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns OK if the client is permitted to log in."),
+        (status = 403, description = "Returns FORBIDDEN if the credentials are invalid."),
+        (status = 404, description = "Returns NOT_FOUND if broker auth is disabled in the server config."),
+    ),
+)]
+
+#[post("/mqtt/auth/user")]
+async fn mqtt_auth_user_handler(body: MqttAuthBody<MqttAuthUserRequest>, state: web::Data<AppState>) -> impl Responder {
+    send_mqtt_auth_result(mqtt_auth::check_user(&body.into_inner(), &state).await)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/mqtt/auth/superuser",
+    request_body(
+        content = MqttAuthSuperuserRequest,
+        description = "Superuser check of a MQTT client forwarded by the broker, only the subscriber of SensBee is a superuser.",
+    ),
+    tag = MQTT_AUTH_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns OK if the client is a superuser."),
+        (status = 403, description = "Returns FORBIDDEN otherwise."),
+        (status = 404, description = "Returns NOT_FOUND if broker auth is disabled in the server config."),
+    ),
+)]
+
+#[post("/mqtt/auth/superuser")]
+async fn mqtt_auth_superuser_handler(body: MqttAuthBody<MqttAuthSuperuserRequest>, state: web::Data<AppState>) -> impl Responder {
+    send_mqtt_auth_result(mqtt_auth::check_superuser(&body.into_inner(), &state))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/mqtt/auth/acl",
+    request_body(
+        content = MqttAuthAclRequest,
+        description = "Topic access of a logged in MQTT client forwarded by the broker. \
+        Publishers may only write the topics of their sensor, with or without API key, and read the acknowledgements on these topics (suffix /ack).",
+    ),
+    tag = MQTT_AUTH_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns OK if the client may access the topic."),
+        (status = 403, description = "Returns FORBIDDEN otherwise."),
+        (status = 404, description = "Returns NOT_FOUND if broker auth is disabled in the server config."),
+    ),
+)]
+
+#[post("/mqtt/auth/acl")]
+async fn mqtt_auth_acl_handler(body: MqttAuthBody<MqttAuthAclRequest>, state: web::Data<AppState>) -> impl Responder {
+    send_mqtt_auth_result(mqtt_auth::check_acl(&body.into_inner(), &state).await)
+}
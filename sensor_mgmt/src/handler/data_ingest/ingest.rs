@@ -1,7 +1,12 @@
-use crate::database::data_db::add_sensor_data;
 use crate::database::models::db_structs::DBOperation;
+use crate::database::models::sensor::FullSensorInfo;
+use crate::features::sensor_col_constraints::ConstraintViolations;
+use crate::features::sensor_data_batch::PendingIngest;
+use crate::features::sensor_data_dedup::assign_message_id;
 use crate::features::user_sens_perm::UserSensorPerm;
 use crate::features::{cache, sensor_data_transform};
+use crate::handler::data_ingest::format::{IngestFormat, MalformedIngestData};
+use crate::handler::models::requests::SensorDataIngestEntry;
 use crate::handler::policy;
 use crate::state::AppState;
 use crate::utils::AppError;
@@ -62,30 +67,49 @@ impl IngestStats {
 /* ------------------------------------------------ API ------------------------------------------------------------ */
 
 /// Insert data into the db for sensor_id using api_key for access control.
+/// The message id applies to all entries that don't provide their own.
 /// The returned boolean value indicates wether an entry has been produced
 pub async fn ingest_data_buisness_logic(
     sensor_id: uuid::Uuid,
     api_key: Option<uuid::Uuid>,
     data: bytes::Bytes,
+    format: IngestFormat,
+    message_id: Option<String>,
     state: &AppState,
 ) -> anyhow::Result<bool, AppError> {
     // TODO Set first msg if its not already set
 
-    // Retrieve key and check access
-    let api_key = match api_key {
-        Some(key) => cache::request_api_key(key, &state).await,
-        None => None,
-    };
-    let has_access = match api_key {
-        Some(key) => key.sensor_id == sensor_id && key.operation == DBOperation::WRITE,
-        None => policy::require_sensor_permission(None, sensor_id, UserSensorPerm::Write, &state)
-            .await
-            .is_none(),
-    };
-    if !has_access {
-        return Err(AppError::unauthorized_generic2()).into();
-    }
+    check_write_access(sensor_id, api_key, state).await?;
+
+    let ingested = ingest_authorized_data(sensor_id, data, format, message_id, state).await?;
 
+    Ok(ingested > 0)
+}
+
+/// Insert data into the db for sensor_id, the write access must have been checked before.
+/// Returns the number of inserted entries
+pub async fn ingest_authorized_data(
+    sensor_id: uuid::Uuid,
+    data: bytes::Bytes,
+    format: IngestFormat,
+    message_id: Option<String>,
+    state: &AppState,
+) -> anyhow::Result<usize, AppError> {
+    enqueue_authorized_data(sensor_id, data, format, message_id, state)
+        .await?
+        .inserted()
+        .await
+}
+
+/// Transforms the data of sensor_id and queues the entries for insertion, without waiting for the insert.
+/// The write access must have been checked before.
+pub async fn enqueue_authorized_data(
+    sensor_id: uuid::Uuid,
+    data: bytes::Bytes,
+    format: IngestFormat,
+    message_id: Option<String>,
+    state: &AppState,
+) -> anyhow::Result<QueuedIngest, AppError> {
     // Data sanity check
     if data.len() == 0 {
         return AppError::internal("missing data to insert".to_string());
@@ -99,24 +123,113 @@ pub async fn ingest_data_buisness_logic(
     let sensor = Arc::new(sensor_opt.unwrap());
 
     // Transform data into ingestable format
-    let tr_res = sensor_data_transform::transform(sensor.clone(), data, &state).await;
+    let mut data = transform_entries(sensor.clone(), data, &format, state).await?;
+
+    // If the vec is empty we dont need to bother with query creation
+    if data.len() == 0 {
+        return Ok(QueuedIngest(None));
+    }
+
+    if let Some(message_id) = message_id {
+        assign_message_id(&mut data, &message_id);
+    }
+
+    // queue data for the batched insert into the db
+    let pending = state.ingest_batcher.enqueue(sensor, data, state).await;
+
+    Ok(QueuedIngest(Some(pending)))
+}
+
+/// Data entries queued for insertion
+pub struct QueuedIngest(Option<PendingIngest>);
+
+impl QueuedIngest {
+    /// Waits until the entries have been inserted and returns their number.
+    pub async fn inserted(self) -> anyhow::Result<usize, AppError> {
+        match self.0 {
+            Some(pending) => match pending.result().await {
+                Ok(res) => Ok(res.rows),
+                Err(err) => insert_error(err),
+            },
+            None => Ok(0),
+        }
+    }
+}
+
+/// Checks if the api key permits writing data of the sensor.
+/// Without a key, the sensor must be writable by guests.
+pub async fn check_write_access(
+    sensor_id: uuid::Uuid,
+    api_key: Option<uuid::Uuid>,
+    state: &AppState,
+) -> anyhow::Result<(), AppError> {
+    // Retrieve key and check access
+    let api_key = match api_key {
+        Some(key) => cache::request_api_key(key, state).await,
+        None => None,
+    };
+    let has_access = match api_key {
+        Some(key) => key.sensor_id == sensor_id && key.operation == DBOperation::WRITE,
+        None => policy::require_sensor_permission(None, sensor_id, UserSensorPerm::Write, state)
+            .await
+            .is_none(),
+    };
+    if !has_access {
+        return Err(AppError::unauthorized_generic2());
+    }
+
+    Ok(())
+}
+
+/// Runs the data chain of the sensor on the payload and returns the resulting data entries.
+pub async fn transform_entries(
+    sensor: Arc<FullSensorInfo>,
+    data: bytes::Bytes,
+    format: &IngestFormat,
+    state: &AppState,
+) -> anyhow::Result<Vec<SensorDataIngestEntry>, AppError> {
+    let tr_res = sensor_data_transform::transform(sensor, data, format, state).await;
     if let Err(err) = tr_res {
+        if let Some(malformed) = err.downcast_ref::<MalformedIngestData>() {
+            return Err(AppError::validation(
+                "malformed data entries",
+                malformed.0.clone(),
+            ));
+        }
+
         return AppError::internal(format!("data transform failed with: {}", err));
     }
-    let data = tr_res.unwrap();
 
-    // If the vec is empty we dont need to bother with query creation
-    if data.len() == 0 {
-        return Ok(false);
+    Ok(tr_res.unwrap())
+}
+
+/// Inserts the data entries into the db, violated column constraints are reported as validation error.
+/// The entries are written in a batch with the other queued entries of the sensor.
+pub async fn insert_entries(
+    sensor: Arc<FullSensorInfo>,
+    data: &[SensorDataIngestEntry],
+    state: &AppState,
+) -> anyhow::Result<(), AppError> {
+    let res = state
+        .ingest_batcher
+        .submit(sensor, data.to_vec(), state)
+        .await;
+    if let Err(err) = res {
+        return insert_error(err);
     }
 
-    // insert data into db
-    let res = add_sensor_data(sensor, &data, state.clone()).await;
-    if res.is_err() {
-        return AppError::db(format!("{:?}", res));
+    Ok(())
+}
+
+fn insert_error<T>(err: anyhow::Error) -> anyhow::Result<T, AppError> {
+    if let Some(violations) = err.downcast_ref::<ConstraintViolations>() {
+        return Err(AppError::validation(
+            "data entries violate column constraints",
+            violations.0.clone(),
+        ));
     }
 
-    Ok(true)
+    AppError::db(format!("{:?}", err))
 }
 
 /* ------------------------------------------------ Tests ------------------------------------------------------------ */
@@ -125,6 +238,7 @@ pub async fn ingest_data_buisness_logic(
 pub mod tests {
     use super::*;
     use crate::database::models::db_structs::DBOrdering;
+    use crate::handler::data_ingest::coap::tests::coap_client_post;
     use crate::handler::data_ingest::mqtt::tests::mqtt_client_publish;
     use crate::handler::models::requests::{
         DataLoadRequestParams, SensorDataIngestEntry, TransportProto,
@@ -176,6 +290,10 @@ pub mod tests {
                 let _ =
                     mqtt_client_publish(sensor_id, api_key, payload, expected_status, state).await;
             }
+            TransportProto::COAP => {
+                coap_client_post(sensor_id, api_key, payload, expected_status, &state).await;
+            }
+            TransportProto::PULL => unreachable!("data can't be pushed to pull sources"),
         }
     }
 
@@ -401,13 +519,19 @@ pub mod tests {
             )
             .await;
 
-            // --- Ingest various invalid data to public sensor - Should succeed ---
+            // --- Ingest various invalid data to public sensor ---
 
             let mut req_payload = DataLoadRequestParams::default();
             req_payload.limit = Some(1);
             req_payload.ordering = Some(DBOrdering::DESC);
 
-            // --- Some columns invalid - should be inserted as NULLs
+            // MQTT has no response, rejected data is counted as failed ingest
+            let invalid_status = match proto {
+                TransportProto::MQTT => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::BAD_REQUEST,
+            };
+
+            // --- Some columns invalid - Should fail
 
             let payload = {
                 json!({
@@ -425,37 +549,13 @@ pub mod tests {
                 None,
                 Some(vec![SensorDataIngestEntry::from_json(payload, None)]),
                 None,
-                StatusCode::OK,
+                invalid_status,
                 &app,
                 state.clone(),
             )
             .await;
 
-            // Check result
-
-            let body = execute_request(
-                &format!("/api/sensors/{}/data/load", public_sensor.1),
-                Method::GET,
-                Some(req_payload.to_vector()),
-                None::<Value>,
-                None,
-                StatusCode::OK,
-                &app,
-            )
-            .await;
-
-            let expected_result = {
-                json!({
-                    "col1": null,
-                    "col2": null,
-                    "col3": "42",
-                })
-            };
-
-            let elm = body.as_array().unwrap().first().unwrap();
-            assert!(compare_payload(&expected_result, elm.as_object().unwrap()));
-
-            // --- All columns invalid - should be inserted as NULLs
+            // --- All columns invalid - Should fail
 
             let payload = {
                 json!({
@@ -473,36 +573,12 @@ pub mod tests {
                 None,
                 Some(vec![SensorDataIngestEntry::from_json(payload, None)]),
                 None,
-                StatusCode::OK,
+                invalid_status,
                 &app,
                 state.clone(),
             )
             .await;
 
-            // Check result
-
-            let body = execute_request(
-                &format!("/api/sensors/{}/data/load", public_sensor.1),
-                Method::GET,
-                Some(req_payload.to_vector()),
-                None::<Value>,
-                None,
-                StatusCode::OK,
-                &app,
-            )
-            .await;
-
-            let expected_result = {
-                json!({
-                    "col1": null,
-                    "col2": null,
-                    "col3": null,
-                })
-            };
-
-            let elm = body.as_array().unwrap().first().unwrap();
-            assert!(compare_payload(&expected_result, elm.as_object().unwrap()));
-
             // --- Some invalid col names - Only col2 should be inserted correctly, rest NULL
 
             let payload = {
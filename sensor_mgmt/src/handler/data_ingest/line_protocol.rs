@@ -0,0 +1,436 @@
+use crate::database::models::sensor::{ColumnType, FullSensorInfo};
+use crate::database::sensor_db;
+use crate::features::cache;
+use crate::features::config::TIMESTAMP_FORMAT;
+use crate::handler::data_ingest::format::{parse_timestamp, MalformedIngestData};
+use crate::handler::data_ingest::ingest::{check_write_access, insert_entries};
+use crate::handler::models::requests::{
+    LineProtocolIngestRequestParams, LineProtocolPrecision, SensorDataIngestEntry,
+};
+use crate::state::AppState;
+use crate::utils::AppError;
+use chrono::{DateTime, NaiveDateTime};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/*
+    InfluxDB line protocol, one point per line:
+
+    <measurement>[,<tag_key>=<tag_value>...] <field_key>=<field_value>[,<field_key>=<field_value>...] [<timestamp>]
+
+    Each point is mapped to a sensor by its measurement (or a tag) and the fields to the sensor columns.
+*/
+
+/// Value of a line protocol field
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Float(f64),
+    Int(i64),
+    UInt(u64),
+    Str(String),
+    Bool(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Point {
+    pub measurement: String,
+    pub tags: Vec<(String, String)>,
+    pub fields: Vec<(String, FieldValue)>,
+    pub timestamp: Option<NaiveDateTime>,
+}
+
+/* ------------------------------------------------ API ------------------------------------------------------------ */
+
+/// Insert the line protocol points into the db of the identified sensors using api_key for access control.
+/// All points are validated before anything is inserted.
+/// The returned boolean value indicates wether an entry has been produced
+pub async fn ingest_line_protocol_buisness_logic(
+    data: bytes::Bytes,
+    params: LineProtocolIngestRequestParams,
+    state: &AppState,
+) -> anyhow::Result<bool, AppError> {
+    let malformed = |errors: Vec<String>| AppError::validation("malformed data entries", errors);
+
+    // Data sanity check
+    if data.is_empty() {
+        return AppError::internal("missing data to insert".to_string());
+    }
+
+    let text = std::str::from_utf8(&data).map_err(|err| malformed(vec![err.to_string()]))?;
+
+    let points =
+        parse(text, params.precision.unwrap_or_default()).map_err(|err| malformed(err.0))?;
+
+    // Group the points by the sensor identifier, in order of appearance
+    let mut groups: Vec<(String, Vec<(usize, Point)>)> = Vec::new();
+
+    for (line, point) in points {
+        let ident = match &params.sensor_tag {
+            Some(tag) => match point.tags.iter().find(|(k, _)| k == tag) {
+                Some((_, v)) => v.clone(),
+                None => {
+                    return Err(malformed(vec![format!(
+                        "line {}: missing tag '{}'",
+                        line, tag
+                    )]));
+                }
+            },
+            None => point.measurement.clone(),
+        };
+
+        match groups.iter_mut().find(|(i, _)| *i == ident) {
+            Some((_, group)) => group.push((line, point)),
+            None => groups.push((ident, vec![(line, point)])),
+        }
+    }
+
+    // Resolve the sensors and check access before inserting anything
+    let mut batches = Vec::new();
+    let mut errors = Vec::new();
+
+    for (ident, group) in groups {
+        let sensor_id = match uuid::Uuid::parse_str(&ident) {
+            Ok(id) => Some(id),
+            Err(_) => sensor_db::get_sensor_id_by_name(&ident, state).await?,
+        };
+
+        let sensor = match sensor_id {
+            Some(id) => {
+                check_write_access(id, params.key, state).await?;
+                cache::request_sensor(id, state).await
+            }
+            None => None,
+        };
+
+        let Some(sensor) = sensor else {
+            let line = group[0].0;
+            errors.push(format!("line {}: unknown sensor '{}'", line, ident));
+            continue;
+        };
+
+        let mut entries = Vec::new();
+
+        for (line, point) in group {
+            match to_entry(&point, &sensor) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => errors.push(format!("line {}: {}", line, err)),
+            }
+        }
+
+        batches.push((Arc::new(sensor), entries));
+    }
+
+    if !errors.is_empty() {
+        return Err(malformed(errors));
+    }
+
+    // If there are no points we dont need to bother with query creation
+    if batches.is_empty() {
+        return Ok(false);
+    }
+
+    for (sensor, entries) in batches {
+        insert_entries(sensor, &entries, state).await?;
+    }
+
+    Ok(true)
+}
+
+/* ------------------------------------------------ Mapping ------------------------------------------------------------ */
+
+/// Maps the fields of the point to the sensor columns, fields without a matching column are ignored.
+fn to_entry(point: &Point, sensor: &FullSensorInfo) -> Result<SensorDataIngestEntry, String> {
+    let mut data = HashMap::new();
+
+    for (key, value) in point.fields.iter() {
+        let Some(col) = sensor.columns.iter().find(|c| c.name == *key) else {
+            continue;
+        };
+
+        match to_column_value(value, col.val_type) {
+            Some(v) => data.insert(col.name.clone(), v),
+            None => {
+                return Err(format!(
+                    "invalid {:?} value {:?} for column {}",
+                    col.val_type, value, col.name
+                ))
+            }
+        };
+    }
+
+    if data.is_empty() {
+        return Err(format!(
+            "no field matches a column of sensor {}",
+            sensor.name
+        ));
+    }
+
+    Ok(SensorDataIngestEntry {
+        timestamp: point.timestamp,
+        data,
+    })
+}
+
+/// Converts the field value into the JSON value expected for the column type.
+/// Floats are accepted for INT columns if they have no fractional part.
+fn to_column_value(value: &FieldValue, val_type: ColumnType) -> Option<Value> {
+    match (val_type, value) {
+        (ColumnType::INT, FieldValue::Int(v)) => i32::try_from(*v).ok().map(Value::from),
+        (ColumnType::INT, FieldValue::UInt(v)) => i32::try_from(*v).ok().map(Value::from),
+        (ColumnType::INT, FieldValue::Float(v)) if v.fract() == 0.0 => {
+            (*v >= i32::MIN as f64 && *v <= i32::MAX as f64).then(|| Value::from(*v as i32))
+        }
+        (ColumnType::FLOAT, FieldValue::Float(v)) => Some(Value::from(*v)),
+        (ColumnType::FLOAT, FieldValue::Int(v)) => Some(Value::from(*v as f64)),
+        (ColumnType::FLOAT, FieldValue::UInt(v)) => Some(Value::from(*v as f64)),
+        (ColumnType::BOOL, FieldValue::Bool(v)) => Some(Value::Bool(*v)),
+        (ColumnType::TIMESTAMP, FieldValue::Str(v)) => parse_timestamp(v, None)
+            .map(|ts| Value::String(ts.format(TIMESTAMP_FORMAT).to_string())),
+        (ColumnType::JSON, FieldValue::Str(v)) => {
+            Some(serde_json::from_str(v).unwrap_or_else(|_| Value::String(v.clone())))
+        }
+        (ColumnType::JSON, FieldValue::Float(v)) => Some(Value::from(*v)),
+        (ColumnType::JSON, FieldValue::Int(v)) => Some(Value::from(*v)),
+        (ColumnType::JSON, FieldValue::UInt(v)) => Some(Value::from(*v)),
+        (ColumnType::JSON, FieldValue::Bool(v)) => Some(Value::Bool(*v)),
+        (ColumnType::STRING, FieldValue::Str(v)) => Some(Value::String(v.clone())),
+        _ => None,
+    }
+}
+
+/* ------------------------------------------------ Parsing ------------------------------------------------------------ */
+
+/// Parses the line protocol points together with their line number.
+/// Empty lines and comments are skipped, all malformed lines are reported.
+pub fn parse(
+    text: &str,
+    precision: LineProtocolPrecision,
+) -> Result<Vec<(usize, Point)>, MalformedIngestData> {
+    let mut points = Vec::new();
+    let mut errors = Vec::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        let line_nr = idx + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match parse_line(line, precision) {
+            Ok(point) => points.push((line_nr, point)),
+            Err(err) => errors.push(format!("line {}: {}", line_nr, err)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(MalformedIngestData(errors));
+    }
+
+    Ok(points)
+}
+
+fn parse_line(line: &str, precision: LineProtocolPrecision) -> Result<Point, String> {
+    let sections = split_unescaped(line, ' ');
+
+    let (series, fields, timestamp) = match sections.as_slice() {
+        [series, fields] => (*series, *fields, None),
+        [series, fields, timestamp] => (*series, *fields, Some(*timestamp)),
+        _ => return Err("expected measurement, fields and optional timestamp".to_string()),
+    };
+
+    // Measurement and tags
+
+    let mut series = split_unescaped(series, ',').into_iter();
+
+    let measurement = unescape(series.next().unwrap_or_default());
+    if measurement.is_empty() {
+        return Err("missing measurement".to_string());
+    }
+
+    let tags = series
+        .map(|tag| {
+            let (key, value) = split_key_value(tag)?;
+            Ok((key, unescape(value)))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Fields
+
+    let fields = split_unescaped(fields, ',')
+        .into_iter()
+        .map(|field| {
+            let (key, value) = split_key_value(field)?;
+            let value = parse_field_value(value)
+                .ok_or_else(|| format!("invalid value '{}' of field {}", value, key))?;
+            Ok((key, value))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    // Timestamp
+
+    let timestamp = match timestamp {
+        Some(ts) => Some(
+            ts.parse::<i64>()
+                .ok()
+                .and_then(|ts| convert_timestamp(ts, precision))
+                .ok_or_else(|| format!("invalid timestamp '{}'", ts))?,
+        ),
+        None => None,
+    };
+
+    Ok(Point {
+        measurement,
+        tags,
+        fields,
+        timestamp,
+    })
+}
+
+fn split_key_value(pair: &str) -> Result<(String, &str), String> {
+    let mut kv = split_unescaped(pair, '=').into_iter();
+
+    match (kv.next(), kv.next(), kv.next()) {
+        (Some(key), Some(value), None) if !key.is_empty() && !value.is_empty() => {
+            Ok((unescape(key), value))
+        }
+        _ => Err(format!("invalid key-value pair '{}'", pair)),
+    }
+}
+
+fn parse_field_value(value: &str) -> Option<FieldValue> {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        return Some(FieldValue::Str(unescape(&value[1..value.len() - 1])));
+    }
+
+    if let Some(v) = value.strip_suffix('i') {
+        return v.parse().ok().map(FieldValue::Int);
+    }
+
+    if let Some(v) = value.strip_suffix('u') {
+        return v.parse().ok().map(FieldValue::UInt);
+    }
+
+    match value {
+        "t" | "T" | "true" | "True" | "TRUE" => Some(FieldValue::Bool(true)),
+        "f" | "F" | "false" | "False" | "FALSE" => Some(FieldValue::Bool(false)),
+        _ => value
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(FieldValue::Float),
+    }
+}
+
+fn convert_timestamp(ts: i64, precision: LineProtocolPrecision) -> Option<NaiveDateTime> {
+    let ts = match precision {
+        LineProtocolPrecision::NS => Some(DateTime::from_timestamp_nanos(ts)),
+        LineProtocolPrecision::US => DateTime::from_timestamp_micros(ts),
+        LineProtocolPrecision::MS => DateTime::from_timestamp_millis(ts),
+        LineProtocolPrecision::S => DateTime::from_timestamp(ts, 0),
+    };
+
+    ts.map(|ts| ts.naive_utc())
+}
+
+/// Splits at the delimiter, ignoring escaped delimiters and delimiters within string field values.
+fn split_unescaped(s: &str, delimiter: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    let mut quoted = false;
+
+    for (idx, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            _ if c == delimiter && !quoted => {
+                parts.push(&s[start..idx]);
+                start = idx + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&s[start..]);
+    parts
+}
+
+/// Removes the escaping backslashes of special characters.
+fn unescape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some(next @ (',' | '=' | ' ' | '"' | '\\'))) => {
+                res.push(*next);
+                chars.next();
+            }
+            _ => res.push(c),
+        }
+    }
+
+    res
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        let (_, point) = parse(
+            "my\\ sensor,site=a\\,b temp=21.5,count=3i,big=7u,ok=t,msg=\"hi \\\"there\\\", x=1\" 1739275200000000000",
+            LineProtocolPrecision::NS,
+        )
+        .unwrap()
+        .remove(0);
+
+        assert_eq!(point.measurement, "my sensor");
+        assert_eq!(point.tags, vec![("site".to_string(), "a,b".to_string())]);
+        assert_eq!(
+            point.fields,
+            vec![
+                ("temp".to_string(), FieldValue::Float(21.5)),
+                ("count".to_string(), FieldValue::Int(3)),
+                ("big".to_string(), FieldValue::UInt(7)),
+                ("ok".to_string(), FieldValue::Bool(true)),
+                (
+                    "msg".to_string(),
+                    FieldValue::Str("hi \"there\", x=1".to_string())
+                ),
+            ]
+        );
+        assert_eq!(
+            point.timestamp,
+            DateTime::from_timestamp(1739275200, 0).map(|ts| ts.naive_utc())
+        );
+    }
+
+    #[test]
+    fn test_parse_precision_and_errors() {
+        let points = parse(
+            "# comment\n\nm v=1 1739275200\nm v=2\n",
+            LineProtocolPrecision::S,
+        )
+        .unwrap();
+
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].0, 3);
+        assert_eq!(
+            points[0].1.timestamp,
+            DateTime::from_timestamp(1739275200, 0).map(|ts| ts.naive_utc())
+        );
+        assert_eq!(points[1].1.timestamp, None);
+
+        let err = parse("m\nm v=\nm v=1 abc\nm v=1x", LineProtocolPrecision::NS).unwrap_err();
+        assert_eq!(err.0.len(), 4);
+        assert!(err.0[0].starts_with("line 1:"));
+        assert!(err.0[3].starts_with("line 4:"));
+    }
+}
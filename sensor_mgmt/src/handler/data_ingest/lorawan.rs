@@ -0,0 +1,375 @@
+use crate::database::lorawan_db;
+use crate::features::cache;
+use crate::features::config::get_lorawan_webhook_token;
+use crate::handler::data_ingest::format::IngestFormat;
+use crate::handler::data_ingest::ingest::{insert_entries, transform_entries};
+use crate::handler::models::requests::{LoRaWANIngestRequestParams, LoRaWANNetworkServer};
+use crate::state::AppState;
+use crate::utils::AppError;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tracing::debug;
+
+/*
+    LoRaWAN uplinks
+
+    Network servers forward the uplinks of end devices as webhooks, authenticated by the
+    configured bearer token. The DevEUI of the end device is mapped to the receiving sensor.
+    The payload decoded by the network server is ingested as JSON object, otherwise the raw
+    frm_payload runs through the data chain of the sensor.
+    The time the uplink was received and the radio metadata of the best gateway are added
+    to the resulting data entries.
+*/
+
+/// Sensor columns receiving the radio metadata of the uplink, if present
+pub const RSSI_COLUMN: &str = "rssi";
+pub const SNR_COLUMN: &str = "snr";
+
+/// Uplink of an end device independent of the network server
+#[derive(Debug, Default, PartialEq)]
+pub struct Uplink {
+    /// Upper case hex DevEUI
+    pub dev_eui: String,
+    pub received_at: Option<NaiveDateTime>,
+    /// Payload decoded by the payload formatter / codec of the network server
+    pub decoded_payload: Option<Value>,
+    /// Raw application payload
+    pub frm_payload: Option<Vec<u8>>,
+    pub rssi: Option<f64>,
+    pub snr: Option<f64>,
+}
+
+/* ------------------------------------------------ The Things Stack ------------------------------------------------------------ */
+
+#[derive(Debug, Deserialize)]
+struct TtnMessage {
+    end_device_ids: TtnEndDeviceIds,
+    received_at: Option<String>,
+    uplink_message: Option<TtnUplinkMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TtnEndDeviceIds {
+    dev_eui: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TtnUplinkMessage {
+    frm_payload: Option<String>,
+    decoded_payload: Option<Value>,
+    #[serde(default)]
+    rx_metadata: Vec<RxMetadata>,
+    received_at: Option<String>,
+}
+
+/* ------------------------------------------------ ChirpStack ------------------------------------------------------------ */
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChirpStackUplinkEvent {
+    device_info: ChirpStackDeviceInfo,
+    time: Option<String>,
+    data: Option<String>,
+    object: Option<Value>,
+    #[serde(default)]
+    rx_info: Vec<RxMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChirpStackDeviceInfo {
+    dev_eui: String,
+}
+
+/// Reception of the uplink by a gateway, same fields for both network servers
+#[derive(Debug, Deserialize)]
+struct RxMetadata {
+    rssi: Option<f64>,
+    snr: Option<f64>,
+}
+
+/* ------------------------------------------------ Parsing ------------------------------------------------------------ */
+
+/// Parses the webhook body of the network server.
+/// Returns None for messages that aren't uplinks, e.g. join or status events.
+pub fn parse_uplink(
+    server: LoRaWANNetworkServer,
+    data: &[u8],
+    params: &LoRaWANIngestRequestParams,
+) -> Result<Option<Uplink>, String> {
+    match server {
+        LoRaWANNetworkServer::TTN => parse_ttn(data),
+        LoRaWANNetworkServer::CHIRPSTACK => {
+            // ChirpStack posts all events to the same url
+            if params.event.as_deref().is_some_and(|e| e != "up") {
+                return Ok(None);
+            }
+            parse_chirpstack(data)
+        }
+    }
+}
+
+fn parse_ttn(data: &[u8]) -> Result<Option<Uplink>, String> {
+    let msg = serde_json::from_slice::<TtnMessage>(data).map_err(|err| err.to_string())?;
+
+    let Some(uplink) = msg.uplink_message else {
+        return Ok(None);
+    };
+
+    let dev_eui = msg
+        .end_device_ids
+        .dev_eui
+        .ok_or("missing dev_eui of the end device")?;
+
+    let received_at = msg.received_at.or(uplink.received_at);
+    let (rssi, snr) = best_reception(&uplink.rx_metadata);
+
+    Ok(Some(Uplink {
+        dev_eui: normalize_dev_eui(&dev_eui)?,
+        received_at: received_at.as_deref().map(parse_time).transpose()?,
+        decoded_payload: uplink.decoded_payload,
+        frm_payload: uplink
+            .frm_payload
+            .as_deref()
+            .map(decode_payload)
+            .transpose()?,
+        rssi,
+        snr,
+    }))
+}
+
+fn parse_chirpstack(data: &[u8]) -> Result<Option<Uplink>, String> {
+    let event =
+        serde_json::from_slice::<ChirpStackUplinkEvent>(data).map_err(|err| err.to_string())?;
+
+    let (rssi, snr) = best_reception(&event.rx_info);
+
+    Ok(Some(Uplink {
+        dev_eui: normalize_dev_eui(&event.device_info.dev_eui)?,
+        received_at: event.time.as_deref().map(parse_time).transpose()?,
+        decoded_payload: event.object,
+        frm_payload: event.data.as_deref().map(decode_payload).transpose()?,
+        rssi,
+        snr,
+    }))
+}
+
+/// DevEUIs are stored as 16 upper case hex digits.
+pub fn normalize_dev_eui(dev_eui: &str) -> Result<String, String> {
+    let dev_eui = dev_eui.replace(['-', ':'], "").to_uppercase();
+
+    if dev_eui.len() != 16 || !dev_eui.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("invalid DevEUI '{}'", dev_eui));
+    }
+
+    Ok(dev_eui)
+}
+
+fn parse_time(time: &str) -> Result<NaiveDateTime, String> {
+    DateTime::parse_from_rfc3339(time)
+        .map(|t| t.naive_utc())
+        .map_err(|err| format!("invalid time '{}': {}", time, err))
+}
+
+fn decode_payload(payload: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(payload)
+        .map_err(|err| format!("invalid frm_payload: {}", err))
+}
+
+// Radio metadata of the gateway with the strongest signal
+fn best_reception(rx: &[RxMetadata]) -> (Option<f64>, Option<f64>) {
+    rx.iter()
+        .max_by(|a, b| {
+            let a = a.rssi.unwrap_or(f64::MIN);
+            let b = b.rssi.unwrap_or(f64::MIN);
+            a.total_cmp(&b)
+        })
+        .map_or((None, None), |m| (m.rssi, m.snr))
+}
+
+/* ------------------------------------------------ Ingest ------------------------------------------------------------ */
+
+/// Ingests the uplink of the webhook into the sensor mapped to the end device.
+/// The returned boolean value indicates wether an entry has been produced
+pub async fn ingest_lorawan_buisness_logic(
+    server: LoRaWANNetworkServer,
+    token: Option<&str>,
+    data: bytes::Bytes,
+    params: LoRaWANIngestRequestParams,
+    state: &AppState,
+) -> anyhow::Result<bool, AppError> {
+    // Authenticate the network server, compared in constant time to not leak the token
+    let permitted = match (get_lorawan_webhook_token(&state.cfg), token) {
+        (Some(expected), Some(token)) => bool::from(expected.as_bytes().ct_eq(token.as_bytes())),
+        _ => false,
+    };
+    if !permitted {
+        return Err(AppError::unauthorized_generic2());
+    }
+
+    let format = match &params.format {
+        Some(name) => IngestFormat::from_name(name).ok_or_else(|| {
+            AppError::validation("unsupported payload format", vec![name.clone()])
+        })?,
+        None => IngestFormat::JSON,
+    };
+
+    let uplink = parse_uplink(server, &data, &params)
+        .map_err(|err| AppError::validation("malformed uplink", vec![err]))?;
+
+    let Some(uplink) = uplink else {
+        debug!("ignoring {:?} message without uplink", server);
+        return Ok(false);
+    };
+
+    let sensor_id = lorawan_db::get_sensor_id(&uplink.dev_eui, &state.db)
+        .await?
+        .ok_or_else(|| AppError::not_found2(format!("unknown device {}", uplink.dev_eui)))?;
+
+    let sensor_opt = cache::request_sensor(sensor_id, state).await;
+    if sensor_opt.is_none() {
+        return AppError::internal(format!("could not find sensor with id: '{}'", sensor_id));
+    }
+    let sensor = Arc::new(sensor_opt.unwrap());
+
+    // The decoded payload is a single object with the values of the uplink
+    let (payload, format) = match (uplink.decoded_payload, uplink.frm_payload) {
+        (Some(decoded), _) => (
+            serde_json::to_vec(&Value::Array(vec![decoded]))
+                .or_else(|err| AppError::internal(err.to_string()))?,
+            IngestFormat::JSON,
+        ),
+        (None, Some(raw)) if !raw.is_empty() => (raw, format),
+        // e.g. uplinks only carrying MAC commands
+        _ => return Ok(false),
+    };
+
+    let mut entries = transform_entries(sensor.clone(), payload.into(), &format, state).await?;
+
+    let has_col = |name: &str| sensor.columns.iter().any(|c| c.name == name);
+
+    for entry in entries.iter_mut() {
+        if entry.timestamp.is_none() {
+            entry.timestamp = uplink.received_at;
+        }
+
+        let metadata = [(RSSI_COLUMN, uplink.rssi), (SNR_COLUMN, uplink.snr)];
+        for (col, value) in metadata {
+            if let (true, Some(value)) = (has_col(col), value) {
+                entry
+                    .data
+                    .entry(col.to_string())
+                    .or_insert(Value::from(value));
+            }
+        }
+    }
+
+    if entries.is_empty() {
+        return Ok(false);
+    }
+
+    insert_entries(sensor, &entries, state).await?;
+
+    Ok(true)
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_ttn_uplink() {
+        let body = json!({
+            "end_device_ids": {"device_id": "dev1", "dev_eui": "70b3d57ed005a1b2"},
+            "received_at": "2025-02-11T08:27:17.123456789Z",
+            "uplink_message": {
+                "f_port": 1,
+                "frm_payload": "AQI=",
+                "decoded_payload": {"col1": 42},
+                "rx_metadata": [{"rssi": -110, "snr": -3.5}, {"rssi": -42, "snr": 9.25}]
+            }
+        });
+
+        let uplink = parse_uplink(
+            LoRaWANNetworkServer::TTN,
+            &serde_json::to_vec(&body).unwrap(),
+            &LoRaWANIngestRequestParams::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(uplink.dev_eui, "70B3D57ED005A1B2");
+        assert_eq!(
+            uplink.received_at.unwrap().to_string(),
+            "2025-02-11 08:27:17.123456789"
+        );
+        assert_eq!(uplink.decoded_payload, Some(json!({"col1": 42})));
+        assert_eq!(uplink.frm_payload, Some(vec![1, 2]));
+        assert_eq!((uplink.rssi, uplink.snr), (Some(-42.0), Some(9.25)));
+
+        // Other messages like join accepts are ignored
+        let body = json!({"end_device_ids": {"dev_eui": "70B3D57ED005A1B2"}, "join_accept": {}});
+        let res = parse_uplink(
+            LoRaWANNetworkServer::TTN,
+            &serde_json::to_vec(&body).unwrap(),
+            &LoRaWANIngestRequestParams::default(),
+        );
+        assert_eq!(res, Ok(None));
+    }
+
+    #[test]
+    fn test_parse_chirpstack_uplink() {
+        let body = json!({
+            "deduplicationId": "3ac7e3c4-4401-4b8d-9386-a5c902f9202d",
+            "time": "2025-02-11T09:27:17+01:00",
+            "deviceInfo": {"deviceName": "dev1", "devEui": "0101010101010101"},
+            "fPort": 1,
+            "data": "eyJjb2wxIjo0Mn0=",
+            "rxInfo": [{"gatewayId": "0016c001f153a14c", "rssi": -60, "snr": 10.5}]
+        });
+        let data = serde_json::to_vec(&body).unwrap();
+
+        let params = LoRaWANIngestRequestParams {
+            event: Some("up".to_string()),
+            ..Default::default()
+        };
+        let uplink = parse_uplink(LoRaWANNetworkServer::CHIRPSTACK, &data, &params)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(uplink.dev_eui, "0101010101010101");
+        assert_eq!(
+            uplink.received_at.unwrap().to_string(),
+            "2025-02-11 08:27:17"
+        );
+        assert_eq!(uplink.decoded_payload, None);
+        assert_eq!(uplink.frm_payload, Some(b"{\"col1\":42}".to_vec()));
+        assert_eq!((uplink.rssi, uplink.snr), (Some(-60.0), Some(10.5)));
+
+        // Other events are ignored
+        let params = LoRaWANIngestRequestParams {
+            event: Some("join".to_string()),
+            ..Default::default()
+        };
+        let res = parse_uplink(LoRaWANNetworkServer::CHIRPSTACK, &data, &params);
+        assert_eq!(res, Ok(None));
+
+        // Invalid DevEUI
+        let body = json!({"deviceInfo": {"devEui": "0101"}});
+        let res = parse_uplink(
+            LoRaWANNetworkServer::CHIRPSTACK,
+            &serde_json::to_vec(&body).unwrap(),
+            &LoRaWANIngestRequestParams::default(),
+        );
+        assert!(res.is_err());
+    }
+}
@@ -1,3 +1,9 @@
+pub mod coap;
+pub mod format;
 pub mod http;
 pub mod ingest;
+pub mod line_protocol;
+pub mod lorawan;
 pub mod mqtt;
+pub mod mqtt_auth;
+pub mod senml;
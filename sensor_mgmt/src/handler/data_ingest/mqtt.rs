@@ -1,13 +1,23 @@
 use crate::database::models::events::LogEvent;
-use crate::features::config::as_compose_service;
-use crate::handler::data_ingest::ingest::ingest_data_buisness_logic;
+use crate::features::config::{
+    get_ingest_queue_capacity, get_mqtt_credentials, get_mqtt_host, get_mqtt_port, get_mqtt_tls,
+    get_mqtt_transport, get_mqtt_ws_path, is_mqtt_ack_topics, is_mqtt_broker_auth,
+    resolve_config_path, MqttTransport, ServerConfig,
+};
+use crate::features::sensor_data_dedup::MESSAGE_ID_FIELD;
+use crate::handler::data_ingest::format::IngestFormat;
+use crate::handler::data_ingest::ingest::{check_write_access, enqueue_authorized_data};
 use crate::handler::models::requests::TransportProto;
 use crate::handler::models::telelmetry::OTelData;
 use crate::state::AppState;
 use crate::utils::AppError;
 use actix_http::StatusCode;
 use actix_web::ResponseError;
-use rumqttc::{AsyncClient, Event, Incoming, MqttOptions, QoS};
+use rumqttc::v5::mqttbytes::v5::{Filter, Publish, PublishProperties};
+use rumqttc::v5::mqttbytes::QoS;
+use rumqttc::v5::{AsyncClient, Event, Incoming, MqttOptions};
+use rumqttc::TlsConfiguration;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::{Arc, RwLock};
@@ -41,19 +51,30 @@ pub struct MQTT {
 
 /// Starts a tokio task that runs the mqtt subscriber. If the subscriber fails the error is logged and the subscriber gets restarted.
 pub fn mqtt_service_init(state: AppState) -> MQTT {
-    // By default we use a WebSocket connection
-    let t = rumqttc::Transport::Ws;
-
     // Create stats which will be shared
     let share = Stats::new();
 
+    #[cfg(not(test))]
+    let client_id = "sensbee-sub";
+    #[cfg(test)]
+    let client_id = "sensbee-sub".to_owned() + state.db.connect_options().get_database().unwrap();
+
+    // An invalid broker config can't be fixed by restarting the subscriber
+    let opt = match mqtt_config(client_id, &state.cfg) {
+        Ok(opt) => opt,
+        Err(err) => {
+            error!("[MQTT] invalid broker config, subscriber not started: '{err:?}'");
+            return MQTT { stats: share };
+        }
+    };
+
     // Spawn the actual task
     let s = share.clone();
     tokio::spawn(async move {
         debug!("[MQTT] subscriber starting.");
 
         loop {
-            match start_mqtt_subscriber(state.clone(), t.clone(), s.clone()).await {
+            match start_mqtt_subscriber(state.clone(), opt.clone(), s.clone()).await {
                 Err(error) => {
                     error!("[MQTT] subscriber failed with: '{error:?}'. Restarting...");
                 }
@@ -79,6 +100,8 @@ pub struct MQTTServiceStats {
     packets_recv: u64,
     // Counts how many times packet parsing has failed
     err_parse: u64,
+    // Counts acknowledgements dropped because the outgoing queue of the client was full
+    acks_dropped: u64,
 
     per_sensor: HashMap<uuid::Uuid, MQTTSensorIngestStats>,
 }
@@ -142,6 +165,12 @@ impl Stats {
         s.err_parse += 1;
     }
 
+    pub fn incr_acks_dropped(&self) {
+        let mut s = self.0.write().unwrap();
+
+        s.acks_dropped += 1;
+    }
+
     // Per sensor stats
     // NOTE we guarantee that this is always called the first time with any new sensor_id which
     // is why the later functions can safely call unwrap on the entry
@@ -181,41 +210,94 @@ impl Stats {
 // This should be the same prefix as with the HTTP handler to ensure that event generation create the same path
 pub const TOPIC_PREFIX: &str = "/api/sensors/";
 
-pub fn mqtt_config<S: Into<String>>(id: S, transport: rumqttc::Transport) -> MqttOptions {
-    let host = as_compose_service("mosquitto");
-
-    match transport {
-        rumqttc::Transport::Tcp => {
-            let mut opt = MqttOptions::new(id, host, 1883);
-            opt.set_keep_alive(Duration::from_secs(5));
-
-            return opt;
-        }
-        rumqttc::Transport::Tls(_tls_configuration) => unimplemented!(),
-        #[cfg(unix)]
-        rumqttc::Transport::Unix => unimplemented!(),
-        rumqttc::Transport::Ws => {
-            let port = 9001;
-            // Host must be a bit special due to:
-            // https://github.com/bytebeamio/rumqtt/issues/808
-            let mut opt = MqttOptions::new(id, format!("ws://{host}:{port}/mqtt"), port);
+/// Suffix of the topics acknowledging messages without response topic
+pub const ACK_TOPIC_SUFFIX: &str = "/ack";
+
+/// Builds the connection options to the broker configured in the server config.
+pub fn mqtt_config<S: Into<String>>(id: S, cfg: &ServerConfig) -> anyhow::Result<MqttOptions> {
+    let host = get_mqtt_host(cfg);
+    let port = get_mqtt_port(cfg);
+
+    // Host of websocket connections must be a bit special due to:
+    // https://github.com/bytebeamio/rumqtt/issues/808
+    let mut opt = match get_mqtt_transport(cfg) {
+        MqttTransport::Tcp => MqttOptions::new(id, host, port),
+        MqttTransport::Ws => {
+            let mut opt = MqttOptions::new(
+                id,
+                format!("ws://{host}:{port}{}", get_mqtt_ws_path(cfg)),
+                port,
+            );
             opt.set_transport(rumqttc::Transport::Ws);
-            opt.set_keep_alive(Duration::from_secs(5));
-
-            return opt;
+            opt
+        }
+        MqttTransport::Tls => {
+            let mut opt = MqttOptions::new(id, host, port);
+            opt.set_transport(rumqttc::Transport::Tls(tls_config(cfg)?));
+            opt
         }
-        rumqttc::Transport::Wss(_tls_configuration) => unimplemented!(),
+        MqttTransport::Wss => {
+            let mut opt = MqttOptions::new(
+                id,
+                format!("wss://{host}:{port}{}", get_mqtt_ws_path(cfg)),
+                port,
+            );
+            opt.set_transport(rumqttc::Transport::Wss(tls_config(cfg)?));
+            opt
+        }
+    };
+    opt.set_keep_alive(Duration::from_secs(5));
+
+    if let Some((username, password)) = get_mqtt_credentials(cfg) {
+        opt.set_credentials(username, password);
     }
+
+    Ok(opt)
+}
+
+// Loads the CA bundle and the optional client certificate of the tls and wss transports
+fn tls_config(cfg: &ServerConfig) -> anyhow::Result<TlsConfiguration> {
+    let Some(tls) = get_mqtt_tls(cfg) else {
+        anyhow::bail!("the tls and wss transports require the 'mqtt.tls' options");
+    };
+
+    let read = |path: &str| {
+        let path = resolve_config_path(path);
+        std::fs::read(&path).map_err(|err| anyhow::anyhow!("failed to read '{}': {}", path, err))
+    };
+
+    let client_auth = match (&tls.client_cert_file, &tls.client_key_file) {
+        (Some(cert), Some(key)) => Some((read(cert)?, read(key)?)),
+        (None, None) => None,
+        _ => anyhow::bail!("client certificate and key must be set together"),
+    };
+
+    let alpn = tls
+        .alpn
+        .map(|protocols| protocols.into_iter().map(String::into_bytes).collect());
+
+    Ok(TlsConfiguration::Simple {
+        ca: read(&tls.ca_file)?,
+        alpn,
+        client_auth,
+    })
 }
 
 #[derive(Debug)]
-struct KeyPair {
-    sensor_id: uuid::Uuid,
-    api_key: Option<uuid::Uuid>,
+pub(crate) struct KeyPair {
+    pub sensor_id: uuid::Uuid,
+    pub api_key: Option<uuid::Uuid>,
+    pub format: IngestFormat,
 }
 
-// takes 'TOPIC_PREFIX<sensor_id>/<api_key>' and returns (Sensor_id::Uuid, Option<api_key::Uuid>)
-fn split_topic(t: String) -> Result<KeyPair, AppError> {
+// Payload formats selectable by the topic level following the api key.
+// Other levels are ignored (like other suffixes) and the payload is treated as JSON.
+fn parse_topic_format(f: &str) -> IngestFormat {
+    IngestFormat::from_name(f).unwrap_or_default()
+}
+
+// takes 'TOPIC_PREFIX<sensor_id>/<api_key>[/<format>]' and returns (Sensor_id::Uuid, Option<api_key::Uuid>, IngestFormat)
+pub(crate) fn split_topic(t: String) -> Result<KeyPair, AppError> {
     let p: Vec<&str> = t.split(TOPIC_PREFIX).collect();
     if p.len() != 2 {
         return AppError::internal(format!(
@@ -237,6 +319,7 @@ fn split_topic(t: String) -> Result<KeyPair, AppError> {
             Ok(KeyPair {
                 sensor_id: sensor_id.unwrap(),
                 api_key: None,
+                format: IngestFormat::JSON,
             })
         }
         _ => {
@@ -247,11 +330,11 @@ fn split_topic(t: String) -> Result<KeyPair, AppError> {
                     &e[0], sensor_id
                 ));
             }
-            let key_len = e[1].len();
-            let maybe_api_key: Option<Uuid> = match key_len {
-                0 if key_len < 36 => None,
-                _ => {
-                    let api_key = Uuid::parse_str(&e[1][..36]);
+            // The key segment is client controlled, it must be empty or exactly a uuid
+            let maybe_api_key: Option<Uuid> = match e[1].is_empty() {
+                true => None,
+                false => {
+                    let api_key = Uuid::parse_str(e[1]);
                     if api_key.is_err() {
                         return AppError::internal(format!(
                             "failed to parse '{}' as uuid: {:?}",
@@ -262,9 +345,14 @@ fn split_topic(t: String) -> Result<KeyPair, AppError> {
                 }
             };
 
+            let format = e
+                .get(2)
+                .map_or(IngestFormat::JSON, |f| parse_topic_format(f));
+
             Ok(KeyPair {
                 sensor_id: sensor_id.unwrap(),
                 api_key: maybe_api_key,
+                format,
             })
         }
     }
@@ -273,34 +361,30 @@ fn split_topic(t: String) -> Result<KeyPair, AppError> {
 /* ------------------------------------------------ MQTT Subscriber ------------------------------------------------------------ */
 
 ///
-/// Starts a WebSocket connection to a MQTT broker.
+/// Starts a connection to the MQTT broker configured in the server config.
 /// It subscribes on all topics with the prefix TOPIC_PREFIX.
-/// Every incoming message must be to a topic in the form of 'TOPIC_PREFIX<sensor_id>/[<api_key>]'
+/// Every incoming message must be to a topic in the form of 'TOPIC_PREFIX<sensor_id>/[<api_key>][/<format>]'
 ///
 /// NOTE AppState is required because we need to call the common ingest buisness logic function!
 async fn start_mqtt_subscriber(
     state: AppState,
-    transport: rumqttc::Transport,
+    opt: MqttOptions,
     stats: Stats,
 ) -> Result<(), AppError> {
     let topic = format!("{}#", TOPIC_PREFIX);
     let mut reconnect_delay = 1;
 
-    #[cfg(not(test))]
-    let client_id = "sensbee-sub";
-    #[cfg(test)]
-    let client_id = "sensbee-sub".to_owned() + state.db.connect_options().get_database().unwrap();
-
-    let opt = mqtt_config(client_id, transport);
-
     debug!("[MQTT] opts: {:?}", opt);
 
-    let (client, mut eventloop) = AsyncClient::new(opt, 10);
+    // Acknowledgements of all queued ingests must fit into the outgoing queue
+    let (client, mut eventloop) = AsyncClient::new(opt, get_ingest_queue_capacity(&state.cfg));
 
     debug!("[MQTT] subscribe on '{}'", topic.clone());
 
-    // Subscribe to all topics
-    client.subscribe(topic, QoS::AtMostOnce).await?;
+    // Subscribe to all topics, without receiving our own acknowledgements
+    let mut filter = Filter::new(topic, QoS::AtMostOnce);
+    filter.nolocal = true;
+    client.subscribe_many([filter]).await?;
 
     info!("[MQTT] starting eventloop polling");
 
@@ -311,22 +395,38 @@ async fn start_mqtt_subscriber(
             Ok(Event::Incoming(Incoming::Publish(p))) => {
                 let start = Instant::now();
 
-                info_span!("MQTT ingest handler", p.topic);
+                let topic = String::from_utf8_lossy(&p.topic).into_owned();
+
+                // Acknowledgements are published below the prefix as well
+                if topic.ends_with(ACK_TOPIC_SUFFIX) {
+                    continue;
+                }
+
+                info_span!("MQTT ingest handler", topic);
 
                 stats.incr_recv();
 
                 // Parse sensor_id and api_key.id
-                let keys_res = split_topic(p.topic.clone());
+                let keys_res = split_topic(topic.clone());
                 if let Err(err) = keys_res {
                     error!(
                         "[MQTT] failed to parse uuids: '{}'\ntopic: '{:?}'",
-                        err, p.topic
+                        err, topic
                     );
                     stats.incr_err_parse();
+                    send_ack(
+                        &client,
+                        &p,
+                        &topic,
+                        None,
+                        IngestAck::error(&err),
+                        &state,
+                        &stats,
+                    );
                     log_event(
                         start.elapsed(),
                         state.clone(),
-                        &p.topic,
+                        &topic,
                         Some(err.to_string()),
                         false,
                         None,
@@ -338,65 +438,95 @@ async fn start_mqtt_subscriber(
                 // NOTE this guarantees that all later per sensor calls have an entry to work with
                 stats.incr_sensor_recv(keys.sensor_id);
 
-                #[cfg(test)]
-                let p_copy = p.payload.clone();
-
                 // DESIGN NOTE
                 /*
-                    .await couples incoming requests and buisness logic processing speed together
-
-                    If we would spawn tokio tasks we could create unmanageable backpressure when load gets higher
+                    .await on queueing couples incoming requests and the ingest speed together,
+                    the eventloop stalls while the ingest queue of the sensor is full.
+                    This also keeps the order of the messages of a sensor.
 
-                    We could think about a queueing system where we create vectors of data that get ingested.
+                    Waiting for the insert happens in a separate task, so that the messages
+                    recieved in the meantime are written in the same batch.
                 */
                 // Call ingest data buisness logic
-                let db_res = ingest_data_buisness_logic(
-                    keys.sensor_id,
-                    keys.api_key,
-                    p.payload.clone(),
-                    &state,
-                )
-                .await;
-                if let Err(err) = db_res {
-                    if err.status_code() == StatusCode::UNAUTHORIZED {
-                        stats.incr_sensor_err_ingest_auth(keys.sensor_id);
-                    } else {
-                        stats.incr_sensor_err_ingest(keys.sensor_id);
+                let access = match keys.api_key {
+                    // The broker only accepts messages of publishers permitted by the broker auth,
+                    // which checks the key of their login with each topic access
+                    None if is_mqtt_broker_auth(&state.cfg) => Ok(()),
+                    _ => check_write_access(keys.sensor_id, keys.api_key, &state).await,
+                };
+                let queued = match access {
+                    Ok(_) => {
+                        enqueue_authorized_data(
+                            keys.sensor_id,
+                            p.payload.clone(),
+                            keys.format.clone(),
+                            message_id(&p),
+                            &state,
+                        )
+                        .await
                     }
+                    Err(err) => Err(err),
+                };
+                if queued.is_ok() {
+                    reconnect_delay = 1;
+                }
 
-                    //#[cfg(not(test))]
-                    error!("[MQTT] failed to ingest into db: '{}' ({keys:?})", err);
-
-                    // we dont store the payload here because it might be invalid json
-                    log_event(
-                        start.elapsed(),
-                        state.clone(),
-                        &p.topic,
-                        Some(err.to_string()),
-                        false,
-                        None,
+                let client = client.clone();
+                let stats = stats.clone();
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let db_res = match queued {
+                        Ok(queued) => queued.inserted().await,
+                        Err(err) => Err(err),
+                    };
+                    send_ack(
+                        &client,
+                        &p,
+                        &topic,
+                        Some(keys.sensor_id),
+                        IngestAck::new(&db_res),
+                        &state,
+                        &stats,
                     );
+                    if let Err(err) = db_res {
+                        if err.status_code() == StatusCode::UNAUTHORIZED {
+                            stats.incr_sensor_err_ingest_auth(keys.sensor_id);
+                        } else {
+                            stats.incr_sensor_err_ingest(keys.sensor_id);
+                        }
 
-                    continue;
-                }
-                let ingested = db_res.unwrap();
+                        //#[cfg(not(test))]
+                        error!("[MQTT] failed to ingest into db: '{}' ({keys:?})", err);
+
+                        // we dont store the payload here because it might be invalid json
+                        log_event(
+                            start.elapsed(),
+                            state,
+                            &topic,
+                            Some(err.to_string()),
+                            false,
+                            None,
+                        );
 
-                #[cfg(test)]
-                debug!("[MQTT] ✅ '{}' <- '{:?}'", p.topic, p_copy);
+                        return;
+                    }
+                    let ingested = db_res.unwrap() > 0;
 
-                reconnect_delay = 1;
+                    #[cfg(test)]
+                    debug!("[MQTT] ✅ '{}' <- '{:?}'", topic, p.payload);
 
-                stats.incr_sensor_db_succ(keys.sensor_id);
+                    stats.incr_sensor_db_succ(keys.sensor_id);
 
-                // we append the payload here because it was valid json
-                log_event(
-                    start.elapsed(),
-                    state.clone(),
-                    &p.topic,
-                    None,
-                    ingested,
-                    Some(String::from_utf8(p.payload.clone().to_vec()).unwrap()),
-                );
+                    // we append the payload here because it was valid (binary formats are logged lossy)
+                    log_event(
+                        start.elapsed(),
+                        state,
+                        &topic,
+                        None,
+                        ingested,
+                        Some(String::from_utf8_lossy(&p.payload).into_owned()),
+                    );
+                });
             }
             // Ignore all other types of packets
             Ok(Event::Incoming(_i)) => {
@@ -432,6 +562,123 @@ async fn start_mqtt_subscriber(
     // Unreachable
 }
 
+/// Result of an ingested message, published to the response topic of the message
+#[derive(Debug, Serialize, PartialEq)]
+pub struct IngestAck {
+    /// HTTP status code of the ingest
+    pub status: u16,
+    /// Number of inserted data entries
+    pub ingested: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl IngestAck {
+    fn new(res: &Result<usize, AppError>) -> Self {
+        match res {
+            Ok(0) => IngestAck {
+                status: StatusCode::NO_CONTENT.as_u16(),
+                ingested: 0,
+                error: None,
+            },
+            Ok(n) => IngestAck {
+                status: StatusCode::OK.as_u16(),
+                ingested: *n,
+                error: None,
+            },
+            Err(err) => IngestAck::error(err),
+        }
+    }
+
+    fn error(err: &AppError) -> Self {
+        IngestAck {
+            status: err.status_code().as_u16(),
+            ingested: 0,
+            error: Some(err.to_string()),
+        }
+    }
+}
+
+// MQTT 5 publishers may set the id of the message as user property
+fn message_id(p: &Publish) -> Option<String> {
+    p.properties.as_ref().and_then(|props| {
+        props
+            .user_properties
+            .iter()
+            .find(|(key, _)| key == MESSAGE_ID_FIELD)
+            .map(|(_, value)| value.clone())
+    })
+}
+
+// Returns the topic and properties of the acknowledgement, if one is requested.
+// MQTT 5 publishers set a response topic, others receive it on '<topic>/ack' if enabled.
+// Response topics are limited to the acknowledgement topics of the sensor, since SensBee may publish
+// on any topic of the broker, other response topics fall back to '<topic>/ack' as well.
+fn ack_target(
+    p: &Publish,
+    topic: &str,
+    sensor_id: Option<Uuid>,
+    ack_topics: bool,
+) -> Option<(String, Option<PublishProperties>)> {
+    let props = p.properties.as_ref();
+
+    let response_topic =
+        props
+            .and_then(|props| props.response_topic.clone())
+            .filter(|response_topic| {
+                sensor_id.is_some_and(|sensor_id| {
+                    response_topic.starts_with(&format!("{}{}/", TOPIC_PREFIX, sensor_id))
+                        && response_topic.ends_with(ACK_TOPIC_SUFFIX)
+                })
+            });
+
+    match response_topic {
+        Some(response_topic) => Some((
+            response_topic,
+            Some(PublishProperties {
+                correlation_data: props.and_then(|props| props.correlation_data.clone()),
+                content_type: Some("application/json".to_string()),
+                ..Default::default()
+            }),
+        )),
+        None if ack_topics => Some((
+            format!("{}{}", topic.trim_end_matches('/'), ACK_TOPIC_SUFFIX),
+            None,
+        )),
+        None => None,
+    }
+}
+
+// Acknowledgements are sent at most once, they are dropped if the outgoing queue of the client is full
+fn send_ack(
+    client: &AsyncClient,
+    p: &Publish,
+    topic: &str,
+    sensor_id: Option<Uuid>,
+    ack: IngestAck,
+    state: &AppState,
+    stats: &Stats,
+) {
+    let Some((ack_topic, props)) = ack_target(p, topic, sensor_id, is_mqtt_ack_topics(&state.cfg))
+    else {
+        return;
+    };
+
+    let payload = serde_json::to_vec(&ack).unwrap_or_default();
+
+    // Must not block, the eventloop sending the message is polled by the caller
+    let sent = match props {
+        Some(props) => {
+            client.try_publish_with_properties(ack_topic, QoS::AtLeastOnce, false, payload, props)
+        }
+        None => client.try_publish(ack_topic, QoS::AtLeastOnce, false, payload),
+    };
+    if let Err(err) = sent {
+        stats.incr_acks_dropped();
+        error!("[MQTT] dropped acknowledgement of '{}': {}", topic, err);
+    }
+}
+
 fn log_event(
     dur: Duration,
     state: AppState,
@@ -465,7 +712,9 @@ fn log_event(
 pub mod tests {
     use actix_http::StatusCode;
     use log::debug;
-    use rumqttc::{AsyncClient, QoS};
+    use rumqttc::v5::mqttbytes::QoS;
+    use rumqttc::v5::AsyncClient;
+    use rumqttc::TlsConfiguration;
     use serde::Serialize;
     use serde_json::json;
     use sqlx::PgPool;
@@ -474,12 +723,19 @@ pub mod tests {
     use uuid::Uuid;
 
     use crate::database::models::db_structs::DBOperation;
-    use crate::handler::data_ingest::mqtt::{mqtt_config, split_topic, TOPIC_PREFIX};
+    use crate::features::config::ServerConfig;
+    use crate::handler::data_ingest::format::IngestFormat;
+    use crate::handler::data_ingest::mqtt::{
+        ack_target, mqtt_config, split_topic, IngestAck, TOPIC_PREFIX,
+    };
+    use crate::handler::data_ingest::senml::SenmlEncoding;
     use crate::handler::models::requests::SensorDataIngestEntry;
     use crate::state::AppState;
     use crate::test_utils::tests::{
         create_test_api_keys, create_test_app, create_test_sensors, john,
     };
+    use crate::utils::AppError;
+    use rumqttc::v5::mqttbytes::v5::{Publish, PublishProperties};
 
     pub async fn mqtt_client_publish<T>(
         sensor_id: uuid::Uuid,
@@ -493,8 +749,9 @@ pub mod tests {
     {
         let opt = mqtt_config(
             "sensbee-pub-test-".to_owned() + state.db.connect_options().get_database().unwrap(),
-            rumqttc::Transport::Ws,
-        );
+            &state.cfg,
+        )
+        .unwrap();
 
         let (client, mut eventloop) = AsyncClient::new(opt, 10);
 
@@ -520,9 +777,9 @@ pub mod tests {
             .unwrap();
         while let Ok(notification) = eventloop.poll().await {
             match notification {
-                rumqttc::Event::Incoming(packet) => {
+                rumqttc::v5::Event::Incoming(packet) => {
                     match packet {
-                        rumqttc::Packet::PubAck(_pub_ack) => {
+                        rumqttc::v5::mqttbytes::v5::Packet::PubAck(_pub_ack) => {
                             // This confirms that the broker has recieved the packet.
                             // Now we must wait until it is recieved by our backend.
                             break;
@@ -715,11 +972,194 @@ pub mod tests {
         assert!(res.sensor_id == sensor_id);
         assert!(res.api_key.is_none());
 
-        // Make sure that arbitrary stuff as suffix is ignored
-        let topic_with_bad_suffix =
-            format!("{TOPIC_PREFIX}{sensor_id}/{api_key}smx/device/051001211/info");
-        let res = split_topic(topic_with_bad_suffix.to_string()).unwrap();
+        // Arbitrary segments after the key are ignored
+        let topic_with_suffix =
+            format!("{TOPIC_PREFIX}{sensor_id}/{api_key}/device/051001211/info");
+        let res = split_topic(topic_with_suffix.to_string()).unwrap();
         assert!(res.sensor_id == sensor_id);
         assert!(res.api_key == Some(api_key));
+        assert!(matches!(res.format, IngestFormat::JSON));
+
+        // Key segments that aren't exactly a uuid are rejected
+        for key in [
+            format!("{api_key}smx"),
+            "abc".to_string(),
+            "ä".repeat(36),
+            format!("{}ä", &api_key.to_string()[..35]),
+        ] {
+            let topic = format!("{TOPIC_PREFIX}{sensor_id}/{key}/device");
+            assert!(split_topic(topic).is_err(), "{}", key);
+        }
+
+        // Payload format after the (optional) key
+        let topic_with_format = format!("{TOPIC_PREFIX}{sensor_id}/{api_key}/senml+cbor");
+        let res = split_topic(topic_with_format.to_string()).unwrap();
+        assert!(res.api_key == Some(api_key));
+        assert!(matches!(
+            res.format,
+            IngestFormat::SENML(SenmlEncoding::CBOR)
+        ));
+
+        let topic_with_format = format!("{TOPIC_PREFIX}{sensor_id}//senml");
+        let res = split_topic(topic_with_format.to_string()).unwrap();
+        assert!(res.api_key.is_none());
+        assert!(matches!(
+            res.format,
+            IngestFormat::SENML(SenmlEncoding::JSON)
+        ));
+    }
+
+    #[test]
+    fn test_mqtt_config() {
+        let cfg = |yml: &str| serde_yml::from_str::<ServerConfig>(yml).unwrap();
+
+        // Default is a websocket connection to the compose service
+        let opt = mqtt_config("id", &cfg("server:\nauth:\n")).unwrap();
+        assert_eq!(
+            opt.broker_address(),
+            ("ws://localhost:9001/mqtt".to_string(), 9001)
+        );
+        assert!(matches!(opt.transport(), rumqttc::Transport::Ws));
+
+        let opt = mqtt_config("id", &cfg("mqtt:\n  transport: tcp\n  host: broker\n")).unwrap();
+        assert_eq!(opt.broker_address(), ("broker".to_string(), 1883));
+
+        // TLS transports require the CA bundle
+        let err = mqtt_config("id", &cfg("mqtt:\n  transport: tls\n")).unwrap_err();
+        assert!(err.to_string().contains("mqtt.tls"));
+
+        let dir = std::env::temp_dir().join(format!("sensbee-mqtt-tls-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for f in ["ca.pem", "client.pem", "client.key"] {
+            std::fs::write(dir.join(f), f).unwrap();
+        }
+        let path = |f: &str| dir.join(f).to_string_lossy().to_string();
+
+        let opt = mqtt_config(
+            "id",
+            &cfg(&format!(
+                "mqtt:\n  transport: tls\n  host: broker\n  tls:\n    ca_file: '{}'\n    client_cert_file: '{}'\n    client_key_file: '{}'\n    alpn: ['mqtt']\n",
+                path("ca.pem"),
+                path("client.pem"),
+                path("client.key")
+            )),
+        )
+        .unwrap();
+        assert_eq!(opt.broker_address(), ("broker".to_string(), 8883));
+        match opt.transport() {
+            rumqttc::Transport::Tls(TlsConfiguration::Simple {
+                ca,
+                alpn,
+                client_auth,
+            }) => {
+                assert_eq!(ca, b"ca.pem");
+                assert_eq!(alpn, Some(vec![b"mqtt".to_vec()]));
+                assert_eq!(
+                    client_auth,
+                    Some((b"client.pem".to_vec(), b"client.key".to_vec()))
+                );
+            }
+            _ => panic!("expected a tls transport"),
+        }
+
+        let opt = mqtt_config(
+            "id",
+            &cfg(&format!(
+                "mqtt:\n  transport: wss\n  host: broker\n  port: 443\n  ws_path: /ws\n  tls:\n    ca_file: '{}'\n",
+                path("ca.pem")
+            )),
+        )
+        .unwrap();
+        assert_eq!(
+            opt.broker_address(),
+            ("wss://broker:443/ws".to_string(), 443)
+        );
+        assert!(matches!(opt.transport(), rumqttc::Transport::Wss(_)));
+
+        // Client certificate without key and missing files are rejected
+        let err = mqtt_config(
+            "id",
+            &cfg(&format!(
+                "mqtt:\n  transport: tls\n  tls:\n    ca_file: '{}'\n    client_cert_file: '{}'\n",
+                path("ca.pem"),
+                path("client.pem")
+            )),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("together"));
+
+        let err = mqtt_config(
+            "id",
+            &cfg(&format!(
+                "mqtt:\n  transport: tls\n  tls:\n    ca_file: '{}'\n",
+                path("missing.pem")
+            )),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("missing.pem"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mqtt_ack() {
+        let sensor_id = Uuid::from_str("5401df3b-9367-4e8b-b2dd-6dba2b6cade6").unwrap();
+        let topic = format!("{TOPIC_PREFIX}{sensor_id}/");
+        let publish = |response_topic: &str| {
+            let props = PublishProperties {
+                response_topic: Some(response_topic.to_string()),
+                correlation_data: Some(bytes::Bytes::from_static(b"msg-1")),
+                ..Default::default()
+            };
+            Publish::new(topic.clone(), QoS::AtLeastOnce, "[]", Some(props))
+        };
+
+        // MQTT 5 publishers receive the ack on their response topic with the correlation data
+        let response_topic = format!("{TOPIC_PREFIX}{sensor_id}/client-1/ack");
+        let p = publish(&response_topic);
+        let (ack_topic, props) = ack_target(&p, &topic, Some(sensor_id), false).unwrap();
+        assert_eq!(ack_topic, response_topic);
+        assert_eq!(
+            props.unwrap().correlation_data,
+            Some(bytes::Bytes::from_static(b"msg-1"))
+        );
+
+        // Response topics outside the ack topics of the sensor are rejected
+        for response_topic in [
+            "client/responses".to_string(),
+            format!("{TOPIC_PREFIX}{sensor_id}/client-1"),
+            format!("{TOPIC_PREFIX}6401df3b-9367-4e8b-b2dd-6dba2b6cade6/ack"),
+        ] {
+            let p = publish(&response_topic);
+            assert!(ack_target(&p, &topic, Some(sensor_id), false).is_none());
+
+            let (ack_topic, props) = ack_target(&p, &topic, Some(sensor_id), true).unwrap();
+            assert_eq!(ack_topic, format!("{TOPIC_PREFIX}{sensor_id}/ack"));
+            assert!(props.is_none());
+        }
+
+        let p = publish(&response_topic);
+        assert!(ack_target(&p, &topic, None, false).is_none());
+
+        // Others only if the ack topics are enabled
+        let p = Publish::new(topic.clone(), QoS::AtLeastOnce, "[]", None);
+        assert!(ack_target(&p, &topic, Some(sensor_id), false).is_none());
+
+        let (ack_topic, props) = ack_target(&p, &topic, Some(sensor_id), true).unwrap();
+        assert_eq!(
+            ack_topic,
+            format!("{TOPIC_PREFIX}5401df3b-9367-4e8b-b2dd-6dba2b6cade6/ack")
+        );
+        assert!(props.is_none());
+
+        // Payload of the ack
+        let ack =
+            |res: Result<usize, AppError>| serde_json::to_value(IngestAck::new(&res)).unwrap();
+        assert_eq!(ack(Ok(3)), json!({"status": 200, "ingested": 3}));
+        assert_eq!(ack(Ok(0)), json!({"status": 204, "ingested": 0}));
+        assert_eq!(
+            ack(Err(AppError::unauthorized_generic2())),
+            json!({"status": 401, "ingested": 0, "error": AppError::unauthorized_generic2().to_string()})
+        );
     }
 }
@@ -0,0 +1,452 @@
+use crate::database::models::db_structs::DBOperation;
+use crate::database::sensor_db;
+use crate::features::cache;
+use crate::features::config::{get_mqtt_credentials, is_mqtt_broker_auth};
+use crate::handler::data_ingest::mqtt::{split_topic, ACK_TOPIC_SUFFIX};
+use crate::handler::models::requests::{
+    MqttAuthAclRequest, MqttAuthSuperuserRequest, MqttAuthUserRequest,
+};
+use crate::state::AppState;
+use crate::utils::AppError;
+use tracing::debug;
+use uuid::Uuid;
+
+/*
+    MQTT broker auth
+
+    Instead of putting the api key into the topic, publishers log into the broker with the
+    sensor id as username and a write api key of the sensor as password. The broker forwards
+    logins and topic accesses to these checks (e.g. the HTTP backend of mosquitto-go-auth).
+    Publishers may only write the topics of their sensor and read the acknowledgements on them,
+    the subscriber of SensBee logs in with the configured credentials and is granted all topics.
+    Since the broker rejects all other publishers, messages on topics without key are trusted.
+
+    The key of a login is remembered per client and checked again with each topic access,
+    so deleting the key also revokes the access of connected publishers. Logins handled by another
+    instance (or before a restart) are unknown, for them the sensor must still have any write api key.
+    The broker may cache the results of the checks, which delays the revocation by its cache duration.
+*/
+
+/// Access to a topic requested by a client
+const ACC_READ: u8 = 1;
+const ACC_WRITE: u8 = 2;
+const ACC_SUBSCRIBE: u8 = 4;
+
+/// Logins remembered for the checks of topic accesses, all are forgotten when exceeded
+const MAX_LOGINS: usize = 10000;
+
+/* ------------------------------------------------ API ------------------------------------------------------------ */
+
+/// Checks the login of a client, returns wether it is permitted.
+pub async fn check_user(req: &MqttAuthUserRequest, state: &AppState) -> Result<bool, AppError> {
+    require_broker_auth(state)?;
+
+    if is_subscriber(&req.username, Some(&req.password), state) {
+        return Ok(true);
+    }
+
+    let (Ok(sensor_id), Ok(key_id)) = (
+        Uuid::parse_str(&req.username),
+        Uuid::parse_str(&req.password),
+    ) else {
+        debug!("[MQTT AUTH] malformed credentials of '{}'", req.username);
+        return Ok(false);
+    };
+
+    let permitted = is_write_key(key_id, sensor_id, state).await;
+
+    // Failed logins don't replace the session of a connected client
+    if permitted {
+        let client = (req.username.clone(), req.clientid.clone());
+        let mut logins = state.cache.mqtt_logins.write().unwrap();
+
+        if logins.len() >= MAX_LOGINS && !logins.contains_key(&client) {
+            logins.clear();
+        }
+        logins.insert(client, key_id);
+    }
+
+    Ok(permitted)
+}
+
+/// Checks if the client is granted access to all topics.
+pub fn check_superuser(req: &MqttAuthSuperuserRequest, state: &AppState) -> Result<bool, AppError> {
+    require_broker_auth(state)?;
+
+    Ok(is_subscriber(&req.username, None, state))
+}
+
+/// Checks the access of an authenticated client to the topic.
+/// Publishers may only write topics of their sensor and read the acknowledgements of these topics,
+/// as long as the key of their login is not deleted.
+pub async fn check_acl(req: &MqttAuthAclRequest, state: &AppState) -> Result<bool, AppError> {
+    require_broker_auth(state)?;
+
+    if is_subscriber(&req.username, None, state) {
+        return Ok(true);
+    }
+
+    let Ok(sensor_id) = Uuid::parse_str(&req.username) else {
+        return Ok(false);
+    };
+
+    let (topic, is_ack) = match req.topic.strip_suffix(ACK_TOPIC_SUFFIX) {
+        Some(topic) => (topic, true),
+        None => (req.topic.as_str(), false),
+    };
+
+    let own_topic = match split_topic(topic.to_string()) {
+        Ok(keys) => keys.sensor_id == sensor_id,
+        Err(_) => false,
+    };
+
+    let permitted = match req.acc {
+        ACC_WRITE => own_topic && !is_ack,
+        ACC_READ | ACC_SUBSCRIBE => own_topic && is_ack,
+        _ => false,
+    };
+
+    if !permitted {
+        return Ok(false);
+    }
+
+    Ok(has_login_key(req, sensor_id, state).await)
+}
+
+/* ------------------------------------------------ Helper functions ------------------------------------------------------------ */
+
+fn require_broker_auth(state: &AppState) -> Result<(), AppError> {
+    match is_mqtt_broker_auth(&state.cfg) {
+        true => Ok(()),
+        false => Err(AppError::not_found2("MQTT broker auth is disabled")),
+    }
+}
+
+async fn is_write_key(key_id: Uuid, sensor_id: Uuid, state: &AppState) -> bool {
+    match cache::request_api_key(key_id, state).await {
+        Some(key) => key.sensor_id == sensor_id && key.operation == DBOperation::WRITE,
+        None => false,
+    }
+}
+
+// Checks that the key the client logged in with still exists
+async fn has_login_key(req: &MqttAuthAclRequest, sensor_id: Uuid, state: &AppState) -> bool {
+    let client = (req.username.clone(), req.clientid.clone());
+    let login_key = state
+        .cache
+        .mqtt_logins
+        .read()
+        .unwrap()
+        .get(&client)
+        .copied();
+
+    match login_key {
+        // The login is kept until the client logs in again, so it isn't checked as unknown client
+        Some(key_id) => is_write_key(key_id, sensor_id, state).await,
+        // Logged in at another instance or before a restart
+        None => sensor_db::has_api_key(sensor_id, DBOperation::WRITE, state)
+            .await
+            .unwrap_or(false),
+    }
+}
+
+// The password is only checked on login, later checks just identify the client
+fn is_subscriber(username: &str, password: Option<&str>, state: &AppState) -> bool {
+    match get_mqtt_credentials(&state.cfg) {
+        Some((u, p)) => u == username && password.is_none_or(|password| p == password),
+        None => false,
+    }
+}
+
+/* ------------------------------------------------ Tests ------------------------------------------------------------ */
+
+#[cfg(test)]
+mod tests {
+    use crate::database::models::db_structs::DBOperation;
+    use crate::database::sensor_db;
+    use crate::handler::models::requests::CreateApiKeyRequest;
+    use crate::test_utils::tests::{
+        create_test_api_keys, create_test_app, create_test_app_with_config, create_test_sensors,
+        john,
+    };
+    use actix_http::StatusCode;
+    use actix_web::test;
+    use serde_json::{json, Value};
+    use sqlx::PgPool;
+
+    #[sqlx::test(
+        migrations = "../migrations",
+        fixtures(
+            "../../handler/fixtures/users.sql",
+            "../../handler/fixtures/roles.sql",
+            "../../handler/fixtures/user_roles.sql"
+        )
+    )]
+    async fn test_broker_auth(pool: PgPool) {
+        // --- Disabled by default ---
+
+        let (app, _) = create_test_app(pool.clone()).await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/mqtt/auth/superuser")
+            .set_json(json!({"username": "sensbee"}))
+            .to_request();
+        assert_eq!(
+            test::call_service(&app, req).await.status(),
+            StatusCode::NOT_FOUND
+        );
+
+        let cfg = serde_yml::from_str(
+            "mqtt:\n  broker_auth: true\n  username: 'sensbee'\n  password: 'sub-secret'",
+        )
+        .unwrap();
+        let (app, state) = create_test_app_with_config(pool, cfg).await;
+
+        let test_sens = create_test_sensors(&state).await;
+        let test_keys = create_test_api_keys(&state).await;
+
+        let sensor_id = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor")
+            .unwrap()
+            .1;
+        let other_sensor_id = test_sens
+            .iter()
+            .find(|(name, _)| name == "MySensor2")
+            .unwrap()
+            .1;
+        let key = |operation: DBOperation| {
+            test_keys
+                .iter()
+                .find(|k| k.sensor_id == sensor_id && k.operation == operation)
+                .unwrap()
+                .id
+        };
+
+        // Returns the status of the check, sent as JSON or form body
+        let check = |path: &'static str, body: Value, form: bool| {
+            let app = &app;
+
+            async move {
+                let req = test::TestRequest::post().uri(&format!("/api/mqtt/auth/{}", path));
+                let req = match form {
+                    true => req.set_form(body),
+                    false => req.set_json(body),
+                };
+                test::call_service(app, req.to_request()).await.status()
+            }
+        };
+
+        // --- Login ---
+
+        let login = |username: String, password: String| json!({"username": username, "password": password, "clientid": "c1"});
+
+        assert_eq!(
+            check("user", login("sensbee".into(), "sub-secret".into()), false).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            check("user", login("sensbee".into(), "wrong".into()), false).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let write_key = key(DBOperation::WRITE).to_string();
+        assert_eq!(
+            check(
+                "user",
+                login(sensor_id.to_string(), write_key.clone()),
+                true
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            check(
+                "user",
+                login(sensor_id.to_string(), write_key.clone()),
+                false
+            )
+            .await,
+            StatusCode::OK
+        );
+        // Keys of other sensors, read keys and malformed credentials are rejected
+        assert_eq!(
+            check(
+                "user",
+                login(other_sensor_id.to_string(), write_key.clone()),
+                false
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            check(
+                "user",
+                login(sensor_id.to_string(), key(DBOperation::READ).to_string()),
+                false
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            check("user", login(sensor_id.to_string(), "no-key".into()), false).await,
+            StatusCode::FORBIDDEN
+        );
+
+        // --- Superuser ---
+
+        assert_eq!(
+            check("superuser", json!({"username": "sensbee"}), false).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            check("superuser", json!({"username": sensor_id}), true).await,
+            StatusCode::FORBIDDEN
+        );
+
+        // --- ACL ---
+
+        let acl = |username: String, topic: String, acc: u8| json!({"username": username, "topic": topic, "clientid": "c1", "acc": acc});
+
+        assert_eq!(
+            check(
+                "acl",
+                acl("sensbee".into(), "/api/sensors/#".into(), 4),
+                false
+            )
+            .await,
+            StatusCode::OK
+        );
+
+        let topic = format!("/api/sensors/{}", sensor_id);
+        assert_eq!(
+            check("acl", acl(sensor_id.to_string(), topic.clone(), 2), true).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            check(
+                "acl",
+                acl(sensor_id.to_string(), format!("{}//senml", topic), 2),
+                false
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            check(
+                "acl",
+                acl(sensor_id.to_string(), format!("{}/{}", topic, write_key), 2),
+                false
+            )
+            .await,
+            StatusCode::OK
+        );
+        // Publishers may only read the acknowledgements of their topics
+        assert_eq!(
+            check(
+                "acl",
+                acl(sensor_id.to_string(), format!("{}/ack", topic), 4),
+                false
+            )
+            .await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            check(
+                "acl",
+                acl(sensor_id.to_string(), format!("{}/ack", topic), 2),
+                false
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        // Publishers can't read or write topics of other sensors
+        assert_eq!(
+            check("acl", acl(sensor_id.to_string(), topic.clone(), 1), false).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            check("acl", acl(sensor_id.to_string(), topic.clone(), 4), false).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            check(
+                "acl",
+                acl(
+                    sensor_id.to_string(),
+                    format!("/api/sensors/{}", other_sensor_id),
+                    2
+                ),
+                false
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            check(
+                "acl",
+                acl(sensor_id.to_string(), "other/topic".into(), 2),
+                false
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        // Malformed key segments are rejected
+        for key in ["abc".to_string(), "ä".repeat(36)] {
+            assert_eq!(
+                check(
+                    "acl",
+                    acl(sensor_id.to_string(), format!("{}/{}", topic, key), 2),
+                    false
+                )
+                .await,
+                StatusCode::FORBIDDEN
+            );
+        }
+
+        // --- Deleted keys revoke the access of connected publishers ---
+
+        // Clients unknown to this instance are checked against the keys of the sensor
+        let acl_c2 = json!({"username": sensor_id, "topic": topic, "clientid": "c2", "acc": 2});
+        assert_eq!(check("acl", acl_c2.clone(), false).await, StatusCode::OK);
+
+        let other_key = sensor_db::create_api_key(
+            sensor_id,
+            john().id,
+            CreateApiKeyRequest {
+                name: "OtherKeyWrite".to_string(),
+                operation: DBOperation::WRITE,
+            },
+            &state,
+        )
+        .await
+        .unwrap();
+
+        // Deleting the key of the login denies further accesses, even if other write keys exist
+        let mut conn = state.db.acquire().await.unwrap();
+        sensor_db::delete_api_keys(vec![key(DBOperation::WRITE)], &mut conn, &state)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            check("acl", acl(sensor_id.to_string(), topic.clone(), 2), false).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            check(
+                "acl",
+                acl(sensor_id.to_string(), format!("{}/ack", topic), 4),
+                false
+            )
+            .await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(check("acl", acl_c2.clone(), false).await, StatusCode::OK);
+
+        sensor_db::delete_api_keys(vec![other_key.id], &mut conn, &state)
+            .await
+            .unwrap();
+
+        assert_eq!(check("acl", acl_c2, false).await, StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::database::models::sensor::{ColumnType, FullSensorInfo, SensorColumn};
+use crate::features::config::TIMESTAMP_FORMAT;
+use crate::handler::data_ingest::format::{parse_timestamp, MalformedIngestData};
+use crate::handler::models::requests::SensorDataIngestEntry;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/*
+    SenML (RFC 8428)
+
+    A pack is an array of records, base fields (bn, bt, bu, bv, bs) apply to the record they
+    appear in and all following records until they are overwritten.
+    The resolved name (bn + n) of a record is mapped to a sensor column, records with the
+    same resolved time are combined into one data entry.
+*/
+
+/// Encodings of SenML packs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SenmlEncoding {
+    JSON,
+    CBOR,
+}
+
+/// Times below 2**28 are relative to the current time (RFC 8428 section 4.5.3)
+const RELATIVE_TIME_THRESHOLD: f64 = 268_435_456.0;
+
+/// Highest SenML version understood by the decoder
+const SENML_VERSION: i64 = 10;
+
+/// Integer labels of the CBOR representation (RFC 8428 section 6)
+const CBOR_LABELS: [(i64, &str); 15] = [
+    (-1, "bver"),
+    (-2, "bn"),
+    (-3, "bt"),
+    (-4, "bu"),
+    (-5, "bv"),
+    (-6, "bs"),
+    (0, "n"),
+    (1, "u"),
+    (2, "v"),
+    (3, "vs"),
+    (4, "vb"),
+    (5, "s"),
+    (6, "t"),
+    (7, "ut"),
+    (8, "vd"),
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct SenmlRecord {
+    bn: Option<String>,
+    bt: Option<f64>,
+    bu: Option<String>,
+    bv: Option<f64>,
+    bs: Option<f64>,
+    bver: Option<i64>,
+    n: Option<String>,
+    u: Option<String>,
+    v: Option<f64>,
+    vs: Option<String>,
+    vb: Option<bool>,
+    vd: Option<String>,
+    s: Option<f64>,
+    t: Option<f64>,
+}
+
+/// Values of the current base fields
+#[derive(Debug, Default)]
+struct BaseFields {
+    name: String,
+    time: f64,
+    unit: Option<String>,
+    value: f64,
+    sum: f64,
+}
+
+/// Decodes the SenML pack into data entries for the sensor.
+/// Records without a matching column are ignored, all other invalid records are reported.
+pub fn decode(
+    sensor: &FullSensorInfo,
+    data: &[u8],
+    encoding: SenmlEncoding,
+) -> Result<Vec<SensorDataIngestEntry>, MalformedIngestData> {
+    let malformed = |msg: String| MalformedIngestData(vec![msg]);
+
+    let records = match encoding {
+        SenmlEncoding::JSON => serde_json::from_slice::<Vec<Map<String, Value>>>(data)
+            .map_err(|err| malformed(format!("Invalid SenML JSON pack: {}", err)))?,
+        SenmlEncoding::CBOR => cbor_pack_to_json(data).map_err(malformed)?,
+    };
+
+    // Relative times are resolved against the same point in time for the whole pack
+    let now = Utc::now();
+
+    let mut base = BaseFields::default();
+    let mut entries: Vec<SensorDataIngestEntry> = Vec::new();
+    // Position of the entry of each resolved time in the entries
+    let mut positions: HashMap<Option<NaiveDateTime>, usize> = HashMap::new();
+    let mut errors = Vec::new();
+    let mut matched = false;
+
+    for (idx, record) in records.into_iter().enumerate() {
+        let mut report = |msg: String| errors.push(format!("record {}: {}", idx, msg));
+
+        // Labels ending with '_' must be understood by the recipient
+        if let Some(label) = record.keys().find(|k| k.ends_with('_')) {
+            report(format!("unsupported label '{}'", label));
+            continue;
+        }
+
+        let record = match serde_json::from_value::<SenmlRecord>(Value::Object(record)) {
+            Ok(record) => record,
+            Err(err) => {
+                report(err.to_string());
+                continue;
+            }
+        };
+
+        if record.bver.is_some_and(|v| v > SENML_VERSION) {
+            report(format!(
+                "unsupported version {}",
+                record.bver.unwrap_or_default()
+            ));
+            continue;
+        }
+
+        if let Some(bn) = &record.bn {
+            base.name = bn.clone();
+        }
+        if let Some(bt) = record.bt {
+            base.time = bt;
+        }
+        if let Some(bu) = &record.bu {
+            base.unit = Some(bu.clone());
+        }
+        if let Some(bv) = record.bv {
+            base.value = bv;
+        }
+        if let Some(bs) = record.bs {
+            base.sum = bs;
+        }
+
+        let has_value = record.v.is_some()
+            || record.vs.is_some()
+            || record.vb.is_some()
+            || record.vd.is_some()
+            || record.s.is_some();
+
+        // Records only defining base fields
+        if record.n.is_none() && !has_value {
+            continue;
+        }
+
+        let name = format!("{}{}", base.name, record.n.as_deref().unwrap_or_default());
+
+        // The name is matched fully or, with a base name like a device id, by the record name
+        let Some(col) = sensor
+            .columns
+            .iter()
+            .find(|c| c.name == name || Some(c.name.as_str()) == record.n.as_deref())
+        else {
+            continue;
+        };
+
+        matched = true;
+
+        let unit = record.u.as_ref().or(base.unit.as_ref());
+        if let Some(unit) = unit {
+            if !col.val_unit.is_empty() && *unit != col.val_unit {
+                report(format!(
+                    "unit '{}' doesn't match unit '{}' of column {}",
+                    unit, col.val_unit, col.name
+                ));
+                continue;
+            }
+        }
+
+        let value = match to_column_value(&record, &base, col) {
+            Ok(value) => value,
+            Err(err) => {
+                report(err);
+                continue;
+            }
+        };
+
+        let timestamp = match resolve_time(base.time + record.t.unwrap_or_default(), now) {
+            Ok(ts) => ts,
+            Err(err) => {
+                report(err);
+                continue;
+            }
+        };
+
+        let pos = *positions.entry(timestamp).or_insert_with(|| {
+            entries.push(SensorDataIngestEntry {
+                timestamp,
+                data: HashMap::new(),
+            });
+            entries.len() - 1
+        });
+        let entry = &mut entries[pos];
+
+        if entry.data.insert(col.name.clone(), value).is_some() {
+            report(format!(
+                "duplicate value for column {} at the same time",
+                col.name
+            ));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(MalformedIngestData(errors));
+    }
+
+    if !matched {
+        return Err(malformed(
+            "SenML pack doesn't contain any record of a sensor column".to_string(),
+        ));
+    }
+
+    Ok(entries)
+}
+
+/// Returns the value of the record, resolved with the base value and sum, for the column type.
+fn to_column_value(
+    record: &SenmlRecord,
+    base: &BaseFields,
+    col: &SensorColumn,
+) -> Result<Value, String> {
+    let number = record
+        .v
+        .map(|v| base.value + v)
+        .or(record.s.map(|s| base.sum + s));
+
+    let invalid = || format!("invalid {:?} value for column {}", col.val_type, col.name);
+
+    let value = match col.val_type {
+        ColumnType::INT => number
+            .filter(|v| v.fract() == 0.0 && *v >= i32::MIN as f64 && *v <= i32::MAX as f64)
+            .map(|v| Value::from(v as i32)),
+        ColumnType::FLOAT => number.map(Value::from),
+        ColumnType::BOOL => record.vb.map(Value::Bool),
+        ColumnType::TIMESTAMP => record
+            .vs
+            .as_deref()
+            .and_then(|v| parse_timestamp(v, None))
+            .map(|ts| Value::String(ts.format(TIMESTAMP_FORMAT).to_string())),
+        ColumnType::JSON => number
+            .map(Value::from)
+            .or(record.vb.map(Value::Bool))
+            .or(record
+                .vs
+                .as_ref()
+                .map(|v| serde_json::from_str(v).unwrap_or_else(|_| Value::String(v.clone()))))
+            .or(record.vd.clone().map(Value::String)),
+        // Data values are kept base64url encoded like in the JSON representation
+        _ => record.vs.clone().or(record.vd.clone()).map(Value::String),
+    };
+
+    value.ok_or_else(invalid)
+}
+
+/// Resolves the SenML time (seconds) into a timestamp, a relative time of 0 is the time of insertion.
+fn resolve_time(time: f64, now: DateTime<Utc>) -> Result<Option<NaiveDateTime>, String> {
+    if time == 0.0 {
+        return Ok(None);
+    }
+
+    let ts = match time < RELATIVE_TIME_THRESHOLD {
+        true => now.timestamp_micros() as f64 / 1_000_000.0 + time,
+        false => time,
+    };
+
+    DateTime::from_timestamp_micros((ts * 1_000_000.0).round() as i64)
+        .map(|ts| Some(ts.naive_utc()))
+        .ok_or_else(|| format!("invalid time {}", time))
+}
+
+/// Decodes the CBOR pack into the records of its JSON representation.
+pub fn cbor_pack_to_json(data: &[u8]) -> Result<Vec<Map<String, Value>>, String> {
+    ciborium::from_reader::<ciborium::Value, _>(data)
+        .map_err(|err| format!("Invalid SenML CBOR pack: {}", err))
+        .and_then(cbor_to_json)
+}
+
+/// Converts the CBOR pack into the JSON representation, replacing the integer labels.
+fn cbor_to_json(pack: ciborium::Value) -> Result<Vec<Map<String, Value>>, String> {
+    let ciborium::Value::Array(records) = pack else {
+        return Err("SenML pack must be an array".to_string());
+    };
+
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(idx, record)| {
+            let ciborium::Value::Map(fields) = record else {
+                return Err(format!("record {}: must be a map", idx));
+            };
+
+            let mut map = Map::new();
+
+            for (key, value) in fields {
+                let label = match key {
+                    ciborium::Value::Text(label) => label,
+                    ciborium::Value::Integer(label) => {
+                        let label = i64::try_from(label).unwrap_or(i64::MAX);
+                        match CBOR_LABELS.iter().find(|(l, _)| *l == label) {
+                            Some((_, name)) => name.to_string(),
+                            // Unknown labels are ignored like unknown JSON fields
+                            None => continue,
+                        }
+                    }
+                    _ => return Err(format!("record {}: invalid label", idx)),
+                };
+
+                map.insert(label, cbor_value_to_json(value));
+            }
+
+            Ok(map)
+        })
+        .collect()
+}
+
+fn cbor_value_to_json(value: ciborium::Value) -> Value {
+    match value {
+        ciborium::Value::Integer(v) => match i64::try_from(v) {
+            Ok(v) => Value::from(v),
+            Err(_) => Value::from(i128::from(v) as f64),
+        },
+        ciborium::Value::Float(v) => Value::from(v),
+        ciborium::Value::Text(v) => Value::String(v),
+        ciborium::Value::Bool(v) => Value::Bool(v),
+        ciborium::Value::Bytes(v) => Value::String(URL_SAFE_NO_PAD.encode(v)),
+        ciborium::Value::Tag(_, v) => cbor_value_to_json(*v),
+        ciborium::Value::Array(v) => Value::Array(v.into_iter().map(cbor_value_to_json).collect()),
+        _ => Value::Null,
+    }
+}
@@ -79,12 +79,22 @@ pub fn config(conf: &mut web::ServiceConfig) {
         .service(sensor_hdl::get_sensor_info_handler)
         .service(sensor_hdl::create_sensor_handler)
         .service(sensor_hdl::edit_sensor_handler)
+        .service(sensor_hdl::edit_sensor_schema_handler)
         .service(sensor_hdl::delete_sensor_handler)
         .service(sensor_hdl::create_sensor_api_key_handler)
         .service(sensor_hdl::delete_sensor_api_key_handler)
+        .service(sensor_hdl::register_lorawan_device_handler)
+        .service(sensor_hdl::list_lorawan_devices_handler)
+        .service(sensor_hdl::delete_lorawan_device_handler)
         .service(sensor_hdl::load_data_chain_handler)
         .service(sensor_hdl::set_data_chain_handler)
         .service(sensor_hdl::delete_data_chain_handler)
+        .service(sensor_hdl::load_pull_source_handler)
+        .service(sensor_hdl::set_pull_source_handler)
+        .service(sensor_hdl::delete_pull_source_handler)
+        .service(sensor_hdl::load_modbus_source_handler)
+        .service(sensor_hdl::set_modbus_source_handler)
+        .service(sensor_hdl::delete_modbus_source_handler)
         .service(data_transform_hdl::list_data_transformer_handler)
         .service(data_transform_hdl::load_data_transformer_handler)
         .service(data_transform_hdl::create_data_transformer_handler)
@@ -95,7 +105,14 @@ pub fn config(conf: &mut web::ServiceConfig) {
         .service(event_handler_hdl::create_event_handler_handler)
         .service(event_handler_hdl::delete_event_handler_handler)
         .service(http::ingest_sensor_data_handler)
+        .service(http::ingest_line_protocol_handler)
+        .service(http::ingest_lorawan_handler)
+        .service(http::mqtt_auth_user_handler)
+        .service(http::mqtt_auth_superuser_handler)
+        .service(http::mqtt_auth_acl_handler)
         .service(data_hdl::get_sensor_data_handler)
+        .service(data_hdl::get_aligned_sensor_data_handler)
+        .service(data_hdl::export_sensor_data_handler)
         .service(data_hdl::delete_sensor_data_handler)
         .service(role_hdl::create_role_handler)
         .service(role_hdl::delete_role_handler)
@@ -1,8 +1,12 @@
 use crate::database::models::data_chain::DataChain;
-use crate::database::models::db_structs::{DBAggregation, DBOperation, DBOrdering};
+use crate::database::models::db_structs::{
+    DBAggregation, DBFill, DBOperation, DBOrdering, DBTimeUnit,
+};
 use crate::database::models::events::EventHandler;
-use crate::database::models::sensor::SensorColumn;
+use crate::database::models::sensor::{ColumnConstraints, ColumnType, SensorColumn};
 use crate::features::config::TIMESTAMP_FORMAT;
+use crate::features::sensor_data_export::DataExportFormat;
+use crate::features::sensor_data_filter::DataFilter;
 use crate::features::sensor_data_storage::SensorDataStorageCfg;
 use crate::utils::uuid_schema;
 use crate::utils::{query_param_vec_deserializer, serialize_vec_query_params, QueryParam};
@@ -28,6 +32,8 @@ pub struct DataLoadRequestParams {
     pub ordering: Option<DBOrdering>,
     /// Which data column to use for ordering the results, by default time column "created_at" is used
     pub order_col: Option<String>,
+    /// Opaque cursor of the previous page to continue reading after its last row
+    pub cursor: Option<String>,
 
     // NaiveDateTime is parsed with a trailing "Z" for the TimeZone which cant be parsed by
     // serde_json since NaiveDateTime does not contain a timezone!
@@ -44,13 +50,28 @@ pub struct DataLoadRequestParams {
     /// Specifies, how to 'to' interval border should be considered. <= or <
     pub to_inclusive: Option<bool>,
 
+    /// Value predicates on the data columns, e.g. filter=temp.gt.20,or(state.eq.on,state.is.null)
+    #[schema(value_type = Option<String>)]
+    pub filter: Option<DataFilter>,
+
     /// Define, which data columns to retrieve with which preprocessing. Serialized to: cols=col1.min,col2.avg
     /// By default, all data columns are returned (including time column created_at).
-    /// If aggregation is defined, the time_grouping value must be specified! [All data cols must provide an aggregation!]
+    /// If aggregation is defined, the time_grouping value or non aggregated STRING/INT columns as group keys must be specified!
     #[serde(default, deserialize_with = "query_param_vec_deserializer")]
     pub cols: Option<Vec<DataLoadRequestColumns>>,
     /// Define the time interval [s] used for grouping the result values. E.g. 3.600=1hour
     pub time_grouping: Option<u32>,
+    /// Define the calendar unit used for grouping the result values, alternative to time_grouping
+    pub time_unit: Option<DBTimeUnit>,
+    /// IANA timezone the time buckets are aligned to, e.g. Europe/Berlin. By default UTC is used
+    pub timezone: Option<String>,
+    /// Origin of the time_grouping buckets in local time of the timezone, by default the unix epoch
+    #[schema(example = "2025-02-11T06:00:00")]
+    pub time_origin: Option<chrono::NaiveDateTime>,
+    /// Offset [s] to shift the borders of time_unit buckets, e.g. 21.600 for days starting at 06:00
+    pub time_offset: Option<i32>,
+    /// Fill time buckets without data values of grouped requests between 'from' and 'to'
+    pub fill: Option<DBFill>,
 }
 
 impl DataLoadRequestParams {
@@ -74,6 +95,10 @@ impl DataLoadRequestParams {
             vec.push(("order_col".to_string(), v.to_string()));
         }
 
+        if let Some(v) = &self.cursor {
+            vec.push(("cursor".to_string(), v.to_string()));
+        }
+
         if let Some(v) = &self.from {
             vec.push(("from".to_string(), v.format(TIMESTAMP_FORMAT).to_string()));
         }
@@ -90,6 +115,10 @@ impl DataLoadRequestParams {
             vec.push(("to_inclusive".to_string(), v.to_string()));
         }
 
+        if let Some(v) = &self.filter {
+            vec.push(("filter".to_string(), v.to_string()));
+        }
+
         if let Some(v) = &self.cols {
             vec.push(("cols".to_string(), serialize_vec_query_params(v)));
         }
@@ -98,6 +127,29 @@ impl DataLoadRequestParams {
             vec.push(("time_grouping".to_string(), v.to_string()));
         }
 
+        if let Some(v) = &self.time_unit {
+            vec.push(("time_unit".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.timezone {
+            vec.push(("timezone".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.time_origin {
+            vec.push((
+                "time_origin".to_string(),
+                v.format(TIMESTAMP_FORMAT).to_string(),
+            ));
+        }
+
+        if let Some(v) = &self.time_offset {
+            vec.push(("time_offset".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.fill {
+            vec.push(("fill".to_string(), v.to_string()));
+        }
+
         vec
     }
 }
@@ -130,10 +182,224 @@ impl QueryParam for DataLoadRequestColumns {
     }
 }
 
+/// Query Parameters for exporting the raw data of a sensor
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Default)]
+pub struct DataExportRequestParams {
+    #[schema(schema_with = uuid_schema)]
+    pub key: Option<uuid::Uuid>,
+    /// The file format, by default determined by the Accept header or CSV
+    pub format: Option<DataExportFormat>,
+    /// How to order the exported rows by time, by default ASC
+    pub ordering: Option<DBOrdering>,
+
+    /// ISO 8601 timestamp
+    #[schema(example = "2025-02-11T08:27:17")]
+    pub from: Option<chrono::NaiveDateTime>,
+    /// ISO 8601 timestamp
+    #[schema(example = "2025-02-11T08:27:17")]
+    pub to: Option<chrono::NaiveDateTime>,
+
+    /// Specifies, how to 'from' interval border should be considered. >= or >
+    pub from_inclusive: Option<bool>,
+
+    /// Specifies, how to 'to' interval border should be considered. <= or <
+    pub to_inclusive: Option<bool>,
+
+    /// Value predicates on the data columns to only export matching entries
+    #[schema(value_type = Option<String>)]
+    pub filter: Option<DataFilter>,
+
+    /// Data columns to export without aggregation, by default all data columns are exported.
+    #[serde(default, deserialize_with = "query_param_vec_deserializer")]
+    pub cols: Option<Vec<DataLoadRequestColumns>>,
+}
+
+impl DataExportRequestParams {
+    /// Method to convert struct fields into a vector when used for query params
+    pub fn to_vector(&self) -> Vec<(String, String)> {
+        let mut vec = Vec::new();
+
+        if let Some(v) = &self.key {
+            vec.push(("key".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.format {
+            vec.push(("format".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.ordering {
+            vec.push(("ordering".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.from {
+            vec.push(("from".to_string(), v.format(TIMESTAMP_FORMAT).to_string()));
+        }
+
+        if let Some(v) = &self.to {
+            vec.push(("to".to_string(), v.format(TIMESTAMP_FORMAT).to_string()));
+        }
+
+        if let Some(v) = &self.from_inclusive {
+            vec.push(("from_inclusive".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.to_inclusive {
+            vec.push(("to_inclusive".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.filter {
+            vec.push(("filter".to_string(), v.to_string()));
+        }
+
+        if let Some(v) = &self.cols {
+            vec.push(("cols".to_string(), serialize_vec_query_params(v)));
+        }
+
+        vec
+    }
+}
+
+/// Request to load the data of multiple sensors aligned on common time buckets
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Default)]
+pub struct AlignedDataRequest {
+    /// The sensors and their data columns to retrieve
+    pub sensors: Vec<AlignedDataRequestSensor>,
+
+    /// ISO 8601 timestamp
+    #[schema(example = "2025-02-11T08:27:17")]
+    pub from: Option<chrono::NaiveDateTime>,
+    /// ISO 8601 timestamp
+    #[schema(example = "2025-02-11T08:27:17")]
+    pub to: Option<chrono::NaiveDateTime>,
+    pub from_inclusive: Option<bool>,
+    pub to_inclusive: Option<bool>,
+
+    /// Time interval [s] of the common time buckets, alternatively use time_unit
+    pub time_grouping: Option<u32>,
+    /// Calendar unit of the common time buckets
+    pub time_unit: Option<DBTimeUnit>,
+    /// IANA timezone the time buckets are aligned to, by default UTC
+    pub timezone: Option<String>,
+    #[schema(example = "2025-02-11T06:00:00")]
+    pub time_origin: Option<chrono::NaiveDateTime>,
+    pub time_offset: Option<i32>,
+    pub fill: Option<DBFill>,
+
+    /// Ordering of the time buckets, by default ASC
+    pub ordering: Option<DBOrdering>,
+    /// How many time buckets to return
+    pub limit: Option<i32>,
+    /// Result format, by default WIDE
+    pub format: Option<AlignedDataFormat>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Default)]
+pub struct AlignedDataRequestSensor {
+    #[schema(schema_with = uuid_schema)]
+    pub id: uuid::Uuid,
+    /// The API key to read the sensor data, leave empty if the data is publicly available
+    #[schema(schema_with = uuid_schema)]
+    pub key: Option<uuid::Uuid>,
+    /// Name of the sensor in the result, by default the sensor id
+    pub alias: Option<String>,
+    /// The data columns with their aggregation
+    pub cols: Vec<DataLoadRequestColumns>,
+    /// Value predicates on the data columns of the sensor
+    #[schema(value_type = Option<String>)]
+    pub filter: Option<DataFilter>,
+}
+
+/// WIDE returns one row per time bucket with a column per sensor and data column (alias.col),
+/// LONG returns one row per time bucket and sensor.
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, PartialEq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AlignedDataFormat {
+    WIDE,
+    LONG,
+}
+
 #[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Default)]
 pub struct DataIngestRequestParams {
     #[schema(schema_with = uuid_schema)]
     pub key: Option<uuid::Uuid>,
+
+    /// CSV only: Header of the column containing the timestamps, by default "timestamp"
+    pub timestamp_col: Option<String>,
+    /// CSV only: chrono format of the timestamps, 'unix' or 'unix_ms' for epoch timestamps, by default ISO 8601
+    pub timestamp_format: Option<String>,
+    /// CSV only: Field delimiter, by default ','
+    pub delimiter: Option<char>,
+}
+
+/// Precision of the timestamps of InfluxDB line protocol points.
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineProtocolPrecision {
+    #[default]
+    NS,
+    US,
+    MS,
+    S,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Default)]
+pub struct LineProtocolIngestRequestParams {
+    #[schema(schema_with = uuid_schema)]
+    pub key: Option<uuid::Uuid>,
+
+    /// Precision of the point timestamps, by default nanoseconds
+    pub precision: Option<LineProtocolPrecision>,
+    /// Tag identifying the sensor (by id or name) of a point, by default the measurement is used
+    pub sensor_tag: Option<String>,
+}
+
+/// LoRaWAN network servers whose uplink webhooks are understood.
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LoRaWANNetworkServer {
+    /// The Things Stack (TTN v3)
+    TTN,
+    /// ChirpStack v4 HTTP integration
+    CHIRPSTACK,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone, Default)]
+pub struct LoRaWANIngestRequestParams {
+    /// Format of the raw frm_payload (json, cbor, msgpack, senml+json, senml+cbor), by default json
+    pub format: Option<String>,
+    /// ChirpStack only: Type of the event, only 'up' events are ingested
+    pub event: Option<String>,
+}
+
+/// Login of a MQTT client, forwarded by the broker
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone)]
+pub struct MqttAuthUserRequest {
+    /// The uuid of the sensor
+    #[schema(example = "5401df3b-9367-4e8b-b2dd-6dba2b6cade6")]
+    pub username: String,
+    /// The write API key of the sensor
+    #[schema(example = "460015cb-1c9f-46b1-bcf2-8a33fd981dcb")]
+    pub password: String,
+    #[serde(default)]
+    pub clientid: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone)]
+pub struct MqttAuthSuperuserRequest {
+    pub username: String,
+}
+
+/// Access of an authenticated MQTT client to a topic, forwarded by the broker
+#[derive(Serialize, Deserialize, Debug, ToSchema, Clone)]
+pub struct MqttAuthAclRequest {
+    pub username: String,
+    #[schema(example = "/api/sensors/5401df3b-9367-4e8b-b2dd-6dba2b6cade6")]
+    pub topic: String,
+    #[serde(default)]
+    pub clientid: Option<String>,
+    /// Requested access: 1 read, 2 write, 3 read and write, 4 subscribe
+    #[schema(example = 2)]
+    pub acc: u8,
 }
 
 #[derive(Serialize, Deserialize, Debug, ToSchema, Clone)]
@@ -161,6 +427,31 @@ pub struct EditSensorRequest {
     // TODO: Later we could update the ingest method (http, mqtt, ...)
 }
 
+/// A single modification of the data columns of an existing sensor.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[serde(tag = "op", rename_all = "UPPERCASE")]
+pub enum SensorSchemaOperation {
+    /// Adds a new data column, existing rows receive NULL values
+    ADD { column: SensorColumn },
+    /// Drops an existing data column including all of its values
+    DROP { name: String },
+    /// Renames an existing data column
+    RENAME { name: String, new_name: String },
+    /// Changes the type of an existing data column, existing values are converted by the database
+    RETYPE { name: String, val_type: ColumnType },
+    /// Replaces (or removes) the validation constraints of an existing data column
+    CONSTRAIN {
+        name: String,
+        constraints: Option<ColumnConstraints>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct EditSensorSchemaRequest {
+    /// Operations are applied in the given order within a single transaction
+    pub operations: Vec<SensorSchemaOperation>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
 pub struct SensorPermissionRequest {
     #[schema(schema_with = uuid_schema)]
@@ -214,6 +505,10 @@ pub struct SensorDataDeletionParams {
     /// Specifies, how to 'to' interval border should be considered. <= or <
     pub to_inclusive: Option<bool>,
 
+    /// Value predicates on the data columns to only delete matching entries
+    #[schema(value_type = Option<String>)]
+    pub filter: Option<DataFilter>,
+
     pub purge: Option<bool>,
 }
 
@@ -242,6 +537,10 @@ impl SensorDataDeletionParams {
             vec.push(("to_inclusive".to_string(), v.to_string()));
         }
 
+        if let Some(v) = &self.filter {
+            vec.push(("filter".to_string(), v.to_string()));
+        }
+
         if let Some(v) = &self.purge {
             vec.push(("purge".to_string(), v.to_string()));
         }
@@ -262,6 +561,13 @@ pub struct CreateApiKeyRequest {
     pub operation: DBOperation,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RegisterLoRaWANDeviceRequest {
+    /// DevEUI of the end device as 16 hex digits
+    #[schema(example = "70B3D57ED005A1B2")]
+    pub dev_eui: String,
+}
+
 #[derive(Serialize, Debug, Deserialize, Clone, ToSchema)]
 pub struct EditUserInfoRequest {
     pub name: String,
@@ -310,10 +616,17 @@ impl CreateEventHandlerRequest {
 pub enum TransportProto {
     HTTP,
     MQTT,
+    COAP,
+    // Data polled from pull sources, not part of the pushing protos
+    PULL,
 }
 impl TransportProto {
     pub fn iterator() -> std::slice::Iter<'static, TransportProto> {
-        static PROTOS: [TransportProto; 2] = [TransportProto::HTTP, TransportProto::MQTT];
+        static PROTOS: [TransportProto; 3] = [
+            TransportProto::HTTP,
+            TransportProto::MQTT,
+            TransportProto::COAP,
+        ];
         PROTOS.iter()
     }
 }
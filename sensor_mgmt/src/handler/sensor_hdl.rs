@@ -1,4 +1,5 @@
 use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use actix_web::http::StatusCode;
 use crate::database::models::data_chain::DataChain;
 use crate::database::models::db_structs::DBOperation;
 use crate::database::models::sensor::ShortSensorInfo;
@@ -6,10 +7,15 @@ use crate::database::sensor_db::{create_sensor, delete_sensor, edit_sensor, get_
 use crate::handler::{main_hdl, policy};
 use crate::authentication::jwt_auth;
 use crate::database::models::api_key::ApiKey;
-use crate::database::{data_chain_db, sensor_db};
+use crate::database::{data_chain_db, lorawan_db, modbus_source_db, pull_source_db, sensor_db};
+use crate::database::models::modbus_source::ModbusSource;
+use crate::database::models::pull_source::PullSource;
+use crate::features::sensor_data_modbus::validate_modbus_source;
+use crate::features::sensor_data_pull::validate_pull_source;
 use crate::features::cache;
 use crate::handler::policy::unauthorized;
-use crate::handler::models::requests::{CreateApiKeyRequest, CreateSensorRequest, EditSensorRequest, SetDataChainRequest};
+use crate::handler::models::requests::{CreateApiKeyRequest, CreateSensorRequest, EditSensorRequest, EditSensorSchemaRequest, RegisterLoRaWANDeviceRequest, SetDataChainRequest};
+use crate::handler::data_ingest::lorawan::normalize_dev_eui;
 use crate::handler::models::responses::{GenericUuidResponse, SensorDetailResponse};
 use crate::features::user_sens_perm::{UserSensorPerm};
 use crate::state::AppState;
@@ -211,6 +217,41 @@ async fn edit_sensor_handler(path: web::Path<uuid::Uuid>, body: web::Json<EditSe
     main_hdl::send_result(&result)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/sensors/{id}/schema/edit",
+    description = "Modifies the data columns of the sensor. Columns may be added, dropped, renamed or retyped.<br>\
+    All operations are applied in the given order within a single transaction. Dropping a column deletes all of its values, \
+    retyping a column converts the existing values and fails if they are not convertible.",
+    request_body(
+        content_type = "application/json",
+        content = EditSensorSchemaRequest,
+        description = "The schema operations to apply.",
+        example = json!({"operations":[{"op":"ADD","column":{"name":"humidity","val_type":"FLOAT","val_unit":"percent","val_ingest":"LITERAL"}},{"op":"RENAME","name":"temp","new_name":"temperature"},{"op":"RETYPE","name":"count","val_type":"FLOAT"},{"op":"DROP","name":"debug"}]}),
+    ),
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    tag = COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns ok if the schema modification was successful."),
+        (status = 401, description= "Returns an unauthorized error if no valid admin or owner token was provided."),
+        (status = 500, description= "Returns an error if the sensor does not exist or the schema couldn't be modified."),
+    ),
+    security(("JWT" = [])),
+)]
+#[post("/sensors/{id}/schema/edit")]
+async fn edit_sensor_schema_handler(path: web::Path<uuid::Uuid>, body: web::Json<EditSensorSchemaRequest>, data: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+
+    if let Some(err) = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::Edit, &data).await {
+        return err;
+    }
+
+    let result = sensor_db::edit_sensor_schema(sensor_id, body.into_inner(), &data).await;
+
+    main_hdl::send_result(&result)
+}
+
 #[utoipa::path(
     delete,
     path = "/api/sensors/{id}/delete",
@@ -334,6 +375,147 @@ async fn delete_sensor_api_key_handler(path: web::Path<(uuid::Uuid, uuid::Uuid)>
     main_hdl::send_result(&res)
 }
 
+/* ------------------------------------------------ LoRaWAN devices -------------------------------------------------- */
+
+const LORAWAN_OPENAPI_COMMON_TAG: &str = "Sensors / LoRaWAN Devices";
+
+#[utoipa::path(
+    post,
+    path = "/api/sensors/{id}/lorawan/register",
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    request_body(
+        content_type = "application/json",
+        content = RegisterLoRaWANDeviceRequest,
+        description = "The end device whose uplinks should be ingested into the sensor. \
+            A device registered for another sensor is only moved if the user may manage the end devices of both sensors.",
+    ),
+    tag = LORAWAN_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns ok if the end device was registered."),
+        (status = 400, description= "Returns a bad request error if the DevEUI is invalid."),
+        (status = 401, description= "Returns an unauthorized error if no permissions to register end devices."),
+        (status = 409, description= "Returns a conflict error if the end device is registered for another sensor."),
+        (status = 500, description= "Returns an error if the end device couldn't be registered."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[post("/sensors/{id}/lorawan/register")]
+async fn register_lorawan_device_handler(path: web::Path<uuid::Uuid>, body: web::Json<RegisterLoRaWANDeviceRequest>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+
+    // Uplinks of the device are ingested without api key
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::ApiKeyWrite, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    let dev_eui = match normalize_dev_eui(&body.dev_eui) {
+        Ok(dev_eui) => dev_eui,
+        Err(err) => return AppError::validation("invalid DevEUI", vec![err]).into(),
+    };
+
+    let conflict = || -> HttpResponse {
+        AppError::InternalError {
+            status: Some(StatusCode::CONFLICT),
+            msg: Some(format!("Device {} is registered for another sensor!", dev_eui)),
+        }
+        .into()
+    };
+
+    // DevEUIs aren't secret, taking over a device requires permissions on its current sensor
+    let res = match lorawan_db::get_sensor_id(&dev_eui, &state.db).await {
+        Ok(Some(current)) if current == sensor_id => Ok(()),
+        Ok(Some(current)) => {
+            let move_check = policy::require_sensor_permission(user_id, current, UserSensorPerm::ApiKeyWrite, &state).await;
+            if move_check.is_some() {
+                return conflict();
+            }
+
+            lorawan_db::move_device(current, sensor_id, &dev_eui, &state.db).await
+        }
+        Ok(None) => lorawan_db::register_device(sensor_id, &dev_eui, &state.db).await,
+        Err(err) => Err(err),
+    };
+
+    // Concurrent registration of the same device
+    if let Err(err) = &res {
+        if let Some(sqlx::Error::Database(db_err)) = err.downcast_ref::<sqlx::Error>() {
+            if db_err.is_unique_violation() {
+                return conflict();
+            }
+        }
+    }
+
+    main_hdl::send_result(&res)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/sensors/{id}/lorawan/list",
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    tag = LORAWAN_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns the DevEUIs of the end devices registered for the sensor.", body = Vec<String>),
+        (status = 401, description= "Returns an unauthorized error if no permissions to list the end devices."),
+        (status = 500, description= "Returns an error if the end devices couldn't be loaded."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[get("/sensors/{id}/lorawan/list")]
+async fn list_lorawan_devices_handler(path: web::Path<uuid::Uuid>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::ApiKeyWrite, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    let res = lorawan_db::list_devices(sensor_id, &state.db).await;
+
+    main_hdl::send_result(&res)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/sensors/{id}/lorawan/{dev_eui}/delete",
+    params(
+        ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string())),
+        ("dev_eui" = String, Path, description = "The DevEUI of the end device", example = "70B3D57ED005A1B2")
+    ),
+    tag = LORAWAN_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns ok if the end device was removed."),
+        (status = 400, description= "Returns a bad request error if the DevEUI is invalid."),
+        (status = 401, description= "Returns an unauthorized error if no permissions to remove end devices."),
+        (status = 500, description= "Returns an error if the end device isn't registered for the sensor."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[delete("/sensors/{id}/lorawan/{dev_eui}/delete")]
+async fn delete_lorawan_device_handler(path: web::Path<(uuid::Uuid, String)>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let (sensor_id, dev_eui) = path.into_inner();
+
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::ApiKeyWrite, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    let dev_eui = match normalize_dev_eui(&dev_eui) {
+        Ok(dev_eui) => dev_eui,
+        Err(err) => return AppError::validation("invalid DevEUI", vec![err]).into(),
+    };
+
+    let res = lorawan_db::delete_device(sensor_id, &dev_eui, &state.db).await;
+
+    main_hdl::send_result(&res)
+}
+
 /* ------------------------------------------------ Data chains -------------------------------------------------- */
 
 const DATA_CHAIN_OPENAPI_COMMON_TAG: &str = "Sensors / Data Chain";
@@ -427,6 +609,231 @@ async fn delete_data_chain_handler(path: web::Path<uuid::Uuid>, state: web::Data
     data_chain_db::delete(sensor_id, &state.db).await
 }
 
+/* ------------------------------------------------ Pull sources -------------------------------------------------- */
+
+const PULL_SOURCE_OPENAPI_COMMON_TAG: &str = "Sensors / Pull Source";
+
+#[utoipa::path(
+    get,
+    path = "/api/sensors/{id}/pull_source/load",
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    tag = PULL_SOURCE_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description= "Returns the pull source of the sensor, null if none is configured.", body = Option<PullSource>),
+        (status = 401, description= "Returns an unauthorized error if no permissions to edit the sensor."),
+        (status = 500, description= "Returns an error if the pull source couldn't be loaded."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[get("/sensors/{id}/pull_source/load")]
+async fn load_pull_source_handler(path: web::Path<uuid::Uuid>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+
+    // The headers may contain credentials of the source
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::Edit, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    let res = pull_source_db::load(sensor_id, &state.db).await;
+
+    main_hdl::send_result(&res)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sensors/{id}/pull_source/set",
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    request_body(
+        content_type = "application/json",
+        content = PullSource,
+        description = "The HTTP endpoint polled in the given interval. The response body is ingested like a JSON ingest body \
+        and runs through the data chain of the sensor. The JSON pointer selects the data entries (or a single entry) in the response.",
+    ),
+    tag = PULL_SOURCE_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns ok if the pull source was set, replacing an existing one."),
+        (status = 400, description= "Returns a bad request error if the pull source is invalid."),
+        (status = 401, description= "Returns an unauthorized error if no permissions to edit the sensor."),
+        (status = 500, description= "Returns an error if the pull source couldn't be set."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[post("/sensors/{id}/pull_source/set")]
+async fn set_pull_source_handler(path: web::Path<uuid::Uuid>, body: web::Json<PullSource>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+    let source = body.into_inner();
+
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::Edit, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    if let Err(err) = validate_pull_source(&source, &state).await {
+        return err.into();
+    }
+
+    let res = pull_source_db::set(sensor_id, &source, &state.db).await;
+    if res.is_ok() {
+        if let Some(scheduler) = &state.pull_scheduler {
+            scheduler.reload();
+        }
+    }
+
+    main_hdl::send_result(&res)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/sensors/{id}/pull_source/delete",
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    tag = PULL_SOURCE_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns ok if the pull source was removed."),
+        (status = 401, description= "Returns an unauthorized error if no permissions to edit the sensor."),
+        (status = 500, description= "Returns an error if the pull source couldn't be removed."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[delete("/sensors/{id}/pull_source/delete")]
+async fn delete_pull_source_handler(path: web::Path<uuid::Uuid>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::Edit, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    let res = pull_source_db::delete(sensor_id, &state.db).await;
+    if res.is_ok() {
+        if let Some(scheduler) = &state.pull_scheduler {
+            scheduler.reload();
+        }
+    }
+
+    main_hdl::send_result(&res)
+}
+
+/* ------------------------------------------------ Modbus sources ------------------------------------------------ */
+
+const MODBUS_SOURCE_OPENAPI_COMMON_TAG: &str = "Sensors / Modbus Source";
+
+#[utoipa::path(
+    get,
+    path = "/api/sensors/{id}/modbus_source/load",
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    tag = MODBUS_SOURCE_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description= "Returns the modbus source of the sensor, null if none is configured.", body = Option<ModbusSource>),
+        (status = 401, description= "Returns an unauthorized error if no permissions to edit the sensor."),
+        (status = 500, description= "Returns an error if the modbus source couldn't be loaded."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[get("/sensors/{id}/modbus_source/load")]
+async fn load_modbus_source_handler(path: web::Path<uuid::Uuid>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::Edit, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    let res = modbus_source_db::load(sensor_id, &state.db).await;
+
+    main_hdl::send_result(&res)
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/sensors/{id}/modbus_source/set",
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    request_body(
+        content_type = "application/json",
+        content = ModbusSource,
+        description = "The Modbus TCP device polled in the given interval. Every poll reads the registers, scales their values \
+        and inserts them as a single data entry into the mapped INT or FLOAT columns.",
+    ),
+    tag = MODBUS_SOURCE_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns ok if the modbus source was set, replacing an existing one."),
+        (status = 400, description= "Returns a bad request error if the modbus source is invalid."),
+        (status = 401, description= "Returns an unauthorized error if no permissions to edit the sensor."),
+        (status = 500, description= "Returns an error if the modbus source couldn't be set."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[post("/sensors/{id}/modbus_source/set")]
+async fn set_modbus_source_handler(path: web::Path<uuid::Uuid>, body: web::Json<ModbusSource>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+    let source = body.into_inner();
+
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::Edit, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    let Some(sensor) = cache::request_sensor(sensor_id, &state).await else {
+        return AppError::not_found2(format!("Sensor with id {} not found!", sensor_id)).into();
+    };
+
+    if let Err(err) = validate_modbus_source(&source, &sensor, &state).await {
+        return err.into();
+    }
+
+    let res = modbus_source_db::set(sensor_id, &source, &state.db).await;
+    if res.is_ok() {
+        if let Some(scheduler) = &state.pull_scheduler {
+            scheduler.reload();
+        }
+    }
+
+    main_hdl::send_result(&res)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/sensors/{id}/modbus_source/delete",
+    params( ("id" = String, Path, description = "The uuid of the sensor", example = json!(uuid::Uuid::new_v4().to_string()))),
+    tag = MODBUS_SOURCE_OPENAPI_COMMON_TAG,
+    responses(
+        (status = 200, description = "Returns ok if the modbus source was removed."),
+        (status = 401, description= "Returns an unauthorized error if no permissions to edit the sensor."),
+        (status = 500, description= "Returns an error if the modbus source couldn't be removed."),
+    ),
+    security(("JWT" = [])),
+)]
+
+#[delete("/sensors/{id}/modbus_source/delete")]
+async fn delete_modbus_source_handler(path: web::Path<uuid::Uuid>, state: web::Data<AppState>, jwt: jwt_auth::JwtMiddleware) -> impl Responder {
+    let user_id = jwt.user_id;
+    let sensor_id = path.into_inner();
+
+    let perm_check = policy::require_sensor_permission(user_id, sensor_id, UserSensorPerm::Edit, &state).await;
+    if let Some(err) = perm_check {
+        return err;
+    }
+
+    let res = modbus_source_db::delete(sensor_id, &state.db).await;
+    if res.is_ok() {
+        if let Some(scheduler) = &state.pull_scheduler {
+            scheduler.reload();
+        }
+    }
+
+    main_hdl::send_result(&res)
+}
+
 /* ------------------------------------------------ Tests ------------------------------------------------------------ */
 
 #[cfg(test)]
@@ -441,8 +848,11 @@ pub mod tests {
     use crate::database::models::sensor::{ColumnIngest, ColumnType, SensorColumn};
     use crate::database::role_db;
     use crate::features::sensor_data_storage::{SensorDataStorageCfg, SensorDataStorageType};
-    use crate::handler::models::requests::SensorPermissionRequest;
-    use crate::test_utils::tests::{anne, create_test_api_keys, create_test_app, create_test_sensors, execute_request, john, login, test_invalid_auth, TEST_SYS_ROLE};
+    use std::sync::Arc;
+    use crate::database::data_db;
+    use crate::database::models::db_structs::DBOrdering;
+    use crate::handler::models::requests::{DataLoadRequestParams, SensorDataIngestEntry, SensorPermissionRequest};
+    use crate::test_utils::tests::{add_dummy_data, anne, create_test_api_keys, create_test_app, create_test_sensors, execute_request, john, login, test_invalid_auth, TEST_SYS_ROLE};
 
     #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
     async fn test_list_sensors(pool: PgPool) {
@@ -636,15 +1046,15 @@ pub mod tests {
                 permissions: vec![SensorPermissionRequest { role_id: ROLE_SYSTEM_USER, operations: vec![DBOperation::INFO, DBOperation::READ] }],
                 columns: vec![
                     SensorColumn {
-                        name: "col1".to_string(), val_type: ColumnType::INT, val_unit: "unit_1".to_string(), val_ingest: ColumnIngest::LITERAL
+                        name: "col1".to_string(), val_type: ColumnType::INT, val_unit: "unit_1".to_string(), val_ingest: ColumnIngest::LITERAL, constraints: None
                     },
                     SensorColumn {
-                        name: "col2".to_string(), val_type: ColumnType::FLOAT, val_unit: "unit_2".to_string(), val_ingest: ColumnIngest::LITERAL
+                        name: "col2".to_string(), val_type: ColumnType::FLOAT, val_unit: "unit_2".to_string(), val_ingest: ColumnIngest::LITERAL, constraints: None
                     },
                     SensorColumn {
-                        name: "col3".to_string(), val_type: ColumnType::STRING, val_unit: "unit_3".to_string(), val_ingest: ColumnIngest::LITERAL
+                        name: "col3".to_string(), val_type: ColumnType::STRING, val_unit: "unit_3".to_string(), val_ingest: ColumnIngest::LITERAL, constraints: None
                     }],
-                storage: SensorDataStorageCfg { variant: SensorDataStorageType::Default, params: None }
+                storage: SensorDataStorageCfg { variant: SensorDataStorageType::Default, params: None, dedup: None }
             }
         }
 
@@ -710,7 +1120,7 @@ pub mod tests {
                 position: Some((50.0, 10.0)),
                 permissions: vec![SensorPermissionRequest { role_id: ROLE_SYSTEM_USER, operations: vec![DBOperation::READ]},
                     SensorPermissionRequest { role_id: TEST_SYS_ROLE, operations: vec![]}],
-                storage: SensorDataStorageCfg { variant: SensorDataStorageType::RingBufferCount, params: json!({"count": 10}).as_object().cloned() }
+                storage: SensorDataStorageCfg { variant: SensorDataStorageType::RingBufferCount, params: json!({"count": 10}).as_object().cloned(), dedup: None }
             }
         }
 
@@ -775,6 +1185,111 @@ pub mod tests {
                                 StatusCode::INTERNAL_SERVER_ERROR, &app).await;
     }
 
+    #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
+    async fn test_edit_sensor_schema(pool: PgPool) {
+        let (app, state) = create_test_app(pool).await;
+
+        let test_sens = create_test_sensors(&state).await;
+
+        let target_sensor_own = test_sens.iter().find(|(name, _)| name == "MySensor").unwrap();
+        let target_sensor_not_allowed = test_sens.iter().find(|(name, _)| name == "MySensor4").unwrap();
+
+        let _ = add_dummy_data(3, 1, 0, target_sensor_own.1, &state).await;
+
+        fn schema_request(operations: Value) -> EditSensorSchemaRequest {
+            serde_json::from_value(json!({"operations": operations})).unwrap()
+        }
+
+        let path = format!("/api/sensors/{}/schema/edit", target_sensor_own.1);
+
+        test_invalid_auth(&path, Method::POST, Some(schema_request(json!([{"op": "DROP", "name": "col3"}]))), &state, &app).await;
+
+        let token = login(&john(), &state).await;
+
+        // --- Add, rename, retype and drop columns of his own sensor as john - Should succeed ---
+
+        let req = schema_request(json!([
+            {"op": "ADD", "column": {"name": "col4", "val_type": "INT", "val_unit": "unit_4", "val_ingest": "INCREMENTAL"}},
+            {"op": "RENAME", "name": "col1", "new_name": "count"},
+            {"op": "RETYPE", "name": "col2", "val_type": "STRING"},
+            {"op": "DROP", "name": "col3"},
+        ]));
+
+        let _ = execute_request(&path, Method::POST, None, Some(req), Some(token.clone()), StatusCode::OK, &app).await;
+
+        let sensor = cache::request_sensor(target_sensor_own.1, &state).await.unwrap();
+
+        let mut col_names: Vec<String> = sensor.columns.iter().map(|c| c.name.clone()).collect();
+        col_names.sort();
+        assert_eq!(col_names, vec!["col2", "col4", "count"]);
+        assert!(sensor.columns.iter().any(|c| c.name == "col2" && c.val_type == ColumnType::STRING));
+
+        // Existing data is kept for renamed and retyped columns
+        let data = data_db::get_data(target_sensor_own.1, DataLoadRequestParams { ordering: Some(DBOrdering::ASC), ..Default::default() }, &state).await.unwrap();
+        let rows = data.as_array().unwrap();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0]["count"], json!(1));
+        assert_eq!(rows[0]["col2"], json!("1.11"));
+        assert_eq!(rows[0]["col4"], Value::Null);
+        assert!(rows[0].get("col3").is_none());
+
+        // Incremental ingest is set up for the new column
+        for val in [2, 3] {
+            let entry = SensorDataIngestEntry::from_json(json!({"col4": val}), None);
+            data_db::add_sensor_data(Arc::new(sensor.clone()), &vec![entry], state.clone()).await.unwrap();
+        }
+
+        let data = data_db::get_data(target_sensor_own.1, DataLoadRequestParams { ordering: Some(DBOrdering::DESC), limit: Some(1), ..Default::default() }, &state).await.unwrap();
+        assert_eq!(data[0]["col4"], json!(5));
+
+        // Unconstrained columns are stored without constraints
+        let constrained: i64 = sqlx::query_scalar("SELECT count(*) FROM sensor_schema WHERE sensor_id = $1 AND col_constraints IS NOT NULL")
+            .bind(target_sensor_own.1).fetch_one(&state.db).await.unwrap();
+        assert_eq!(constrained, 0);
+
+        // --- Invalid operation within the batch - Should fail and keep the schema untouched ---
+
+        let req = schema_request(json!([
+            {"op": "ADD", "column": {"name": "col5", "val_type": "FLOAT", "val_unit": "unit_5", "val_ingest": "LITERAL"}},
+            {"op": "DROP", "name": "col3"},
+        ]));
+
+        let _ = execute_request(&path, Method::POST, None, Some(req), Some(token.clone()), StatusCode::INTERNAL_SERVER_ERROR, &app).await;
+
+        let sensor = cache::request_sensor(target_sensor_own.1, &state).await.unwrap();
+        assert_eq!(sensor.columns.len(), 3);
+        assert!(!sensor.columns.iter().any(|c| c.name == "col5"));
+
+        // --- Invalid schema operations - Should fail ---
+
+        let invalid_ops = [
+            // Column already exists
+            json!([{"op": "ADD", "column": {"name": "count", "val_type": "INT", "val_unit": "", "val_ingest": "LITERAL"}}]),
+            // Invalid column names
+            json!([{"op": "ADD", "column": {"name": "created_at", "val_type": "INT", "val_unit": "", "val_ingest": "LITERAL"}}]),
+            json!([{"op": "RENAME", "name": "count", "new_name": "x; DROP TABLE sensor"}]),
+            json!([{"op": "RENAME", "name": "count", "new_name": "row_id"}]),
+            // Incremental ingest not supported for type
+            json!([{"op": "RETYPE", "name": "col4", "val_type": "BOOL"}]),
+            // Values not convertible
+            json!([{"op": "RETYPE", "name": "col2", "val_type": "INT"}]),
+            // No columns left
+            json!([{"op": "DROP", "name": "count"}, {"op": "DROP", "name": "col2"}, {"op": "DROP", "name": "col4"}]),
+            json!([]),
+        ];
+
+        for ops in invalid_ops {
+            let _ = execute_request(&path, Method::POST, None, Some(schema_request(ops)), Some(token.clone()), StatusCode::INTERNAL_SERVER_ERROR, &app).await;
+        }
+
+        // --- Edit schema of not-allowed sensor as john - Should fail ---
+
+        let _ = execute_request(&format!("/api/sensors/{}/schema/edit", target_sensor_not_allowed.1), Method::POST, None,
+                                Some(schema_request(json!([{"op": "DROP", "name": "col3"}]))), Some(token.clone()),
+                                StatusCode::UNAUTHORIZED, &app).await;
+    }
+
     #[sqlx::test(migrations = "../migrations", fixtures("users", "roles", "user_roles"))]
     async fn test_delete_sensor(pool: PgPool) {
         let (app, state) = create_test_app(pool).await;
@@ -10,14 +10,19 @@ use crate::features::cache::CachedData;
 #[cfg(feature = "cache_sync")]
 use crate::features::cache_sync::CacheSyncData;
 use crate::features::config::{
-    is_prod_mode, parse_config, root_user_email, JWTConfig, ServerConfig,
+    get_data_export_max_concurrent, is_prod_mode, parse_config, root_user_email, JWTConfig,
+    ServerConfig,
 };
 use crate::features::event_generation::init_event_service;
+use crate::features::sensor_data_batch::IngestBatcher;
+use crate::features::sensor_data_pull::{start_pull_scheduler, PullScheduler};
 use crate::features::sensor_data_transform::{start_websocket_task, TransformService};
+use crate::handler::data_ingest::coap::{coap_service_init, COAP};
 use crate::handler::data_ingest::ingest::{IngestStats, RuntimeIngestStats};
 use crate::handler::data_ingest::mqtt::{mqtt_service_init, MQTT};
 use crate::handler::models::requests::RegisterUserRequest;
 use std::sync::Arc;
+use tokio::sync::Semaphore;
 use tracing::{error, warn};
 
 pub type AppState = Arc<SharedState>;
@@ -39,6 +44,12 @@ pub struct SharedState {
     // Because they also depend on the database connection contained in this struct
     pub data_transform: Arc<TransformService>,
     pub mqtt_listener: Option<Arc<MQTT>>,
+    pub coap_listener: Option<Arc<COAP>>,
+    pub pull_scheduler: Option<Arc<PullScheduler>>,
+    pub ingest_batcher: Arc<IngestBatcher>,
+
+    // Permits for data exports, each running export holds a database connection
+    pub export_permits: Arc<Semaphore>,
 
     // Logging & Event Service Channel
     pub events: Option<Arc<EventEngineState>>,
@@ -55,13 +66,11 @@ pub struct SharedState {
 }
 
 impl SharedState {
-    fn new(pool: sqlx::PgPool) -> AppState {
+    fn new(pool: sqlx::PgPool, cfg: ServerConfig) -> AppState {
         // Due to the fact that some inits require the AppState before it is fully initialized we create one
         // where these services are none and set afterwards
         // so during init those are not available!
 
-        let cfg = parse_config().unwrap();
-
         // Initialize JWT config
         let jwt = JWTConfig::init(&cfg);
 
@@ -77,6 +86,10 @@ impl SharedState {
             sync: CacheSyncData::new(cache.clone(), pool.clone()),
             data_transform: Arc::new(start_websocket_task(pool.clone())),
             mqtt_listener: None,
+            coap_listener: None,
+            pull_scheduler: None,
+            ingest_batcher: Arc::new(IngestBatcher::new(&cfg)),
+            export_permits: Arc::new(Semaphore::new(get_data_export_max_concurrent(&cfg))),
             events: None,
             rt_stats: IngestStats::new(),
             jwt: Arc::new(jwt),
@@ -89,6 +102,8 @@ impl SharedState {
 
         // Now that we have an AppState struct and the event handling we can init the mqtt service which depends on the other things existing
         state.mqtt_listener = Some(Arc::new(mqtt_service_init(Arc::new(state.clone()))));
+        state.coap_listener = coap_service_init(Arc::new(state.clone())).map(Arc::new);
+        state.pull_scheduler = Some(Arc::new(start_pull_scheduler(Arc::new(state.clone()))));
 
         // the now correctly initilaized AppState
         Arc::new(state)
@@ -99,7 +114,14 @@ impl SharedState {
 pub fn init_app_state(pool: sqlx::PgPool) -> AppState {
     let _ = env_logger::try_init_from_env(env_logger::Env::new().default_filter_or("info"));
 
-    SharedState::new(pool)
+    SharedState::new(pool, parse_config().unwrap())
+}
+
+/// Initializes the app state with the given config instead of the config file.
+pub fn init_app_state_with_config(pool: sqlx::PgPool, cfg: ServerConfig) -> AppState {
+    let _ = env_logger::try_init_from_env(env_logger::Env::new().default_filter_or("info"));
+
+    SharedState::new(pool, cfg)
 }
 
 /// When we are in a development mode and and have a root user set, then we create it with correct roles
@@ -9,13 +9,14 @@ pub mod tests {
     use crate::database::models::sensor::{ColumnIngest, ColumnType, SensorColumn};
     use crate::database::{data_db, sensor_db, user_db};
     use crate::features::cache;
+    use crate::features::config::ServerConfig;
     use crate::features::sensor_data_storage::{SensorDataStorageCfg, SensorDataStorageType};
     use crate::features::user_sens_perm::UserSensorPerm;
     use crate::handler::main_hdl::config;
     use crate::handler::models::requests::{
         CreateApiKeyRequest, CreateSensorRequest, SensorDataIngestEntry, SensorPermissionRequest,
     };
-    use crate::state::{init_app_state, AppState};
+    use crate::state::{init_app_state, init_app_state_with_config, AppState};
     use actix_http::body::BoxBody;
     use actix_http::{header, Method, Request};
     use actix_web::dev::{Service, ServiceResponse};
@@ -104,6 +105,25 @@ pub mod tests {
         (app, state)
     }
 
+    /// Creates the test app with the given server config instead of the config file.
+    pub async fn create_test_app_with_config(
+        pool: PgPool,
+        cfg: ServerConfig,
+    ) -> (
+        impl Service<Request, Response = ServiceResponse<BoxBody>, Error = actix_web::Error>,
+        AppState,
+    ) {
+        let state = init_app_state_with_config(pool, cfg);
+
+        let app = App::new()
+            .app_data(web::Data::new(state.clone()))
+            .configure(config);
+
+        let app = test::init_service(app).await;
+
+        (app, state)
+    }
+
     fn test_sensors() -> Vec<TestSensor> {
         // John's sensor that no one has access to
         let test1 = TestSensor {
@@ -168,23 +188,27 @@ pub mod tests {
                         val_type: ColumnType::INT,
                         val_unit: "unit_1".to_string(),
                         val_ingest: ColumnIngest::LITERAL,
+                        constraints: None,
                     },
                     SensorColumn {
                         name: "col2".to_string(),
                         val_type: ColumnType::FLOAT,
                         val_unit: "unit_2".to_string(),
                         val_ingest: ColumnIngest::LITERAL,
+                        constraints: None,
                     },
                     SensorColumn {
                         name: "col3".to_string(),
                         val_type: ColumnType::STRING,
                         val_unit: "unit_3".to_string(),
                         val_ingest: ColumnIngest::LITERAL,
+                        constraints: None,
                     },
                 ],
                 storage: SensorDataStorageCfg {
                     variant: SensorDataStorageType::Default,
                     params: None,
+                    dedup: None,
                 },
             };
 
@@ -198,6 +222,49 @@ pub mod tests {
         res
     }
 
+    /// Literal data column without unit and constraints.
+    pub fn test_column(name: &str, val_type: ColumnType) -> SensorColumn {
+        SensorColumn {
+            name: name.to_string(),
+            val_type,
+            val_unit: "".to_string(),
+            val_ingest: ColumnIngest::LITERAL,
+            constraints: None,
+        }
+    }
+
+    /// Request for a system sensor with the given columns that guests may read and write.
+    pub fn guest_sensor_request(name: &str, columns: Vec<SensorColumn>) -> CreateSensorRequest {
+        CreateSensorRequest {
+            name: name.to_string(),
+            position: None,
+            description: None,
+            permissions: vec![SensorPermissionRequest {
+                role_id: ROLE_SYSTEM_GUEST,
+                operations: vec![DBOperation::INFO, DBOperation::READ, DBOperation::WRITE],
+            }],
+            columns,
+            storage: SensorDataStorageCfg {
+                variant: SensorDataStorageType::Default,
+                params: None,
+                dedup: None,
+            },
+        }
+    }
+
+    /// Creates a system sensor with the given columns that guests may read and write.
+    pub async fn create_guest_sensor(
+        name: &str,
+        columns: Vec<SensorColumn>,
+        state: &AppState,
+    ) -> Uuid {
+        let sensor = sensor_db::create_sensor(guest_sensor_request(name, columns), None, state)
+            .await
+            .unwrap();
+
+        Uuid::parse_str(&sensor.uuid).unwrap()
+    }
+
     /// Creates an API key for each user for each sensor he has access (READ, WRITE) to.
     pub async fn create_test_api_keys(state: &AppState) -> Vec<ApiKey> {
         let mut res: Vec<ApiKey> = Vec::new();
@@ -94,7 +94,10 @@ pub enum AppError {
     // HTTP errors
 
     // 400
-    // Validation {}
+    Validation {
+        msg: Option<String>,
+        fields: Vec<String>,
+    },
 
     // 403
     Unauthorized {
@@ -128,6 +131,12 @@ impl fmt::Display for AppError {
             AppError::NotFound { msg, .. } => {
                 write!(f, "Not Found: {}", msg.clone().unwrap_or("".to_string()))
             }
+            AppError::Validation { msg, fields } => write!(
+                f,
+                "Validation Error: {} [{}]",
+                msg.clone().unwrap_or("".to_string()),
+                fields.join("; ")
+            ),
             AppError::DatabaseError { msg, .. } => {
                 write!(f, "DB Error: {}", msg.clone().unwrap_or("".to_string()))
             }
@@ -184,6 +193,13 @@ impl AppError {
             msg: Some(msg.into()),
         }
     }
+
+    pub fn validation(msg: impl Into<String>, fields: Vec<String>) -> Self {
+        AppError::Validation {
+            msg: Some(msg.into()),
+            fields,
+        }
+    }
 }
 
 // Define the structure of the JSON response body - public facing
@@ -208,6 +224,7 @@ impl actix_web::error::ResponseError for AppError {
             AppError::DatabaseError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             AppError::Unauthorized { .. } => StatusCode::UNAUTHORIZED,
             AppError::NotFound { .. } => StatusCode::NOT_FOUND,
+            AppError::Validation { .. } => StatusCode::BAD_REQUEST,
         }
     }
 
@@ -224,6 +241,11 @@ impl actix_web::error::ResponseError for AppError {
             AppError::NotFound { msg, .. } => {
                 (msg.clone().unwrap_or_else(|| "Not found".to_string()), None)
             }
+            AppError::Validation { msg, fields } => (
+                msg.clone()
+                    .unwrap_or_else(|| "Validation failed".to_string()),
+                Some(fields.clone()),
+            ),
             AppError::InternalError { msg, .. } | AppError::DatabaseError { msg, .. } => (
                 msg.clone()
                     .unwrap_or_else(|| "Unexpected internal error".to_string()),
@@ -260,8 +282,8 @@ impl From<actix_web::Error> for AppError {
         }
     }
 }
-impl From<rumqttc::ClientError> for AppError {
-    fn from(value: rumqttc::ClientError) -> Self {
+impl From<rumqttc::v5::ClientError> for AppError {
+    fn from(value: rumqttc::v5::ClientError) -> Self {
         AppError::InternalError {
             status: None,
             msg: Some(value.to_string()),
@@ -6,6 +6,7 @@ use sensor_mgmt::features::config::{
 };
 use sensor_mgmt::features::event_generation::EventGenerator;
 use sensor_mgmt::features::telemetry::{init_telemetry, stop_telemetry};
+use sensor_mgmt::handler::data_hdl;
 use sensor_mgmt::state::{development_setup, init_app_state};
 use sqlx::postgres::PgPoolOptions;
 use tracing::{error, info};
@@ -78,6 +79,7 @@ async fn main() -> std::io::Result<()> {
                 header::AUTHORIZATION,
                 header::ACCEPT,
             ])
+            .expose_headers(vec![data_hdl::NEXT_CURSOR_HEADER])
             .supports_credentials()
             .allow_any_origin();
         App::new()
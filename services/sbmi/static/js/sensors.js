@@ -234,6 +234,9 @@ SBMI.sensorsAPI = {
         <option value="INT">INT</option>
         <option value="FLOAT">FLOAT</option>
         <option value="STRING">STRING</option>
+        <option value="BOOL">BOOL</option>
+        <option value="TIMESTAMP">TIMESTAMP</option>
+        <option value="JSON">JSON</option>
       </select>
       <input name="colUnit" type="text" class="form-control" placeholder="Unit" aria-label="unit">
       <select name="colIngest" class="form-select">
@@ -625,6 +628,15 @@ SBMI.sensorsAPI = {
         case "FLOAT":
           entry[col.name] = 5.1;
           break;
+        case "BOOL":
+          entry[col.name] = true;
+          break;
+        case "TIMESTAMP":
+          entry[col.name] = new Date().toISOString().slice(0, 19);
+          break;
+        case "JSON":
+          entry[col.name] = { key: "value" };
+          break;
         default:
           entry[col.name] = "A string";
           break;